@@ -153,10 +153,16 @@ impl<'a> GrpcConnect<'a> {
 
     /// return grpc handle to the container
     pub async fn grpc_handle(&self, name: &str) -> Result<RpcHandle, String> {
+        self.grpc_handle_at(name, 10124).await
+    }
+
+    /// return grpc handle to the io-engine listening on the given port of
+    /// the container
+    pub async fn grpc_handle_at(&self, name: &str, port: u16) -> Result<RpcHandle, String> {
         match self.ct.containers().iter().find(|&c| c.0 == name) {
             Some(container) => Ok(RpcHandle::connect(
                 container.0.clone(),
-                format!("{}:10124", container.1 .1)
+                format!("{}:{port}", container.1 .1)
                     .parse::<std::net::SocketAddr>()
                     .unwrap(),
             )
@@ -166,7 +172,15 @@ impl<'a> GrpcConnect<'a> {
     }
 
     pub async fn grpc_handle_shared(&self, name: &str) -> Result<SharedRpcHandle, String> {
-        self.grpc_handle(name).await.map(|rpc| {
+        self.grpc_handle_shared_at(name, 10124).await
+    }
+
+    pub async fn grpc_handle_shared_at(
+        &self,
+        name: &str,
+        port: u16,
+    ) -> Result<SharedRpcHandle, String> {
+        self.grpc_handle_at(name, port).await.map(|rpc| {
             let name = rpc.name.clone();
             let endpoint = rpc.endpoint;
             SharedRpcHandle {
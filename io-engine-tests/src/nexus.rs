@@ -5,7 +5,7 @@ use super::{
             ChildStateReason, CreateNexusRequest, DestroyNexusRequest, ListNexusOptions, Nexus,
             NexusNvmePreemption, NvmeReservation, PublishNexusRequest, RebuildHistoryRecord,
             RebuildHistoryRequest, RemoveChildNexusRequest, ResizeNexusRequest,
-            ShutdownNexusRequest,
+            ShutdownNexusRequest, UnpublishNexusRequest,
         },
         snapshot::SnapshotInfo,
         SharedRpcHandle, Status,
@@ -231,6 +231,16 @@ impl NexusBuilder {
             .map(|r| r.into_inner().nexus.unwrap())
     }
 
+    pub async fn unpublish(&self) -> Result<Nexus, Status> {
+        self.rpc()
+            .lock()
+            .await
+            .nexus
+            .unpublish_nexus(UnpublishNexusRequest { uuid: self.uuid() })
+            .await
+            .map(|r| r.into_inner().nexus.unwrap())
+    }
+
     pub async fn resize(&self, req_size: u64) -> Result<Nexus, Status> {
         self.rpc()
             .lock()
@@ -183,3 +183,203 @@ pub fn get_nvme_resv_report(nvme_dev: &str) -> serde_json::Value {
         serde_json::from_str(&resv_rep).expect("JSON was not well-formatted");
     v
 }
+
+/// Runs the given nvme-cli command on the given device.
+fn nvme_dev_cmd(cmd: &str, nvme_dev: &str, args: &[String]) -> std::process::Output {
+    Command::new("nvme")
+        .args([cmd, nvme_dev])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Runs the given nvme-cli reservation command on the given device.
+fn nvme_resv_cmd(cmd: &str, nvme_dev: &str, args: &[String]) {
+    let output = nvme_dev_cmd(cmd, nvme_dev, args);
+    assert!(
+        output.status.success(),
+        "failed to {} on {}: {}",
+        cmd,
+        nvme_dev,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Registers the given reservation key, persisting it through power loss.
+pub fn nvme_resv_register(nvme_dev: &str, key: u64) {
+    nvme_resv_cmd(
+        "resv-register",
+        nvme_dev,
+        &[
+            format!("--nrkey={key}"),
+            "--rrega=0".into(),
+            "--cptpl=3".into(),
+        ],
+    );
+}
+
+/// Acquires a reservation of the given type with the given registered key.
+pub fn nvme_resv_acquire(nvme_dev: &str, key: u64, rtype: u8) {
+    nvme_resv_cmd(
+        "resv-acquire",
+        nvme_dev,
+        &[
+            format!("--crkey={key}"),
+            format!("--rtype={rtype}"),
+            "--racqa=0".into(),
+        ],
+    );
+}
+
+/// Releases the reservation of the given type held with the given key.
+pub fn nvme_resv_release(nvme_dev: &str, key: u64, rtype: u8) {
+    nvme_resv_cmd(
+        "resv-release",
+        nvme_dev,
+        &[
+            format!("--crkey={key}"),
+            format!("--rtype={rtype}"),
+            "--rrela=0".into(),
+        ],
+    );
+}
+
+/// A connection of an NVMe host with its own NQN and host ID to a subsystem,
+/// through a controller of its own, which is disconnected when dropped.
+pub struct NvmeHostConnection {
+    ctrl: String,
+}
+
+impl NvmeHostConnection {
+    /// Connects the host with the given NQN and ID to the given subsystem.
+    pub fn connect(target_addr: &str, nqn: &str, hostnqn: &str, hostid: &str) -> Self {
+        let status = Command::new("nvme")
+            .args(["connect", "-t", "tcp", "-a", target_addr, "-s", "8420"])
+            .args(["-n", nqn, "--hostnqn", hostnqn, "--hostid", hostid])
+            .status()
+            .unwrap();
+        assert!(
+            status.success(),
+            "failed to connect {hostnqn} to {target_addr}, nqn '{nqn}': {status}"
+        );
+        std::thread::sleep(std::time::Duration::from_secs(1));
+
+        let read = |ctrl: &std::path::Path, attr: &str| {
+            std::fs::read_to_string(ctrl.join(attr))
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default()
+        };
+        let ctrl = std::fs::read_dir("/sys/class/nvme")
+            .unwrap()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .find(|ctrl| read(ctrl, "subsysnqn") == nqn && read(ctrl, "hostnqn") == hostnqn)
+            .and_then(|ctrl| ctrl.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| panic!("no controller of {hostnqn} for nqn '{nqn}'"));
+
+        Self { ctrl }
+    }
+
+    /// Runs the given nvme-cli reservation command on the namespace through
+    /// the controller of the host, and returns whether it succeeded.
+    fn resv_cmd(&self, cmd: &str, args: &[String]) -> bool {
+        let mut args = args.to_vec();
+        args.push("--namespace-id=1".into());
+        nvme_dev_cmd(cmd, &format!("/dev/{}", self.ctrl), &args)
+            .status
+            .success()
+    }
+
+    /// Registers the given reservation key, persisting it through power loss.
+    pub fn resv_register(&self, key: u64) -> bool {
+        self.resv_cmd(
+            "resv-register",
+            &[
+                format!("--nrkey={key}"),
+                "--rrega=0".into(),
+                "--cptpl=3".into(),
+            ],
+        )
+    }
+
+    /// Registers the given reservation key, without persisting it through
+    /// power loss.
+    pub fn resv_register_volatile(&self, key: u64) -> bool {
+        self.resv_cmd(
+            "resv-register",
+            &[
+                format!("--nrkey={key}"),
+                "--rrega=0".into(),
+                "--cptpl=2".into(),
+            ],
+        )
+    }
+
+    /// Acquires a reservation of the given type with the registered key.
+    pub fn resv_acquire(&self, key: u64, rtype: u8) -> bool {
+        self.resv_cmd(
+            "resv-acquire",
+            &[
+                format!("--crkey={key}"),
+                format!("--rtype={rtype}"),
+                "--racqa=0".into(),
+            ],
+        )
+    }
+
+    /// Preempts the reservation held with the given key, acquiring one of the
+    /// given type with the registered key.
+    pub fn resv_preempt(&self, key: u64, preempt_key: u64, rtype: u8) -> bool {
+        self.resv_cmd(
+            "resv-acquire",
+            &[
+                format!("--crkey={key}"),
+                format!("--prkey={preempt_key}"),
+                format!("--rtype={rtype}"),
+                "--racqa=1".into(),
+            ],
+        )
+    }
+
+    /// Releases the reservation of the given type held with the given key.
+    pub fn resv_release(&self, key: u64, rtype: u8) -> bool {
+        self.resv_cmd(
+            "resv-release",
+            &[
+                format!("--crkey={key}"),
+                format!("--rtype={rtype}"),
+                "--rrela=0".into(),
+            ],
+        )
+    }
+
+    /// Gets the extended reservation report of the namespace.
+    pub fn resv_report(&self) -> serde_json::Value {
+        let output = nvme_dev_cmd(
+            "resv-report",
+            &format!("/dev/{}", self.ctrl),
+            &[
+                "--namespace-id=1".into(),
+                "-c".into(),
+                "1".into(),
+                "-o".into(),
+                "json".into(),
+            ],
+        );
+        assert!(
+            output.status.success(),
+            "failed to get reservation report from {}: {}",
+            self.ctrl,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        serde_json::from_slice(&output.stdout).expect("JSON was not well-formatted")
+    }
+}
+
+impl Drop for NvmeHostConnection {
+    fn drop(&mut self) {
+        Command::new("nvme")
+            .args(["disconnect", "-d", &self.ctrl])
+            .output()
+            .ok();
+    }
+}
@@ -7,7 +7,7 @@ use super::{
 use io_engine::{constants::NVME_NQN_PREFIX, subsys::make_subsystem_serial};
 use io_engine_api::v1::replica::{
     destroy_replica_request, CreateReplicaRequest, DestroyReplicaRequest, ListReplicaOptions,
-    Replica, ResizeReplicaRequest, ShareReplicaRequest,
+    Replica, ResizeReplicaRequest, ShareReplicaRequest, UnshareReplicaRequest,
 };
 
 use tonic::{Code, Status};
@@ -185,6 +185,19 @@ impl ReplicaBuilder {
         Ok(r)
     }
 
+    pub async fn unshare(&mut self) -> Result<Replica, Status> {
+        let r = self
+            .rpc()
+            .lock()
+            .await
+            .replica
+            .unshare_replica(UnshareReplicaRequest { uuid: self.uuid() })
+            .await
+            .map(|r| r.into_inner())?;
+        self.shared_uri = None;
+        Ok(r)
+    }
+
     pub async fn resize(&mut self, req_size: u64) -> Result<Replica, Status> {
         let r = self
             .rpc()
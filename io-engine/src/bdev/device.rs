@@ -32,7 +32,7 @@ use crate::core::{
 
 #[cfg(feature = "fault-injection")]
 use crate::core::fault_injection::{
-    inject_completion_error, inject_submission_error, FaultDomain, InjectIoCtx,
+    inject_completion, inject_submission_error, FaultDomain, InjectIoCtx,
 };
 use crate::replica_backend::ReplicaFactory;
 
@@ -535,6 +535,14 @@ impl BlockDeviceHandle for SpdkBlockDeviceHandle {
             });
         };
 
+        // The pool is locked as for any other snapshot, so that a check of the
+        // pool doesn't take the new snapshot for an orphaned blob.
+        let _lock_guard = crate::lvs::lock_pool(&replica.pool_name())
+            .await
+            .map_err(|e| CoreError::SnapshotCreate {
+                reason: e.to_string(),
+                source: e.to_errno(),
+            })?;
         replica
             .create_snapshot(snapshot)
             .await
@@ -656,7 +664,7 @@ fn free_bdev_io_ctx(ctx: *mut IoCtx) {
 }
 
 extern "C" fn bdev_io_completion(child_bio: *mut spdk_bdev_io, success: bool, ctx: *mut c_void) {
-    let bio = unsafe { &mut *(ctx as *mut IoCtx) };
+    let bio = ctx as *mut IoCtx;
 
     // Get extended NVMe error status from original bio in case of error.
     let status = if success {
@@ -666,12 +674,12 @@ extern "C" fn bdev_io_completion(child_bio: *mut spdk_bdev_io, success: bool, ct
     };
 
     #[cfg(feature = "fault-injection")]
-    let status = inject_completion_error(&bio.inj_op, status);
-
-    (bio.cb)(&bio.device, status, bio.cb_arg);
+    inject_completion(unsafe { &(*bio).inj_op }, status, move |status| {
+        finish_bdev_io(bio, status)
+    });
 
-    // Free ctx.
-    free_bdev_io_ctx(&mut *bio);
+    #[cfg(not(feature = "fault-injection"))]
+    finish_bdev_io(bio, status);
 
     // Free replica's bio.
     unsafe {
@@ -679,6 +687,17 @@ extern "C" fn bdev_io_completion(child_bio: *mut spdk_bdev_io, success: bool, ct
     }
 }
 
+/// Invokes caller's callback and frees bdev I/O context.
+#[inline]
+fn finish_bdev_io(ctx: *mut IoCtx, status: IoCompletionStatus) {
+    let bio = unsafe { &mut *ctx };
+
+    (bio.cb)(&bio.device, status, bio.cb_arg);
+
+    // Free ctx.
+    free_bdev_io_ctx(bio);
+}
+
 /// Forwards event to the high-level handler.
 fn dispatch_bdev_event(event: DeviceEventType, name: &str) {
     let mut map = BDEV_EVENT_DISPATCHER.lock().expect("lock poisoned");
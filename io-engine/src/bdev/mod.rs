@@ -3,7 +3,10 @@ use async_trait::async_trait;
 pub use dev::{device_create, device_destroy, device_lookup, device_open};
 pub use device::{bdev_event_callback, bdev_io_ctx_pool_init, SpdkBlockDevice};
 pub use nexus::{Nexus, NexusInfo, NexusState};
-pub use nvmx::{nvme_io_ctx_pool_init, NvmeController, NvmeControllerState, NVME_CONTROLLERS};
+pub use nvmx::{
+    nvme_controller_set_timeout_policy, nvme_controller_timeouts, nvme_io_ctx_pool_init,
+    EffectiveTimeouts, NvmeController, NvmeControllerState, TimeoutPolicy, NVME_CONTROLLERS,
+};
 
 mod aio;
 pub(crate) mod dev;
@@ -20,7 +20,10 @@ mod nexus_iter;
 mod nexus_module;
 mod nexus_nbd;
 mod nexus_persistence;
+mod nexus_reservation;
 mod nexus_share;
+mod nexus_ublk;
+mod nexus_vhost;
 
 use crate::{
     bdev::nexus::nexus_iter::NexusIterMut,
@@ -33,6 +36,7 @@ pub use nexus_bdev::{
 };
 pub(crate) use nexus_bdev_error::nexus_err;
 pub use nexus_bdev_error::Error;
+pub use nexus_channel::{nexus_channels_io, NexusChannelIo};
 pub(crate) use nexus_channel::{DrEvent, IoMode, NexusChannel};
 pub use nexus_child::{
     ChildError, ChildState, ChildStateClient, ChildSyncState, FaultReason, NexusChild,
@@ -49,7 +53,10 @@ pub(crate) use nexus_module::{NexusModule, NEXUS_MODULE_NAME};
 pub(crate) use nexus_nbd::{NbdDisk, NbdError};
 pub(crate) use nexus_persistence::PersistOp;
 pub use nexus_persistence::{ChildInfo, NexusInfo};
+pub(crate) use nexus_reservation::ReservationMirror;
 pub(crate) use nexus_share::NexusPtpl;
+pub(crate) use nexus_ublk::{UblkDisk, UblkError};
+pub(crate) use nexus_vhost::{VhostBlk, VhostError};
 
 pub use nexus_bdev_snapshot::{
     NexusReplicaSnapshotDescriptor, NexusReplicaSnapshotStatus, NexusSnapshotStatus,
@@ -75,6 +82,17 @@ struct NexusShareReply {
     uri: String,
 }
 
+/// Arguments of the `nexus_rebuild_from_snapshot` json-rpc method.
+#[derive(Debug, Deserialize)]
+struct NexusRebuildFromSnapshotArgs {
+    /// Name of the nexus.
+    name: String,
+    /// Uri of the child to online and rebuild.
+    child_uri: String,
+    /// Uri of the snapshot to copy into the child before the delta.
+    snapshot_uri: String,
+}
+
 /// public function which simply calls register module
 pub fn register_module(register_json: bool) {
     nexus_module::register_module();
@@ -84,8 +102,12 @@ pub fn register_module(register_json: bool) {
     }
 
     use crate::{
-        core::{NvmfShareProps, Share, UntypedBdev},
-        jsonrpc::{jsonrpc_register, Code, JsonRpcError, Result},
+        core::{
+            drain::check_serving,
+            lock::{ProtectedSubsystems, ResourceLockManager},
+            NvmfShareProps, Share, UntypedBdev,
+        },
+        jsonrpc::{jsonrpc_register, Code, JsonRpcError, Result, RpcErrorCode},
     };
 
     jsonrpc_register(
@@ -93,6 +115,10 @@ pub fn register_module(register_json: bool) {
         |args: NexusShareArgs| -> Pin<Box<dyn Future<Output = Result<NexusShareReply>>>> {
             // FIXME: shares bdev, not a nexus
             let f = async move {
+                check_serving().map_err(|e| JsonRpcError {
+                    code: e.rpc_error_code(),
+                    message: e.to_string(),
+                })?;
                 let proto = args.protocol;
                 if proto != "nvmf" {
                     return Err(JsonRpcError {
@@ -130,6 +156,52 @@ pub fn register_module(register_json: bool) {
             Box::pin(f.boxed_local())
         },
     );
+
+    jsonrpc_register(
+        "nexus_rebuild_from_snapshot",
+        |args: NexusRebuildFromSnapshotArgs| -> Pin<Box<dyn Future<Output = Result<()>>>> {
+            let f = async move {
+                info!("{:?}", args);
+                check_serving().map_err(|e| JsonRpcError {
+                    code: e.rpc_error_code(),
+                    message: e.to_string(),
+                })?;
+                let nexus_not_found = || JsonRpcError {
+                    code: Code::NotFound,
+                    message: "nexus not found".to_string(),
+                };
+                // The nexus is serialized with the gRPC operations on it.
+                let uuid = nexus_lookup_mut(&args.name)
+                    .ok_or_else(nexus_not_found)?
+                    .uuid()
+                    .to_string();
+                let _guard = ResourceLockManager::get_instance()
+                    .get_subsystem(ProtectedSubsystems::NEXUS)
+                    .lock_resource(uuid.clone(), None, true)
+                    .await
+                    .ok_or_else(|| JsonRpcError {
+                        code: Code::InternalError,
+                        message: format!("nexus {uuid} is busy"),
+                    })?;
+                let nexus = nexus_lookup_mut(&uuid).ok_or_else(nexus_not_found)?;
+                nexus
+                    .online_child_from_snapshot(&args.child_uri, &args.snapshot_uri)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| JsonRpcError {
+                        code: match e {
+                            Error::ChildNotFound { .. } => Code::NotFound,
+                            Error::ChildNotDegraded { .. }
+                            | Error::SnapshotBeforeIoLog { .. }
+                            | Error::SnapshotNotOfNexus { .. } => Code::InvalidParams,
+                            _ => Code::InternalError,
+                        },
+                        message: e.verbose(),
+                    })
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
 }
 
 /// called during shutdown so that all nexus children are in Destroying state
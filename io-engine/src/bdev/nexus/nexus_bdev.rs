@@ -6,7 +6,7 @@
 
 use std::{
     cmp::min,
-    collections::HashSet,
+    collections::{HashSet, VecDeque},
     convert::TryFrom,
     fmt::{Debug, Display, Formatter},
     marker::PhantomPinned,
@@ -25,7 +25,7 @@ use uuid::Uuid;
 
 use super::{
     nexus_err, nexus_lookup_name_uuid, DrEvent, Error, NbdDisk, NexusBio, NexusChannel, NexusChild,
-    NexusModule, PersistOp,
+    NexusModule, NexusPtpl, PersistOp, ReservationMirror, UblkDisk, VhostBlk,
 };
 
 use crate::{
@@ -64,6 +64,8 @@ pub(crate) static NEXUS_PRODUCT_ID: &str = "Nexus CAS Driver v0.0.1";
 #[derive(Debug)]
 pub enum NexusTarget {
     NbdDisk(NbdDisk),
+    UblkDisk(UblkDisk),
+    VhostBlk(VhostBlk),
     NexusNvmfTarget,
 }
 
@@ -246,10 +248,15 @@ pub struct Nexus<'n> {
     event_sink: Option<DeviceEventSink>,
     /// Rebuild history of all children of this nexus instance.
     pub(super) rebuild_history: parking_lot::Mutex<Vec<HistoryRecord>>,
+    /// Snapshots taken by this nexus instance, with the I/O log sequence
+    /// number they were taken at.
+    pub(super) snapshot_seqs: parking_lot::Mutex<VecDeque<(String, u64)>>,
     /// Flag to control shutdown from I/O path.
     pub(crate) shutdown_requested: AtomicCell<bool>,
     /// Last child I/O error.
     pub(super) last_error: IoCompletionStatus,
+    /// Mirroring of the front-end reservations onto the children.
+    pub(super) resv_mirror: parking_lot::Mutex<ReservationMirror>,
     /// Prevent auto-Unpin.
     _pin: PhantomPinned,
 }
@@ -368,8 +375,10 @@ impl<'n> Nexus<'n> {
             nexus_uuid: Default::default(),
             event_sink: None,
             rebuild_history: parking_lot::Mutex::new(Vec::new()),
+            snapshot_seqs: parking_lot::Mutex::new(VecDeque::new()),
             shutdown_requested: AtomicCell::new(false),
             last_error: IoCompletionStatus::Success,
+            resv_mirror: Default::default(),
             _pin: Default::default(),
         };
 
@@ -851,6 +860,7 @@ impl<'n> Nexus<'n> {
                     power loss for nexus: {error}",
                 );
             }
+            NexusPtpl::from(self.deref()).forget().await;
         }
 
         unsafe {
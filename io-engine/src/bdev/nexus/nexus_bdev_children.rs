@@ -35,7 +35,11 @@ use super::{
 };
 
 use crate::{
-    bdev::{dev::device_name, device_create, device_destroy, device_lookup},
+    bdev::{
+        dev::device_name,
+        device_create, device_destroy, device_lookup,
+        nvmx::{nvme_controller_set_timeout_policy, strip_timeout_parameters, TimeoutPolicy},
+    },
     bdev_api::BdevError,
     core::{
         device_cmd_queue, DeviceCommand, DeviceEventListener, DeviceEventType, Reactors,
@@ -48,6 +52,7 @@ use crate::{
 use events_api::event::EventAction;
 
 use spdk_rs::{ffihelper::cb_arg, ChannelTraverseStatus, IoDeviceChannelTraverse};
+use url::Url;
 
 impl<'n> Nexus<'n> {
     /// Create and register a single child to nexus, only allowed during the
@@ -320,6 +325,45 @@ impl<'n> Nexus<'n> {
         res
     }
 
+    /// Changes the I/O timeout policy of an NVMe-oF child. The URI is the URI
+    /// of the child along with the timeout parameters to change, e.g.
+    /// `nvmf://host/nqn?uuid=<uuid>&io_timeout_us=30000000`. The changes are
+    /// kept, and applied again when the device of the child is re-created.
+    pub fn set_child_timeout_policy(&self, uri: &str) -> Result<NexusStatus, Error> {
+        let invalid = |args: String| Error::InvalidArguments {
+            name: self.name.clone(),
+            args,
+        };
+
+        let url = Url::parse(uri).map_err(|e| invalid(format!("{uri}: {e}")))?;
+        let mut parameters = url.query_pairs().into_owned().collect();
+        let policy = TimeoutPolicy::from_parameters(&url, &mut parameters)
+            .map_err(|e| invalid(e.to_string()))?;
+
+        let device_url = strip_timeout_parameters(&url);
+        let child = self
+            .children_iter()
+            .find(|c| {
+                Url::parse(c.uri()).map_or(false, |u| strip_timeout_parameters(&u) == device_url)
+            })
+            .ok_or_else(|| Error::ChildNotFound {
+                child: uri.to_owned(),
+                name: self.name.clone(),
+            })?;
+
+        let Some(dev_name) = child.get_device_name() else {
+            return Err(Error::OperationNotAllowed {
+                reason: format!("child '{}' has no device", child.uri()),
+            });
+        };
+        let timeouts = nvme_controller_set_timeout_policy(&dev_name, &policy)
+            .map_err(|_| invalid(format!("child '{}' is not an NVMe-oF device", child.uri())))?;
+        child.merge_timeout_policy(&policy);
+
+        info!("{child:?}: timeouts set to {timeouts:?}");
+        Ok(self.status())
+    }
+
     /// Faults a child with the given reason.
     pub async fn fault_child(
         mut self: Pin<&mut Self>,
@@ -383,9 +427,26 @@ impl<'n> Nexus<'n> {
 
     /// Onlines a child by re-opening its underlying block device and rebuilding
     /// the data from an existing child.
-    pub async fn online_child(
+    pub async fn online_child(self: Pin<&mut Self>, child_uri: &str) -> Result<NexusStatus, Error> {
+        self.online_child_ext(child_uri, None).await
+    }
+
+    /// Onlines a child by re-opening its underlying block device and rebuilding
+    /// the data from the given snapshot first, and then the delta from an
+    /// existing child.
+    pub async fn online_child_from_snapshot(
+        self: Pin<&mut Self>,
+        child_uri: &str,
+        snapshot_uri: &str,
+    ) -> Result<NexusStatus, Error> {
+        self.online_child_ext(child_uri, Some(snapshot_uri)).await
+    }
+
+    /// Onlines a child, rebuilding it from the given snapshot, if any.
+    async fn online_child_ext(
         mut self: Pin<&mut Self>,
         child_uri: &str,
+        snapshot: Option<&str>,
     ) -> Result<NexusStatus, Error> {
         let nexus_name = self.name.clone();
         let nexus_size = self.req_size();
@@ -423,7 +484,14 @@ impl<'n> Nexus<'n> {
         child.set_event_listener(self.get_event_sink());
 
         // Start rebuild.
-        if let Err(e) = self.start_rebuild(child_uri).await {
+        let rebuild = match snapshot {
+            Some(snapshot_uri) => {
+                self.start_rebuild_from_snapshot(child_uri, snapshot_uri)
+                    .await
+            }
+            None => self.start_rebuild(child_uri).await,
+        };
+        if let Err(e) = rebuild {
             child.close().await.ok();
             return Err(e);
         }
@@ -810,9 +878,11 @@ impl<'n> Nexus<'n> {
     }
 
     /// Returns list of I/O log channels of all children for the current core.
+    /// Children being copied from a snapshot keep logging, as their log tells
+    /// which segments have to be copied from the source child afterwards.
     pub(super) fn io_log_channels(&self) -> Vec<IOLogChannel> {
         self.children_iter()
-            .filter(|c| !c.is_rebuilding())
+            .filter(|c| !c.is_rebuilding() || c.is_copying_snapshot())
             .filter_map(|c| c.io_log_channel())
             .collect()
     }
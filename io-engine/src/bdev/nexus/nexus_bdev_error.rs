@@ -2,7 +2,7 @@ use nix::errno::Errno;
 use snafu::Snafu;
 use tonic::{Code, Status};
 
-use super::{ChildError, NbdError, NexusPauseState};
+use super::{ChildError, NbdError, NexusPauseState, UblkError, VhostError};
 
 use crate::{
     bdev_api::BdevError,
@@ -41,6 +41,14 @@ pub enum Error {
     NotSharedNvmf { name: String },
     #[snafu(display("Failed to share nexus over NBD {}", name))]
     ShareNbdNexus { source: NbdError, name: String },
+    #[snafu(display("Failed to share nexus over ublk {}", name))]
+    ShareUblkNexus { source: UblkError, name: String },
+    #[snafu(display("Failed to stop ublk device of nexus {}", name))]
+    UnshareUblkNexus { source: UblkError, name: String },
+    #[snafu(display("Failed to share nexus over vhost-user-blk {}", name))]
+    ShareVhostNexus { source: VhostError, name: String },
+    #[snafu(display("Failed to remove vhost-user-blk controller of nexus {}", name))]
+    UnshareVhostNexus { source: VhostError, name: String },
     #[snafu(display("Failed to share nvmf nexus {}", name))]
     ShareNvmfNexus { source: CoreError, name: String },
     #[snafu(display("Failed to unshare nexus {}", name))]
@@ -159,6 +167,19 @@ pub enum Error {
         name: String,
         state: String,
     },
+    #[snafu(display(
+        "Snapshot {} was taken before child {} of nexus {} went out of sync",
+        snapshot,
+        child,
+        name
+    ))]
+    SnapshotBeforeIoLog {
+        snapshot: String,
+        child: String,
+        name: String,
+    },
+    #[snafu(display("Snapshot {} was not taken by nexus {}", snapshot, name))]
+    SnapshotNotOfNexus { snapshot: String, name: String },
     #[snafu(display("Failed to get BdevHandle for snapshot operation"))]
     FailedGetHandle,
     #[snafu(display("Failed to create snapshot on nexus {}: {}", name, reason))]
@@ -204,6 +225,9 @@ impl From<Error> for tonic::Status {
             Error::ChildTooSmall { .. } => Status::invalid_argument(e.to_string()),
             Error::OpenChild { .. } => Status::invalid_argument(e.to_string()),
             Error::OperationNotAllowed { .. } => Status::failed_precondition(e.to_string()),
+            Error::SnapshotBeforeIoLog { .. } | Error::SnapshotNotOfNexus { .. } => {
+                Status::failed_precondition(e.to_string())
+            }
             Error::RemoveLastChild { .. } => Status::failed_precondition(e.to_string()),
             Error::RemoveLastHealthyChild { .. } => Status::failed_precondition(e.to_string()),
             Error::ChildNotFound { .. } => Status::not_found(e.to_string()),
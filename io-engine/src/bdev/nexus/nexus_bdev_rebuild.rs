@@ -1,6 +1,7 @@
 use futures::channel::oneshot::Receiver;
 use snafu::ResultExt;
 use std::{marker::PhantomData, sync::Arc};
+use url::Url;
 
 use super::{
     nexus_err, nexus_lookup_mut, nexus_persistence::PersistOp, ChildSyncState, DrEvent, Error,
@@ -8,11 +9,12 @@ use super::{
 };
 
 use crate::{
-    core::{Reactors, VerboseError},
+    bdev_api::bdev_get_name,
+    core::{Reactors, UntypedBdev, VerboseError},
     eventing::{EventMetaGen, EventWithMeta},
     rebuild::{
         HistoryRecord, NexusRebuildJob, NexusRebuildJobStarter, RebuildError, RebuildJobOptions,
-        RebuildState, RebuildStats, RebuildVerifyMode,
+        RebuildMap, RebuildState, RebuildStats, RebuildVerifyMode, SnapshotDeltaFn,
     },
 };
 use events_api::event::EventAction;
@@ -68,6 +70,47 @@ impl<'n> Nexus<'n> {
     /// Starts a rebuild job and returns a receiver channel
     /// which can be used to await the rebuild completion
     pub async fn start_rebuild(&self, child_uri: &str) -> Result<Receiver<RebuildState>, Error> {
+        self.start_rebuild_ext(child_uri, None).await
+    }
+
+    /// Starts a rebuild job which first copies the given snapshot into the
+    /// child, and then only the delta from a healthy child. Returns a receiver
+    /// channel which can be used to await the rebuild completion.
+    ///
+    /// The delta is tracked by the I/O log of the child, therefore the
+    /// snapshot must be one this nexus took of its replicas after the child
+    /// went out of sync, as told by the I/O log sequence. Any other snapshot
+    /// is refused, as the writes made between the snapshot and the start of
+    /// the I/O log would be lost.
+    /// A child without an I/O log, e.g. a new child, is fully rebuilt from a
+    /// healthy child instead.
+    pub async fn start_rebuild_from_snapshot(
+        &self,
+        child_uri: &str,
+        snapshot_uri: &str,
+    ) -> Result<Receiver<RebuildState>, Error> {
+        self.start_rebuild_ext(child_uri, Some(snapshot_uri)).await
+    }
+
+    /// Returns the I/O log sequence number the snapshot with the given uri
+    /// was taken at, if it was taken by this nexus instance. The uri tells the
+    /// uuid of the snapshot by its `uuid` parameter, or the name of a local
+    /// snapshot.
+    fn snapshot_uri_seq(&self, uri: &str) -> Option<u64> {
+        let url = Url::parse(uri).ok()?;
+        let uuid = match url.query_pairs().find(|(k, _)| k == "uuid") {
+            Some((_, uuid)) => uuid.to_string(),
+            None => UntypedBdev::lookup_by_name(&bdev_get_name(uri).ok()?)?.uuid_as_string(),
+        };
+        self.snapshot_seq(&uuid)
+    }
+
+    /// Starts a rebuild job, from the given snapshot if any.
+    async fn start_rebuild_ext(
+        &self,
+        child_uri: &str,
+        snapshot_uri: Option<&str>,
+    ) -> Result<Receiver<RebuildState>, Error> {
         let name = self.name.clone();
         info!("{self:?}: start rebuild request for {child_uri}");
 
@@ -76,6 +119,7 @@ impl<'n> Nexus<'n> {
             return Err(Error::NoRebuildSource { name: name.clone() });
         };
 
+        let mut snapshot_uri = snapshot_uri;
         let dst_child_uri = match self.lookup_child(child_uri) {
             Some(c) if c.is_opened_unsync() => {
                 if c.rebuild_job().is_some() {
@@ -84,7 +128,30 @@ impl<'n> Nexus<'n> {
                         name: name.clone(),
                     })
                 } else {
-                    Ok(c.uri().to_owned())
+                    match (snapshot_uri, c.io_log_started_seq()) {
+                        (Some(_), None) => {
+                            warn!(
+                                "{c:?}: no I/O log tells the writes made \
+                                since the snapshot was taken, rebuilding \
+                                from the source child only"
+                            );
+                            snapshot_uri = None;
+                            Ok(c.uri().to_owned())
+                        }
+                        (Some(uri), Some(io_log_seq)) => match self.snapshot_uri_seq(uri) {
+                            None => Err(Error::SnapshotNotOfNexus {
+                                snapshot: uri.to_owned(),
+                                name: name.clone(),
+                            }),
+                            Some(seq) if seq < io_log_seq => Err(Error::SnapshotBeforeIoLog {
+                                snapshot: uri.to_owned(),
+                                child: child_uri.to_owned(),
+                                name: name.clone(),
+                            }),
+                            Some(_) => Ok(c.uri().to_owned()),
+                        },
+                        (None, _) => Ok(c.uri().to_owned()),
+                    }
                 }
             }
             Some(c) => Err(Error::ChildNotDegraded {
@@ -100,7 +167,7 @@ impl<'n> Nexus<'n> {
 
         // Create a rebuild job for the child.
         let starter = self
-            .create_rebuild_job(&src_child_uri, &dst_child_uri)
+            .create_rebuild_job(&src_child_uri, &dst_child_uri, snapshot_uri)
             .await?;
 
         self.event(
@@ -109,6 +176,27 @@ impl<'n> Nexus<'n> {
         )
         .generate();
 
+        if snapshot_uri.is_some() {
+            // The child is kept out of the write path and its I/O log keeps
+            // running while it's copied from the snapshot, so that the copy
+            // cannot overwrite the front-end writes. Only then it joins the
+            // write path, and its I/O log tells the delta to copy from the
+            // source child.
+            let job = self.rebuild_job_mut(&dst_child_uri)?;
+            let nexus_name = name.clone();
+            let delta: SnapshotDeltaFn = Box::new({
+                let job = job.clone();
+                move || Box::pin(Nexus::snapshot_delta(nexus_name, job))
+            });
+
+            return starter.start_from_snapshot(job, delta).await.context(
+                nexus_err::RebuildOperation {
+                    job: child_uri.to_owned(),
+                    name: name.clone(),
+                },
+            );
+        }
+
         // We're now rebuilding the `dst_child` which means it HAS to become an
         // active participant in the frontend nexus bdev for Writes.
         // This is because the rebuild job copies from src to target child
@@ -123,9 +211,10 @@ impl<'n> Nexus<'n> {
         // As this is done after the reconfiguration, any new write I/Os will
         // now reach the destination child, and no rebuild will be required
         // for them.
-        let map = self
-            .lookup_child(&dst_child_uri)
-            .and_then(|c| c.stop_io_log());
+        let map = match self.lookup_child(&dst_child_uri) {
+            Some(c) => c.stop_io_log().await,
+            None => None,
+        };
 
         starter
             .start(self.rebuild_job_mut(&dst_child_uri)?, map)
@@ -136,6 +225,20 @@ impl<'n> Nexus<'n> {
             })
     }
 
+    /// Adds the child rebuilt by the given job to the write path, once it's
+    /// been copied from the snapshot, and stops its I/O log, returning the map
+    /// of the segments written since the snapshot was taken.
+    async fn snapshot_delta(nexus_name: String, job: Arc<NexusRebuildJob>) -> Option<RebuildMap> {
+        let nexus = nexus_lookup_mut(&nexus_name)?;
+
+        job.snapshot_copied();
+        // As with any other rebuild, the I/O log is stopped once the child
+        // receives all the front-end writes.
+        nexus.reconfigure(DrEvent::ChildRebuild).await;
+
+        nexus.lookup_child(job.dst_uri())?.stop_io_log().await
+    }
+
     /// Finds the best suited source replica for the given destination.
     fn find_src_replica(&self, dst_uri: &str) -> Option<String> {
         let candidates: Vec<_> = self
@@ -155,6 +258,7 @@ impl<'n> Nexus<'n> {
         &self,
         src_child_uri: &str,
         dst_child_uri: &str,
+        snapshot_uri: Option<&str>,
     ) -> Result<NexusRebuildJobStarter, Error> {
         let verify_mode = match std::env::var("NEXUS_REBUILD_VERIFY")
             .unwrap_or_default()
@@ -180,9 +284,10 @@ impl<'n> Nexus<'n> {
         let opts = RebuildJobOptions {
             verify_mode,
             read_opts: crate::core::ReadOptions::UnwrittenFail,
+            skip_zeroes: false,
         };
 
-        NexusRebuildJob::new_starter(
+        let starter = NexusRebuildJob::new_starter(
             &self.name,
             src_child_uri,
             dst_child_uri,
@@ -197,12 +302,19 @@ impl<'n> Nexus<'n> {
                 });
             },
         )
-        .await
-        .and_then(NexusRebuildJobStarter::store)
-        .context(nexus_err::CreateRebuild {
-            child: dst_child_uri.to_owned(),
-            name: self.name.clone(),
-        })
+        .await;
+
+        let starter = match (starter, snapshot_uri) {
+            (Ok(starter), Some(uri)) => starter.with_snapshot(uri).await,
+            (starter, _) => starter,
+        };
+
+        starter
+            .and_then(NexusRebuildJobStarter::store)
+            .context(nexus_err::CreateRebuild {
+                child: dst_child_uri.to_owned(),
+                name: self.name.clone(),
+            })
     }
 
     /// Translates the job into a new history record and pushes into
@@ -416,6 +528,8 @@ impl<'n> Nexus<'n> {
                 c.set_sync_state(ChildSyncState::Synced);
 
                 if c.is_healthy() {
+                    self.reservation_replay(c);
+
                     match self
                         .persist(PersistOp::Update {
                             child_uri: child_uri.to_owned(),
@@ -479,12 +593,24 @@ impl<'n> Nexus<'n> {
                 return Ok(());
             }
             Some(job) => {
+                // The I/O log no longer tells what the child misses once it's
+                // been partly copied from the snapshot, so it is dropped after
+                // the channels have stopped logging into it.
+                let io_log = if job.snapshot_uri().is_some() {
+                    c.take_io_log()
+                } else {
+                    None
+                };
                 self.create_history_record(job);
+
+                self.reconfigure(DrEvent::ChildRebuild).await;
+
+                if let Some(io_log) = io_log {
+                    io_log.finalize().await;
+                }
             }
         }
 
-        self.reconfigure(DrEvent::ChildRebuild).await;
-
         Ok(())
     }
 
@@ -3,13 +3,17 @@ use std::collections::{HashMap, HashSet};
 
 use futures::future::join_all;
 
-use super::{Error, Nexus, NexusOperation, NexusState};
+use super::{nexus_io_log::next_io_log_seq, Error, Nexus, NexusOperation, NexusState};
 use crate::{
     bdev::nexus::{nexus_lookup, NexusChild},
     core::{snapshot::ISnapshotDescriptor, CoreError, Reactor, SnapshotParams, ToErrno},
 };
 use chrono::{DateTime, Utc};
 use std::pin::Pin;
+
+/// Maximum number of the snapshots a nexus remembers having taken.
+const MAX_SNAPSHOT_SEQS: usize = 64;
+
 /// Per-replica descriptor for nexus snapshot operation.
 #[derive(Debug, Clone)]
 pub struct NexusReplicaSnapshotDescriptor {
@@ -280,11 +284,25 @@ impl<'n> Nexus<'n> {
         snapshot: SnapshotParams,
         replicas: Vec<NexusReplicaSnapshotDescriptor>,
     ) -> Result<NexusSnapshotStatus, Error> {
+        let snapshot_uuids = replicas
+            .iter()
+            .filter_map(|r| Some((r.replica_uuid.clone(), r.snapshot_uuid.clone()?)))
+            .collect::<HashMap<_, _>>();
+        // The I/O is paused, so that the snapshots hold all the writes made
+        // before this point of the I/O log sequence, and none made after.
+        let seq = next_io_log_seq();
         let (replicas_done, replicas_skipped) =
             ReplicaSnapshotExecutor::new(self.as_ref(), replicas)
                 .await?
                 .take_snapshot(&snapshot)
                 .await;
+        self.record_snapshot_seqs(
+            replicas_done
+                .iter()
+                .filter(|r| r.status == 0)
+                .filter_map(|r| snapshot_uuids.get(&r.replica_uuid).cloned()),
+            seq,
+        );
         Ok(NexusSnapshotStatus {
             replicas_done,
             replicas_skipped,
@@ -294,6 +312,26 @@ impl<'n> Nexus<'n> {
         })
     }
 
+    /// Remembers that the given snapshots were taken by this nexus at the
+    /// given I/O log sequence number, forgetting the oldest ones.
+    fn record_snapshot_seqs(&self, snapshots: impl Iterator<Item = String>, seq: u64) {
+        let mut seqs = self.snapshot_seqs.lock();
+        seqs.extend(snapshots.map(|uuid| (uuid, seq)));
+        while seqs.len() > MAX_SNAPSHOT_SEQS {
+            seqs.pop_front();
+        }
+    }
+
+    /// Returns the I/O log sequence number the snapshot with the given uuid
+    /// was taken at, if it was taken by this nexus instance.
+    pub(crate) fn snapshot_seq(&self, uuid: &str) -> Option<u64> {
+        self.snapshot_seqs
+            .lock()
+            .iter()
+            .find(|(u, _)| u == uuid)
+            .map(|(_, seq)| *seq)
+    }
+
     /// Create a snapshot on all children
     pub async fn create_snapshot(
         mut self: Pin<&mut Self>,
@@ -4,9 +4,15 @@ use std::{
     cell::UnsafeCell,
     fmt::{Debug, Display, Formatter},
     pin::Pin,
-    sync::atomic::Ordering,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
 };
 
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
 use super::{FaultReason, IOLogChannel, Nexus, NexusBio};
 
 use crate::core::{BlockDeviceHandle, CoreError, Cores};
@@ -26,6 +32,44 @@ pub struct NexusChannel<'n> {
     nexus: Pin<&'n mut Nexus<'n>>,
     core: u32,
     is_io_chan: bool,
+    in_flight: Arc<AtomicU64>,
+}
+
+/// In-flight I/O counter of a nexus channel.
+struct ChannelIoCounter {
+    nexus: String,
+    core: u32,
+    in_flight: Arc<AtomicU64>,
+}
+
+/// In-flight I/O counters of all nexus channels. The counters are shared with
+/// the channels, so that they can be read from any thread, even when the
+/// reactor owning the channel is frozen.
+static CHANNEL_IO_COUNTERS: Lazy<parking_lot::Mutex<Vec<ChannelIoCounter>>> =
+    Lazy::new(Default::default);
+
+/// Number of in-flight I/Os of a nexus channel, as reported by diagnostics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NexusChannelIo {
+    /// Name of the nexus.
+    pub nexus: String,
+    /// Core the channel belongs to.
+    pub core: u32,
+    /// Number of nexus I/Os submitted to the channel and not yet completed.
+    pub in_flight: u64,
+}
+
+/// Get the number of in-flight I/Os of all nexus channels.
+pub fn nexus_channels_io() -> Vec<NexusChannelIo> {
+    CHANNEL_IO_COUNTERS
+        .lock()
+        .iter()
+        .map(|c| NexusChannelIo {
+            nexus: c.nexus.clone(),
+            core: c.core,
+            in_flight: c.in_flight.load(Ordering::Relaxed),
+        })
+        .collect()
 }
 
 impl Debug for NexusChannel<'_> {
@@ -123,8 +167,15 @@ impl<'n> NexusChannel<'n> {
             frozen_ios: Vec::new(),
             core: Cores::current(),
             is_io_chan,
+            in_flight: Arc::new(AtomicU64::new(0)),
         };
 
+        CHANNEL_IO_COUNTERS.lock().push(ChannelIoCounter {
+            nexus: res.nexus.name.clone(),
+            core: res.core,
+            in_flight: res.in_flight.clone(),
+        });
+
         res.connect_children();
 
         if is_channel_debug_enabled() {
@@ -146,6 +197,21 @@ impl<'n> NexusChannel<'n> {
         self.readers.clear();
         self.detached.clear();
         self.io_logs.clear();
+        CHANNEL_IO_COUNTERS
+            .lock()
+            .retain(|c| !Arc::ptr_eq(&c.in_flight, &self.in_flight));
+    }
+
+    /// Accounts for a new nexus I/O submitted to this channel.
+    #[inline(always)]
+    pub(super) fn io_started(&self) {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Accounts for a nexus I/O of this channel being completed.
+    #[inline(always)]
+    pub(super) fn io_completed(&self) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
     }
 
     /// Returns reference to channel's Nexus.
@@ -337,7 +403,7 @@ impl<'n> NexusChannel<'n> {
         if !readers.is_empty() {
             self.nexus()
                 .children_iter()
-                .filter(|c| c.is_rebuilding())
+                .filter(|c| c.is_rebuilding() && !c.is_copying_snapshot())
                 .for_each(|c| match c.get_io_handle() {
                     Ok(hdl) => {
                         debug!(
@@ -418,6 +484,8 @@ impl<'n> NexusChannel<'n> {
             n = self.frozen_ios.len()
         );
 
+        self.in_flight
+            .fetch_sub(self.frozen_ios.len() as u64, Ordering::Relaxed);
         self.frozen_ios.drain(..).for_each(|io| {
             trace!("{io:?}: aborting a frozen I/O");
             io.fail();
@@ -16,7 +16,7 @@ use url::Url;
 use super::{nexus_lookup_mut, DrEvent, IOLog, IOLogChannel};
 
 use crate::{
-    bdev::{device_create, device_destroy, device_lookup},
+    bdev::{device_create, device_destroy, device_lookup, TimeoutPolicy},
     bdev_api::BdevError,
     core::{
         BlockDevice, BlockDeviceDescriptor, BlockDeviceHandle, CoreError, DeviceEventSink,
@@ -293,6 +293,10 @@ pub struct NexusChild<'c> {
     /// I/O log.
     #[serde(skip_serializing)]
     io_log: Mutex<Option<IOLog>>,
+    /// Timeout policy changes made at runtime, which are applied when the
+    /// device is re-created.
+    #[serde(skip_serializing)]
+    timeout_policy: Mutex<TimeoutPolicy>,
     /// TODO
     #[serde(skip_serializing)]
     _c: PhantomData<&'c ()>,
@@ -504,6 +508,13 @@ impl<'c> NexusChild<'c> {
         self.rebuild_job().is_some() && self.is_opened_unsync()
     }
 
+    /// Check if the child is being copied from a snapshot, ahead of joining
+    /// the write path for the rest of its rebuild.
+    pub(crate) fn is_copying_snapshot(&self) -> bool {
+        self.rebuild_job()
+            .map_or(false, |j| j.is_copying_snapshot())
+    }
+
     /// Register an NVMe reservation, specifying a new key
     async fn resv_register(
         &self,
@@ -944,9 +955,11 @@ impl<'c> NexusChild<'c> {
 
         // Re-create the block device as it will have been previously
         // destroyed.
-        let name = device_create(&self.name).await.context(ChildBdevCreate {
-            child: self.name.clone(),
-        })?;
+        let name = device_create(&self.device_uri())
+            .await
+            .context(ChildBdevCreate {
+                child: self.name.clone(),
+            })?;
 
         self.device = device_lookup(&name);
         if self.device.is_none() {
@@ -960,6 +973,25 @@ impl<'c> NexusChild<'c> {
         self.open(parent_size, ChildSyncState::OutOfSync)
     }
 
+    /// Keep the timeout policy changes made at runtime, so that they are
+    /// applied to the device when it's re-created.
+    pub(crate) fn merge_timeout_policy(&self, policy: &TimeoutPolicy) {
+        self.timeout_policy.lock().merge(policy);
+    }
+
+    /// Get the URI to create the device with, i.e. the URI of the child with
+    /// the timeout policy changes made at runtime.
+    fn device_uri(&self) -> String {
+        let policy = *self.timeout_policy.lock();
+        if policy == TimeoutPolicy::default() {
+            return self.name.clone();
+        }
+        match Url::parse(&self.name) {
+            Ok(url) => policy.apply_to(&url).to_string(),
+            Err(_) => self.name.clone(),
+        }
+    }
+
     /// Extract a UUID from a URI.
     pub(crate) fn uuid(uri: &str) -> Option<String> {
         let url = Url::parse(uri).expect("Failed to parse URI");
@@ -1096,6 +1128,7 @@ impl<'c> NexusChild<'c> {
             faulted_at: parking_lot::Mutex::new(None),
             remove_channel: async_channel::bounded(1),
             io_log: Mutex::new(None),
+            timeout_policy: Mutex::new(TimeoutPolicy::default()),
             _c: Default::default(),
         }
     }
@@ -1229,9 +1262,20 @@ impl<'c> NexusChild<'c> {
     }
 
     /// Stops the I/O log and returns a map of segments to be rebuilt.
-    pub(super) fn stop_io_log(&self) -> Option<RebuildMap> {
+    pub(super) async fn stop_io_log(&self) -> Option<RebuildMap> {
         debug!("{self:?}: stopping I/O log and creating rebuild map");
-        self.io_log.lock().take().map(|log| log.finalize())
+        let io_log = self.io_log.lock().take();
+        match io_log {
+            Some(log) => Some(log.finalize().await),
+            None => None,
+        }
+    }
+
+    /// Takes the I/O log away from the child, without stopping it.
+    /// The channels which still refer to the log may keep logging into it
+    /// until they are reconnected.
+    pub(super) fn take_io_log(&self) -> Option<IOLog> {
+        self.io_log.lock().take()
     }
 
     /// Returns I/O log channel for the current core.
@@ -1243,4 +1287,10 @@ impl<'c> NexusChild<'c> {
     pub(crate) fn has_io_log(&self) -> bool {
         self.io_log.lock().is_some()
     }
+
+    /// Returns the sequence number of the start of the I/O log of this
+    /// child, if any.
+    pub(crate) fn io_log_started_seq(&self) -> Option<u64> {
+        self.io_log.lock().as_ref().map(|log| log.started_seq())
+    }
 }
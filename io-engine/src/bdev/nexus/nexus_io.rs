@@ -34,14 +34,16 @@ mod debug_nexus_io {
     }
 }
 
-#[cfg(feature = "nexus-io-tracing")]
+/// Traces a nexus I/O, if I/O tracing of its nexus has been enabled at
+/// runtime, or for all the nexuses with the `nexus-io-tracing` feature.
 macro_rules! trace_nexus_io {
-    ($($arg:tt)*) => {{ trace!($($arg)*); }}
-}
-
-#[cfg(not(feature = "nexus-io-tracing"))]
-macro_rules! trace_nexus_io {
-    ($($arg:tt)*) => {};
+    ($bio:expr, $($arg:tt)*) => {{
+        if $crate::log_control::nexus_io_tracing(&$bio.nexus().name) {
+            info!($($arg)*);
+        } else if cfg!(feature = "nexus-io-tracing") {
+            trace!($($arg)*);
+        }
+    }};
 }
 
 /// TODO
@@ -157,7 +159,9 @@ impl<'n> NexusBio<'n> {
             ctx.serial = debug_nexus_io::new_serial();
         }
 
-        trace_nexus_io!("New: {bio:?}");
+        bio.channel().io_started();
+
+        trace_nexus_io!(bio, "New: {bio:?}");
 
         bio
     }
@@ -170,7 +174,7 @@ impl<'n> NexusBio<'n> {
             return;
         }
 
-        if let Err(_e) = match self.io_type() {
+        if let Err(e) = match self.io_type() {
             IoType::Read => self.readv(),
             // these IOs are submitted to all the underlying children
             IoType::Write | IoType::WriteZeros | IoType::Reset | IoType::Unmap | IoType::Flush => {
@@ -190,7 +194,7 @@ impl<'n> NexusBio<'n> {
                 })
             }
         } {
-            trace_nexus_io!("Submission error: {self:?}: {_e}");
+            trace_nexus_io!(self, "Submission error: {self:?}: {e}");
         }
     }
 
@@ -244,13 +248,13 @@ impl<'n> NexusBio<'n> {
 
         if self.ctx().in_flight > 0 {
             // More child I/Os to complete, not yet ready to complete nexus I/O.
-            trace_nexus_io!("Inflight: {self:?}");
+            trace_nexus_io!(self, "Inflight: {self:?}");
             return;
         }
 
         if self.ctx().failed == 0 {
             // No child failures, complete nexus I/O with success.
-            trace_nexus_io!("Success: {self:?}");
+            trace_nexus_io!(self, "Success: {self:?}");
             self.ok();
         } else if self.ctx().successful > 0 {
             // Having some child failures, resubmit the I/O.
@@ -274,14 +278,24 @@ impl<'n> NexusBio<'n> {
             IoCompletionStatus::LvolError(LvolFailure::NoSpace) => {
                 self.fail_nvme_status(NvmeStatus::Generic(SPDK_NVME_SC_CAPACITY_EXCEEDED))
             }
-            _ => self.0.fail(),
+            _ => {
+                self.channel().io_completed();
+                self.0.fail()
+            }
         }
     }
 
+    /// Completes the I/O successfully.
+    fn ok(&self) {
+        self.channel().io_completed();
+        self.0.ok();
+    }
+
     /// Completes the I/O with the given `NvmeStatus`.
     #[inline(always)]
     fn fail_nvme_status(&self, status: NvmeStatus) {
         let (sct, sc) = status.as_sct_sc_codes();
+        self.channel().io_completed();
         unsafe {
             spdk_bdev_io_complete_nvme_status(self.as_ptr(), 0, sct, sc);
         }
@@ -303,7 +317,7 @@ impl<'n> NexusBio<'n> {
         ctx.failed = 0;
 
         let bio = self.clone();
-        trace_nexus_io!("New resubmit: {bio:?}");
+        trace_nexus_io!(bio, "New resubmit: {bio:?}");
         bio.submit_request();
     }
 
@@ -471,6 +485,7 @@ impl<'n> NexusBio<'n> {
     #[inline]
     fn submit_write(&self, hdl: &dyn BlockDeviceHandle) -> Result<(), CoreError> {
         trace_nexus_io!(
+            self,
             "Submitting: {self:?} -> {name}",
             name = hdl.get_device().device_name()
         );
@@ -490,6 +505,7 @@ impl<'n> NexusBio<'n> {
     #[inline]
     fn submit_unmap(&self, hdl: &dyn BlockDeviceHandle) -> Result<(), CoreError> {
         trace_nexus_io!(
+            self,
             "Submitting: {self:?} -> {name}",
             name = hdl.get_device().device_name()
         );
@@ -505,6 +521,7 @@ impl<'n> NexusBio<'n> {
     #[inline]
     fn submit_write_zeroes(&self, hdl: &dyn BlockDeviceHandle) -> Result<(), CoreError> {
         trace_nexus_io!(
+            self,
             "Submitting: {self:?} -> {name}",
             name = hdl.get_device().device_name()
         );
@@ -523,6 +540,7 @@ impl<'n> NexusBio<'n> {
     #[inline]
     fn submit_reset(&self, hdl: &dyn BlockDeviceHandle) -> Result<(), CoreError> {
         trace_nexus_io!(
+            self,
             "Submitting: {self:?} -> {name}",
             name = hdl.get_device().device_name()
         );
@@ -533,6 +551,7 @@ impl<'n> NexusBio<'n> {
     #[inline]
     fn submit_flush(&self, hdl: &dyn BlockDeviceHandle) -> Result<(), CoreError> {
         trace_nexus_io!(
+            self,
             "Submitting: {self:?} -> {name}",
             name = hdl.get_device().device_name()
         );
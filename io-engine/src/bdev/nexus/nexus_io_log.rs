@@ -4,13 +4,15 @@ use std::{
     fmt::{Debug, Formatter},
     ops::Deref,
     rc::Rc,
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 use crate::{
-    core::SegmentMap,
+    core::{Reactors, SegmentMap},
     rebuild::{RebuildMap, SEGMENT_SIZE},
 };
 
+use futures::{channel::oneshot, future::join_all};
 use parking_lot::Mutex;
 use spdk_rs::{Cores, IoType};
 
@@ -119,12 +121,24 @@ impl IOLogChannel {
     }
 }
 
+/// Sequence of the starts of the I/O logs and of the nexus snapshots, which
+/// tells which of them came first, unlike the wall clock.
+static IO_LOG_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the next number of the I/O log sequence.
+pub(crate) fn next_io_log_seq() -> u64 {
+    IO_LOG_SEQ.fetch_add(1, Ordering::SeqCst) + 1
+}
+
 /// I/O log.
 pub struct IOLog {
     /// Name of the underlying block device.
     device_name: String,
     /// Per-core log channels.
     channels: Mutex<HashMap<u32, IOLogChannel>>,
+    /// Sequence number of the start of the log, any write made since then is
+    /// logged.
+    started_seq: u64,
 }
 
 impl Debug for IOLog {
@@ -152,9 +166,15 @@ impl IOLog {
         Self {
             device_name: device_name.to_owned(),
             channels: Mutex::new(channels),
+            started_seq: next_io_log_seq(),
         }
     }
 
+    /// Returns the sequence number of the start of the log.
+    pub(crate) fn started_seq(&self) -> u64 {
+        self.started_seq
+    }
+
     /// Returns I/O log channel for the current core.
     pub(crate) fn current_channel(&self) -> IOLogChannel {
         self.channels
@@ -165,12 +185,30 @@ impl IOLog {
     }
 
     /// Consumes an I/O log instance and returns the corresponding rebuild map.
-    pub(crate) fn finalize(self) -> RebuildMap {
-        let segments = self
-            .channels
-            .lock()
-            .values_mut()
-            .map(|x| x.take_segments())
+    /// The segments of each channel are taken on the channel's own core rather
+    /// than accessed from the current one, and merged once all collected.
+    /// The log channels must have been disconnected from the nexus channels
+    /// beforehand.
+    pub(crate) async fn finalize(self) -> RebuildMap {
+        let channels = std::mem::take(&mut *self.channels.lock());
+
+        let segments = channels.into_iter().map(|(core, chan)| {
+            let (sender, receiver) = oneshot::channel();
+            match Reactors::get_by_core(core) {
+                Some(reactor) => reactor.send_future(async move {
+                    sender.send(chan.take_segments()).ok();
+                }),
+                None => {
+                    sender.send(chan.take_segments()).ok();
+                }
+            }
+            receiver
+        });
+
+        let segments = join_all(segments)
+            .await
+            .into_iter()
+            .map(|segs| segs.expect("I/O log channel segments must be sent"))
             .reduce(|acc, e| acc.merge(&e))
             .expect("Should have at least 1 core");
 
@@ -27,6 +27,12 @@ impl PersistentNexusInfo {
     fn inner_mut(&mut self) -> &mut NexusInfo {
         &mut self.inner
     }
+
+    /// Get the key the NexusInfo structure is persisted with, if supplied by
+    /// the control plane.
+    pub(crate) fn key(&self) -> Option<String> {
+        self.key.clone()
+    }
 }
 
 /// Definition of the nexus information that gets saved in the persistent
@@ -73,6 +79,12 @@ pub(crate) enum PersistOp<'a> {
 }
 
 impl<'n> Nexus<'n> {
+    /// Get the key the nexus information is persisted with, if supplied by
+    /// the control plane.
+    pub(crate) async fn nexus_info_key(&self) -> Option<String> {
+        self.nexus_info.lock().await.key()
+    }
+
     /// Persists nexus's information to the store.
     pub(crate) async fn persist(&self, op: PersistOp<'_>) -> Result<(), Error> {
         if !PersistentStore::enabled() {
@@ -0,0 +1,308 @@
+//! Front-end NVMe reservation support for the nexus.
+//!
+//! The reservation commands of the hosts are handled by the nvmf subsystem of
+//! the nexus, which keeps the registration and the reservation of every host.
+//! The children however see the nexus as their only host, so the front-end
+//! state is mirrored onto them as a registration of the nexus with the key of
+//! the reservation holder, or of the first registered host otherwise, and as a
+//! reservation of the type held by the holder.
+//!
+//! The front-end state is received from the custom reservation operations of
+//! the nvmf subsystem, which SPDK calls from every reservation command of the
+//! hosts on a namespace shared with a PTPL file, so this requires the nexus to
+//! be shared with one. Its reservations are kept persisting through power
+//! loss for every command to be reported, see `subsys::nvmf::reservation`.
+//! Each state is then fanned out to all the healthy children, one at a time,
+//! once the command completes, a state which fails on a child being rolled
+//! back on the children it was applied to, and retried. The current state is
+//! replayed onto a child once it is rebuilt.
+//!
+//! The front-end reservations are not mirrored when the nexus fences its
+//! children with reservations of its own.
+
+use std::{collections::HashSet, time::Duration};
+
+use once_cell::sync::Lazy;
+use spdk_rs::{
+    nvme_reservation_acquire_action, nvme_reservation_register_action,
+    nvme_reservation_register_cptpl,
+};
+
+use super::{nexus_lookup, Nexus, NexusChild};
+use crate::{
+    bdev::PtplFileOps,
+    core::{BlockDeviceHandle, CoreError, MayastorEnvironment, Reactors},
+    sleep::mayastor_sleep,
+    subsys::{listen_reservations, unlisten_reservations, PtplState},
+};
+
+/// Reservation Register action which unregisters the current key.
+const REGISTER_ACTION_UNREGISTER: u8 = 1;
+
+/// Reservation Release action which releases the reservation.
+const RELEASE_ACTION_RELEASE: u8 = 0;
+
+/// How long to wait before retrying to mirror the front-end reservations onto
+/// the children.
+const MIRROR_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Whether the nexus may fence its children with reservations of its own.
+static NEXUS_RESV_ENABLED: Lazy<bool> =
+    Lazy::new(|| std::env::var("NEXUS_NVMF_RESV_ENABLE").is_ok());
+
+/// Reservation state of the children, on behalf of the hosts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct ChildReservation {
+    /// Key registered by the nexus, 0 if no host is registered.
+    key: u64,
+    /// Type of the reservation acquired by the nexus, if a host holds one.
+    rtype: Option<u8>,
+}
+
+impl From<&PtplState> for ChildReservation {
+    fn from(state: &PtplState) -> Self {
+        let first_key = state.registrants.first().map_or(0, |r| r.rkey);
+        match state.rtype {
+            0 => Self {
+                key: first_key,
+                rtype: None,
+            },
+            rtype => Self {
+                key: if state.crkey != 0 {
+                    state.crkey
+                } else {
+                    first_key
+                },
+                rtype: Some(rtype as u8),
+            },
+        }
+    }
+}
+
+/// Mirroring of the front-end reservations onto the children of a nexus.
+#[derive(Debug, Default)]
+pub(crate) struct ReservationMirror {
+    /// State applied to the healthy children.
+    applied: ChildReservation,
+    /// Latest state which isn't applied yet.
+    pending: Option<ChildReservation>,
+    /// Children which became healthy, and need the applied state replayed.
+    replay: HashSet<String>,
+    /// Whether the children are being updated.
+    running: bool,
+}
+
+impl<'n> Nexus<'n> {
+    /// Checks whether the front-end reservations are mirrored onto the
+    /// children, i.e. the nexus does not hold reservations on its children
+    /// for its own fencing.
+    fn mirrors_reservations(&self) -> bool {
+        !*NEXUS_RESV_ENABLED || !self.nvme_params.reservations_enabled()
+    }
+
+    /// Starts mirroring the front-end reservations onto the children, before
+    /// the nexus is shared over NVMe-oF, so that the restored reservations
+    /// are mirrored too.
+    pub(super) fn start_reservation_mirror(&self) {
+        if !self.mirrors_reservations() {
+            return;
+        }
+        let Some(path) = self.ptpl().path() else {
+            return;
+        };
+
+        let name = self.name.clone();
+        listen_reservations(
+            path,
+            Box::new(move |state| {
+                let next = ChildReservation::from(state);
+                let name = name.clone();
+                Reactors::master().send_future(async move {
+                    if let Some(nexus) = nexus_lookup(&name) {
+                        nexus.queue_reservation_mirror(Some(next), None);
+                    }
+                });
+            }),
+        );
+    }
+
+    /// Stops mirroring the front-end reservations onto the children.
+    pub(super) fn stop_reservation_mirror(&self) {
+        if let Some(path) = self.ptpl().path() {
+            unlisten_reservations(&path);
+        }
+    }
+
+    /// Replays the front-end reservations onto the child, which has just been
+    /// rebuilt.
+    pub(super) fn reservation_replay(&self, child: &NexusChild<'n>) {
+        if self.mirrors_reservations() {
+            self.queue_reservation_mirror(None, Some(child.uri()));
+        }
+    }
+
+    /// Queues the given state to be applied and the given child to be
+    /// replayed onto, starting the mirroring if not in progress.
+    fn queue_reservation_mirror(&self, next: Option<ChildReservation>, replay: Option<&str>) {
+        let mut mirror = self.resv_mirror.lock();
+        if let Some(next) = next {
+            mirror.pending = Some(next);
+        }
+        if let Some(uri) = replay {
+            mirror.replay.insert(uri.to_string());
+        }
+        if mirror.running {
+            // The mirroring in progress picks up the queued work once done.
+            return;
+        }
+        mirror.running = true;
+        Reactors::master().send_future(mirror_reservations(self.name.clone()));
+    }
+}
+
+/// Mirrors the queued front-end reservation states onto the children of the
+/// nexus, until none is left.
+async fn mirror_reservations(nexus_name: String) {
+    loop {
+        let Some(nexus) = nexus_lookup(&nexus_name) else {
+            return;
+        };
+        let (applied, pending, replay) = {
+            let mut mirror = nexus.resv_mirror.lock();
+            if mirror.pending.is_none() && mirror.replay.is_empty() {
+                mirror.running = false;
+                return;
+            }
+            let replay = std::mem::take(&mut mirror.replay);
+            (mirror.applied, mirror.pending.take(), replay)
+        };
+
+        // Bring the rebuilt children in line with the others first, leaving
+        // those which fail out of the next state until replayed again.
+        let mut failed = HashSet::new();
+        for uri in replay {
+            let Some(child) = nexus.lookup_child(&uri).filter(|c| c.is_healthy()) else {
+                continue;
+            };
+            if let Err(error) = child_apply(child, None, applied).await {
+                warn!("{child:?}: failed to replay front-end reservation {applied:?}: {error}");
+                failed.insert(uri);
+            }
+        }
+
+        let mut retry = !failed.is_empty();
+        if let Some(next) = pending.filter(|next| *next != applied) {
+            let children = nexus
+                .children_iter()
+                .filter(|c| c.is_healthy() && !failed.contains(c.uri()))
+                .map(|c| c.uri().to_string())
+                .collect::<Vec<_>>();
+
+            let mut done = Vec::new();
+            let mut result = Ok(());
+            for uri in children {
+                let Some(child) = nexus.lookup_child(&uri) else {
+                    continue;
+                };
+                result = child_apply(child, Some(applied), next).await;
+                if let Err(error) = &result {
+                    error!("{child:?}: failed to mirror front-end reservation {next:?}: {error}");
+                    break;
+                }
+                done.push(uri);
+            }
+
+            if result.is_ok() {
+                nexus.resv_mirror.lock().applied = next;
+            } else {
+                // Keep the children in line with each other, and retry.
+                for uri in done.into_iter().rev() {
+                    let Some(child) = nexus.lookup_child(&uri) else {
+                        continue;
+                    };
+                    if let Err(error) = child_apply(child, Some(next), applied).await {
+                        error!(
+                            "{child:?}: failed to roll back front-end reservation \
+                            {next:?} to {applied:?}: {error}"
+                        );
+                        failed.insert(uri);
+                    }
+                }
+                nexus.resv_mirror.lock().pending.get_or_insert(next);
+                retry = true;
+            }
+        }
+
+        nexus.resv_mirror.lock().replay.extend(failed);
+        if retry {
+            mayastor_sleep(MIRROR_RETRY_INTERVAL).await.ok();
+        }
+    }
+}
+
+/// Moves the reservation of the child from the given state, or from an
+/// unknown one, to the other.
+/// # Warning: Ignores bdevs without NVMe reservation support.
+async fn child_apply(
+    child: &NexusChild<'_>,
+    from: Option<ChildReservation>,
+    to: ChildReservation,
+) -> Result<(), CoreError> {
+    let hdl = child.get_io_handle_nonblock().await?;
+    match handle_apply(&*hdl, from, to).await {
+        Err(CoreError::NotSupported { .. }) => Ok(()),
+        result => result,
+    }
+}
+
+/// Moves the reservation of the device handle from the given state, or from
+/// an unknown one, to the other.
+async fn handle_apply(
+    hdl: &dyn BlockDeviceHandle,
+    from: Option<ChildReservation>,
+    to: ChildReservation,
+) -> Result<(), CoreError> {
+    let cptpl = match MayastorEnvironment::global_or_default().ptpl_dir() {
+        Some(_) => nvme_reservation_register_cptpl::PERSIST_POWER_LOSS,
+        None => nvme_reservation_register_cptpl::CLEAR_POWER_ON,
+    };
+
+    if to.key == 0 {
+        // Unregistering releases the reservation too.
+        return match from {
+            Some(from) if from.key != 0 => {
+                hdl.nvme_resv_register(from.key, 0, REGISTER_ACTION_UNREGISTER, cptpl)
+                    .await
+            }
+            _ => Ok(()),
+        };
+    }
+
+    // Replacing the key keeps the reservation.
+    let held = match from {
+        Some(from) if from.key == to.key => from.rtype,
+        _ => {
+            hdl.nvme_resv_register(
+                0,
+                to.key,
+                nvme_reservation_register_action::REPLACE_KEY,
+                cptpl,
+            )
+            .await?;
+            from.and_then(|from| from.rtype)
+        }
+    };
+
+    if held == to.rtype {
+        return Ok(());
+    }
+    if let Some(rtype) = held {
+        hdl.nvme_resv_release(to.key, rtype, RELEASE_ACTION_RELEASE)
+            .await?;
+    }
+    if let Some(rtype) = to.rtype {
+        hdl.nvme_resv_acquire(to.key, 0, nvme_reservation_acquire_action::ACQUIRE, rtype)
+            .await?;
+    }
+    Ok(())
+}
@@ -1,11 +1,23 @@
 use crate::bdev::PtplFileOps;
 use async_trait::async_trait;
 use snafu::ResultExt;
-use std::pin::Pin;
+use std::{pin::Pin, time::Duration};
 
-use super::{nexus_err, Error, NbdDisk, Nexus, NexusTarget};
+use super::{nexus_err, Error, NbdDisk, Nexus, NexusTarget, UblkDisk, VhostBlk};
 
-use crate::core::{NvmfShareProps, Protocol, PtplProps, Share, UpdateProps};
+use crate::{
+    core::{MayastorEnvironment, NvmfShareProps, Protocol, PtplProps, Share, UpdateProps},
+    persistent_store::PersistentStore,
+    store::store_defs::StoreError,
+    subsys::{
+        flush_sync_reservations, restore_reservations, stop_sync_reservations, sync_reservations,
+        PtplState,
+    },
+};
+
+/// How long an unshared nexus waits for its PTPL state to be saved to the
+/// persistent store.
+const PTPL_FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
 
 ///
 /// The sharing of the nexus is different compared to regular bdevs
@@ -36,7 +48,7 @@ impl Share for Nexus<'_> {
         props: Option<NvmfShareProps>,
     ) -> Result<Self::Output, Self::Error> {
         let uri = match self.shared() {
-            Some(Protocol::Off) | None => {
+            Some(Protocol::Off | Protocol::Ublk | Protocol::Vhost) | None => {
                 info!("{:?}: sharing NVMF target...", self);
 
                 let name = self.name.clone();
@@ -116,6 +128,8 @@ impl From<&NexusTarget> for Protocol {
     fn from(target: &NexusTarget) -> Protocol {
         match target {
             NexusTarget::NexusNvmfTarget => Protocol::Nvmf,
+            NexusTarget::UblkDisk(_) => Protocol::Ublk,
+            NexusTarget::VhostBlk(_) => Protocol::Vhost,
             _ => Protocol::Off,
         }
     }
@@ -175,7 +189,40 @@ impl<'n> Nexus<'n> {
                 }
                 Ok(uri)
             }
+            Protocol::Ublk => {
+                let disk =
+                    UblkDisk::create(&self.name)
+                        .await
+                        .context(nexus_err::ShareUblkNexus {
+                            name: self.name.clone(),
+                        })?;
+                let uri = disk.as_uri();
+                unsafe {
+                    self.as_mut().get_unchecked_mut().nexus_target =
+                        Some(NexusTarget::UblkDisk(disk));
+                }
+                Ok(uri)
+            }
+            Protocol::Vhost => {
+                let vhost = VhostBlk::create(&self.name).context(nexus_err::ShareVhostNexus {
+                    name: self.name.clone(),
+                })?;
+                let uri = vhost.as_uri();
+                unsafe {
+                    self.as_mut().get_unchecked_mut().nexus_target =
+                        Some(NexusTarget::VhostBlk(vhost));
+                }
+                Ok(uri)
+            }
             Protocol::Nvmf => {
+                // Restore the host reservations saved on another node before
+                // the subsystem starts accepting IO, save their changes and
+                // mirror them onto the children.
+                let ptpl = NexusPtpl::from(&*self);
+                ptpl.restore().await;
+                ptpl.start_sync();
+                self.start_reservation_mirror();
+
                 let props = NvmfShareProps::new()
                     .with_range(Some((
                         self.nvme_params.min_cntlid,
@@ -202,8 +249,37 @@ impl<'n> Nexus<'n> {
                 info!("{:?}: destroying NBD device target...", self);
                 disk.destroy();
             }
+            Some(NexusTarget::UblkDisk(disk)) => {
+                info!("{:?}: destroying ublk device target...", self);
+                if let Err(source) = disk.destroy().await {
+                    // The device is still in use, so keep tracking it.
+                    let name = self.name.clone();
+                    unsafe {
+                        self.as_mut().get_unchecked_mut().nexus_target =
+                            Some(NexusTarget::UblkDisk(disk));
+                    }
+                    return Err(Error::UnshareUblkNexus { source, name });
+                }
+            }
+            Some(NexusTarget::VhostBlk(vhost)) => {
+                info!("{:?}: removing vhost-user-blk target...", self);
+                if let Err(source) = vhost.destroy() {
+                    // The controller is still in use, so keep tracking it.
+                    let name = self.name.clone();
+                    unsafe {
+                        self.as_mut().get_unchecked_mut().nexus_target =
+                            Some(NexusTarget::VhostBlk(vhost));
+                    }
+                    return Err(Error::UnshareVhostNexus { source, name });
+                }
+            }
             Some(NexusTarget::NexusNvmfTarget) => {
                 info!("{:?}: unsharing NVMF target...", self);
+                self.stop_reservation_mirror();
+                self.as_mut().unshare().await?;
+                // The nexus may be re-created on another node next.
+                NexusPtpl::from(&*self).flush().await;
+                return Ok(());
             }
             None => {
                 // Try unshare nexus bdev anyway, just in case it was shared
@@ -218,6 +294,8 @@ impl<'n> Nexus<'n> {
     pub fn get_share_uri(&self) -> Option<String> {
         match self.nexus_target {
             Some(NexusTarget::NbdDisk(ref disk)) => Some(disk.as_uri()),
+            Some(NexusTarget::UblkDisk(ref disk)) => Some(disk.as_uri()),
+            Some(NexusTarget::VhostBlk(ref vhost)) => Some(vhost.as_uri()),
             Some(NexusTarget::NexusNvmfTarget) => self.share_uri(),
             None => None,
         }
@@ -241,6 +319,92 @@ impl NexusPtpl {
     fn uuid(&self) -> &uuid::Uuid {
         &self.uuid
     }
+    /// Key of the PTPL state in the persistent store.
+    fn store_key(&self) -> String {
+        format!("ptpl/nexus/{}", self.uuid())
+    }
+    /// Check if the PTPL state is kept in the persistent store.
+    fn store_enabled() -> bool {
+        PersistentStore::enabled() && MayastorEnvironment::global_or_default().ptpl_store()
+    }
+
+    /// Restore the PTPL state saved in the persistent store, if any, when
+    /// the nexus is next shared over NVMe-oF.
+    pub(crate) async fn restore(&self) {
+        if !Self::store_enabled() {
+            return;
+        }
+        let Some(path) = self.path() else {
+            return;
+        };
+
+        let state = match PersistentStore::get(&self.store_key()).await {
+            Ok(value) => match serde_json::from_value::<PtplState>(value) {
+                Ok(state) => Some(state),
+                Err(error) => {
+                    warn!(
+                        "Ignoring invalid PTPL state of nexus {} in the persistent store: {error}",
+                        self.uuid()
+                    );
+                    None
+                }
+            },
+            Err(StoreError::MissingEntry { .. }) => None,
+            Err(error) => {
+                // Fall back to the PTPL file, but don't leave a stale state
+                // to be restored.
+                warn!(
+                    "Failed to get PTPL state of nexus {} from the persistent store: {error}",
+                    self.uuid()
+                );
+                None
+            }
+        };
+        restore_reservations(path, state);
+    }
+
+    /// Save the PTPL state to the persistent store on every reservation
+    /// change of the nexus.
+    pub(crate) fn start_sync(&self) {
+        if !Self::store_enabled() {
+            return;
+        }
+        if let Some(path) = self.path() {
+            sync_reservations(path, self.store_key());
+        }
+    }
+
+    /// Wait for the reservation changes to be saved to the persistent store.
+    pub(crate) async fn flush(&self) {
+        if !Self::store_enabled() {
+            return;
+        }
+        let Some(path) = self.path() else {
+            return;
+        };
+        if !flush_sync_reservations(&path, PTPL_FLUSH_TIMEOUT).await {
+            warn!(
+                "The PTPL state of nexus {} is not saved to the persistent store yet",
+                self.uuid()
+            );
+        }
+    }
+
+    /// Remove the PTPL state from the persistent store.
+    pub(crate) async fn forget(&self) {
+        if !Self::store_enabled() {
+            return;
+        }
+        if let Some(path) = self.path() {
+            stop_sync_reservations(&path).await;
+        }
+        if let Err(error) = PersistentStore::delete(&self.store_key()).await {
+            warn!(
+                "Failed to delete PTPL state of nexus {} from the persistent store: {error}",
+                self.uuid()
+            );
+        }
+    }
 }
 impl<'n> From<&Nexus<'n>> for NexusPtpl {
     fn from(n: &Nexus<'n>) -> Self {
@@ -0,0 +1,173 @@
+//! Utility functions and wrappers for working with ublk devices in SPDK.
+//!
+//! SPDK only exposes its ublk target through json-rpc methods, so they're
+//! called through the json-rpc socket of this process, from the runtime.
+
+use futures::{channel::oneshot, lock::Mutex as AsyncMutex};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use snafu::{ResultExt, Snafu};
+use std::{collections::HashSet, fmt, path::Path};
+
+use crate::core::{runtime, Cores, MayastorEnvironment};
+
+/// Maximum number of ublk devices probed for a free device id.
+const UBLK_MAX_DEVICES: u32 = 1024;
+
+/// Depth of each ublk queue.
+const UBLK_QUEUE_DEPTH: u32 = 128;
+
+/// Set once the SPDK ublk target has been created, and held while creating
+/// it, so that concurrent shares don't start disks before it exists.
+static UBLK_TARGET: Lazy<AsyncMutex<bool>> = Lazy::new(|| AsyncMutex::new(false));
+
+/// Ids of the ublk devices which are being started or are in use by this
+/// process, as their device nodes only appear once they're started.
+static UBLK_IDS: Mutex<Option<HashSet<u32>>> = Mutex::new(None);
+
+#[derive(Debug, Snafu)]
+#[snafu(context(suffix(false)))]
+pub enum UblkError {
+    #[snafu(display("Failed to create ublk target (is ublk_drv kmod loaded?): {}", source))]
+    CreateTarget { source: jsonrpc::error::Error },
+    #[snafu(display("No free ublk device ids available"))]
+    UblkUnavailable {},
+    #[snafu(display("Failed to start ublk device {}: {}", id, source))]
+    StartUblk {
+        source: jsonrpc::error::Error,
+        id: u32,
+    },
+    #[snafu(display("Failed to stop ublk device {}: {}", id, source))]
+    StopUblk {
+        source: jsonrpc::error::Error,
+        id: u32,
+    },
+}
+
+/// Calls an SPDK ublk json-rpc method, and waits for its reply. The call is
+/// made from the runtime, while the reactors keep serving the socket.
+async fn rpc_call(
+    method: &'static str,
+    params: serde_json::Value,
+) -> Result<(), jsonrpc::error::Error> {
+    let (sender, receiver) = oneshot::channel();
+    let rpc_addr = MayastorEnvironment::global_or_default().rpc_addr;
+    runtime::spawn(async move {
+        let result = jsonrpc::call::<_, serde_json::Value>(&rpc_addr, method, Some(params))
+            .await
+            .map(|_| ());
+        sender.send(result).ok();
+    });
+    receiver.await.expect("ublk json-rpc sender is gone")
+}
+
+/// Creates the SPDK ublk target, unless it already exists. The target uses
+/// the application core mask, so that ublk queues are served by all reactors.
+async fn ensure_target() -> Result<(), UblkError> {
+    let mut created = UBLK_TARGET.lock().await;
+    if *created {
+        return Ok(());
+    }
+
+    rpc_call("ublk_create_target", serde_json::json!({}))
+        .await
+        .context(CreateTarget)?;
+
+    info!("Created ublk target");
+    *created = true;
+    Ok(())
+}
+
+/// Reserve the first unused ublk device id, until it's released.
+pub fn find_unused() -> Result<u32, UblkError> {
+    let mut ids = UBLK_IDS.lock();
+    let ids = ids.get_or_insert_with(HashSet::new);
+    let id = (0..UBLK_MAX_DEVICES)
+        .find(|id| {
+            !ids.contains(id)
+                && !Path::new(&format!("/dev/ublkc{id}")).exists()
+                && !Path::new(&format!("/dev/ublkb{id}")).exists()
+        })
+        .ok_or(UblkError::UblkUnavailable {})?;
+    ids.insert(id);
+    Ok(id)
+}
+
+/// Release a ublk device id reserved by `find_unused`.
+fn release(id: u32) {
+    if let Some(ids) = UBLK_IDS.lock().as_mut() {
+        ids.remove(&id);
+    }
+}
+
+/// ublk disk representation.
+pub struct UblkDisk {
+    id: u32,
+}
+
+impl UblkDisk {
+    /// Allocate a ublk device for the bdev and start it, with one queue per
+    /// reactor. When the function returns the ublk disk is ready for IO.
+    pub async fn create(bdev_name: &str) -> Result<Self, UblkError> {
+        ensure_target().await?;
+
+        let id = find_unused()?;
+        let num_queues = Cores::count().into_iter().count() as u32;
+
+        let params = serde_json::json!({
+            "bdev_name": bdev_name,
+            "ublk_id": id,
+            "num_queues": num_queues,
+            "queue_depth": UBLK_QUEUE_DEPTH,
+        });
+        if let Err(source) = rpc_call("ublk_start_disk", params).await {
+            release(id);
+            return Err(UblkError::StartUblk { source, id });
+        }
+
+        let disk = Self { id };
+        info!(
+            "Started ublk disk {} for {} with {} queues",
+            disk, bdev_name, num_queues
+        );
+
+        Ok(disk)
+    }
+
+    /// Stop and release ublk device. The device is still in use if it fails
+    /// to stop.
+    pub async fn destroy(&self) -> Result<(), UblkError> {
+        let id = self.id;
+        debug!("Stopping ublk device {}...", self);
+
+        rpc_call("ublk_stop_disk", serde_json::json!({ "ublk_id": id }))
+            .await
+            .context(StopUblk { id })?;
+        release(id);
+
+        info!("ublk {} device stopped", self);
+        Ok(())
+    }
+
+    /// Get ublk block device path (/dev/ublkb...) for the ublk disk.
+    pub fn get_path(&self) -> String {
+        format!("/dev/ublkb{}", self.id)
+    }
+
+    /// Get ublk device path uri (file:///dev/ublkb...) for the ublk disk.
+    pub fn as_uri(&self) -> String {
+        format!("file://{}", self.get_path())
+    }
+}
+
+impl fmt::Debug for UblkDisk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}#{}", self.get_path(), self.id)
+    }
+}
+
+impl fmt::Display for UblkDisk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.get_path())
+    }
+}
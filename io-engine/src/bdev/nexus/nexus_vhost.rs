@@ -0,0 +1,142 @@
+//! Utility functions and wrappers for working with vhost-user-blk controllers
+//! in SPDK.
+
+use nix::errno::Errno;
+use snafu::Snafu;
+use spdk_rs::libspdk::{
+    spdk_vhost_blk_construct, spdk_vhost_dev_find, spdk_vhost_dev_remove, spdk_vhost_lock,
+    spdk_vhost_set_socket_path, spdk_vhost_unlock,
+};
+use std::{
+    ffi::CString,
+    fmt,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::core::MayastorEnvironment;
+
+/// Set once the SPDK vhost socket directory has been configured.
+static VHOST_SOCKET_DIR: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Snafu)]
+#[snafu(context(suffix(false)))]
+pub enum VhostError {
+    #[snafu(display("Failed to set vhost socket directory {}", dir))]
+    SocketDir { source: Errno, dir: String },
+    #[snafu(display("Failed to create vhost-user-blk controller {}", name))]
+    CreateVhost { source: Errno, name: String },
+    #[snafu(display("vhost-user-blk controller {} not found", name))]
+    VhostNotFound { name: String },
+    #[snafu(display("Failed to remove vhost-user-blk controller {}", name))]
+    RemoveVhost { source: Errno, name: String },
+}
+
+/// Points SPDK at the configured vhost socket directory, unless done already.
+fn ensure_socket_dir() -> Result<PathBuf, VhostError> {
+    let dir = MayastorEnvironment::global_or_default().vhost_socket_dir();
+
+    if !VHOST_SOCKET_DIR.load(Ordering::SeqCst) {
+        // SPDK concatenates the basename with the controller name.
+        let basename = CString::new(format!("{}/", dir.display())).unwrap();
+        let rc = unsafe { spdk_vhost_set_socket_path(basename.as_ptr()) };
+        if rc != 0 {
+            return Err(VhostError::SocketDir {
+                source: Errno::from_raw(rc.abs()),
+                dir: dir.display().to_string(),
+            });
+        }
+        VHOST_SOCKET_DIR.store(true, Ordering::SeqCst);
+    }
+
+    Ok(dir)
+}
+
+/// vhost-user-blk controller representation.
+pub struct VhostBlk {
+    name: String,
+    socket: PathBuf,
+}
+
+impl VhostBlk {
+    /// Create a vhost-user-blk controller for the bdev. The controller is
+    /// named after the bdev and listens on a UNIX socket of the same name in
+    /// the vhost socket directory.
+    pub fn create(bdev_name: &str) -> Result<Self, VhostError> {
+        let dir = ensure_socket_dir()?;
+
+        let c_name = CString::new(bdev_name).unwrap();
+        let rc = unsafe {
+            spdk_vhost_blk_construct(
+                c_name.as_ptr(),
+                std::ptr::null(),
+                c_name.as_ptr(),
+                std::ptr::null(),
+                std::ptr::null(),
+            )
+        };
+        if rc != 0 {
+            return Err(VhostError::CreateVhost {
+                source: Errno::from_raw(rc.abs()),
+                name: bdev_name.to_owned(),
+            });
+        }
+
+        let vhost = Self {
+            name: bdev_name.to_owned(),
+            socket: dir.join(bdev_name),
+        };
+        info!("Started vhost-user-blk controller {}", vhost);
+
+        Ok(vhost)
+    }
+
+    /// Remove the vhost-user-blk controller. This fails while a VM is still
+    /// connected to the socket.
+    pub fn destroy(&self) -> Result<(), VhostError> {
+        let c_name = CString::new(self.name.as_str()).unwrap();
+
+        let rc = unsafe {
+            spdk_vhost_lock();
+            let vdev = spdk_vhost_dev_find(c_name.as_ptr());
+            let rc = if vdev.is_null() {
+                None
+            } else {
+                Some(spdk_vhost_dev_remove(vdev))
+            };
+            spdk_vhost_unlock();
+            rc
+        };
+
+        match rc {
+            None => Err(VhostError::VhostNotFound {
+                name: self.name.clone(),
+            }),
+            Some(0) => {
+                info!("vhost-user-blk controller {} removed", self);
+                Ok(())
+            }
+            Some(rc) => Err(VhostError::RemoveVhost {
+                source: Errno::from_raw(rc.abs()),
+                name: self.name.clone(),
+            }),
+        }
+    }
+
+    /// Get the vhost-user socket uri (vhost-user:///...) for the controller.
+    pub fn as_uri(&self) -> String {
+        format!("vhost-user://{}", self.socket.display())
+    }
+}
+
+impl fmt::Debug for VhostBlk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.name, self.socket.display())
+    }
+}
+
+impl fmt::Display for VhostBlk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.socket.display())
+    }
+}
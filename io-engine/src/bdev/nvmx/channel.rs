@@ -2,6 +2,9 @@
 
 use std::{mem::size_of, os::raw::c_void, ptr::NonNull, time::Duration};
 
+#[cfg(feature = "fault-injection")]
+use std::time::Instant;
+
 use spdk_rs::{
     libspdk::{
         nvme_qpair_abort_all_queued_reqs, nvme_transport_qpair_abort_reqs, spdk_io_channel,
@@ -16,6 +19,18 @@ use crate::{
     core::{BlockDevice, BlockDeviceIoStats, IoType},
 };
 
+#[cfg(feature = "fault-injection")]
+use spdk_rs::{
+    libspdk::{SPDK_NVME_SC_ABORTED_BY_REQUEST, SPDK_NVME_SC_ABORTED_SQ_DELETION},
+    NvmeStatus,
+};
+
+#[cfg(feature = "fault-injection")]
+use crate::core::IoCompletionStatus;
+
+#[cfg(feature = "fault-injection")]
+use super::controller_inner::TimeoutConfig;
+
 use super::{
     nvme_bdev_running_config, NvmeControllerState, PollGroup, QPair, SpdkNvmeController,
     NVME_CONTROLLERS,
@@ -80,6 +95,28 @@ pub struct NvmeIoChannelInner<'a> {
     // shutdown (if case reset is initiated before shutdown), and
     // not to reinitialize channels already processed by shutdown logic.
     is_shutdown: bool,
+
+    /// I/Os whose completion is held back by a delay or drop fault injection.
+    /// They stay accounted as pending I/Os till completed.
+    #[cfg(feature = "fault-injection")]
+    held_ios: Vec<HeldIo>,
+}
+
+/// An I/O whose completion is held back by a delay or drop fault injection.
+#[cfg(feature = "fault-injection")]
+struct HeldIo {
+    /// Context of the I/O, passed to `complete`.
+    ctx: *mut c_void,
+    /// Completes the I/O with the given status.
+    complete: fn(*mut c_void, IoCompletionStatus),
+    /// Completion status of the I/O.
+    status: IoCompletionStatus,
+    /// When a delayed I/O is completed, `None` for a dropped I/O.
+    release_at: Option<Instant>,
+    /// When the I/O was held.
+    held_at: Instant,
+    /// Whether the timeout of a dropped I/O has been handled.
+    timed_out: bool,
 }
 
 impl NvmeIoChannelInner<'_> {
@@ -108,6 +145,10 @@ impl NvmeIoChannelInner<'_> {
 
     /// Reset channel, making it unusable till reinitialize() is called.
     pub fn reset(&mut self) -> i32 {
+        // The held I/Os are completed along with the ones of the qpair.
+        #[cfg(feature = "fault-injection")]
+        self.release_all_held_ios();
+
         // Remove qpair and trigger its deallocation via drop().
         match self.remove_qpair() {
             Some(qpair) => {
@@ -163,6 +204,110 @@ impl NvmeIoChannelInner<'_> {
         }
     }
 
+    /// Holds back the completion of the given I/O, on behalf of a fault
+    /// injection: a delayed I/O is completed with the given status after the
+    /// given delay, a dropped I/O, without a delay, is left to time out as
+    /// if the controller never completed it.
+    #[cfg(feature = "fault-injection")]
+    pub(crate) fn hold_io(
+        &mut self,
+        ctx: *mut c_void,
+        complete: fn(*mut c_void, IoCompletionStatus),
+        status: IoCompletionStatus,
+        delay: Option<Duration>,
+    ) {
+        let now = Instant::now();
+        self.held_ios.push(HeldIo {
+            ctx,
+            complete,
+            status,
+            release_at: delay.map(|d| now + d),
+            held_at: now,
+            timed_out: false,
+        });
+    }
+
+    /// Completes the held I/Os which are due, and handles the timeout of the
+    /// dropped ones the way the controller handles a timed out command.
+    /// Returns the number of completed I/Os.
+    #[cfg(feature = "fault-injection")]
+    fn release_held_ios(&mut self) -> usize {
+        if self.held_ios.is_empty() {
+            return 0;
+        }
+
+        // The timeout of a dropped I/O is handled once, as the controller
+        // does.
+        let now = Instant::now();
+        let mut abort = false;
+        if self
+            .held_ios
+            .iter()
+            .any(|io| io.release_at.is_none() && !io.timed_out)
+        {
+            if let Some((timeout, timeout_cfg)) = self.io_timeout() {
+                let mut timed_out = false;
+                for io in self.held_ios.iter_mut() {
+                    if io.release_at.is_none() && !io.timed_out && io.held_at + timeout <= now {
+                        io.timed_out = true;
+                        timed_out = true;
+                    }
+                }
+                abort =
+                    timed_out && TimeoutConfig::from_ptr(timeout_cfg.as_ptr()).dropped_io_timeout();
+            }
+        }
+
+        // Complete the I/Os outside of the list, as completions may issue new
+        // I/Os.
+        let (due, held): (Vec<_>, Vec<_>) = std::mem::take(&mut self.held_ios)
+            .into_iter()
+            .partition(|io| match io.release_at {
+                Some(release_at) => release_at <= now,
+                None => abort && io.timed_out,
+            });
+        self.held_ios = held;
+
+        let count = due.len();
+        for io in due {
+            let status = match io.release_at {
+                Some(_) => io.status,
+                None => IoCompletionStatus::NvmeError(NvmeStatus::Generic(
+                    SPDK_NVME_SC_ABORTED_BY_REQUEST,
+                )),
+            };
+            (io.complete)(io.ctx, status);
+        }
+        count
+    }
+
+    /// Completes all the held I/Os: the delayed ones with their status, the
+    /// dropped ones as aborted due to the deletion of their queue.
+    #[cfg(feature = "fault-injection")]
+    fn release_all_held_ios(&mut self) {
+        for io in std::mem::take(&mut self.held_ios) {
+            let status = match io.release_at {
+                Some(_) => io.status,
+                None => IoCompletionStatus::NvmeError(NvmeStatus::Generic(
+                    SPDK_NVME_SC_ABORTED_SQ_DELETION,
+                )),
+            };
+            (io.complete)(io.ctx, status);
+        }
+    }
+
+    /// Returns the I/O timeout of the controller, if enabled, and its timeout
+    /// configuration. Skipped while the controller is busy, to not block the
+    /// poller.
+    #[cfg(feature = "fault-injection")]
+    fn io_timeout(&self) -> Option<(Duration, NonNull<TimeoutConfig>)> {
+        let ctrl = self.ctrl.as_ref()?.try_lock()?;
+        match ctrl.timeouts().io_timeout_us {
+            0 => None,
+            us => Some((Duration::from_micros(us), ctrl.timeout_config)),
+        }
+    }
+
     /// Reinitialize channel after reset unless the channel is shutdown.
     pub fn reinitialize(&mut self, ctrlr_name: &str, ctrlr_handle: SpdkNvmeController) -> i32 {
         if self.is_shutdown {
@@ -301,6 +446,9 @@ extern "C" fn nvme_poll(ctx: *mut c_void) -> i32 {
         )
     };
 
+    #[cfg(feature = "fault-injection")]
+    let num_completions = num_completions + inner.release_held_ios() as i64;
+
     if num_completions > 0 {
         1
     } else {
@@ -407,6 +555,8 @@ impl NvmeControllerIoChannel {
             device,
             ctrl: Some(carc),
             num_pending_ios: 0,
+            #[cfg(feature = "fault-injection")]
+            held_ios: Vec::new(),
         });
 
         nvme_channel.inner = Box::into_raw(inner);
@@ -426,6 +576,9 @@ impl NvmeControllerIoChannel {
             let ch = NvmeIoChannel::from_raw(ctx);
             let mut inner = unsafe { Box::from_raw(ch.inner) };
 
+            #[cfg(feature = "fault-injection")]
+            inner.release_all_held_ios();
+
             let qpair = inner.remove_qpair();
 
             // Stop the poller and do extra handling for I/O qpair, as it needs
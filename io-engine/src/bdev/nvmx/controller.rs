@@ -32,6 +32,7 @@ use crate::{
         controller_inner::{SpdkNvmeController, TimeoutConfig},
         controller_state::{ControllerFailureReason, ControllerFlag, ControllerStateMachine},
         nvme_bdev_running_config,
+        timeout_policy::TimeoutPolicy,
         uri::NvmeControllerContext,
         utils::{nvme_cpl_succeeded, NvmeAerInfoNotice, NvmeAerInfoNvmCommandSet, NvmeAerType},
         NvmeControllerState,
@@ -120,6 +121,12 @@ pub struct NvmeController<'a> {
     pub(crate) name: String,
     id: u64,
     prchk_flags: u32,
+    /// Timeout settings which override the NVMe bdev options.
+    pub(crate) timeout_policy: TimeoutPolicy,
+    /// Whether the timeout handler is registered with the SPDK controller.
+    pub(crate) timeout_handler_registered: bool,
+    /// Transport retry count the queue pairs were connected with.
+    pub(crate) qpair_retry_count: u8,
     inner: Option<NvmeControllerInner<'a>>,
     state_machine: ControllerStateMachine,
     event_dispatcher: DeviceEventDispatcher,
@@ -134,6 +141,7 @@ impl fmt::Debug for NvmeController<'_> {
         f.debug_struct("NvmeController")
             .field("name", &self.name)
             .field("prchk_flags", &self.prchk_flags)
+            .field("timeout_policy", &self.timeout_policy)
             .field("state_machine", &self.state_machine)
             .finish()
     }
@@ -144,11 +152,16 @@ unsafe impl Sync for NvmeController<'_> {}
 
 impl NvmeController<'_> {
     /// Creates a new NVMe controller with the given name.
-    pub fn new(name: &str, prchk_flags: u32) -> Option<Self> {
+    pub fn new(name: &str, prchk_flags: u32, timeout_policy: TimeoutPolicy) -> Option<Self> {
         let l = NvmeController {
             name: String::from(name),
             id: 0,
             prchk_flags,
+            timeout_policy,
+            timeout_handler_registered: false,
+            qpair_retry_count: timeout_policy
+                .resolve(&nvme_bdev_running_config())
+                .retry_count,
             state_machine: ControllerStateMachine::new(name),
             inner: None,
             event_dispatcher: DeviceEventDispatcher::new(),
@@ -531,6 +544,10 @@ impl NvmeController<'_> {
                 let _ = controller
                     .state_machine
                     .transition_checked(Running, Faulted(ControllerFailureReason::Reset));
+            } else {
+                // The queue pairs have been connected again, with the current
+                // transport retry count.
+                controller.qpair_retry_count = reset_ctx.spdk_handle.transport_retry_count();
             }
 
             // Unlock the controller before calling the callback to avoid
@@ -18,7 +18,8 @@ use spdk_rs::libspdk::{
 
 use crate::{
     bdev::nvmx::{
-        nvme_bdev_running_config, utils::nvme_cpl_succeeded, NvmeController, NVME_CONTROLLERS,
+        nvme_bdev_running_config, timeout_policy::EffectiveTimeouts, utils::nvme_cpl_succeeded,
+        NvmeController, NVME_CONTROLLERS,
     },
     core::{CoreError, DeviceIoController, DeviceTimeoutAction},
 };
@@ -233,6 +234,33 @@ impl TimeoutConfig {
         );
     }
 
+    /// Handles the timeout of an I/O dropped by a fault injection, which the
+    /// controller never sees, the way `io_timeout_handler` handles a timed
+    /// out command. Returns true if the I/O is to be aborted, otherwise it
+    /// is completed once the channel is reset.
+    #[cfg(feature = "fault-injection")]
+    pub(crate) fn dropped_io_timeout(&mut self) -> bool {
+        let timeout_action = self.timeout_action.load();
+
+        error!(
+            "{}: detected timeout of a dropped I/O, action={:?}",
+            self.name, timeout_action
+        );
+
+        match timeout_action {
+            DeviceTimeoutAction::Abort => true,
+            DeviceTimeoutAction::Reset => {
+                self.reset_controller();
+                false
+            }
+            DeviceTimeoutAction::Ignore => false,
+            DeviceTimeoutAction::HotRemove => {
+                self.hot_remove();
+                false
+            }
+        }
+    }
+
     /// Set new I/O timeout action.
     pub fn set_timeout_action(&mut self, action: DeviceTimeoutAction) {
         self.timeout_action.store(action);
@@ -304,6 +332,17 @@ impl SpdkNvmeController {
     pub fn ext_host_id(&self) -> &[u8; 16] {
         unsafe { &(*self.as_ptr()).opts.extended_host_id }
     }
+
+    /// Returns the transport retry count.
+    pub fn transport_retry_count(&self) -> u8 {
+        unsafe { (*self.as_ptr()).opts.transport_retry_count }
+    }
+
+    /// Sets the transport retry count, which applies to the queue pairs
+    /// connected from then on, e.g. once the controller is reset.
+    pub fn set_transport_retry_count(&self, count: u8) {
+        unsafe { (*self.as_ptr()).opts.transport_retry_count = count };
+    }
 }
 
 impl From<*mut spdk_nvme_ctrlr> for SpdkNvmeController {
@@ -424,44 +463,59 @@ impl NvmeController<'_> {
         }
     }
 
+    /// Get the timeout settings in effect.
+    pub fn timeouts(&self) -> EffectiveTimeouts {
+        let mut timeouts = self.timeout_policy.resolve(&nvme_bdev_running_config());
+        timeouts.handler_registered = self.timeout_handler_registered;
+        timeouts.retry_count = self.qpair_retry_count;
+        timeouts.pending_retry_count = self
+            .controller()
+            .map(|ctrlr| ctrlr.transport_retry_count())
+            .filter(|count| *count != self.qpair_retry_count);
+        timeouts
+    }
+
     pub(crate) fn configure_timeout(&mut self) {
-        let device_defaults = nvme_bdev_running_config();
+        let timeouts = self.timeout_policy.resolve(&nvme_bdev_running_config());
 
-        if device_defaults.timeout_us == 0 {
+        if timeouts.io_timeout_us == 0 {
             warn!(
                 "{} no timeout configured for NVMe controller, I/O timeout handling disabled.",
                 self.name
             );
             self.set_timeout_action(DeviceTimeoutAction::Ignore)
                 .unwrap();
+            // Unregister the handler, in case a timeout was configured before.
+            if self.timeout_handler_registered {
+                unsafe {
+                    spdk_nvme_ctrlr_register_timeout_callback(
+                        self.ctrlr_as_ptr(),
+                        0,
+                        0,
+                        None,
+                        std::ptr::null_mut(),
+                    );
+                }
+                self.timeout_handler_registered = false;
+            }
             return;
         }
 
-        let action = match DeviceTimeoutAction::try_from(device_defaults.action_on_timeout) {
-            Ok(action) => action,
-            Err(e) => {
-                error!(
-                    "{}: can not apply requested I/O timeout action: {}, falling back to Ignore",
-                    self.name, e
-                );
-                DeviceTimeoutAction::Ignore
-            }
-        };
-
-        self.set_timeout_action(action).unwrap();
+        self.set_timeout_action(timeouts.action).unwrap();
 
         unsafe {
             spdk_nvme_ctrlr_register_timeout_callback(
                 self.ctrlr_as_ptr(),
-                device_defaults.timeout_us,
-                device_defaults.timeout_admin_us,
+                timeouts.io_timeout_us,
+                timeouts.admin_timeout_us,
                 Some(NvmeController::io_timeout_handler),
                 self.timeout_config.as_ptr().cast(),
             );
         }
+        self.timeout_handler_registered = true;
         info!(
-            "{} I/O timeout set to {} us",
-            self.name, device_defaults.timeout_us
+            "{} I/O timeout set to {} us, admin timeout to {} us, action {}",
+            self.name, timeouts.io_timeout_us, timeouts.admin_timeout_us, timeouts.action
         );
     }
 }
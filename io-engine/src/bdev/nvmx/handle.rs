@@ -42,7 +42,7 @@ use crate::{
 
 #[cfg(feature = "fault-injection")]
 use crate::core::fault_injection::{
-    inject_completion_error, inject_submission_error, FaultDomain, InjectIoCtx,
+    inject_completion_fault, inject_submission_error, FaultDomain, InjectIoCtx, InjectedFault,
 };
 
 use super::NvmeIoChannelInner;
@@ -300,28 +300,60 @@ fn complete_nvme_command(ctx: *mut NvmeIoCtx, cpl: *const spdk_nvme_cpl) {
         stats_controller.account_block_io(io_ctx.op, 1, io_ctx.num_blocks);
     }
 
-    // Adjust the number of active I/O operations in case operation is
-    // accountable.
-    match io_ctx.op {
-        IoType::Flush => {}
-        _ => inner.discard_io(),
-    }
-
-    // Invoke caller's callback and free I/O context.
     let status = if op_succeeded {
         IoCompletionStatus::Success
     } else {
         IoCompletionStatus::from(NvmeStatus::from(cpl))
     };
 
+    // A delayed or dropped I/O is held back by the channel, and stays
+    // accounted as an active I/O till completed.
     #[cfg(feature = "fault-injection")]
-    let status = inject_completion_error(&io_ctx.inj_op, status);
+    let status = match inject_completion_fault(&io_ctx.inj_op, status) {
+        None => status,
+        Some(InjectedFault::Status(s)) => s,
+        Some(InjectedFault::Delay(d)) => {
+            inner.hold_io(
+                ctx as *mut c_void,
+                finish_held_nvme_command,
+                status,
+                Some(d),
+            );
+            return;
+        }
+        Some(InjectedFault::Drop) => {
+            inner.hold_io(ctx as *mut c_void, finish_held_nvme_command, status, None);
+            return;
+        }
+    };
+
+    finish_nvme_command(ctx, status);
+}
+
+/// Invoke caller's callback and free I/O context.
+#[inline]
+fn finish_nvme_command(ctx: *mut NvmeIoCtx, status: IoCompletionStatus) {
+    let io_ctx = unsafe { &mut *ctx };
+    let inner = NvmeIoChannel::inner_from_channel(io_ctx.channel);
+
+    // Adjust the number of active I/O operations in case operation is
+    // accountable.
+    match io_ctx.op {
+        IoType::Flush => {}
+        _ => inner.discard_io(),
+    }
 
     (io_ctx.cb)(&*inner.device, status, io_ctx.cb_arg);
 
     free_nvme_io_ctx(ctx);
 }
 
+/// Completes an I/O held back by the channel on behalf of a fault injection.
+#[cfg(feature = "fault-injection")]
+fn finish_held_nvme_command(ctx: *mut c_void, status: IoCompletionStatus) {
+    finish_nvme_command(ctx as *mut NvmeIoCtx, status);
+}
+
 /// Completion handler for vectored write requests.
 extern "C" fn nvme_writev_done(ctx: *mut c_void, cpl: *const spdk_nvme_cpl) {
     let nvme_io_ctx = ctx as *mut NvmeIoCtx;
@@ -1,5 +1,6 @@
-use std::{collections::HashMap, fmt::Display, sync::Arc};
+use std::{collections::HashMap, fmt::Display, future::Future, pin::Pin, sync::Arc};
 
+use futures::FutureExt;
 use once_cell::sync::Lazy;
 use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
@@ -13,10 +14,13 @@ pub use namespace::NvmeNamespace;
 use poll_group::PollGroup;
 pub use qpair::QPair;
 pub use snapshot::{NvmeSnapshotMessage, NvmeSnapshotMessageV1};
+pub(crate) use timeout_policy::strip_timeout_parameters;
+pub use timeout_policy::{EffectiveTimeouts, TimeoutPolicy};
 pub(crate) use uri::NvmfDeviceTemplate;
 
 use crate::{
     core::CoreError,
+    jsonrpc::{jsonrpc_register, Code, JsonRpcError},
     subsys::{Config, NvmeBdevOpts},
 };
 
@@ -30,6 +34,7 @@ mod namespace;
 mod poll_group;
 mod qpair;
 mod snapshot;
+mod timeout_policy;
 mod uri;
 pub mod utils;
 
@@ -106,6 +111,84 @@ impl Default for NVMeCtlrList<'_> {
 
 pub static NVME_CONTROLLERS: Lazy<NVMeCtlrList> = Lazy::new(NVMeCtlrList::default);
 
-pub fn nvme_bdev_running_config() -> &'static NvmeBdevOpts {
-    &Config::get().nvme_bdev_opts
+pub fn nvme_bdev_running_config() -> Arc<NvmeBdevOpts> {
+    Config::nvme_bdev_opts()
+}
+
+/// Change the timeout policy of the NVMe controller, the settings which are
+/// not given are left unchanged. The timeouts and the action apply at once,
+/// the retry count to the queue pairs connected from then on.
+pub fn nvme_controller_set_timeout_policy(
+    name: &str,
+    policy: &TimeoutPolicy,
+) -> Result<EffectiveTimeouts, CoreError> {
+    let controller =
+        NVME_CONTROLLERS
+            .lookup_by_name(name)
+            .ok_or_else(|| CoreError::BdevNotFound {
+                name: name.to_string(),
+            })?;
+    let mut controller = controller.lock();
+
+    controller.timeout_policy.merge(policy);
+    if controller.get_state() == NvmeControllerState::Running {
+        controller.configure_timeout();
+    }
+    if let (Some(count), Some(ctrlr)) = (policy.retry_count, controller.controller()) {
+        ctrlr.set_transport_retry_count(count);
+    }
+
+    info!(
+        "{name}: timeout policy set to {:?}",
+        controller.timeout_policy
+    );
+    Ok(controller.timeouts())
+}
+
+/// Get the timeout settings in effect for the NVMe controller.
+pub fn nvme_controller_timeouts(name: &str) -> Option<EffectiveTimeouts> {
+    NVME_CONTROLLERS
+        .lookup_by_name(name)
+        .map(|controller| controller.lock().timeouts())
+}
+
+/// Arguments of the `nvme_controller_timeouts` json-rpc method.
+#[derive(Debug, Deserialize)]
+struct ControllerTimeoutsArgs {
+    /// Name of the controller.
+    name: String,
+}
+
+type ControllerFuture<R> = Pin<Box<dyn Future<Output = Result<R, JsonRpcError>>>>;
+
+/// Register the NVMe controller json-rpc methods.
+pub(crate) fn register_jsonrpc_methods() {
+    jsonrpc_register(
+        "nvme_controller_timeouts",
+        |args: ControllerTimeoutsArgs| -> ControllerFuture<EffectiveTimeouts> {
+            async move {
+                nvme_controller_timeouts(&args.name).ok_or_else(|| {
+                    JsonRpcError::new(
+                        Code::NotFound,
+                        format!("NVMe controller {} not found", args.name),
+                    )
+                })
+            }
+            .boxed_local()
+        },
+    );
+}
+
+/// Apply the running I/O timeout settings to the existing NVMe controllers,
+/// e.g. once the configuration has been reloaded.
+pub(crate) fn nvme_bdev_reconfigure_timeouts() {
+    for name in NVME_CONTROLLERS.controllers() {
+        let Some(controller) = NVME_CONTROLLERS.lookup_by_name(&name) else {
+            continue;
+        };
+        let mut controller = controller.lock();
+        if controller.get_state() == NvmeControllerState::Running {
+            controller.configure_timeout();
+        }
+    }
 }
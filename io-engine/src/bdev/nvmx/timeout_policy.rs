@@ -0,0 +1,212 @@
+//! Per controller I/O timeout policy.
+//!
+//! The I/O timeouts, the action taken when a command times out and the
+//! transport retry count of an NVMe controller default to the NVMe bdev
+//! options, and can be overridden by the parameters of the device URI, e.g.
+//! `nvmf://host/nqn?io_timeout_us=30000000&timeout_action=reset`. The policy
+//! of a running controller can be changed as well, and the settings in effect
+//! are reported by the `nvme_controller_timeouts` json-rpc method.
+
+use std::collections::HashMap;
+
+use snafu::ResultExt;
+use url::Url;
+
+use crate::{
+    bdev_api::{self, BdevError},
+    core::DeviceTimeoutAction,
+    subsys::NvmeBdevOpts,
+};
+
+/// URI parameters of the timeout policy.
+pub(crate) const TIMEOUT_PARAMETERS: [&str; 4] = [
+    "io_timeout_us",
+    "admin_timeout_us",
+    "timeout_action",
+    "retry_count",
+];
+
+/// Timeout settings which override the NVMe bdev options.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TimeoutPolicy {
+    /// Timeout for I/O commands, 0 disables the timeout handling.
+    pub io_timeout_us: Option<u64>,
+    /// Timeout for admin commands.
+    pub admin_timeout_us: Option<u64>,
+    /// Action taken when a command times out.
+    pub action: Option<DeviceTimeoutAction>,
+    /// Transport retry count.
+    pub retry_count: Option<u8>,
+}
+
+/// Timeout settings in effect for a controller.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct EffectiveTimeouts {
+    /// Timeout for I/O commands, 0 if the timeout handling is disabled.
+    pub io_timeout_us: u64,
+    /// Timeout for admin commands.
+    pub admin_timeout_us: u64,
+    /// Action taken when a command times out.
+    #[serde(serialize_with = "serialize_action")]
+    pub action: DeviceTimeoutAction,
+    /// Whether the timeout handler is registered with the controller.
+    pub handler_registered: bool,
+    /// Transport retry count of the connected queue pairs.
+    pub retry_count: u8,
+    /// Transport retry count of the queue pairs connected from now on, e.g.
+    /// upon a controller reset, if it differs from the one in use.
+    pub pending_retry_count: Option<u8>,
+}
+
+impl TimeoutPolicy {
+    /// Take the timeout policy out of the URI parameters.
+    pub(crate) fn from_parameters(
+        url: &Url,
+        parameters: &mut HashMap<String, String>,
+    ) -> Result<Self, BdevError> {
+        Ok(Self {
+            io_timeout_us: int_parameter(url, parameters, "io_timeout_us")?,
+            admin_timeout_us: int_parameter(url, parameters, "admin_timeout_us")?,
+            action: match parameters.remove("timeout_action") {
+                Some(value) => Some(parse_action(&value).ok_or_else(|| BdevError::InvalidUri {
+                    uri: url.to_string(),
+                    message: format!(
+                        "invalid timeout_action '{value}', expected one of ignore, \
+                            reset, abort or hot_remove"
+                    ),
+                })?),
+                None => None,
+            },
+            retry_count: int_parameter(url, parameters, "retry_count")?,
+        })
+    }
+
+    /// Take the settings of the other policy which are set.
+    pub fn merge(&mut self, other: &TimeoutPolicy) {
+        self.io_timeout_us = other.io_timeout_us.or(self.io_timeout_us);
+        self.admin_timeout_us = other.admin_timeout_us.or(self.admin_timeout_us);
+        self.action = other.action.or(self.action);
+        self.retry_count = other.retry_count.or(self.retry_count);
+    }
+
+    /// Get the timeout settings, using the NVMe bdev options for the ones
+    /// which are not overridden. Without an I/O timeout no action is taken.
+    pub fn resolve(&self, defaults: &NvmeBdevOpts) -> EffectiveTimeouts {
+        let action = self.action.unwrap_or_else(|| {
+            DeviceTimeoutAction::try_from(defaults.action_on_timeout).unwrap_or_else(|e| {
+                error!("can not apply requested I/O timeout action: {e}, falling back to Ignore");
+                DeviceTimeoutAction::Ignore
+            })
+        });
+
+        let io_timeout_us = self.io_timeout_us.unwrap_or(defaults.timeout_us);
+        EffectiveTimeouts {
+            io_timeout_us,
+            admin_timeout_us: self.admin_timeout_us.unwrap_or(defaults.timeout_admin_us),
+            action: if io_timeout_us == 0 {
+                DeviceTimeoutAction::Ignore
+            } else {
+                action
+            },
+            handler_registered: io_timeout_us != 0,
+            retry_count: self
+                .retry_count
+                .unwrap_or(defaults.transport_retry_count as u8),
+            pending_retry_count: None,
+        }
+    }
+
+    /// Get the URI with the parameters of the settings which are set,
+    /// replacing the ones it already has.
+    pub(crate) fn apply_to(&self, url: &Url) -> Url {
+        let settings = [
+            ("io_timeout_us", self.io_timeout_us.map(|v| v.to_string())),
+            (
+                "admin_timeout_us",
+                self.admin_timeout_us.map(|v| v.to_string()),
+            ),
+            (
+                "timeout_action",
+                self.action.map(|a| action_parameter(a).to_string()),
+            ),
+            ("retry_count", self.retry_count.map(|v| v.to_string())),
+        ];
+
+        let mut pairs = url.query_pairs().into_owned().collect::<Vec<_>>();
+        for (name, value) in settings {
+            if let Some(value) = value {
+                pairs.retain(|(k, _)| k != name);
+                pairs.push((name.to_string(), value));
+            }
+        }
+
+        let mut url = url.clone();
+        if pairs.is_empty() {
+            url.set_query(None);
+        } else {
+            url.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+        url
+    }
+}
+
+fn int_parameter<T: std::str::FromStr<Err = std::num::ParseIntError>>(
+    url: &Url,
+    parameters: &mut HashMap<String, String>,
+    name: &str,
+) -> Result<Option<T>, BdevError> {
+    parameters
+        .remove(name)
+        .map(|value| {
+            value.parse().context(bdev_api::IntParamParseFailed {
+                uri: url.to_string(),
+                parameter: name.to_string(),
+                value: value.clone(),
+            })
+        })
+        .transpose()
+}
+
+fn parse_action(value: &str) -> Option<DeviceTimeoutAction> {
+    match value {
+        "ignore" => Some(DeviceTimeoutAction::Ignore),
+        "reset" => Some(DeviceTimeoutAction::Reset),
+        "abort" => Some(DeviceTimeoutAction::Abort),
+        "hot_remove" => Some(DeviceTimeoutAction::HotRemove),
+        _ => None,
+    }
+}
+
+fn action_parameter(action: DeviceTimeoutAction) -> &'static str {
+    match action {
+        DeviceTimeoutAction::Ignore => "ignore",
+        DeviceTimeoutAction::Reset => "reset",
+        DeviceTimeoutAction::Abort => "abort",
+        DeviceTimeoutAction::HotRemove => "hot_remove",
+    }
+}
+
+/// Serialize the action by the name of its URI parameter value.
+fn serialize_action<S>(action: &DeviceTimeoutAction, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_str(action_parameter(*action))
+}
+
+/// Get the URI without the timeout policy parameters, which identifies the
+/// device.
+pub(crate) fn strip_timeout_parameters(url: &Url) -> Url {
+    let mut url = url.clone();
+    let pairs = url
+        .query_pairs()
+        .into_owned()
+        .filter(|(k, _)| !TIMEOUT_PARAMETERS.contains(&k.as_str()))
+        .collect::<Vec<_>>();
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    url
+}
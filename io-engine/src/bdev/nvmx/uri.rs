@@ -33,7 +33,8 @@ use spdk_rs::{
 use crate::{
     bdev::{
         nvmx::{
-            controller, controller_inner::SpdkNvmeController, NvmeControllerState, NVME_CONTROLLERS,
+            controller, controller_inner::SpdkNvmeController, nvme_bdev_running_config,
+            timeout_policy::TimeoutPolicy, NvmeControllerState, NVME_CONTROLLERS,
         },
         util::uri,
         CreateDestroy, GetName,
@@ -42,7 +43,6 @@ use crate::{
     constants::NVME_NQN_PREFIX,
     core::MayastorEnvironment,
     ffihelper::ErrnoResult,
-    subsys::Config,
 };
 
 use super::controller::transport::NvmeTransportId;
@@ -103,6 +103,8 @@ pub struct NvmfDeviceTemplate {
     uuid: Option<uuid::Uuid>,
     /// The HostNqn to connect to the nvmf target with.
     hostnqn: Option<String>,
+    /// Timeout settings which override the NVMe bdev options.
+    timeout_policy: TimeoutPolicy,
 }
 
 impl TryFrom<&Url> for NvmfDeviceTemplate {
@@ -161,6 +163,8 @@ impl TryFrom<&Url> for NvmfDeviceTemplate {
 
         let hostnqn = parameters.remove("hostnqn");
 
+        let timeout_policy = TimeoutPolicy::from_parameters(url, &mut parameters)?;
+
         Ok(NvmfDeviceTemplate {
             name: url[url::Position::BeforeHost..url::Position::AfterPath].to_string(),
             alias: url.to_string(),
@@ -170,6 +174,7 @@ impl TryFrom<&Url> for NvmfDeviceTemplate {
             prchk_flags,
             uuid,
             hostnqn,
+            timeout_policy,
         })
     }
 }
@@ -203,9 +208,10 @@ impl NvmeControllerContext<'_> {
         // makes debugging connections easier in certain cases. If no
         // HOSTNQN is provided.
 
+        let nvme_opts = nvme_bdev_running_config();
         let mut opts = controller::options::Builder::new()
-            .with_keep_alive_timeout_ms(Config::get().nvme_bdev_opts.keep_alive_timeout_ms)
-            .with_transport_retry_count(Config::get().nvme_bdev_opts.transport_retry_count as u8)
+            .with_keep_alive_timeout_ms(nvme_opts.keep_alive_timeout_ms)
+            .with_transport_retry_count(template.timeout_policy.resolve(&nvme_opts).retry_count)
             .with_fabrics_connect_timeout_us(crate::subsys::config::opts::try_from_env(
                 "NVMF_FABRICS_CONNECT_TIMEOUT",
                 1_000_000,
@@ -271,7 +277,7 @@ impl CreateDestroy for NvmfDeviceTemplate {
         // release the lock to keep the write path as short, as
         // possible.
         let rc = Arc::new(Mutex::new(
-            controller::NvmeController::new(&cname, self.prchk_flags)
+            controller::NvmeController::new(&cname, self.prchk_flags, self.timeout_policy)
                 .expect("failed to create new NVMe controller instance"),
         ));
 
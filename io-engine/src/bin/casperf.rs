@@ -1,6 +1,6 @@
-use std::{cell::RefCell, os::raw::c_void, ptr::NonNull};
+use std::{cell::RefCell, os::raw::c_void, ptr::NonNull, time::Instant};
 
-use clap::{Arg, Command};
+use clap::{Arg, ArgAction, Command};
 use rand::Rng;
 
 use io_engine::{
@@ -14,32 +14,190 @@ use io_engine::{
 };
 use spdk_rs::{
     libspdk::{
-        spdk_bdev_free_io, spdk_bdev_io, spdk_bdev_read, spdk_bdev_write, spdk_poller,
-        spdk_poller_register, spdk_poller_unregister,
+        spdk_bdev_free_io, spdk_bdev_io, spdk_bdev_read, spdk_bdev_write, spdk_crc32c_update,
+        spdk_get_ticks, spdk_get_ticks_hz, spdk_poller, spdk_poller_register,
+        spdk_poller_unregister, SPDK_CRC32C_INITIAL,
     },
     DmaBuf, IoChannelGuard,
 };
 use version_info::version_info_str;
 
+/// Workload of a job.
 #[derive(Debug, Clone, Copy)]
+struct Workload {
+    /// pick the offsets at random, rather than sequentially
+    random: bool,
+    /// percentage of the IOs which are reads, the others being writes
+    read_pct: u64,
+    /// read back every write and check its CRC
+    verify: bool,
+}
+
+/// Bounds of a run, when neither is set the run goes on until interrupted.
+#[derive(Debug, Clone, Copy, Default)]
+struct Bounds {
+    /// stop after this many seconds
+    runtime: Option<u64>,
+    /// stop each job after it has submitted this many IOs
+    io_count: Option<u64>,
+}
+
+/// Format of the results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Default,
+    Json,
+}
+
+/// type of an IO being submitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum IoType {
-    /// perform random read operations
+    /// read IO
     Read,
-    /// perform random write operations
-    #[allow(dead_code)]
+    /// write IO
     Write,
+    /// read IO checking the data of the previous write
+    VerifyRead,
 }
 
 /// default queue depth
 const QD: u64 = 64;
 /// default io_size
 const IO_SIZE: u64 = 512;
+/// latency percentiles which are reported
+const PERCENTILES: [f64; 5] = [50.0, 90.0, 99.0, 99.9, 99.99];
+
+/// Histogram of latencies, in nanoseconds, with log-linear buckets: each
+/// power of two is split into 16 buckets, which keeps the error under ~6%.
+#[derive(Debug, Clone)]
+struct Histogram {
+    buckets: Vec<u64>,
+    count: u64,
+    sum: u64,
+    min: u64,
+    max: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0; 64 * 16],
+            count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+}
+
+impl Histogram {
+    fn bucket(value: u64) -> usize {
+        if value < 16 {
+            return value as usize;
+        }
+        let msb = 63 - value.leading_zeros() as u64;
+        ((msb - 3) * 16 + ((value >> (msb - 4)) & 0xf)) as usize
+    }
+
+    /// midpoint of the values of a bucket
+    fn value(bucket: usize) -> u64 {
+        let bucket = bucket as u64;
+        if bucket < 16 {
+            return bucket;
+        }
+        let shift = bucket / 16 - 1;
+        let lower = (16 + bucket % 16) << shift;
+        lower + (1 << shift) / 2
+    }
+
+    fn record(&mut self, value: u64) {
+        self.buckets[Self::bucket(value)] += 1;
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.buckets
+            .iter_mut()
+            .zip(&other.buckets)
+            .for_each(|(b, o)| *b += o);
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    fn percentile(&self, pct: f64) -> u64 {
+        let rank = ((pct / 100.0) * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return Self::value(bucket).clamp(self.min, self.max);
+            }
+        }
+        self.max
+    }
+
+    /// latency summary, in microseconds
+    fn summary(&self) -> serde_json::Value {
+        let us = |ns: u64| ns as f64 / 1000.0;
+        if self.count == 0 {
+            return serde_json::json!({});
+        }
+        let percentiles = PERCENTILES
+            .iter()
+            .map(|p| (format!("p{p}"), serde_json::json!(us(self.percentile(*p)))))
+            .collect::<serde_json::Map<_, _>>();
+        serde_json::json!({
+            "min_us": us(self.min),
+            "avg_us": us(self.sum / self.count),
+            "max_us": us(self.max),
+            "percentiles_us": percentiles,
+        })
+    }
+}
+
+/// Results of a job, once it's finished.
+#[derive(Debug, Clone, Default)]
+struct JobResult {
+    name: String,
+    seconds: f64,
+    io_size: u64,
+    reads: u64,
+    writes: u64,
+    errors: u64,
+    verify_failures: u64,
+    latency: Histogram,
+}
+
+impl JobResult {
+    fn to_json(&self) -> serde_json::Value {
+        let ios = self.reads + self.writes;
+        let seconds = self.seconds.max(f64::EPSILON);
+        serde_json::json!({
+            "name": self.name,
+            "seconds": self.seconds,
+            "reads": self.reads,
+            "writes": self.writes,
+            "iops": ios as f64 / seconds,
+            "mb_per_second": (ios * self.io_size) as f64 / seconds / (1024.0 * 1024.0),
+            "errors": self.errors,
+            "verify_failures": self.verify_failures,
+            "latency": self.latency.summary(),
+        })
+    }
+}
 
 /// a Job refers to a set of work typically defined by either time or size
 /// that drives IO to a bdev using its own channel.
 #[derive(Debug)]
 #[allow(dead_code)]
 struct Job {
+    /// unique id of the job
+    id: usize,
     bdev: UntypedBdev,
     /// descriptor to the bdev
     desc: UntypedDescriptorGuard,
@@ -57,8 +215,19 @@ struct Job {
     io_blocks: u64,
     /// io queue
     queue: Vec<Io>,
+    /// workload of the job
+    workload: Workload,
+    /// bounds of the job
+    bounds: Bounds,
+    /// number of lanes the device is split into when verifying, so that IOs
+    /// never overlap, each IO using its own lane
+    lanes: u64,
+    /// next IO for sequential workloads
+    cursor: u64,
     /// number of IO's completed
     n_io: u64,
+    /// number of IO's submitted
+    n_submitted: u64,
     /// number of IO's currently inflight
     n_inflight: u32,
     /// generate random number between 0 and num_block
@@ -66,8 +235,12 @@ struct Job {
     /// drain the job which means that we wait for all pending IO to complete
     /// and stop the run
     drain: bool,
-    /// number of seconds we are running
-    period: u64,
+    /// when the job started
+    started: Instant,
+    /// ticks per second, to compute the latencies
+    ticks_hz: u64,
+    /// results so far
+    result: JobResult,
 }
 
 #[allow(clippy::non_send_fields_in_send_ty)]
@@ -76,7 +249,10 @@ unsafe impl Send for Job {}
 thread_local! {
     #[allow(clippy::vec_box)]
     static JOBLIST: RefCell<Vec<Box<Job>>> = const { RefCell::new(Vec::new()) };
+    static RESULTS: RefCell<Vec<JobResult>> = const { RefCell::new(Vec::new()) };
     static PERF_TICK: RefCell<Option<NonNull<spdk_poller>>> = const { RefCell::new(None) };
+    static SETTINGS: RefCell<(OutputFormat, Bounds, u64)> =
+        const { RefCell::new((OutputFormat::Default, Bounds { runtime: None, io_count: None }, 0)) };
 }
 
 impl Job {
@@ -89,8 +265,27 @@ impl Job {
         let ioq: &mut Io = unsafe { &mut *arg.cast() };
         let job = unsafe { ioq.job.as_mut() };
 
+        let ticks = unsafe { spdk_get_ticks() } - ioq.submitted;
+        job.result
+            .latency
+            .record((ticks as u128 * 1_000_000_000 / job.ticks_hz as u128) as u64);
+
         if !success {
             eprintln!("IO error for bdev {}, LBA {}", job.bdev.name(), ioq.offset);
+            job.result.errors += 1;
+        }
+
+        match ioq.iot {
+            IoType::Read | IoType::VerifyRead => job.result.reads += 1,
+            IoType::Write => job.result.writes += 1,
+        }
+        if success && ioq.iot == IoType::VerifyRead && ioq.crc() != ioq.crc {
+            eprintln!(
+                "Data mismatch for bdev {}, offset {}",
+                job.bdev.name(),
+                ioq.offset
+            );
+            job.result.verify_failures += 1;
         }
 
         job.n_io += 1;
@@ -98,37 +293,35 @@ impl Job {
 
         unsafe { spdk_bdev_free_io(bdev_io) }
 
-        if job.drain && job.n_inflight == 0 {
-            JOBLIST.with(|l| {
-                let mut list = l.borrow_mut();
-                list.retain(|this| job.bdev.name() != this.bdev.name());
-                if list.is_empty() {
-                    Reactors::master().send_future(async {
-                        mayastor_env_stop(0);
-                    });
-                }
-            });
+        if !job.drain {
+            // read back what was just written, before moving on
+            if success && ioq.iot == IoType::Write && job.workload.verify {
+                ioq.submit(IoType::VerifyRead, ioq.offset);
+                return;
+            }
+            if !job.limit_reached() {
+                let (iot, offset) = job.next_io(ioq);
+                ioq.submit(iot, offset);
+                return;
+            }
         }
 
-        if job.drain {
-            return;
+        if job.n_inflight == 0 {
+            job.finish();
         }
-
-        let offset = job.rng.gen_range(0..job.io_blocks) * job.io_size;
-        ioq.next(offset);
     }
 
-    /// construct a new job
-    async fn new(bdev: &str, size: u64, qd: u64, io_type: IoType) -> Box<Self> {
-        let bdev = bdev_create(bdev)
-            .await
-            .map_err(|e| {
-                eprintln!("Failed to open URI {bdev}: {e}");
-                std::process::exit(1);
-            })
-            .map(|name| UntypedBdev::lookup_by_name(&name).unwrap())
-            .unwrap();
-
+    /// construct a new job, the index of the job among the jobs of the same
+    /// bdev and their number being given by `lane`
+    fn new(
+        id: usize,
+        bdev: UntypedBdev,
+        size: u64,
+        qd: u64,
+        workload: Workload,
+        bounds: Bounds,
+        lane: (u64, u64),
+    ) -> Box<Self> {
         let desc = bdev.open(true).unwrap();
 
         let blk_size = bdev.block_len() as u64;
@@ -137,18 +330,39 @@ impl Job {
         let io_size = size / blk_size;
         let io_blocks = num_blocks / io_size;
 
+        // when verifying, each IO of each job of the device gets its own lane
+        let (job_index, jobs) = lane;
+        let lanes = if workload.verify { (qd + 1) * jobs } else { 1 };
+        if lanes > io_blocks {
+            eprintln!(
+                "Device {} is too small to verify {lanes} concurrent IOs",
+                bdev.name()
+            );
+            std::process::exit(1);
+        }
+
         let mut queue = Vec::new();
 
         (0..=qd).for_each(|offset| {
             queue.push(Io {
                 buf: DmaBuf::new(size, bdev.alignment()).unwrap(),
-                iot: io_type,
+                iot: IoType::Read,
                 offset,
+                lane: if workload.verify {
+                    job_index * (qd + 1) + offset
+                } else {
+                    0
+                },
+                cursor: 0,
+                submitted: 0,
+                crc: 0,
                 job: NonNull::dangling(),
             });
         });
 
+        let name = bdev.name().to_string();
         Box::new(Self {
+            id,
             bdev,
             desc,
             ch: None,
@@ -158,11 +372,23 @@ impl Job {
             num_blocks,
             queue,
             io_blocks,
+            workload,
+            bounds,
+            lanes,
+            // spread the sequential jobs of a device over it
+            cursor: io_blocks / jobs * job_index,
             n_io: 0,
+            n_submitted: 0,
             n_inflight: 0,
             rng: Default::default(),
             drain: false,
-            period: 0,
+            started: Instant::now(),
+            ticks_hz: unsafe { spdk_get_ticks_hz() },
+            result: JobResult {
+                name,
+                io_size: size,
+                ..Default::default()
+            },
         })
     }
 
@@ -170,13 +396,67 @@ impl Job {
         self as *const _ as *mut _
     }
 
+    /// whether the job has submitted all the IOs it was asked to
+    fn limit_reached(&self) -> bool {
+        self.bounds
+            .io_count
+            .map_or(false, |count| self.n_submitted >= count)
+    }
+
+    /// pick the type and offset of the next IO
+    fn next_io(&mut self, io: &mut Io) -> (IoType, u64) {
+        let iot = if self.rng.gen_range(0..100) < self.workload.read_pct {
+            IoType::Read
+        } else {
+            IoType::Write
+        };
+
+        let per_lane = self.io_blocks / self.lanes;
+        let index = match (self.workload.random, self.workload.verify) {
+            (true, _) => self.rng.gen_range(0..per_lane),
+            (false, true) => {
+                io.cursor = (io.cursor + 1) % per_lane;
+                io.cursor
+            }
+            (false, false) => {
+                self.cursor = (self.cursor + 1) % per_lane;
+                self.cursor
+            }
+        };
+        (iot, (index * self.lanes + io.lane) * self.io_size)
+    }
+
     /// start the job that will dispatch an IO up to the provided queue depth
     fn run(mut self: Box<Self>) {
         self.ch = self.desc.io_channel().ok();
+        self.started = Instant::now();
         let ptr = self.as_ptr();
-        self.queue.iter_mut().for_each(|q| q.run(ptr));
+        self.queue
+            .iter_mut()
+            .for_each(|q| q.job = NonNull::new(ptr).unwrap());
+        self.queue.iter_mut().for_each(|q| q.start());
         JOBLIST.with(|l| l.borrow_mut().push(self));
     }
+
+    /// the job has no IOs in flight anymore, record its results and stop
+    /// the run once all the jobs are finished
+    fn finish(&mut self) {
+        let mut result = self.result.clone();
+        result.seconds = self.started.elapsed().as_secs_f64();
+        let id = self.id;
+        RESULTS.with(|r| r.borrow_mut().push(result));
+        JOBLIST.with(|l| {
+            let mut list = l.borrow_mut();
+            list.retain(|this| this.id != id);
+            if list.is_empty() {
+                stop_ticker();
+                let failed = print_results();
+                Reactors::master().send_future(async move {
+                    mayastor_env_stop(if failed { 1 } else { 0 });
+                });
+            }
+        });
+    }
 }
 
 #[derive(Debug)]
@@ -187,6 +467,14 @@ struct Io {
     iot: IoType,
     /// current offset where we are reading from
     offset: u64,
+    /// lane of the IO when verifying
+    lane: u64,
+    /// next IO within the lane, for sequential workloads
+    cursor: u64,
+    /// ticks when the IO was submitted
+    submitted: u64,
+    /// crc of the data last written
+    crc: u32,
     /// pointer to our the job we belong too
     job: NonNull<Job>,
 }
@@ -194,20 +482,54 @@ struct Io {
 unsafe impl Send for Io {}
 
 impl Io {
-    /// start submitting
-    fn run(&mut self, job: *mut Job) {
-        self.job = NonNull::new(job).unwrap();
-        match self.iot {
-            IoType::Read => self.read(0),
-            IoType::Write => self.write(0),
-        };
+    /// submit the first IO, unless the job is already over
+    fn start(&mut self) {
+        let job = unsafe { self.job.as_mut() };
+        if !job.limit_reached() {
+            let (iot, offset) = job.next_io(self);
+            self.submit(iot, offset);
+        }
     }
 
     /// dispatch the next IO, this is called from within the completion callback
-    pub fn next(&mut self, offset: u64) {
-        match self.iot {
-            IoType::Read => self.read(offset),
-            IoType::Write => self.write(offset),
+    fn submit(&mut self, iot: IoType, offset: u64) {
+        self.iot = iot;
+        self.offset = offset;
+        match iot {
+            IoType::Read | IoType::VerifyRead => self.read(offset),
+            IoType::Write => {
+                if unsafe { self.job.as_ref() }.workload.verify {
+                    self.fill();
+                }
+                self.write(offset)
+            }
+        }
+    }
+
+    /// fill the buffer with a pattern unique to this write, and remember its
+    /// crc
+    fn fill(&mut self) {
+        let seed = self.offset ^ (unsafe { self.job.as_ref() }.n_submitted << 32);
+        self.buf
+            .as_mut_slice()
+            .chunks_exact_mut(8)
+            .enumerate()
+            .for_each(|(i, word)| word.copy_from_slice(&(seed ^ i as u64).to_le_bytes()));
+        self.crc = self.crc();
+    }
+
+    /// crc of the buffer
+    fn crc(&self) -> u32 {
+        unsafe { spdk_crc32c_update(self.buf.as_ptr(), self.buf.len(), SPDK_CRC32C_INITIAL) }
+    }
+
+    /// account for a submitted IO
+    fn submitted(&mut self) {
+        self.submitted = unsafe { spdk_get_ticks() };
+        let job = unsafe { self.job.as_mut() };
+        job.n_inflight += 1;
+        if self.iot != IoType::VerifyRead {
+            job.n_submitted += 1;
         }
     }
 
@@ -225,7 +547,7 @@ impl Io {
                 self as *const _ as *mut _,
             ) == 0
             {
-                self.job.as_mut().n_inflight += 1;
+                self.submitted();
             } else {
                 eprintln!(
                     "failed to submit read IO to {}, offset={offset}, nbytes={nbytes}",
@@ -248,7 +570,7 @@ impl Io {
                 self as *const _ as *mut _,
             ) == 0
             {
-                self.job.as_mut().n_inflight += 1;
+                self.submitted();
             } else {
                 eprintln!(
                     "failed to submit write IO to {}",
@@ -259,21 +581,35 @@ impl Io {
     }
 }
 
+/// stop printing the statistics
+fn stop_ticker() {
+    PERF_TICK.with(|t| {
+        if let Some(ticker) = t.borrow_mut().take() {
+            unsafe { spdk_poller_unregister(&mut ticker.as_ptr()) }
+        }
+    });
+}
+
+/// drain all the jobs, the run stops once their IOs are completed
+fn drain_jobs() {
+    stop_ticker();
+    if output_format() == OutputFormat::Default {
+        println!("Draining jobs....");
+    }
+    JOBLIST.with(|l| {
+        l.borrow_mut().iter_mut().for_each(|j| j.drain = true);
+    });
+}
+
+fn output_format() -> OutputFormat {
+    SETTINGS.with(|s| s.borrow().0)
+}
+
 /// override the default signal handler as we need to stop the jobs first
 /// before we can shut down
 fn sig_override() {
     let handler = || {
-        Mthread::primary().send_msg((), |_| {
-            PERF_TICK.with(|t| {
-                let ticker = t.borrow_mut().take().unwrap();
-                unsafe { spdk_poller_unregister(&mut ticker.as_ptr()) }
-            });
-
-            println!("Draining jobs....");
-            JOBLIST.with(|l| {
-                l.borrow_mut().iter_mut().for_each(|j| j.drain = true);
-            });
-        });
+        Mthread::primary().send_msg((), |_| drain_jobs());
     };
 
     unsafe {
@@ -284,34 +620,112 @@ fn sig_override() {
     };
 }
 
-/// prints the performance statistics to stdout on every tick (1s)
+/// prints the performance statistics to stdout on every tick (1s), and stops
+/// the run once its runtime is over
 extern "C" fn perf_tick(_: *mut c_void) -> i32 {
-    let mut total_io_per_second = 0;
-    let mut total_mb_per_second = 0;
-    JOBLIST.with(|l| {
-        for j in l.borrow_mut().iter_mut() {
-            j.period += 1;
-            let io_per_second = j.n_io / j.period;
-            let mb_per_second = io_per_second * j.io_size / (1024 * 1024);
+    let (format, bounds, elapsed) = SETTINGS.with(|s| {
+        let mut s = s.borrow_mut();
+        s.2 += 1;
+        *s
+    });
+
+    if format == OutputFormat::Default {
+        let mut total_io_per_second = 0;
+        let mut total_mb_per_second = 0;
+        JOBLIST.with(|l| {
+            for j in l.borrow_mut().iter_mut() {
+                let io_per_second = j.n_io / elapsed;
+                let mb_per_second = io_per_second * j.io_size / (1024 * 1024);
+                println!(
+                    "\r {:20}: {:10} IO/s {:10}: MB/s",
+                    j.bdev.name(),
+                    io_per_second,
+                    mb_per_second
+                );
+                total_io_per_second += io_per_second;
+                total_mb_per_second += mb_per_second;
+            }
+
+            println!("\r ==================================================== +");
             println!(
-                "\r {:20}: {:10} IO/s {:10}: MB/s",
-                j.bdev.name(),
-                io_per_second,
-                mb_per_second
+                "\r {:20}: {:10} IO/s {:10}: MB/s\n",
+                "Total", total_io_per_second, total_mb_per_second
             );
-            total_io_per_second += io_per_second;
-            total_mb_per_second += mb_per_second;
-        }
+        });
+    }
 
-        println!("\r ==================================================== +");
-        println!(
-            "\r {:20}: {:10} IO/s {:10}: MB/s\n",
-            "Total", total_io_per_second, total_mb_per_second
-        );
-    });
+    if bounds.runtime.map_or(false, |runtime| elapsed >= runtime) {
+        drain_jobs();
+    }
     0
 }
 
+/// print the results of all the jobs, returning true if any IO failed
+fn print_results() -> bool {
+    let results = RESULTS.with(|r| r.borrow().clone());
+    let mut total = JobResult {
+        name: "Total".to_string(),
+        ..Default::default()
+    };
+    let mut total_mb_per_second = 0.0;
+    for r in &results {
+        total.reads += r.reads;
+        total.writes += r.writes;
+        total.errors += r.errors;
+        total.verify_failures += r.verify_failures;
+        total.seconds = total.seconds.max(r.seconds);
+        total.latency.merge(&r.latency);
+        total_mb_per_second +=
+            ((r.reads + r.writes) * r.io_size) as f64 / r.seconds.max(f64::EPSILON);
+    }
+    total_mb_per_second /= 1024.0 * 1024.0;
+
+    match output_format() {
+        OutputFormat::Json => {
+            let mut summary = total.to_json();
+            summary["mb_per_second"] = serde_json::json!(total_mb_per_second);
+            let output = serde_json::json!({
+                "jobs": results.iter().map(JobResult::to_json).collect::<Vec<_>>(),
+                "total": summary,
+            });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        }
+        OutputFormat::Default => {
+            let seconds = total.seconds.max(f64::EPSILON);
+            println!("\r ==================== Results ======================= +");
+            println!(
+                "\r {:20}: {:10.0} IO/s {:10.0}: MB/s",
+                "Total",
+                (total.reads + total.writes) as f64 / seconds,
+                total_mb_per_second
+            );
+            println!(
+                "\r {:20}: {} reads, {} writes, {} errors, {} verify failures",
+                "", total.reads, total.writes, total.errors, total.verify_failures
+            );
+            if total.latency.count > 0 {
+                let us = |ns: u64| ns as f64 / 1000.0;
+                println!(
+                    "\r {:20}: min {:.1} avg {:.1} max {:.1} us",
+                    "Latency",
+                    us(total.latency.min),
+                    us(total.latency.sum / total.latency.count),
+                    us(total.latency.max)
+                );
+                for p in PERCENTILES {
+                    println!(
+                        "\r {:>20}: {:.1} us",
+                        format!("p{p}"),
+                        us(total.latency.percentile(p))
+                    );
+                }
+            }
+        }
+    }
+
+    total.errors > 0 || total.verify_failures > 0
+}
+
 fn main() {
     logger::init("INFO");
 
@@ -336,7 +750,14 @@ fn main() {
                 .value_name("io-type")
                 .short('t')
                 .help("type of IOs")
-                .value_parser(["randread", "randwrite"]),
+                .value_parser(["randread", "randwrite", "randrw", "read", "write", "rw"]),
+        )
+        .arg(
+            Arg::new("rwmixread")
+                .long("rwmixread")
+                .value_name("PERCENT")
+                .value_parser(clap::value_parser!(u64).range(0..=100))
+                .help("percentage of reads of the rw and randrw IO types (default 50)"),
         )
         .arg(
             Arg::new("queue-depth")
@@ -345,6 +766,41 @@ fn main() {
                 .value_parser(clap::value_parser!(u64))
                 .help("queue depth"),
         )
+        .arg(
+            Arg::new("jobs")
+                .long("jobs")
+                .short('j')
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help("number of jobs per URI, each with its own queue (default 1)"),
+        )
+        .arg(
+            Arg::new("runtime")
+                .long("runtime")
+                .short('T')
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help("stop after the given number of seconds"),
+        )
+        .arg(
+            Arg::new("io-count")
+                .long("io-count")
+                .short('n')
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help("stop each job after it has submitted the given number of IOs"),
+        )
+        .arg(
+            Arg::new("verify")
+                .long("verify")
+                .action(ArgAction::SetTrue)
+                .help("read back every write and check its CRC"),
+        )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .short('o')
+                .value_parser(["default", "json"])
+                .help("format of the results, json only prints the results at the end"),
+        )
         .arg(
             Arg::new("URI")
                 .value_name("URI")
@@ -356,7 +812,7 @@ fn main() {
         .subcommand_required(false)
         .get_matches();
 
-    let mut uris = matches
+    let uris = matches
         .get_many::<String>("URI")
         .unwrap()
         .map(|u| u.to_string())
@@ -366,17 +822,36 @@ fn main() {
         Some(io_size) => byte_unit::Byte::parse_str(io_size, true).unwrap().as_u64(),
         None => IO_SIZE,
     };
-    let io_type = match matches
+    let rwmixread = *matches.get_one::<u64>("rwmixread").unwrap_or(&50);
+    let io_type = matches
         .get_one::<String>("io-type")
         .map(|s| s.as_str())
-        .unwrap_or("randread")
-    {
-        "randread" => IoType::Read,
-        "randwrite" => IoType::Write,
+        .unwrap_or("randread");
+    let (random, read_pct) = match io_type {
+        "randread" => (true, 100),
+        "randwrite" => (true, 0),
+        "randrw" => (true, rwmixread),
+        "read" => (false, 100),
+        "write" => (false, 0),
+        "rw" => (false, rwmixread),
         io_type => panic!("Invalid io_type: {}", io_type),
     };
+    let workload = Workload {
+        random,
+        read_pct,
+        verify: matches.get_flag("verify"),
+    };
+    let bounds = Bounds {
+        runtime: matches.get_one::<u64>("runtime").copied(),
+        io_count: matches.get_one::<u64>("io-count").copied(),
+    };
+    let output = match matches.get_one::<String>("output").map(|s| s.as_str()) {
+        Some("json") => OutputFormat::Json,
+        _ => OutputFormat::Default,
+    };
 
     let qd = *matches.get_one::<u64>("queue-depth").unwrap_or(&QD);
+    let jobs = *matches.get_one::<u64>("jobs").unwrap_or(&1);
     let args = MayastorCliArgs {
         reactor_mask: "0x2".to_string(),
         skip_sig_handler: true,
@@ -386,20 +861,29 @@ fn main() {
     };
 
     MayastorEnvironment::new(args).init();
+    SETTINGS.with(|s| *s.borrow_mut() = (output, bounds, 0));
     sig_override();
     io_engine::bdev::nexus::register_module(false);
     Reactors::master().send_future(async move {
-        let jobs = uris
-            .iter_mut()
-            .map(|u| Job::new(u, io_size, qd, io_type))
-            .collect::<Vec<_>>();
-
-        for j in jobs {
-            let job = j.await;
-            let thread = Mthread::new(job.bdev.name().to_string(), Cores::current()).unwrap();
-            thread.send_msg(job, |job| {
-                job.run();
-            });
+        let mut id = 0;
+        for uri in &uris {
+            let bdev = bdev_create(uri)
+                .await
+                .map_err(|e| {
+                    eprintln!("Failed to open URI {uri}: {e}");
+                    std::process::exit(1);
+                })
+                .map(|name| UntypedBdev::lookup_by_name(&name).unwrap())
+                .unwrap();
+            for index in 0..jobs {
+                let job = Job::new(id, bdev, io_size, qd, workload, bounds, (index, jobs));
+                id += 1;
+                let thread =
+                    Mthread::new(format!("{}-{index}", job.bdev.name()), Cores::current()).unwrap();
+                thread.send_msg(job, |job| {
+                    job.run();
+                });
+            }
         }
 
         unsafe {
@@ -1,5 +1,6 @@
 //! Command line test utility to copy bytes to/from a replica which can be any
-//! target type understood by the nexus.
+//! target type understood by the nexus, or to check that several replicas
+//! hold the same data.
 
 use std::{
     fmt, fs,
@@ -7,7 +8,7 @@ use std::{
 };
 
 use chrono::Utc;
-use clap::{Arg, Command};
+use clap::{Arg, ArgAction, Command};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 use version_info::version_info_str;
@@ -16,14 +17,17 @@ use io_engine::{
     bdev::{device_create, device_open},
     bdev_api::{bdev_create, BdevError},
     core::{
-        mayastor_env_stop, CoreError, MayastorCliArgs, MayastorEnvironment, Reactor,
-        SnapshotParams, UntypedBdev,
+        mayastor_env_stop, BlockDeviceHandle, CoreError, IoCompletionStatus, MayastorCliArgs,
+        MayastorEnvironment, Reactor, ReadOptions, SnapshotParams, UntypedBdev,
     },
     jsonrpc::print_error_chain,
     logger, subsys,
     subsys::Config,
 };
-use spdk_rs::DmaError;
+use spdk_rs::{
+    libspdk::{spdk_crc32c_update, SPDK_CRC32C_INITIAL},
+    DmaBuf, DmaError, NvmeStatus,
+};
 
 unsafe extern "C" fn run_static_initializers() {
     spdk_rs::libspdk::spdk_add_subsystem(subsys::ConfigSubsystem::new().0)
@@ -148,6 +152,181 @@ async fn create_snapshot(uri: &str) -> Result<()> {
     Ok(())
 }
 
+/// Content of a segment of a device.
+#[derive(Debug, Clone, Copy)]
+enum Segment {
+    /// The segment is allocated, with the given checksum.
+    Data(u32),
+    /// Some blocks of the segment are not allocated, which read as zeroes,
+    /// with the given checksum.
+    Unallocated(u32),
+}
+
+impl Segment {
+    fn crc(&self) -> u32 {
+        match self {
+            Self::Data(crc) | Self::Unallocated(crc) => *crc,
+        }
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Data(crc) => write!(f, "crc32c {crc:#010x}"),
+            Self::Unallocated(crc) => write!(f, "crc32c {crc:#010x} (unallocated)"),
+        }
+    }
+}
+
+/// Range of blocks whose data differ between the devices.
+struct Mismatch {
+    /// First block of the range.
+    start: u64,
+    /// Number of blocks of the range.
+    blocks: u64,
+    /// Content of the first segment of the range on each device.
+    segments: Vec<Segment>,
+}
+
+/// Read a segment of a device and take its checksum. Thin-provisioned
+/// devices fail the read of unallocated blocks, in which case the segment is
+/// read again, the unallocated blocks reading as zeroes.
+async fn read_segment(
+    h: &dyn BlockDeviceHandle,
+    buf: &mut DmaBuf,
+    lba: u64,
+    blocks: u64,
+) -> Result<Segment> {
+    let len = blocks * h.get_device().block_len();
+    let mut iov = buf.to_io_vec();
+    unsafe { iov.set_len(len) };
+
+    let allocated = match h
+        .readv_blocks_async(&mut [iov], lba, blocks, ReadOptions::UnwrittenFail)
+        .await
+    {
+        Ok(_) => true,
+        Err(CoreError::ReadFailed {
+            status: IoCompletionStatus::NvmeError(NvmeStatus::UNWRITTEN_BLOCK),
+            ..
+        }) => {
+            let mut iov = buf.to_io_vec();
+            unsafe { iov.set_len(len) };
+            h.readv_blocks_async(&mut [iov], lba, blocks, ReadOptions::None)
+                .await?;
+            false
+        }
+        Err(error) => return Err(error.into()),
+    };
+    let crc = unsafe { spdk_crc32c_update(buf.as_ptr(), len, SPDK_CRC32C_INITIAL) };
+    Ok(if allocated {
+        Segment::Data(crc)
+    } else {
+        Segment::Unallocated(crc)
+    })
+}
+
+/// Compare devices segment by segment, and print the ranges of blocks whose
+/// data differ.
+async fn verify(uris: &[String], offset: u64, length: Option<u64>, segment: u64) -> Result<()> {
+    let mut handles = Vec::with_capacity(uris.len());
+    for uri in uris {
+        let bdev = device_create(uri).await?;
+        handles.push(device_open(&bdev, false)?.into_handle()?);
+    }
+
+    let block_len = handles[0].get_device().block_len();
+    if handles
+        .iter()
+        .any(|h| h.get_device().block_len() != block_len)
+    {
+        return Err(Error {
+            msg: "The devices have different block sizes".to_string(),
+        });
+    }
+    let num_blocks = handles
+        .iter()
+        .map(|h| h.get_device().num_blocks())
+        .min()
+        .unwrap_or_default();
+    if handles
+        .iter()
+        .any(|h| h.get_device().num_blocks() != num_blocks)
+    {
+        warn!("The devices have different sizes, comparing the first {num_blocks} blocks");
+    }
+
+    let start = offset / block_len;
+    let end = match length {
+        Some(length) => (start + length.div_ceil(block_len)).min(num_blocks),
+        None => num_blocks,
+    };
+    let segment_blocks = (segment / block_len).max(1);
+    let mut buf = handles[0].dma_malloc(segment_blocks * block_len)?;
+
+    info!(
+        "Comparing blocks {start}..{end} of {} bytes of {} devices",
+        block_len,
+        handles.len()
+    );
+
+    let mut mismatches: Vec<Mismatch> = vec![];
+    let mut unallocated = vec![0u64; handles.len()];
+    let mut lba = start;
+    while lba < end {
+        let blocks = segment_blocks.min(end - lba);
+        let mut segments = Vec::with_capacity(handles.len());
+        for (i, h) in handles.iter().enumerate() {
+            let segment = read_segment(h.as_ref(), &mut buf, lba, blocks).await?;
+            if matches!(segment, Segment::Unallocated(_)) {
+                unallocated[i] += blocks;
+            }
+            segments.push(segment);
+        }
+
+        if segments.iter().any(|s| s.crc() != segments[0].crc()) {
+            match mismatches.last_mut() {
+                Some(last) if last.start + last.blocks == lba => last.blocks += blocks,
+                _ => mismatches.push(Mismatch {
+                    start: lba,
+                    blocks,
+                    segments,
+                }),
+            }
+        }
+        lba += blocks;
+    }
+
+    for m in &mismatches {
+        println!(
+            "MISMATCH blocks {}..{} ({} blocks)",
+            m.start,
+            m.start + m.blocks,
+            m.blocks
+        );
+        for (uri, segment) in uris.iter().zip(&m.segments) {
+            println!("    {uri}: {segment}");
+        }
+    }
+    for (uri, blocks) in uris.iter().zip(&unallocated) {
+        println!("{uri}: {blocks} blocks in unallocated segments");
+    }
+
+    let blocks = mismatches.iter().map(|m| m.blocks).sum::<u64>();
+    if mismatches.is_empty() {
+        println!("The devices are identical over blocks {start}..{end}");
+        Ok(())
+    } else {
+        Err(Error {
+            msg: format!(
+                "{} ranges of {blocks} blocks in total differ",
+                mismatches.len()
+            ),
+        })
+    }
+}
+
 /// Connect to the target.
 async fn connect(uri: &str) -> Result<()> {
     let _bdev = create_bdev(uri).await?;
@@ -214,6 +393,31 @@ fn main() {
                 ),
         )
         .subcommand(Command::new("create-snapshot").about("Create a snapshot on the replica"))
+        .subcommand(
+            Command::new("verify")
+                .about("Compare the data of the replica with other replicas, e.g. nexus children")
+                .arg(
+                    Arg::new("OTHER_URI")
+                        .help("URIs of the replicas to compare with")
+                        .required(true)
+                        .index(1)
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("length")
+                        .short('l')
+                        .long("length")
+                        .value_name("NUMBER")
+                        .help("Number of bytes to compare from the offset (default all)"),
+                )
+                .arg(
+                    Arg::new("segment")
+                        .short('s')
+                        .long("segment")
+                        .value_name("NUMBER")
+                        .help("Size of the compared segments in bytes (default 1MiB)"),
+                ),
+        )
         .subcommand_required(true)
         .subcommand_required(true)
         .get_matches();
@@ -251,6 +455,17 @@ fn main() {
             identify_ctrlr(&uri, matches.get_one::<String>("FILE").unwrap()).await
         } else if matches.subcommand_matches("create-snapshot").is_some() {
             create_snapshot(&uri).await
+        } else if let Some(matches) = matches.subcommand_matches("verify") {
+            let mut uris = vec![uri.clone()];
+            uris.extend(matches.get_many::<String>("OTHER_URI").unwrap().cloned());
+            let length = matches
+                .get_one::<String>("length")
+                .map(|val| val.parse().expect("Length must be a number"));
+            let segment = match matches.get_one::<String>("segment") {
+                Some(val) => val.parse().expect("Segment size must be a number"),
+                None => 1024 * 1024,
+            };
+            verify(&uris, offset, length, segment).await
         } else {
             connect(&uri).await
         };
@@ -1,10 +1,11 @@
 use crate::{BdevClient, JsonClient, MayaClient};
 use byte_unit::Byte;
 use bytes::Bytes;
-use clap::ArgMatches;
+use clap::{Arg, ArgMatches};
+use colored_json::ToColoredJson;
 use http::uri::{Authority, PathAndQuery, Scheme, Uri};
 use snafu::{Backtrace, ResultExt, Snafu};
-use std::{cmp::max, str::FromStr};
+use std::{cmp::max, str::FromStr, time::Duration};
 use tonic::transport::Endpoint;
 
 #[derive(Debug, Snafu)]
@@ -37,6 +38,7 @@ pub enum Error {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum OutputFormat {
     Json,
+    Yaml,
     Default,
 }
 
@@ -46,6 +48,7 @@ impl FromStr for OutputFormat {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "json" => Ok(Self::Json),
+            "yaml" => Ok(Self::Yaml),
             "default" => Ok(Self::Default),
             s => Err(Error::OutputFormatInvalid {
                 format: s.to_string(),
@@ -54,6 +57,23 @@ impl FromStr for OutputFormat {
     }
 }
 
+/// Argument to refresh a command on an interval, in seconds.
+pub(crate) fn watch_arg() -> Arg {
+    Arg::new("watch")
+        .long("watch")
+        .num_args(0..=1)
+        .default_missing_value("2")
+        .value_parser(clap::value_parser!(u64).range(1..))
+        .help("refresh every N seconds (default 2), showing the rates since the last refresh")
+}
+
+/// Refresh interval requested with the `watch` argument.
+pub(crate) fn watch_interval(matches: &ArgMatches) -> Option<Duration> {
+    matches
+        .get_one::<u64>("watch")
+        .map(|s| Duration::from_secs(*s))
+}
+
 mod v1 {
     use super::Error;
     use io_engine_api::v1::*;
@@ -212,6 +232,20 @@ impl Context {
         }
     }
 
+    /// Print a response in the requested machine-readable format.
+    pub(crate) fn print_structured<T: serde::Serialize + ?Sized>(&self, value: &T) {
+        match self.output {
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value).unwrap()),
+            _ => println!(
+                "{}",
+                serde_json::to_string_pretty(value)
+                    .unwrap()
+                    .to_colored_json_auto()
+                    .unwrap()
+            ),
+        }
+    }
+
     pub(crate) fn print_list(&self, headers: Vec<&str>, mut data: Vec<Vec<String>>) {
         assert_ne!(data.len(), 0);
         let ncols = data.first().unwrap().len();
@@ -34,6 +34,18 @@ pub enum ClientError {
     },
     #[snafu(display("Missing value for {}", field))]
     MissingValue { field: String },
+    #[snafu(display("Failed to read {}: {}", path, source))]
+    SpecRead {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Invalid document {}: {}", path, source))]
+    SpecParse {
+        path: String,
+        source: serde_yaml::Error,
+    },
+    #[snafu(display("{} resources differ from the document, see the plan", count))]
+    SpecDrift { count: usize },
 }
 
 pub(crate) fn parse_size(src: &str) -> Result<Byte, String> {
@@ -72,15 +72,7 @@ async fn list(mut ctx: Context, _args: &ArgMatches) -> crate::Result<()> {
     let response = ctx.bdev.list(Null {}).await.context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             let bdevs = &response.get_ref().bdevs;
             if bdevs.is_empty() {
@@ -126,15 +118,7 @@ async fn create(mut ctx: Context, args: &ArgMatches) -> crate::Result<()> {
     let response = ctx.bdev.create(BdevUri { uri }).await.context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &response.get_ref().name);
         }
@@ -180,15 +164,7 @@ async fn destroy(mut ctx: Context, args: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", found.name,);
         }
@@ -227,15 +203,7 @@ async fn share(mut ctx: Context, args: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &response.get_ref().uri,);
         }
@@ -258,15 +226,7 @@ async fn unshare(mut ctx: Context, args: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{name}",);
         }
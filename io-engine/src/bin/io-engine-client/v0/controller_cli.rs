@@ -4,7 +4,6 @@
 use super::context::Context;
 use crate::{context::OutputFormat, GrpcStatus};
 use clap::{ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::v0 as rpc;
 use snafu::ResultExt;
 use std::convert::TryFrom;
@@ -52,15 +51,7 @@ async fn controller_stats(mut ctx: Context, _matches: &ArgMatches) -> crate::Res
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let controllers = &response.get_ref().controllers;
             if controllers.is_empty() {
@@ -104,15 +95,7 @@ async fn list_controllers(mut ctx: Context, _matches: &ArgMatches) -> crate::Res
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let controllers = &response.get_ref().controllers;
             if controllers.is_empty() {
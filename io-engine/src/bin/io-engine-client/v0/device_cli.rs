@@ -4,7 +4,6 @@
 use super::context::Context;
 use crate::{context::OutputFormat, GrpcStatus};
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::v0 as rpc;
 use snafu::ResultExt;
 use tonic::Status;
@@ -54,15 +53,7 @@ async fn list_block_devices(mut ctx: Context, matches: &ArgMatches) -> crate::Re
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.into_inner())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            )
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.into_inner()),
         OutputFormat::Default => {
             let devices: &Vec<rpc::BlockDevice> = &response.get_ref().devices;
 
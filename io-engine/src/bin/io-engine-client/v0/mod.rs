@@ -63,7 +63,7 @@ pub(super) async fn main_() -> crate::Result<()> {
                 .long("output")
                 .value_name("FORMAT")
                 .default_value("default")
-                .value_parser(["default", "json"])
+                .value_parser(["default", "json", "yaml"])
                 .global(true)
                 .help("Output format."),
         )
@@ -6,7 +6,6 @@ use crate::{
     ClientError, GrpcStatus,
 };
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::v0 as rpc;
 use snafu::ResultExt;
 use tonic::Status;
@@ -118,15 +117,7 @@ async fn fault(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{uri}");
         }
@@ -160,15 +151,7 @@ async fn child_operation(mut ctx: Context, matches: &ArgMatches, action: i32) ->
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{uri}");
         }
@@ -5,7 +5,6 @@ use crate::{
 };
 use byte_unit::Byte;
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::{v0, v1};
 use snafu::ResultExt;
 use std::convert::TryFrom;
@@ -311,15 +310,7 @@ async fn nexus_create(mut ctx: Context, matches: &ArgMatches) -> crate::Result<(
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &response.get_ref().uuid);
         }
@@ -359,15 +350,7 @@ async fn nexus_create_v2(mut ctx: Context, matches: &ArgMatches) -> crate::Resul
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &response.get_ref().uuid);
         }
@@ -386,15 +369,7 @@ async fn nexus_shutdown(mut ctx: Context, matches: &ArgMatches) -> crate::Result
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uuid,);
         }
@@ -413,15 +388,7 @@ async fn nexus_destroy(mut ctx: Context, matches: &ArgMatches) -> crate::Result<
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uuid,);
         }
@@ -438,15 +405,7 @@ async fn nexus_list(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()>
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let nexus = &response.get_ref().nexus_list;
             if nexus.is_empty() {
@@ -500,15 +459,7 @@ async fn nexus_list_v2(mut ctx: Context, matches: &ArgMatches) -> crate::Result<
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let nexus = &response.get_ref().nexus_list;
             if nexus.is_empty() {
@@ -583,15 +534,7 @@ async fn nexus_children(mut ctx: Context, matches: &ArgMatches) -> crate::Result
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&nexus.children)
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&nexus.children),
         OutputFormat::Default => {
             let table = nexus
                 .children
@@ -640,15 +583,7 @@ async fn nexus_children_2(mut ctx: Context, matches: &ArgMatches) -> crate::Resu
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&nexus.children)
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&nexus.children),
         OutputFormat::Default => {
             let table = nexus
                 .children
@@ -709,15 +644,7 @@ async fn nexus_publish(mut ctx: Context, matches: &ArgMatches) -> crate::Result<
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             println!("{}", response.get_ref().device_uri,)
         }
@@ -741,15 +668,7 @@ async fn nexus_unpublish(mut ctx: Context, matches: &ArgMatches) -> crate::Resul
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uuid,)
         }
@@ -838,15 +757,7 @@ async fn nexus_add(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()>
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uuid,)
         }
@@ -879,15 +790,7 @@ async fn nexus_remove(mut ctx: Context, matches: &ArgMatches) -> crate::Result<(
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uri,)
         }
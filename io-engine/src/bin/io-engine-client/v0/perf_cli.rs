@@ -9,7 +9,6 @@ use super::{
     GrpcStatus,
 };
 use clap::{ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::v0 as rpc;
 use snafu::ResultExt;
 use tonic::Status;
@@ -45,15 +44,7 @@ async fn get_resource_usage(mut ctx: Context, _matches: &ArgMatches) -> crate::R
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             if let Some(usage) = &response.get_ref().usage {
                 table.push(vec![
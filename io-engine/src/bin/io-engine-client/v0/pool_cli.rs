@@ -4,7 +4,6 @@ use crate::{
 };
 use byte_unit::Byte;
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::v0 as rpc;
 use snafu::ResultExt;
 use std::convert::TryFrom;
@@ -77,15 +76,7 @@ async fn create(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &name);
         }
@@ -109,15 +100,7 @@ async fn destroy(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &name);
         }
@@ -136,15 +119,7 @@ async fn list(mut ctx: Context, _matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             let pools: &Vec<rpc::Pool> = &response.get_ref().pools;
             if pools.is_empty() {
@@ -6,7 +6,6 @@ use crate::{
     ClientError, GrpcStatus,
 };
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::v0 as rpc;
 use snafu::ResultExt;
 use tonic::Status;
@@ -169,15 +168,7 @@ async fn start(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uri);
         }
@@ -210,15 +201,7 @@ async fn stop(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uri);
         }
@@ -251,15 +234,7 @@ async fn pause(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uri);
         }
@@ -292,15 +267,7 @@ async fn resume(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uri);
         }
@@ -333,15 +300,7 @@ async fn state(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             ctx.print_list(vec!["state"], vec![vec![response.get_ref().state.clone()]]);
         }
@@ -377,15 +336,7 @@ async fn stats(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let response = &response.get_ref();
             ctx.print_list(
@@ -441,15 +392,7 @@ async fn progress(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             ctx.print_list(
                 vec!["progress (%)"],
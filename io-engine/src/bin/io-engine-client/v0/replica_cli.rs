@@ -4,7 +4,6 @@ use crate::{
 };
 use byte_unit::Byte;
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::v0 as rpc;
 use snafu::ResultExt;
 use std::convert::TryFrom;
@@ -203,15 +202,7 @@ async fn replica_create(mut ctx: Context, matches: &ArgMatches) -> crate::Result
     let response = ctx.client.create_replica(rq).await.context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &response.get_ref().uri);
         }
@@ -270,15 +261,7 @@ async fn replica_create_v2(mut ctx: Context, matches: &ArgMatches) -> crate::Res
     let response = ctx.client.create_replica_v2(rq).await.context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &response.get_ref().uri);
         }
@@ -302,15 +285,7 @@ async fn replica_destroy(mut ctx: Context, matches: &ArgMatches) -> crate::Resul
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uuid);
         }
@@ -327,15 +302,7 @@ async fn replica_list(mut ctx: Context, _matches: &ArgMatches) -> crate::Result<
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let replicas = &response.get_ref().replicas;
             if replicas.is_empty() {
@@ -376,15 +343,7 @@ async fn replica_list2(mut ctx: Context, _matches: &ArgMatches) -> crate::Result
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let replicas = &response.get_ref().replicas;
             if replicas.is_empty() {
@@ -439,15 +398,7 @@ async fn replica_share(mut ctx: Context, matches: &ArgMatches) -> crate::Result<
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &response.get_ref().uri);
         }
@@ -464,15 +415,7 @@ async fn replica_stat(mut ctx: Context, _matches: &ArgMatches) -> crate::Result<
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             let replicas = &response.get_ref().replicas;
             if replicas.is_empty() {
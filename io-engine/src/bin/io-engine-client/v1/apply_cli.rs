@@ -0,0 +1,789 @@
+//!
+//! methods to apply a desired state document to the node, e.g. to reproduce
+//! the layout of another node for testing.
+//!
+//! The document lists the pools, replicas, snapshots and nexuses which must
+//! exist. It's compared with the current resources, and only the missing
+//! resources are created, or the shares which differ are changed. Resources
+//! which are not in the document are left untouched.
+//!
+//! Existing resources whose disks, size or thin provisioning differ from the
+//! document can't be changed this way: they're reported as drift, and nothing
+//! is applied until they're fixed by hand.
+
+use super::pool_cli::PoolType;
+use crate::{
+    context::{Context, OutputFormat},
+    parse_size, ClientError, GrpcStatus, SpecParse, SpecRead,
+};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use io_engine_api::v1 as v1rpc;
+use snafu::ResultExt;
+use std::{fmt, str::FromStr};
+use tonic::Status;
+
+pub fn subcommands() -> Command {
+    Command::new("apply")
+        .about("create the resources of a desired state document which don't exist yet")
+        .arg(
+            Arg::new("filename")
+                .short('f')
+                .long("filename")
+                .required(true)
+                .value_name("FILE")
+                .help("YAML or JSON document with the pools, replicas, snapshots and nexuses"),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("only show the plan, without changing anything"),
+        )
+}
+
+/// Size of a resource, either in bytes or with units, e.g. `64MiB`.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(untagged)]
+enum Size {
+    Bytes(u64),
+    Text(String),
+}
+
+impl Size {
+    fn bytes(&self) -> crate::Result<u64> {
+        match self {
+            Self::Bytes(bytes) => Ok(*bytes),
+            Self::Text(text) => parse_size(text)
+                .map(|size| size.as_u64())
+                .map_err(|s| Status::invalid_argument(format!("Bad size '{s}'")))
+                .context(GrpcStatus),
+        }
+    }
+}
+
+/// Share protocol of a replica or nexus.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Share {
+    #[default]
+    None,
+    Nvmf,
+}
+
+impl From<Share> for v1rpc::common::ShareProtocol {
+    fn from(share: Share) -> Self {
+        match share {
+            Share::None => Self::None,
+            Share::Nvmf => Self::Nvmf,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PoolSpec {
+    name: String,
+    uuid: Option<String>,
+    disks: Vec<String>,
+    #[serde(default = "PoolSpec::default_type", rename = "type")]
+    pooltype: String,
+    cluster_size: Option<Size>,
+}
+
+impl PoolSpec {
+    fn default_type() -> String {
+        PoolType::Lvs.as_ref().to_string()
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReplicaSpec {
+    name: String,
+    uuid: String,
+    /// Name or uuid of the pool.
+    pool: String,
+    size: Size,
+    #[serde(default)]
+    thin: bool,
+    #[serde(default)]
+    share: Share,
+    #[serde(default)]
+    allowed_hosts: Vec<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SnapshotSpec {
+    /// Uuid of the replica the snapshot is taken from.
+    replica: String,
+    uuid: String,
+    name: String,
+    #[serde(default)]
+    entity_id: String,
+    #[serde(default)]
+    txn_id: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NexusSpec {
+    uuid: String,
+    name: Option<String>,
+    size: Size,
+    children: Vec<String>,
+    #[serde(default)]
+    share: Share,
+    #[serde(default)]
+    allowed_hosts: Vec<String>,
+    #[serde(default)]
+    nexus_info_key: String,
+    #[serde(default = "NexusSpec::default_min_cntl_id")]
+    min_cntl_id: u32,
+    #[serde(default = "NexusSpec::default_max_cntl_id")]
+    max_cntl_id: u32,
+    #[serde(default)]
+    resv_key: u64,
+}
+
+impl NexusSpec {
+    fn default_min_cntl_id() -> u32 {
+        1
+    }
+    fn default_max_cntl_id() -> u32 {
+        0xffef
+    }
+}
+
+/// Desired state of the node.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Spec {
+    #[serde(default)]
+    pools: Vec<PoolSpec>,
+    #[serde(default)]
+    replicas: Vec<ReplicaSpec>,
+    #[serde(default)]
+    snapshots: Vec<SnapshotSpec>,
+    #[serde(default)]
+    nexuses: Vec<NexusSpec>,
+}
+
+/// A call needed to reach the desired state.
+#[derive(Debug)]
+enum Step {
+    CreatePool(PoolSpec),
+    CreateReplica(ReplicaSpec),
+    ShareReplica(ReplicaSpec),
+    UnshareReplica(ReplicaSpec),
+    CreateSnapshot(SnapshotSpec),
+    CreateNexus(NexusSpec),
+    AddChild { nexus: String, uri: String },
+    PublishNexus(NexusSpec),
+    UnpublishNexus(NexusSpec),
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CreatePool(p) => write!(f, "create pool {} on {}", p.name, p.disks.join(",")),
+            Self::CreateReplica(r) => write!(f, "create replica {} on pool {}", r.uuid, r.pool),
+            Self::ShareReplica(r) => write!(f, "share replica {} over nvmf", r.uuid),
+            Self::UnshareReplica(r) => write!(f, "unshare replica {}", r.uuid),
+            Self::CreateSnapshot(s) => {
+                write!(f, "create snapshot {} of replica {}", s.uuid, s.replica)
+            }
+            Self::CreateNexus(n) => write!(f, "create nexus {}", n.uuid),
+            Self::AddChild { nexus, uri } => write!(f, "add child {uri} to nexus {nexus}"),
+            Self::PublishNexus(n) => write!(f, "publish nexus {} over nvmf", n.uuid),
+            Self::UnpublishNexus(n) => write!(f, "unpublish nexus {}", n.uuid),
+        }
+    }
+}
+
+/// A field of an existing resource which differs from the document, and which
+/// can't be changed by applying it.
+#[derive(Debug, PartialEq)]
+struct Drift {
+    resource: String,
+    field: &'static str,
+    current: String,
+    desired: String,
+}
+
+impl Drift {
+    fn new(
+        resource: String,
+        field: &'static str,
+        current: impl fmt::Debug,
+        desired: impl fmt::Debug,
+    ) -> Self {
+        Self {
+            resource,
+            field,
+            current: format!("{current:?}"),
+            desired: format!("{desired:?}"),
+        }
+    }
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} has {} {}, the document wants {}",
+            self.resource, self.field, self.current, self.desired
+        )
+    }
+}
+
+/// The calls needed to reach the desired state, and the differences they
+/// can't fix.
+#[derive(Debug, Default)]
+struct Plan {
+    steps: Vec<Step>,
+    drift: Vec<Drift>,
+}
+
+/// The current resources of the node.
+#[derive(Debug, Default)]
+struct Current {
+    pools: Vec<v1rpc::pool::Pool>,
+    replicas: Vec<v1rpc::replica::Replica>,
+    snapshots: Vec<v1rpc::snapshot::SnapshotInfo>,
+    nexuses: Vec<v1rpc::nexus::Nexus>,
+}
+
+pub async fn handler(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let path = matches
+        .get_one::<String>("filename")
+        .ok_or_else(|| ClientError::MissingValue {
+            field: "filename".to_string(),
+        })?;
+    let text = std::fs::read_to_string(path).context(SpecRead { path })?;
+    // YAML is a superset of JSON, so both are accepted.
+    let spec: Spec = serde_yaml::from_str(&text).context(SpecParse { path })?;
+
+    let current = current(&mut ctx).await?;
+    let plan = plan(spec, &current)?;
+    let dry_run = matches.get_flag("dry-run");
+
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => {
+            let steps = plan
+                .steps
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            let drift = plan
+                .drift
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            ctx.print_structured(
+                &serde_json::json!({ "steps": steps, "drift": drift, "dry_run": dry_run }),
+            );
+        }
+        OutputFormat::Default => {
+            if plan.steps.is_empty() && plan.drift.is_empty() {
+                println!("Nothing to do, the node is up to date");
+            }
+            for (i, step) in plan.steps.iter().enumerate() {
+                println!("{:>3}. {step}", i + 1);
+            }
+            for drift in &plan.drift {
+                println!("drift: {drift}");
+            }
+        }
+    }
+    if dry_run {
+        return Ok(());
+    }
+    if !plan.drift.is_empty() {
+        return Err(ClientError::SpecDrift {
+            count: plan.drift.len(),
+        });
+    }
+
+    for step in plan.steps {
+        ctx.v1(&format!("Applying: {step}"));
+        apply(&mut ctx, step).await?;
+    }
+    Ok(())
+}
+
+/// List the current resources of the node.
+async fn current(ctx: &mut Context) -> crate::Result<Current> {
+    let pools = ctx
+        .v1
+        .pool
+        .list_pools(v1rpc::pool::ListPoolOptions {
+            name: None,
+            uuid: None,
+            pooltype: None,
+        })
+        .await
+        .context(GrpcStatus)?
+        .into_inner()
+        .pools;
+    let replicas = ctx
+        .v1
+        .replica
+        .list_replicas(v1rpc::replica::ListReplicaOptions {
+            name: None,
+            poolname: None,
+            uuid: None,
+            pooluuid: None,
+            query: None,
+            pooltypes: vec![],
+        })
+        .await
+        .context(GrpcStatus)?
+        .into_inner()
+        .replicas;
+    let snapshots = ctx
+        .v1
+        .snapshot
+        .list_snapshot(v1rpc::snapshot::ListSnapshotsRequest {
+            source_uuid: None,
+            snapshot_uuid: None,
+            query: None,
+        })
+        .await
+        .context(GrpcStatus)?
+        .into_inner()
+        .snapshots;
+    let nexuses = ctx
+        .v1
+        .nexus
+        .list_nexus(v1rpc::nexus::ListNexusOptions {
+            name: None,
+            uuid: None,
+        })
+        .await
+        .context(GrpcStatus)?
+        .into_inner()
+        .nexus_list;
+
+    Ok(Current {
+        pools,
+        replicas,
+        snapshots,
+        nexuses,
+    })
+}
+
+/// Compare the document with the current resources, and return the calls
+/// needed to reach it, in order, and the differences they can't fix.
+fn plan(spec: Spec, current: &Current) -> crate::Result<Plan> {
+    let mut plan = Plan::default();
+
+    for pool in spec.pools {
+        let existing = current
+            .pools
+            .iter()
+            .find(|p| p.name == pool.name || Some(&p.uuid) == pool.uuid.as_ref());
+        match existing {
+            None => plan.steps.push(Step::CreatePool(pool)),
+            Some(existing) => {
+                let mut disks = existing
+                    .disks
+                    .iter()
+                    .map(|d| disk_path(d))
+                    .collect::<Vec<_>>();
+                let mut desired = pool.disks.iter().map(|d| disk_path(d)).collect::<Vec<_>>();
+                disks.sort_unstable();
+                desired.sort_unstable();
+                if disks != desired {
+                    plan.drift.push(Drift::new(
+                        format!("pool {}", pool.name),
+                        "disks",
+                        disks,
+                        desired,
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut shares = vec![];
+    for replica in spec.replicas {
+        let Some(existing) = current.replicas.iter().find(|r| r.uuid == replica.uuid) else {
+            plan.steps.push(Step::CreateReplica(replica));
+            continue;
+        };
+        let resource = format!("replica {}", replica.uuid);
+        // The replicas are allocated in clusters of their pool.
+        let cluster_size = current
+            .pools
+            .iter()
+            .find(|p| p.name == replica.pool || p.uuid == replica.pool)
+            .map_or(1, |p| u64::from(p.cluster_size).max(1));
+        let size = replica.size.bytes()?;
+        let size = (size + cluster_size - 1) / cluster_size * cluster_size;
+        if existing.size != size {
+            plan.drift
+                .push(Drift::new(resource.clone(), "size", existing.size, size));
+        }
+        if existing.thin != replica.thin {
+            plan.drift
+                .push(Drift::new(resource, "thin", existing.thin, replica.thin));
+        }
+
+        let share = v1rpc::common::ShareProtocol::from(replica.share) as i32;
+        let hosts_differ = existing.allowed_hosts != replica.allowed_hosts;
+        match replica.share {
+            Share::None if existing.share != share => shares.push(Step::UnshareReplica(replica)),
+            Share::Nvmf if existing.share != share || hosts_differ => {
+                shares.push(Step::ShareReplica(replica))
+            }
+            _ => {}
+        }
+    }
+    plan.steps.extend(shares);
+
+    for snapshot in spec.snapshots {
+        if !current
+            .snapshots
+            .iter()
+            .any(|s| s.snapshot_uuid == snapshot.uuid)
+        {
+            plan.steps.push(Step::CreateSnapshot(snapshot));
+        }
+    }
+
+    let mut publishes = vec![];
+    for nexus in spec.nexuses {
+        let Some(existing) = current.nexuses.iter().find(|n| n.uuid == nexus.uuid) else {
+            if nexus.share == Share::Nvmf {
+                publishes.push(Step::PublishNexus(nexus.clone()));
+            }
+            plan.steps.push(Step::CreateNexus(nexus));
+            continue;
+        };
+        let size = nexus.size.bytes()?;
+        if existing.size != size {
+            plan.drift.push(Drift::new(
+                format!("nexus {}", nexus.uuid),
+                "size",
+                existing.size,
+                size,
+            ));
+        }
+        for uri in &nexus.children {
+            if !existing.children.iter().any(|c| &c.uri == uri) {
+                plan.steps.push(Step::AddChild {
+                    nexus: nexus.uuid.clone(),
+                    uri: uri.clone(),
+                });
+            }
+        }
+        let published = !existing.device_uri.is_empty();
+        match nexus.share {
+            Share::None if published => publishes.push(Step::UnpublishNexus(nexus)),
+            // Publishing a published nexus again updates its allowed hosts.
+            Share::Nvmf if !published || existing.allowed_hosts != nexus.allowed_hosts => {
+                publishes.push(Step::PublishNexus(nexus))
+            }
+            _ => {}
+        }
+    }
+    plan.steps.extend(publishes);
+
+    Ok(plan)
+}
+
+/// The path of a pool disk, without the parameters of its URI, which the pool
+/// may have added to, e.g. with the uuid of the bdev.
+fn disk_path(disk: &str) -> &str {
+    disk.split('?').next().unwrap_or(disk)
+}
+
+/// Perform a step of the plan.
+async fn apply(ctx: &mut Context, step: Step) -> crate::Result<()> {
+    match step {
+        Step::CreatePool(pool) => {
+            let pooltype = PoolType::from_str(&pool.pooltype)
+                .map_err(|e| Status::invalid_argument(e.to_string()))
+                .context(GrpcStatus)?;
+            let cluster_size = match &pool.cluster_size {
+                Some(size) => Some(size.bytes()? as u32),
+                None => None,
+            };
+            ctx.v1
+                .pool
+                .create_pool(v1rpc::pool::CreatePoolRequest {
+                    name: pool.name,
+                    uuid: pool.uuid,
+                    disks: pool.disks,
+                    pooltype: v1rpc::pool::PoolType::from(pooltype) as i32,
+                    cluster_size,
+                    md_args: None,
+                })
+                .await
+                .context(GrpcStatus)?;
+        }
+        Step::CreateReplica(replica) => {
+            ctx.v1
+                .replica
+                .create_replica(v1rpc::replica::CreateReplicaRequest {
+                    name: replica.name,
+                    uuid: replica.uuid,
+                    entity_id: None,
+                    pooluuid: replica.pool,
+                    thin: replica.thin,
+                    share: v1rpc::common::ShareProtocol::from(replica.share) as i32,
+                    size: replica.size.bytes()?,
+                    allowed_hosts: replica.allowed_hosts,
+                })
+                .await
+                .context(GrpcStatus)?;
+        }
+        Step::ShareReplica(replica) => {
+            ctx.v1
+                .replica
+                .share_replica(v1rpc::replica::ShareReplicaRequest {
+                    uuid: replica.uuid,
+                    share: v1rpc::common::ShareProtocol::Nvmf as i32,
+                    allowed_hosts: replica.allowed_hosts,
+                })
+                .await
+                .context(GrpcStatus)?;
+        }
+        Step::UnshareReplica(replica) => {
+            ctx.v1
+                .replica
+                .unshare_replica(v1rpc::replica::UnshareReplicaRequest { uuid: replica.uuid })
+                .await
+                .context(GrpcStatus)?;
+        }
+        Step::CreateSnapshot(snapshot) => {
+            ctx.v1
+                .snapshot
+                .create_replica_snapshot(v1rpc::snapshot::CreateReplicaSnapshotRequest {
+                    replica_uuid: snapshot.replica,
+                    snapshot_uuid: snapshot.uuid,
+                    snapshot_name: snapshot.name,
+                    entity_id: snapshot.entity_id,
+                    txn_id: snapshot.txn_id,
+                })
+                .await
+                .context(GrpcStatus)?;
+        }
+        Step::CreateNexus(nexus) => {
+            ctx.v1
+                .nexus
+                .create_nexus(v1rpc::nexus::CreateNexusRequest {
+                    name: nexus.name.unwrap_or_else(|| nexus.uuid.clone()),
+                    uuid: nexus.uuid,
+                    size: nexus.size.bytes()?,
+                    min_cntl_id: nexus.min_cntl_id,
+                    max_cntl_id: nexus.max_cntl_id,
+                    resv_key: nexus.resv_key,
+                    preempt_key: 0,
+                    children: nexus.children,
+                    nexus_info_key: nexus.nexus_info_key,
+                    resv_type: None,
+                    preempt_policy: 0,
+                })
+                .await
+                .context(GrpcStatus)?;
+        }
+        Step::AddChild { nexus, uri } => {
+            ctx.v1
+                .nexus
+                .add_child_nexus(v1rpc::nexus::AddChildNexusRequest {
+                    uuid: nexus,
+                    uri,
+                    norebuild: false,
+                })
+                .await
+                .context(GrpcStatus)?;
+        }
+        Step::PublishNexus(nexus) => {
+            ctx.v1
+                .nexus
+                .publish_nexus(v1rpc::nexus::PublishNexusRequest {
+                    uuid: nexus.uuid,
+                    key: String::new(),
+                    share: v1rpc::common::ShareProtocol::Nvmf as i32,
+                    allowed_hosts: nexus.allowed_hosts,
+                })
+                .await
+                .context(GrpcStatus)?;
+        }
+        Step::UnpublishNexus(nexus) => {
+            ctx.v1
+                .nexus
+                .unpublish_nexus(v1rpc::nexus::UnpublishNexusRequest { uuid: nexus.uuid })
+                .await
+                .context(GrpcStatus)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{plan, Current, Drift, Spec, Step};
+    use io_engine_api::v1 as v1rpc;
+
+    const MIB: u64 = 1024 * 1024;
+
+    fn spec(yaml: &str) -> Spec {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn current() -> Current {
+        Current {
+            pools: vec![v1rpc::pool::Pool {
+                name: "pool-1".into(),
+                uuid: "pool-1-uuid".into(),
+                disks: vec!["aio:///dev/sdb?uuid=b1".into()],
+                cluster_size: 4 * MIB as u32,
+                ..Default::default()
+            }],
+            replicas: vec![v1rpc::replica::Replica {
+                name: "replica-1".into(),
+                uuid: "r1".into(),
+                size: 8 * MIB,
+                thin: true,
+                share: v1rpc::common::ShareProtocol::Nvmf as i32,
+                ..Default::default()
+            }],
+            snapshots: vec![],
+            nexuses: vec![v1rpc::nexus::Nexus {
+                uuid: "n1".into(),
+                size: 8 * MIB,
+                device_uri: "nvmf://host/nqn".into(),
+                allowed_hosts: vec!["host-1".into()],
+                children: vec![v1rpc::nexus::Child {
+                    uri: "bdev:///replica-1".into(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    const UP_TO_DATE: &str = r#"
+pools:
+  - name: pool-1
+    disks: ["aio:///dev/sdb"]
+replicas:
+  - name: replica-1
+    uuid: r1
+    pool: pool-1
+    size: 6MiB
+    thin: true
+    share: nvmf
+nexuses:
+  - uuid: n1
+    size: 8MiB
+    children: ["bdev:///replica-1"]
+    share: nvmf
+    allowed_hosts: ["host-1"]
+"#;
+
+    #[test]
+    fn plan_up_to_date() {
+        let plan = plan(spec(UP_TO_DATE), &current()).unwrap();
+        assert!(plan.steps.is_empty(), "{:?}", plan.steps);
+        assert!(plan.drift.is_empty(), "{:?}", plan.drift);
+    }
+
+    #[test]
+    fn plan_missing() {
+        let plan = plan(spec(UP_TO_DATE), &Current::default()).unwrap();
+        let steps = plan
+            .steps
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            steps,
+            vec![
+                "create pool pool-1 on aio:///dev/sdb",
+                "create replica r1 on pool pool-1",
+                "create nexus n1",
+                "publish nexus n1 over nvmf",
+            ]
+        );
+        match &plan.steps[2] {
+            Step::CreateNexus(nexus) => {
+                assert_eq!(nexus.min_cntl_id, 1);
+                assert_eq!(nexus.max_cntl_id, 0xffef);
+                assert_eq!(nexus.resv_key, 0);
+            }
+            step => panic!("unexpected step {step}"),
+        }
+    }
+
+    #[test]
+    fn plan_nexus_ids() {
+        let plan = plan(
+            spec("nexuses: [{uuid: n2, size: 8MiB, children: [], min_cntl_id: 10, max_cntl_id: 20, resv_key: 7}]"),
+            &current(),
+        )
+        .unwrap();
+        match &plan.steps[..] {
+            [Step::CreateNexus(nexus)] => {
+                assert_eq!(nexus.min_cntl_id, 10);
+                assert_eq!(nexus.max_cntl_id, 20);
+                assert_eq!(nexus.resv_key, 7);
+            }
+            steps => panic!("unexpected steps {steps:?}"),
+        }
+    }
+
+    #[test]
+    fn plan_drift() {
+        let yaml = UP_TO_DATE
+            .replace("/dev/sdb", "/dev/sdc")
+            .replace("size: 6MiB", "size: 12MiB")
+            .replace("thin: true", "thin: false")
+            .replace("size: 8MiB", "size: 16MiB");
+        let plan = plan(spec(&yaml), &current()).unwrap();
+        assert!(plan.steps.is_empty(), "{:?}", plan.steps);
+        assert_eq!(
+            plan.drift,
+            vec![
+                Drift::new(
+                    "pool pool-1".into(),
+                    "disks",
+                    vec!["aio:///dev/sdb"],
+                    vec!["aio:///dev/sdc"]
+                ),
+                Drift::new("replica r1".into(), "size", 8 * MIB, 12 * MIB),
+                Drift::new("replica r1".into(), "thin", true, false),
+                Drift::new("nexus n1".into(), "size", 8 * MIB, 16 * MIB),
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_shares() {
+        let yaml = UP_TO_DATE
+            .replace("share: nvmf\nnexuses", "share: none\nnexuses")
+            .replace("[\"host-1\"]", "[\"host-2\"]")
+            .replace(
+                "children: [\"bdev:///replica-1\"]",
+                "children: [\"bdev:///replica-1\", \"nvmf://other/r2\"]",
+            );
+        let plan = plan(spec(&yaml), &current()).unwrap();
+        let steps = plan
+            .steps
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            steps,
+            vec![
+                "unshare replica r1",
+                "add child nvmf://other/r2 to nexus n1",
+                "publish nexus n1 over nvmf",
+            ]
+        );
+        assert!(plan.drift.is_empty(), "{:?}", plan.drift);
+    }
+}
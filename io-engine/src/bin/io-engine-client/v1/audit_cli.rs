@@ -0,0 +1,136 @@
+//!
+//! methods to query the audit log of the mutating gRPC calls
+
+use super::jsonrpc_cli::json_call;
+use crate::{
+    context::{Context, OutputFormat},
+    GrpcStatus,
+};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use snafu::ResultExt;
+use tonic::Status;
+
+pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    match matches.subcommand().unwrap() {
+        ("list", args) => list(ctx, args).await,
+        (cmd, _) => {
+            Err(Status::not_found(format!("command {cmd} does not exist"))).context(GrpcStatus)
+        }
+    }
+}
+
+pub fn subcommands() -> Command {
+    let list = Command::new("list")
+        .about("list the audited gRPC calls, from the oldest to the newest")
+        .arg(
+            Arg::new("since")
+                .long("since")
+                .value_parser(clap::value_parser!(u64))
+                .help("only the calls of the last given seconds"),
+        )
+        .arg(
+            Arg::new("method")
+                .long("method")
+                .help("only the calls of the given gRPC method"),
+        )
+        .arg(
+            Arg::new("resource")
+                .long("resource")
+                .help("only the calls which touched the resource with the given uuid"),
+        )
+        .arg(
+            Arg::new("caller")
+                .long("caller")
+                .help("only the calls whose caller identity contains the given string"),
+        )
+        .arg(
+            Arg::new("failed")
+                .long("failed")
+                .action(ArgAction::SetTrue)
+                .help("only the failed calls"),
+        )
+        .arg(
+            Arg::new("limit")
+                .long("limit")
+                .short('n')
+                .value_parser(clap::value_parser!(usize))
+                .help("maximum number of calls, the newest ones are listed"),
+        );
+
+    Command::new("audit")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .about("Audit Log Management")
+        .subcommand(list)
+}
+
+async fn list(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let response = json_call(
+        &mut ctx,
+        "audit_log",
+        serde_json::json!({
+            "since_secs": matches.get_one::<u64>("since"),
+            "method": matches.get_one::<String>("method"),
+            "resource": matches.get_one::<String>("resource"),
+            "caller": matches.get_one::<String>("caller"),
+            "failed": matches.get_flag("failed"),
+            "limit": matches.get_one::<usize>("limit"),
+        }),
+    )
+    .await?;
+
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response),
+        OutputFormat::Default => {
+            let records = match response {
+                serde_json::Value::Array(records) => records,
+                _ => vec![],
+            };
+            if records.is_empty() {
+                return Ok(());
+            }
+            let field = |r: &serde_json::Value, name: &str| match &r[name] {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Null => "".to_string(),
+                value => value.to_string(),
+            };
+            let table = records
+                .iter()
+                .map(|r| {
+                    let resources = r["resources"]
+                        .as_array()
+                        .map(|uuids| {
+                            uuids
+                                .iter()
+                                .filter_map(|u| u.as_str())
+                                .collect::<Vec<_>>()
+                                .join(",")
+                        })
+                        .unwrap_or_default();
+                    vec![
+                        field(r, "id"),
+                        field(r, "timestamp"),
+                        field(r, "method"),
+                        field(r, "caller"),
+                        field(r, "result"),
+                        field(r, "duration_ms"),
+                        resources,
+                    ]
+                })
+                .collect();
+            ctx.print_list(
+                vec![
+                    "ID",
+                    "TIMESTAMP",
+                    "METHOD",
+                    "CALLER",
+                    "RESULT",
+                    "DURATION_MS",
+                    "RESOURCES",
+                ],
+                table,
+            );
+        }
+    }
+    Ok(())
+}
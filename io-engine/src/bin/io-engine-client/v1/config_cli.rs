@@ -0,0 +1,71 @@
+//!
+//! methods to reload the configuration file at runtime
+
+use super::jsonrpc_cli::json_call;
+use crate::{
+    context::{Context, OutputFormat},
+    GrpcStatus,
+};
+use clap::{ArgMatches, Command};
+use snafu::ResultExt;
+use tonic::Status;
+
+pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    match matches.subcommand().unwrap() {
+        ("reload", args) => reload(ctx, args).await,
+        (cmd, _) => {
+            Err(Status::not_found(format!("command {cmd} does not exist"))).context(GrpcStatus)
+        }
+    }
+}
+
+pub fn subcommands() -> Command {
+    let reload = Command::new("reload").about(
+        "re-read the configuration file and apply the settings which can be changed at runtime",
+    );
+
+    Command::new("config")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .about("Configuration File")
+        .subcommand(reload)
+}
+
+async fn reload(mut ctx: Context, _matches: &ArgMatches) -> crate::Result<()> {
+    let response = json_call(&mut ctx, "mayastor_config_reload", serde_json::json!({})).await?;
+
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response),
+        OutputFormat::Default => {
+            let settings = |name: &str| {
+                response[name]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|s| s.as_str().map(|s| s.to_string()))
+                    .collect::<Vec<_>>()
+            };
+            let rows = settings("applied")
+                .into_iter()
+                .map(|s| vec![s, "applied".to_string()])
+                .chain(
+                    settings("applied_to_new_controllers")
+                        .into_iter()
+                        .map(|s| vec![s, "applied to new controllers".to_string()]),
+                )
+                .chain(
+                    settings("restart_required")
+                        .into_iter()
+                        .map(|s| vec![s, "restart required".to_string()]),
+                )
+                .collect::<Vec<_>>();
+            if rows.is_empty() {
+                println!("No configuration changes");
+            } else {
+                ctx.print_list(vec!["SETTING", "STATUS"], rows);
+            }
+        }
+    }
+
+    Ok(())
+}
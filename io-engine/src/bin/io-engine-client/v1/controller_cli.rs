@@ -1,15 +1,24 @@
 //!
 //! methods to interact with NVMe controllers
 
-use super::context::Context;
+use super::{context::Context, jsonrpc_cli::json_call};
 use crate::{context::OutputFormat, GrpcStatus};
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::v1 as v1rpc;
 use snafu::ResultExt;
 use std::convert::TryFrom;
 use tonic::Status;
 
+/// Fields of the timeout settings which are listed with the controller stats.
+const TIMEOUT_FIELDS: [&str; 6] = [
+    "io_timeout_us",
+    "admin_timeout_us",
+    "action",
+    "handler_registered",
+    "retry_count",
+    "pending_retry_count",
+];
+
 pub fn subcommands() -> Command {
     let list = Command::new("list").about("List existing NVMe controllers");
     let stats = Command::new("stats")
@@ -64,15 +73,19 @@ async fn controller_stats(mut ctx: Context, matches: &ArgMatches) -> crate::Resu
         .await
         .context(GrpcStatus)?;
 
+    // The gRPC stats have no fields for the timeout settings in effect.
+    let timeouts = json_call(
+        &mut ctx,
+        "nvme_controller_timeouts",
+        serde_json::json!({ "name": name }),
+    )
+    .await?;
+
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
+        OutputFormat::Json | OutputFormat::Yaml => {
+            let mut value = serde_json::to_value(response.get_ref()).unwrap();
+            value["timeouts"] = timeouts;
+            ctx.print_structured(&value)
         }
         OutputFormat::Default => {
             let controllers = &response.get_ref().stats;
@@ -93,7 +106,7 @@ async fn controller_stats(mut ctx: Context, matches: &ArgMatches) -> crate::Resu
                     let bytes_written = c.bytes_written.to_string();
                     let num_unmap_ops = c.num_unmap_ops.to_string();
                     let bytes_unmapped = c.bytes_unmapped.to_string();
-                    vec![
+                    let mut row = vec![
                         name.to_string(),
                         num_read_ops,
                         num_write_ops,
@@ -101,7 +114,13 @@ async fn controller_stats(mut ctx: Context, matches: &ArgMatches) -> crate::Resu
                         bytes_written,
                         num_unmap_ops,
                         bytes_unmapped,
-                    ]
+                    ];
+                    row.extend(TIMEOUT_FIELDS.iter().map(|field| match &timeouts[field] {
+                        serde_json::Value::Null => String::new(),
+                        serde_json::Value::String(value) => value.clone(),
+                        value => value.to_string(),
+                    }));
+                    row
                 }
             };
 
@@ -113,6 +132,12 @@ async fn controller_stats(mut ctx: Context, matches: &ArgMatches) -> crate::Resu
                 "WRITTEN/B",
                 "NUM_UNMAPS",
                 "BYTES_UNMAPPED",
+                "IO_TIMEOUT_US",
+                "ADMIN_TIMEOUT_US",
+                "TIMEOUT_ACTION",
+                "TIMEOUT_HANDLER",
+                "RETRY_COUNT",
+                "PENDING_RETRY_COUNT",
             ];
             ctx.print_list(hdr, vec![row]);
         }
@@ -130,15 +155,7 @@ async fn list_controllers(mut ctx: Context, _matches: &ArgMatches) -> crate::Res
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let controllers = &response.get_ref().controllers;
             if controllers.is_empty() {
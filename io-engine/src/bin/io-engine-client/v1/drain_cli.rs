@@ -0,0 +1,198 @@
+//!
+//! methods to drain the node before stopping it
+
+use super::jsonrpc_cli::json_call;
+use crate::{
+    context::{Context, OutputFormat},
+    ClientError, GrpcStatus,
+};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use snafu::ResultExt;
+use std::time::Duration;
+use tonic::Status;
+
+/// How often the drain progress is polled while waiting for it.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    match matches.subcommand().unwrap() {
+        ("start", args) => start(ctx, args).await,
+        ("status", args) => status(ctx, args).await,
+        ("undrain", args) => undrain(ctx, args).await,
+        (cmd, _) => {
+            Err(Status::not_found(format!("command {cmd} does not exist"))).context(GrpcStatus)
+        }
+    }
+}
+
+pub fn subcommands() -> Command {
+    let start = Command::new("start")
+        .about("start draining the node, which can be undone until the nexuses are shut down")
+        .arg(
+            Arg::new("ana-state")
+                .long("ana-state")
+                .value_parser(["non_optimized", "inaccessible"])
+                .default_value("inaccessible")
+                .help("ANA state of the nexuses while draining"),
+        )
+        .arg(
+            Arg::new("rebuild-timeout")
+                .long("rebuild-timeout")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("300")
+                .help(
+                    "seconds to wait for the rebuilds to finish before pausing them, 0 pauses \
+                    them right away",
+                ),
+        )
+        .arg(
+            Arg::new("wait")
+                .long("wait")
+                .short('w')
+                .action(ArgAction::SetTrue)
+                .help("wait until the node is safe to stop"),
+        );
+
+    let status = Command::new("status")
+        .about("show the progress of the drain")
+        .arg(
+            Arg::new("wait")
+                .long("wait")
+                .short('w')
+                .action(ArgAction::SetTrue)
+                .help("wait until the node is safe to stop"),
+        );
+
+    let undrain = Command::new("undrain")
+        .about("undo the drain, unless the nexuses are already shut down")
+        .arg(
+            Arg::new("wait")
+                .long("wait")
+                .short('w')
+                .action(ArgAction::SetTrue)
+                .help("wait until the node is serving again"),
+        );
+
+    Command::new("drain")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .about("Node Drain Management")
+        .subcommand(start)
+        .subcommand(status)
+        .subcommand(undrain)
+}
+
+async fn start(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let ana_state =
+        matches
+            .get_one::<String>("ana-state")
+            .ok_or_else(|| ClientError::MissingValue {
+                field: "ana-state".to_string(),
+            })?;
+    let rebuild_timeout = matches
+        .get_one::<u64>("rebuild-timeout")
+        .copied()
+        .unwrap_or_default();
+
+    let response = json_call(
+        &mut ctx,
+        "node_drain",
+        serde_json::json!({
+            "ana_state": ana_state,
+            "rebuild_timeout": rebuild_timeout,
+        }),
+    )
+    .await?;
+
+    if matches.get_flag("wait") {
+        wait(ctx).await
+    } else {
+        print_status(&ctx, &response);
+        Ok(())
+    }
+}
+
+async fn status(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    if matches.get_flag("wait") {
+        return wait(ctx).await;
+    }
+    let response = json_call(&mut ctx, "node_drain_status", serde_json::json!({})).await?;
+    print_status(&ctx, &response);
+    Ok(())
+}
+
+async fn undrain(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let response = json_call(&mut ctx, "node_undrain", serde_json::json!({})).await?;
+    if matches.get_flag("wait") {
+        wait(ctx).await
+    } else {
+        print_status(&ctx, &response);
+        Ok(())
+    }
+}
+
+/// Report the progress of the drain until the node is safe to stop, or serves
+/// again.
+async fn wait(mut ctx: Context) -> crate::Result<()> {
+    let mut phase = String::new();
+    loop {
+        let response = json_call(&mut ctx, "node_drain_status", serde_json::json!({})).await?;
+        let current = response["phase"].as_str().unwrap_or_default();
+        if current == "serving" {
+            println!("Node is not draining");
+            return Ok(());
+        }
+        if current != phase {
+            phase = current.to_string();
+            if phase == "drained" {
+                print_status(&ctx, &response);
+                return Ok(());
+            }
+            println!("Draining: {phase}...");
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+fn print_status(ctx: &Context, response: &serde_json::Value) {
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response),
+        OutputFormat::Default => {
+            let field = |name: &str| match &response[name] {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Null => "".to_string(),
+                value => value.to_string(),
+            };
+            ctx.print_list(
+                vec![
+                    "PHASE",
+                    ">NEXUSES",
+                    ">FAILED_OVER",
+                    ">REBUILDS",
+                    ">PAUSED_REBUILDS",
+                    ">RESTORED",
+                    ">RESUMED_REBUILDS",
+                    "STARTED",
+                    "FINISHED",
+                ],
+                vec![vec![
+                    field("phase"),
+                    field("nexuses"),
+                    field("failed_over"),
+                    field("rebuilds"),
+                    field("paused_rebuilds"),
+                    field("restored"),
+                    field("resumed_rebuilds"),
+                    field("started_at"),
+                    field("finished_at"),
+                ]],
+            );
+            if let Some(errors) = response["errors"].as_array() {
+                errors
+                    .iter()
+                    .filter_map(|e| e.as_str())
+                    .for_each(|e| eprintln!("error: {e}"));
+            }
+        }
+    }
+}
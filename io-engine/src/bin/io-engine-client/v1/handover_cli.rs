@@ -0,0 +1,100 @@
+//!
+//! methods to hand over to a new io-engine instance, for a live upgrade
+
+use super::jsonrpc_cli::json_call;
+use crate::{
+    context::{Context, OutputFormat},
+    ClientError, GrpcStatus,
+};
+use clap::{Arg, ArgMatches, Command};
+use snafu::ResultExt;
+use tonic::Status;
+
+pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    match matches.subcommand().unwrap() {
+        ("start", args) => start(ctx, args).await,
+        ("status", args) => status(ctx, args).await,
+        (cmd, _) => {
+            Err(Status::not_found(format!("command {cmd} does not exist"))).context(GrpcStatus)
+        }
+    }
+}
+
+pub fn subcommands() -> Command {
+    let start = Command::new("start")
+        .about("hand over to the new instance started with --handover-from, and stop")
+        .arg(
+            Arg::new("manifest")
+                .required(true)
+                .index(1)
+                .help("path of the handover manifest, as given to the new instance"),
+        );
+
+    let status = Command::new("status").about("show the progress of the handover");
+
+    Command::new("handover")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .about("Live Upgrade Handover")
+        .subcommand(start)
+        .subcommand(status)
+}
+
+async fn start(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let manifest = matches
+        .get_one::<String>("manifest")
+        .ok_or_else(|| ClientError::MissingValue {
+            field: "manifest".to_string(),
+        })?
+        .to_string();
+
+    let response = json_call(
+        &mut ctx,
+        "handover_start",
+        serde_json::json!({ "manifest": manifest }),
+    )
+    .await?;
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response),
+        OutputFormat::Default => {
+            let count = |name: &str| response[name].as_array().map_or(0, |a| a.len());
+            println!(
+                "Handing over {} pools and {} nexuses through {manifest}",
+                count("pools"),
+                count("nexuses")
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn status(mut ctx: Context, _matches: &ArgMatches) -> crate::Result<()> {
+    let response = json_call(&mut ctx, "handover_status", serde_json::json!({})).await?;
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response),
+        OutputFormat::Default => {
+            let field = |name: &str| match &response[name] {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Null => "".to_string(),
+                value => value.to_string(),
+            };
+            ctx.print_list(
+                vec!["ROLE", "MANIFEST", "COMPLETED", ">POOLS", ">NEXUSES"],
+                vec![vec![
+                    field("role"),
+                    field("manifest"),
+                    field("completed"),
+                    field("pools"),
+                    field("nexuses"),
+                ]],
+            );
+            if let Some(errors) = response["errors"].as_array() {
+                errors
+                    .iter()
+                    .filter_map(|e| e.as_str())
+                    .for_each(|e| eprintln!("error: {e}"));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,186 @@
+//!
+//! methods to seed replicas from external images
+
+use super::jsonrpc_cli::json_call;
+use crate::{
+    context::{Context, OutputFormat},
+    ClientError, GrpcStatus,
+};
+use clap::{Arg, ArgMatches, Command};
+use snafu::ResultExt;
+use tonic::Status;
+
+pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    match matches.subcommand().unwrap() {
+        ("create", args) => create(ctx, args).await,
+        ("destroy", args) => destroy(ctx, args).await,
+        ("list", args) => list(ctx, args).await,
+        (cmd, _) => {
+            Err(Status::not_found(format!("command {cmd} does not exist"))).context(GrpcStatus)
+        }
+    }
+}
+
+pub fn subcommands() -> Command {
+    let create = Command::new("create")
+        .about("create and start a replica import")
+        .arg(
+            Arg::new("replica-uuid")
+                .required(true)
+                .index(1)
+                .help("uuid of the replica to import into"),
+        )
+        .arg(Arg::new("source").required(true).index(2).help(
+            "uri of the source to import, eg: file:///images/vol.img or \
+                    stream:///run/import.fifo",
+        ))
+        .arg(
+            Arg::new("uuid")
+                .long("uuid")
+                .required(false)
+                .help("uuid of the import, defaults to the replica uuid"),
+        );
+
+    let destroy = Command::new("destroy")
+        .about("stop and destroy a replica import")
+        .arg(
+            Arg::new("uuid")
+                .required(true)
+                .index(1)
+                .help("uuid of the replica import"),
+        );
+
+    let list = Command::new("list")
+        .about("list a specific one or all replica imports")
+        .arg(
+            Arg::new("uuid")
+                .required(false)
+                .index(1)
+                .help("uuid of the replica import"),
+        );
+
+    Command::new("import")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .about("Replica Import Management")
+        .subcommand(create)
+        .subcommand(destroy)
+        .subcommand(list)
+}
+
+async fn create(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let replica_uuid = matches
+        .get_one::<String>("replica-uuid")
+        .ok_or_else(|| ClientError::MissingValue {
+            field: "replica-uuid".to_string(),
+        })?
+        .to_string();
+    let source_uri = matches
+        .get_one::<String>("source")
+        .ok_or_else(|| ClientError::MissingValue {
+            field: "source".to_string(),
+        })?
+        .to_string();
+    let uuid = matches.get_one::<String>("uuid").cloned();
+
+    let response = json_call(
+        &mut ctx,
+        "replica_import_create",
+        serde_json::json!({
+            "uuid": uuid,
+            "replica_uuid": replica_uuid,
+            "source_uri": source_uri,
+        }),
+    )
+    .await?;
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response),
+        OutputFormat::Default => {
+            let uuid = response["uuid"].as_str().unwrap_or_default();
+            println!("Replica Import {uuid} created");
+        }
+    };
+
+    Ok(())
+}
+
+async fn destroy(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let uuid = matches
+        .get_one::<String>("uuid")
+        .ok_or_else(|| ClientError::MissingValue {
+            field: "uuid".to_string(),
+        })?
+        .to_string();
+
+    json_call(
+        &mut ctx,
+        "replica_import_destroy",
+        serde_json::json!({ "uuid": uuid }),
+    )
+    .await?;
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => {
+            ctx.print_structured(&serde_json::json!({ "uuid": uuid, "deleted": true }))
+        }
+        OutputFormat::Default => println!("Replica Import {uuid} deleted"),
+    }
+
+    Ok(())
+}
+
+async fn list(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let uuid = matches.get_one::<String>("uuid").cloned();
+
+    let response = json_call(
+        &mut ctx,
+        "replica_import_list",
+        serde_json::json!({ "uuid": uuid }),
+    )
+    .await?;
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response),
+        OutputFormat::Default => {
+            let imports = response.as_array().cloned().unwrap_or_default();
+            if imports.is_empty() {
+                return Ok(());
+            }
+            let field = |i: &serde_json::Value, name: &str| match &i[name] {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Null => "".to_string(),
+                value => value.to_string(),
+            };
+            let table = imports
+                .iter()
+                .map(|i| {
+                    vec![
+                        field(i, "uuid"),
+                        field(i, "replica_uuid"),
+                        field(i, "source_uri"),
+                        field(i, "state"),
+                        field(i, "spooled"),
+                        field(i, "total"),
+                        field(i, "transferred"),
+                        field(i, "remaining"),
+                        field(i, "progress"),
+                    ]
+                })
+                .collect();
+            ctx.print_list(
+                vec![
+                    "UUID",
+                    "REPLICA",
+                    "SOURCE",
+                    "STATE",
+                    ">SPOOLED",
+                    ">TOTAL",
+                    ">TRANSFERRED",
+                    ">REMAINING",
+                    ">PROGRESS",
+                ],
+                table,
+            );
+        }
+    };
+
+    Ok(())
+}
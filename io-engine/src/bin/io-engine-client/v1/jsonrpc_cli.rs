@@ -51,10 +51,35 @@ pub async fn json_rpc_call(mut ctx: Context, matches: &ArgMatches) -> crate::Res
         debug!("Default output for jsonrpc calls is JSON.");
     };
 
-    println!(
-        "{}",
-        response.get_ref().result.to_colored_json_auto().unwrap()
-    );
+    match ctx.output {
+        OutputFormat::Yaml => {
+            let result = serde_json::from_str::<serde_json::Value>(&response.get_ref().result)
+                .unwrap_or_else(|_| serde_json::Value::String(response.get_ref().result.clone()));
+            ctx.print_structured(&result);
+        }
+        _ => println!(
+            "{}",
+            response.get_ref().result.to_colored_json_auto().unwrap()
+        ),
+    }
 
     Ok(())
 }
+
+/// Call a json-rpc method with the given parameters and parse its result.
+pub(super) async fn json_call(
+    ctx: &mut Context,
+    method: &str,
+    params: serde_json::Value,
+) -> crate::Result<serde_json::Value> {
+    let response = ctx
+        .v1
+        .json
+        .json_rpc_call(v1rpc::json::JsonRpcRequest {
+            method: method.to_string(),
+            params: params.to_string(),
+        })
+        .await
+        .context(GrpcStatus)?;
+    Ok(serde_json::from_str(&response.into_inner().result).unwrap_or_default())
+}
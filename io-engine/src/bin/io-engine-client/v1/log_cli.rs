@@ -0,0 +1,156 @@
+//!
+//! methods to change the log settings at runtime
+
+use super::jsonrpc_cli::json_call;
+use crate::{
+    context::{Context, OutputFormat},
+    GrpcStatus,
+};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use snafu::ResultExt;
+use tonic::Status;
+
+pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    match matches.subcommand().unwrap() {
+        ("get", args) => get(ctx, args).await,
+        ("set", args) => set(ctx, args).await,
+        ("reset", args) => reset(ctx, args).await,
+        (cmd, _) => {
+            Err(Status::not_found(format!("command {cmd} does not exist"))).context(GrpcStatus)
+        }
+    }
+}
+
+pub fn subcommands() -> Command {
+    let get = Command::new("get").about("show the current log settings");
+
+    let set = Command::new("set")
+        .about("change the log settings, until they time out")
+        .arg(
+            Arg::new("filter")
+                .long("filter")
+                .help("tracing filter directives, e.g. info,io_engine::bdev=debug"),
+        )
+        .arg(
+            Arg::new("spdk-level")
+                .long("spdk-level")
+                .value_parser(["disabled", "error", "warn", "notice", "info", "debug"])
+                .help("SPDK log level"),
+        )
+        .arg(
+            Arg::new("enable-flag")
+                .long("enable-flag")
+                .action(ArgAction::Append)
+                .help("SPDK log flag to enable"),
+        )
+        .arg(
+            Arg::new("disable-flag")
+                .long("disable-flag")
+                .action(ArgAction::Append)
+                .help("SPDK log flag to disable"),
+        )
+        .arg(
+            Arg::new("trace-nexus")
+                .long("trace-nexus")
+                .action(ArgAction::Append)
+                .help("name or uuid of a nexus whose I/Os are to be traced"),
+        )
+        .arg(
+            Arg::new("untrace-nexus")
+                .long("untrace-nexus")
+                .action(ArgAction::Append)
+                .help("name or uuid of a nexus whose I/Os are no longer to be traced"),
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .value_parser(clap::value_parser!(u64))
+                .help(
+                    "seconds after which the changes are reverted, 0 to keep them [default: 600]",
+                ),
+        );
+
+    let reset = Command::new("reset").about("revert the log settings to the ones at startup");
+
+    Command::new("log")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .about("Runtime Log Settings")
+        .subcommand(get)
+        .subcommand(set)
+        .subcommand(reset)
+}
+
+/// Get all the values of a repeated argument.
+fn values(matches: &ArgMatches, name: &str) -> Vec<String> {
+    matches
+        .get_many::<String>(name)
+        .map(|v| v.cloned().collect())
+        .unwrap_or_default()
+}
+
+async fn get(mut ctx: Context, _matches: &ArgMatches) -> crate::Result<()> {
+    let response = json_call(&mut ctx, "log_get", serde_json::json!({})).await?;
+    print_settings(&ctx, response);
+    Ok(())
+}
+
+async fn set(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let response = json_call(
+        &mut ctx,
+        "log_set",
+        serde_json::json!({
+            "filter": matches.get_one::<String>("filter"),
+            "spdk_level": matches.get_one::<String>("spdk-level"),
+            "enable_flags": values(matches, "enable-flag"),
+            "disable_flags": values(matches, "disable-flag"),
+            "trace_nexus": values(matches, "trace-nexus"),
+            "untrace_nexus": values(matches, "untrace-nexus"),
+            "timeout_secs": matches.get_one::<u64>("timeout"),
+        }),
+    )
+    .await?;
+    print_settings(&ctx, response);
+    Ok(())
+}
+
+async fn reset(mut ctx: Context, _matches: &ArgMatches) -> crate::Result<()> {
+    let response = json_call(&mut ctx, "log_reset", serde_json::json!({})).await?;
+    print_settings(&ctx, response);
+    Ok(())
+}
+
+/// Print the log settings.
+fn print_settings(ctx: &Context, response: serde_json::Value) {
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response),
+        OutputFormat::Default => {
+            let field = |name: &str| match &response[name] {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Array(values) => values
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .collect::<Vec<_>>()
+                    .join(","),
+                serde_json::Value::Null => "".to_string(),
+                value => value.to_string(),
+            };
+            ctx.print_list(
+                vec![
+                    "FILTER",
+                    "SPDK_LEVEL",
+                    "SPDK_FLAGS",
+                    "NEXUS_IO_TRACING",
+                    "REVERT_AT",
+                ],
+                vec![vec![
+                    field("filter"),
+                    field("spdk_level"),
+                    field("spdk_flags"),
+                    field("nexus_io_tracing"),
+                    field("revert_at"),
+                ]],
+            );
+        }
+    }
+}
@@ -1,9 +1,17 @@
+mod apply_cli;
+mod audit_cli;
 pub mod bdev_cli;
+mod config_cli;
 pub mod controller_cli;
 pub mod device_cli;
+mod drain_cli;
+mod handover_cli;
+mod import_cli;
 pub mod jsonrpc_cli;
+mod log_cli;
 mod nexus_child_cli;
 pub mod nexus_cli;
+mod operation_cli;
 pub mod perf_cli;
 pub mod pool_cli;
 pub mod rebuild_cli;
@@ -67,7 +75,7 @@ pub(super) async fn main_() -> crate::Result<()> {
                 .long("output")
                 .value_name("FORMAT")
                 .default_value("default")
-                .value_parser(["default", "json"])
+                .value_parser(["default", "json", "yaml"])
                 .global(true)
                 .help("Output format."),
         )
@@ -80,10 +88,18 @@ pub(super) async fn main_() -> crate::Result<()> {
         .subcommand(rebuild_cli::subcommands())
         .subcommand(snapshot_rebuild_cli::subcommands())
         .subcommand(snapshot_cli::subcommands())
+        .subcommand(import_cli::subcommands())
         .subcommand(jsonrpc_cli::subcommands())
         .subcommand(controller_cli::subcommands())
         .subcommand(test_cli::subcommands())
         .subcommand(stats_cli::subcommands())
+        .subcommand(operation_cli::subcommands())
+        .subcommand(drain_cli::subcommands())
+        .subcommand(handover_cli::subcommands())
+        .subcommand(apply_cli::subcommands())
+        .subcommand(audit_cli::subcommands())
+        .subcommand(log_cli::subcommands())
+        .subcommand(config_cli::subcommands())
         .subcommand_required(true)
         .arg_required_else_help(true)
         .get_matches();
@@ -102,7 +118,15 @@ pub(super) async fn main_() -> crate::Result<()> {
         ("rebuild", args) => rebuild_cli::handler(ctx, args).await,
         ("snapshot-rebuild", args) => snapshot_rebuild_cli::handler(ctx, args).await,
         ("snapshot", args) => snapshot_cli::handler(ctx, args).await,
+        ("import", args) => import_cli::handler(ctx, args).await,
         ("stats", args) => stats_cli::handler(ctx, args).await,
+        ("operation", args) => operation_cli::handler(ctx, args).await,
+        ("drain", args) => drain_cli::handler(ctx, args).await,
+        ("handover", args) => handover_cli::handler(ctx, args).await,
+        ("apply", args) => apply_cli::handler(ctx, args).await,
+        ("audit", args) => audit_cli::handler(ctx, args).await,
+        ("log", args) => log_cli::handler(ctx, args).await,
+        ("config", args) => config_cli::handler(ctx, args).await,
         ("controller", args) => controller_cli::handler(ctx, args).await,
         ("jsonrpc", args) => jsonrpc_cli::json_rpc_call(ctx, args).await,
         ("test", args) => test_cli::handler(ctx, args).await,
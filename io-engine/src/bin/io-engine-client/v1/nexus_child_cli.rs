@@ -6,7 +6,7 @@ use crate::{
     ClientError, GrpcStatus,
 };
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
+use io_engine::grpc::v1::nexus::CHILD_ACTION_SET_TIMEOUT_POLICY;
 use io_engine_api::v1 as v1rpc;
 use snafu::ResultExt;
 use tonic::Status;
@@ -17,6 +17,7 @@ pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         ("offline", args) => child_operation(ctx, args, 0).await,
         ("online", args) => child_operation(ctx, args, 1).await,
         ("retire", args) => child_operation(ctx, args, 2).await,
+        ("timeout", args) => timeout(ctx, args).await,
         (cmd, _) => {
             Err(Status::not_found(format!("command {cmd} does not exist"))).context(GrpcStatus)
         }
@@ -84,6 +85,46 @@ pub fn subcommands() -> Command {
                 .help("uri of the child"),
         );
 
+    let timeout = Command::new("timeout")
+        .about("change the I/O timeout policy of an NVMe-oF child")
+        .arg(
+            Arg::new("uuid")
+                .required(true)
+                .index(1)
+                .help("uuid of the nexus"),
+        )
+        .arg(
+            Arg::new("uri")
+                .required(true)
+                .index(2)
+                .value_parser(clap::value_parser!(url::Url))
+                .help("uri of the child"),
+        )
+        .arg(
+            Arg::new("io-timeout-us")
+                .long("io-timeout-us")
+                .value_parser(clap::value_parser!(u64))
+                .help("timeout for I/O commands, 0 disables the timeout handling"),
+        )
+        .arg(
+            Arg::new("admin-timeout-us")
+                .long("admin-timeout-us")
+                .value_parser(clap::value_parser!(u64))
+                .help("timeout for admin commands"),
+        )
+        .arg(
+            Arg::new("action")
+                .long("action")
+                .value_parser(["ignore", "reset", "abort", "hot_remove"])
+                .help("action taken when a command times out"),
+        )
+        .arg(
+            Arg::new("retry-count")
+                .long("retry-count")
+                .value_parser(clap::value_parser!(u8))
+                .help("transport retry count, applies once the child reconnects"),
+        );
+
     Command::new("child")
         .subcommand_required(true)
         .arg_required_else_help(true)
@@ -92,6 +133,7 @@ pub fn subcommands() -> Command {
         .subcommand(offline)
         .subcommand(online)
         .subcommand(retire)
+        .subcommand(timeout)
 }
 
 async fn fault(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
@@ -119,15 +161,7 @@ async fn fault(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{uri}");
         }
@@ -136,19 +170,62 @@ async fn fault(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
     Ok(())
 }
 
-async fn child_operation(mut ctx: Context, matches: &ArgMatches, action: i32) -> crate::Result<()> {
-    let uuid = matches
-        .get_one::<String>("uuid")
+async fn timeout(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let mut url = matches
+        .get_one::<url::Url>("uri")
         .ok_or_else(|| ClientError::MissingValue {
-            field: "uuid".to_string(),
+            field: "uri".to_string(),
         })?
-        .to_string();
+        .clone();
+
+    {
+        let mut query = url.query_pairs_mut();
+        if let Some(value) = matches.get_one::<u64>("io-timeout-us") {
+            query.append_pair("io_timeout_us", &value.to_string());
+        }
+        if let Some(value) = matches.get_one::<u64>("admin-timeout-us") {
+            query.append_pair("admin_timeout_us", &value.to_string());
+        }
+        if let Some(value) = matches.get_one::<String>("action") {
+            query.append_pair("timeout_action", value);
+        }
+        if let Some(value) = matches.get_one::<u8>("retry-count") {
+            query.append_pair("retry_count", &value.to_string());
+        }
+    }
+
+    // The timeout parameters are passed along with the uri of the child.
+    child_operation_uri(
+        ctx,
+        matches,
+        url.to_string(),
+        CHILD_ACTION_SET_TIMEOUT_POLICY,
+    )
+    .await
+}
+
+async fn child_operation(ctx: Context, matches: &ArgMatches, action: i32) -> crate::Result<()> {
     let uri = matches
         .get_one::<String>("uri")
         .ok_or_else(|| ClientError::MissingValue {
             field: "uri".to_string(),
         })?
         .to_string();
+    child_operation_uri(ctx, matches, uri, action).await
+}
+
+async fn child_operation_uri(
+    mut ctx: Context,
+    matches: &ArgMatches,
+    uri: String,
+    action: i32,
+) -> crate::Result<()> {
+    let uuid = matches
+        .get_one::<String>("uuid")
+        .ok_or_else(|| ClientError::MissingValue {
+            field: "uuid".to_string(),
+        })?
+        .to_string();
 
     let response = ctx
         .v1
@@ -162,15 +239,7 @@ async fn child_operation(mut ctx: Context, matches: &ArgMatches, action: i32) ->
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{uri}");
         }
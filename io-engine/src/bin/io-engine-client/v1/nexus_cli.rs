@@ -1,14 +1,17 @@
-use super::nexus_child_cli;
+use super::{nexus_child_cli, stats_cli};
 use crate::{
-    context::{Context, OutputFormat},
+    context::{watch_arg, watch_interval, Context, OutputFormat},
     parse_size, ClientError, GrpcStatus,
 };
 use byte_unit::Byte;
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
+use io_engine::core::Protocol;
 use io_engine_api::{v1, v1::nexus::NvmeReservation};
 use snafu::ResultExt;
-use std::convert::TryFrom;
+use std::{
+    convert::TryFrom,
+    time::{Duration, Instant},
+};
 use tonic::{Code, Status};
 use uuid::Uuid;
 
@@ -129,7 +132,10 @@ pub fn subcommands() -> Command {
                 .short('p')
                 .long("protocol")
                 .value_name("PROTOCOL")
-                .help("Name of a protocol (nvmf) used for publishing the nexus remotely"),
+                .help(
+                    "Name of a protocol (nvmf, ublk, vhost) used for publishing the nexus, \
+                    ublk exposes it as a local block device, vhost as a vhost-user-blk socket",
+                ),
         );
 
     let unpublish = Command::new("unpublish").about("unpublish the nexus").arg(
@@ -191,13 +197,16 @@ pub fn subcommands() -> Command {
                 .help("uri of child to remove"),
         );
 
-    let list = Command::new("list").about("list all nexus devices").arg(
-        Arg::new("children")
-            .short('c')
-            .long("show-children")
-            .required(false)
-            .action(clap::ArgAction::SetTrue),
-    );
+    let list = Command::new("list")
+        .about("list all nexus devices")
+        .arg(
+            Arg::new("children")
+                .short('c')
+                .long("show-children")
+                .required(false)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(watch_arg());
 
     let children = Command::new("children").about("list nexus children").arg(
         Arg::new("uuid")
@@ -337,15 +346,7 @@ async fn nexus_create(mut ctx: Context, matches: &ArgMatches) -> crate::Result<(
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &response.get_ref().nexus.as_ref().unwrap().uuid);
         }
@@ -364,15 +365,7 @@ async fn nexus_shutdown(mut ctx: Context, matches: &ArgMatches) -> crate::Result
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uuid,);
         }
@@ -401,15 +394,7 @@ async fn nexus_destroy(mut ctx: Context, matches: &ArgMatches) -> crate::Result<
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uuid,);
         }
@@ -419,6 +404,9 @@ async fn nexus_destroy(mut ctx: Context, matches: &ArgMatches) -> crate::Result<
 }
 
 async fn nexus_list(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    if let Some(interval) = watch_interval(matches) {
+        return nexus_watch(ctx, interval).await;
+    }
     let response = ctx
         .v1
         .nexus
@@ -429,15 +417,7 @@ async fn nexus_list(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()>
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let nexus = &response.get_ref().nexus_list;
             if nexus.is_empty() {
@@ -484,6 +464,73 @@ async fn nexus_list(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()>
     Ok(())
 }
 
+/// Refresh the nexus list on an interval, showing the IO rates of each nexus
+/// since the last refresh.
+async fn nexus_watch(mut ctx: Context, interval: Duration) -> crate::Result<()> {
+    let mut previous = stats_cli::io_stats(&mut ctx, stats_cli::Resource::Nexus, None).await?;
+    let mut sampled_at = Instant::now();
+    loop {
+        tokio::time::sleep(interval).await;
+        let response = ctx
+            .v1
+            .nexus
+            .list_nexus(v1::nexus::ListNexusOptions {
+                name: None,
+                uuid: None,
+            })
+            .await
+            .context(GrpcStatus)?;
+        let current = stats_cli::io_stats(&mut ctx, stats_cli::Resource::Nexus, None).await?;
+        let rates = stats_cli::io_rates(&previous, &current, sampled_at.elapsed());
+        sampled_at = Instant::now();
+        previous = current;
+
+        let nexus = &response.get_ref().nexus_list;
+        let rate = |n: &v1::nexus::Nexus| rates.iter().find(|r| r.name == n.name);
+        match ctx.output {
+            OutputFormat::Json | OutputFormat::Yaml => {
+                let list = nexus
+                    .iter()
+                    .map(|n| {
+                        serde_json::json!({
+                            "name": n.name,
+                            "uuid": n.uuid,
+                            "state": nexus_state_to_str(n.state),
+                            "rebuilds": n.rebuilds,
+                            "rates": rate(n),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                ctx.print_structured(&list);
+            }
+            OutputFormat::Default => {
+                if nexus.is_empty() {
+                    ctx.v1("No nexus found");
+                    continue;
+                }
+                let table = nexus
+                    .iter()
+                    .map(|n| {
+                        let mut row = vec![
+                            n.name.clone(),
+                            nexus_state_to_str(n.state).to_string(),
+                            n.rebuilds.to_string(),
+                        ];
+                        match rate(n) {
+                            Some(rate) => row.extend(rate.columns()),
+                            None => row.extend(vec!["".to_string(); 4]),
+                        }
+                        row
+                    })
+                    .collect();
+                let mut hdr = vec!["NAME", "STATE", ">REBUILDS"];
+                hdr.extend(stats_cli::IoRates::HEADERS);
+                ctx.print_list(hdr, table);
+            }
+        }
+    }
+}
+
 async fn nexus_children_2(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
     let uuid = matches
         .get_one::<String>("uuid")
@@ -516,15 +563,7 @@ async fn nexus_children_2(mut ctx: Context, matches: &ArgMatches) -> crate::Resu
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&nexus.children)
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&nexus.children),
         OutputFormat::Default => {
             let table = nexus
                 .children
@@ -584,15 +623,7 @@ async fn nexus_resize(mut ctx: Context, matches: &ArgMatches) -> crate::Result<(
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("Resized nexus {uuid} to {requested_size}");
         }
@@ -616,6 +647,9 @@ async fn nexus_publish(mut ctx: Context, matches: &ArgMatches) -> crate::Result<
     let protocol = match matches.get_one::<String>("protocol").map(|s| s.as_str()) {
         None => v1::common::ShareProtocol::Nvmf as i32,
         Some("nvmf") => v1::common::ShareProtocol::Nvmf as i32,
+        // ublk and vhost have no ShareProtocol value in the API yet.
+        Some("ublk") => Protocol::Ublk.into(),
+        Some("vhost") => Protocol::Vhost.into(),
         Some(_) => {
             return Err(Status::new(
                 Code::Internal,
@@ -643,15 +677,7 @@ async fn nexus_publish(mut ctx: Context, matches: &ArgMatches) -> crate::Result<
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             println!(
                 "Nexus published over: {}",
@@ -678,15 +704,7 @@ async fn nexus_unpublish(mut ctx: Context, matches: &ArgMatches) -> crate::Resul
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uuid,)
         }
@@ -779,15 +797,7 @@ async fn nexus_add(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()>
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uuid,)
         }
@@ -821,15 +831,7 @@ async fn nexus_remove(mut ctx: Context, matches: &ArgMatches) -> crate::Result<(
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             println!("Removed {} from specified nexus", &uri,)
         }
@@ -0,0 +1,125 @@
+//!
+//! methods to query and cancel the tracked gRPC operations
+
+use super::jsonrpc_cli::json_call;
+use crate::{
+    context::{Context, OutputFormat},
+    ClientError, GrpcStatus,
+};
+use clap::{Arg, ArgMatches, Command};
+use snafu::ResultExt;
+use tonic::Status;
+
+pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    match matches.subcommand().unwrap() {
+        ("get", args) => get(ctx, args).await,
+        ("list", args) => list(ctx, args).await,
+        ("cancel", args) => cancel(ctx, args).await,
+        (cmd, _) => {
+            Err(Status::not_found(format!("command {cmd} does not exist"))).context(GrpcStatus)
+        }
+    }
+}
+
+pub fn subcommands() -> Command {
+    let get = Command::new("get").about("get an operation").arg(
+        Arg::new("id")
+            .required(true)
+            .index(1)
+            .help("id of the operation"),
+    );
+
+    let list = Command::new("list").about("list all the tracked operations");
+
+    let cancel = Command::new("cancel")
+        .about("cancel an operation which is still queued")
+        .arg(
+            Arg::new("id")
+                .required(true)
+                .index(1)
+                .help("id of the operation"),
+        );
+
+    Command::new("operation")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .about("Operation Management")
+        .subcommand(get)
+        .subcommand(list)
+        .subcommand(cancel)
+}
+
+fn id_arg(matches: &ArgMatches) -> crate::Result<String> {
+    matches
+        .get_one::<String>("id")
+        .cloned()
+        .ok_or_else(|| ClientError::MissingValue {
+            field: "id".to_string(),
+        })
+}
+
+async fn get(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let id = id_arg(matches)?;
+    let response = json_call(&mut ctx, "operation_get", serde_json::json!({ "id": id })).await?;
+    print_operations(&ctx, response);
+    Ok(())
+}
+
+async fn list(mut ctx: Context, _matches: &ArgMatches) -> crate::Result<()> {
+    let response = json_call(&mut ctx, "operation_list", serde_json::json!({})).await?;
+    print_operations(&ctx, response);
+    Ok(())
+}
+
+async fn cancel(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let id = id_arg(matches)?;
+    let response = json_call(
+        &mut ctx,
+        "operation_cancel",
+        serde_json::json!({ "id": id }),
+    )
+    .await?;
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => print_operations(&ctx, response),
+        OutputFormat::Default => println!("Operation {id} cancelled"),
+    }
+    Ok(())
+}
+
+/// Print an operation, or a list of operations.
+fn print_operations(ctx: &Context, response: serde_json::Value) {
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response),
+        OutputFormat::Default => {
+            let operations = match response {
+                serde_json::Value::Array(operations) => operations,
+                operation => vec![operation],
+            };
+            if operations.is_empty() {
+                return;
+            }
+            let field = |o: &serde_json::Value, name: &str| match &o[name] {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Null => "".to_string(),
+                value => value.to_string(),
+            };
+            let table = operations
+                .iter()
+                .map(|o| {
+                    vec![
+                        field(o, "id"),
+                        field(o, "method"),
+                        field(o, "state"),
+                        field(o, "started_at"),
+                        field(o, "finished_at"),
+                        field(o, "error"),
+                    ]
+                })
+                .collect();
+            ctx.print_list(
+                vec!["ID", "METHOD", "STATE", "STARTED", "FINISHED", "ERROR"],
+                table,
+            );
+        }
+    }
+}
@@ -1,10 +1,10 @@
+use super::jsonrpc_cli::json_call;
 use crate::{
     context::{Context, OutputFormat},
     parse_size, ClientError, GrpcStatus,
 };
 use byte_unit::Byte;
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::v1 as v1rpc;
 use snafu::ResultExt;
 use std::{convert::TryFrom, str::FromStr};
@@ -176,6 +176,41 @@ pub fn subcommands() -> Command {
                 .value_parser(PoolType::types().to_vec()),
         );
 
+    let check = Command::new("check")
+        .about("Check the metadata of a storage pool and optionally repair it")
+        .arg(
+            Arg::new("name")
+                .required(true)
+                .index(1)
+                .help("Storage pool name"),
+        )
+        .arg(
+            Arg::new("disk")
+                .short('d')
+                .long("disk")
+                .required(false)
+                .action(clap::ArgAction::Append)
+                .help("Disk device files, required if the pool is not imported"),
+        )
+        .arg(
+            Arg::new("repair")
+                .long("repair")
+                .action(clap::ArgAction::SetTrue)
+                .help("Repair the issues which can be repaired, except for orphaned blobs"),
+        )
+        .arg(
+            Arg::new("delete-orphans")
+                .long("delete-orphans")
+                .action(clap::ArgAction::SetTrue)
+                .help("Delete the orphaned blobs, which are otherwise only reported"),
+        )
+        .arg(
+            Arg::new("release-zeroes")
+                .long("release-zeroes")
+                .action(clap::ArgAction::SetTrue)
+                .help("Release the zeroed clusters of thin provisioned replicas"),
+        );
+
     Command::new("pool")
         .subcommand_required(true)
         .arg_required_else_help(true)
@@ -186,6 +221,7 @@ pub fn subcommands() -> Command {
         .subcommand(export)
         .subcommand(grow)
         .subcommand(list)
+        .subcommand(check)
 }
 
 pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
@@ -196,6 +232,7 @@ pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         ("export", args) => export(ctx, args).await,
         ("grow", args) => grow(ctx, args).await,
         ("list", args) => list(ctx, args).await,
+        ("check", args) => check(ctx, args).await,
         (cmd, _) => {
             Err(Status::not_found(format!("command {cmd} does not exist"))).context(GrpcStatus)
         }
@@ -266,15 +303,7 @@ async fn create(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &name);
         }
@@ -338,15 +367,7 @@ async fn import(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &name);
         }
@@ -375,7 +396,9 @@ async fn destroy(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {}
+        OutputFormat::Json | OutputFormat::Yaml => {
+            ctx.print_structured(&serde_json::json!({ "name": name, "deleted": true }))
+        }
         OutputFormat::Default => {
             println!("pool: {} is deleted", &name);
         }
@@ -404,7 +427,9 @@ async fn export(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {}
+        OutputFormat::Json | OutputFormat::Yaml => {
+            ctx.print_structured(&serde_json::json!({ "name": name, "exported": true }))
+        }
         OutputFormat::Default => {
             println!("pool: {} is exported", &name);
         }
@@ -435,10 +460,14 @@ async fn grow(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
     let old_cap = response.get_ref().previous_pool.as_ref().unwrap().capacity;
     let new_cap = response.get_ref().current_pool.as_ref().unwrap().capacity;
 
-    if old_cap == new_cap {
-        println!("Pool capacity did not change: {new_cap} bytes");
-    } else {
-        println!("Pool capacity was {old_cap}, now {new_cap} bytes");
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
+        OutputFormat::Default if old_cap == new_cap => {
+            println!("Pool capacity did not change: {new_cap} bytes");
+        }
+        OutputFormat::Default => {
+            println!("Pool capacity was {old_cap}, now {new_cap} bytes");
+        }
     }
 
     Ok(())
@@ -470,15 +499,7 @@ async fn list(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             let pools: &Vec<v1rpc::pool::Pool> = &response.get_ref().pools;
             if pools.is_empty() {
@@ -568,6 +589,75 @@ async fn list(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
     Ok(())
 }
 
+async fn check(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let name = matches
+        .get_one::<String>("name")
+        .ok_or_else(|| ClientError::MissingValue {
+            field: "name".to_string(),
+        })?
+        .to_owned();
+    let disks = matches
+        .get_many::<String>("disk")
+        .map(|disks| disks.cloned().collect::<Vec<_>>());
+
+    let response = json_call(
+        &mut ctx,
+        "pool_check",
+        serde_json::json!({
+            "name": name,
+            "disks": disks,
+            "repair": matches.get_flag("repair"),
+            "delete_orphans": matches.get_flag("delete-orphans"),
+            "release_zeroes": matches.get_flag("release-zeroes"),
+        }),
+    )
+    .await?;
+
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response),
+        OutputFormat::Default => {
+            let count = |name: &str| response[name].as_u64().unwrap_or_default();
+            let issues = response["issues"].as_array().cloned().unwrap_or_default();
+            if !issues.is_empty() {
+                let table = issues
+                    .iter()
+                    .map(|i| {
+                        vec![
+                            i["kind"].as_str().unwrap_or_default().to_string(),
+                            i["blob_id"]
+                                .as_u64()
+                                .map(|id| format!("{id:#x}"))
+                                .unwrap_or("-".to_string()),
+                            i["lvol"].as_str().unwrap_or("-").to_string(),
+                            i["repaired"].as_bool().unwrap_or_default().to_string(),
+                            i["description"].as_str().unwrap_or_default().to_string(),
+                        ]
+                    })
+                    .collect();
+                ctx.print_list(
+                    vec!["KIND", "BLOB", "LVOL", "REPAIRED", "DESCRIPTION"],
+                    table,
+                );
+            }
+            println!(
+                "pool: {} checked{}, {} blobs, {} issues, {} leaked clusters, {} released",
+                &name,
+                if response["offline"].as_bool().unwrap_or_default() {
+                    " offline"
+                } else {
+                    ""
+                },
+                count("blobs_checked"),
+                issues.len(),
+                count("leaked_clusters"),
+                ctx.units(Byte::from_u64(count("released_bytes")))
+            );
+        }
+    };
+
+    Ok(())
+}
+
 fn pool_state_to_str(idx: i32) -> &'static str {
     match v1rpc::pool::PoolState::try_from(idx).unwrap() {
         v1rpc::pool::PoolState::PoolUnknown => "unknown",
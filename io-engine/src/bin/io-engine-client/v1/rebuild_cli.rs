@@ -2,15 +2,17 @@
 //! methods to interact with the rebuild process
 
 use crate::{
-    context::{Context, OutputFormat},
+    context::{watch_arg, watch_interval, Context, OutputFormat},
     ClientError, GrpcStatus,
 };
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::v1;
 use snafu::ResultExt;
-use std::convert::TryFrom;
-use tonic::Status;
+use std::{
+    convert::TryFrom,
+    time::{Duration, Instant},
+};
+use tonic::{Code, Status};
 
 pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
     match matches.subcommand().unwrap() {
@@ -132,7 +134,8 @@ pub fn subcommands() -> Command {
                 .required(true)
                 .index(2)
                 .help("uri of child to get the rebuild progress from"),
-        );
+        )
+        .arg(watch_arg());
 
     let history = Command::new("history")
         .about("shows the rebuild history for children of a nexus")
@@ -181,15 +184,7 @@ async fn start(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uri);
         }
@@ -222,15 +217,7 @@ async fn stop(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uri);
         }
@@ -264,15 +251,7 @@ async fn pause(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uri);
         }
@@ -306,15 +285,7 @@ async fn resume(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &uri);
         }
@@ -347,15 +318,7 @@ async fn state(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             ctx.print_list(vec!["state"], vec![vec![response.get_ref().state.clone()]]);
         }
@@ -379,15 +342,7 @@ async fn history(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let response = &response.get_ref();
             if response.records.is_empty() {
@@ -464,15 +419,7 @@ async fn stats(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let response = &response.get_ref();
             ctx.print_list(
@@ -521,6 +468,10 @@ async fn progress(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         })?
         .to_string();
 
+    if let Some(interval) = watch_interval(matches) {
+        return progress_watch(ctx, uuid, uri, interval).await;
+    }
+
     let response = ctx
         .v1
         .nexus
@@ -532,15 +483,7 @@ async fn progress(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             ctx.print_list(
                 vec!["progress (%)"],
@@ -551,6 +494,75 @@ async fn progress(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
     Ok(())
 }
 
+/// Refresh the progress of a rebuild on an interval, showing its rate since
+/// the last refresh, until the rebuild is no longer running.
+async fn progress_watch(
+    mut ctx: Context,
+    uuid: String,
+    uri: String,
+    interval: Duration,
+) -> crate::Result<()> {
+    let mut previous: Option<(v1::nexus::RebuildStatsResponse, Instant)> = None;
+    loop {
+        let response = ctx
+            .v1
+            .nexus
+            .get_rebuild_stats(v1::nexus::RebuildStatsRequest {
+                nexus_uuid: uuid.clone(),
+                uri: uri.clone(),
+            })
+            .await;
+        let stats = match response {
+            Ok(response) => response.into_inner(),
+            Err(status) if previous.is_some() && status.code() == Code::NotFound => {
+                ctx.v1(&format!("Rebuild of {uri} is no longer running"));
+                return Ok(());
+            }
+            Err(status) => return Err(status).context(GrpcStatus),
+        };
+
+        // bytes per second transferred since the last refresh
+        let rate = previous.as_ref().map(|(prev, sampled_at)| {
+            let blocks = stats
+                .blocks_transferred
+                .saturating_sub(prev.blocks_transferred);
+            (blocks * stats.block_size) as f64 / sampled_at.elapsed().as_secs_f64()
+        });
+        let eta = rate
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| ((stats.blocks_remaining * stats.block_size) as f64 / rate).ceil() as u64);
+
+        match ctx.output {
+            OutputFormat::Json | OutputFormat::Yaml => {
+                ctx.print_structured(&serde_json::json!({
+                    "progress": stats.progress,
+                    "blocks_remaining": stats.blocks_remaining,
+                    "mbps": rate.map(|rate| rate / 1_000_000.0),
+                    "eta_secs": eta,
+                }));
+            }
+            OutputFormat::Default => {
+                ctx.print_list(
+                    vec!["progress (%)", ">REMAINING", ">MB/s", ">ETA"],
+                    vec![vec![
+                        stats.progress.to_string(),
+                        stats.blocks_remaining.to_string(),
+                        rate.map(|rate| format!("{:.2}", rate / 1_000_000.0))
+                            .unwrap_or_default(),
+                        eta.map(|eta| format!("{eta}s")).unwrap_or_default(),
+                    ]],
+                );
+            }
+        }
+
+        if stats.progress >= 100 {
+            return Ok(());
+        }
+        previous = Some((stats, Instant::now()));
+        tokio::time::sleep(interval).await;
+    }
+}
+
 fn rebuild_state_to_str(s: v1::nexus::RebuildJobState) -> &'static str {
     match s {
         v1::nexus::RebuildJobState::Init => "init",
@@ -1,11 +1,10 @@
-use super::pool_cli;
+use super::{jsonrpc_cli::json_call, pool_cli};
 use crate::{
     context::{Context, OutputFormat},
     parse_size, ClientError, GrpcParseStatus, GrpcStatus,
 };
 use byte_unit::Byte;
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::{v0 as rpc, v1 as v1_rpc};
 use snafu::ResultExt;
 use std::{convert::TryFrom, str::FromStr};
@@ -144,6 +143,22 @@ pub fn subcommands() -> Command {
             .action(clap::ArgAction::Append)
             .value_parser(pool_cli::PoolType::types().to_vec()),
     );
+    let trim = Command::new("trim")
+        .about("Release the all-zero clusters of an unused thin replica")
+        .arg(
+            Arg::new("uuid")
+                .required(true)
+                .index(1)
+                .help("Replica uuid"),
+        );
+    let reclaimed = Command::new("reclaimed")
+        .about("Space reclaimed from replicas")
+        .arg(
+            Arg::new("uuid")
+                .required(false)
+                .index(1)
+                .help("Replica uuid"),
+        );
     Command::new("replica")
         .subcommand_required(true)
         .arg_required_else_help(true)
@@ -155,6 +170,8 @@ pub fn subcommands() -> Command {
         .subcommand(resize)
         .subcommand(list)
         .subcommand(Command::new("stats").about("IO stats of replicas"))
+        .subcommand(trim)
+        .subcommand(reclaimed)
 }
 
 pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
@@ -166,6 +183,8 @@ pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         ("unshare", args) => replica_unshare(ctx, args).await,
         ("resize", args) => replica_resize(ctx, args).await,
         ("stats", args) => replica_stat(ctx, args).await,
+        ("trim", args) => replica_trim(ctx, args).await,
+        ("reclaimed", args) => replica_reclaimed(ctx, args).await,
         (cmd, _) => {
             Err(Status::not_found(format!("command {cmd} does not exist"))).context(GrpcStatus)
         }
@@ -229,15 +248,7 @@ async fn replica_create(mut ctx: Context, matches: &ArgMatches) -> crate::Result
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &response.get_ref().uri);
         }
@@ -274,7 +285,9 @@ async fn replica_destroy(mut ctx: Context, matches: &ArgMatches) -> crate::Resul
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {}
+        OutputFormat::Json | OutputFormat::Yaml => {
+            ctx.print_structured(&serde_json::json!({ "uuid": uuid, "deleted": true }))
+        }
         OutputFormat::Default => {
             println!("replica: {} is deleted", &uuid);
         }
@@ -310,15 +323,7 @@ async fn replica_list(mut ctx: Context, matches: &ArgMatches) -> crate::Result<(
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let replicas = &response.get_ref().replicas;
             if replicas.is_empty() {
@@ -399,15 +404,7 @@ async fn replica_share(mut ctx: Context, matches: &ArgMatches) -> crate::Result<
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &response.get_ref().uri);
         }
@@ -426,15 +423,7 @@ async fn replica_unshare(mut ctx: Context, matches: &ArgMatches) -> crate::Resul
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             println!("{}", &response.get_ref().uri);
         }
@@ -462,7 +451,7 @@ async fn replica_resize(mut ctx: Context, matches: &ArgMatches) -> crate::Result
         .map_err(|s| Status::invalid_argument(format!("Bad size '{s}'")))
         .context(GrpcStatus)?;
 
-    let _ = ctx
+    let response = ctx
         .v1
         .replica
         .resize_replica(v1_rpc::replica::ResizeReplicaRequest {
@@ -473,7 +462,7 @@ async fn replica_resize(mut ctx: Context, matches: &ArgMatches) -> crate::Result
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {}
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             println!("replica {} is resized", &uuid);
         }
@@ -491,15 +480,7 @@ async fn replica_stat(mut ctx: Context, _matches: &ArgMatches) -> crate::Result<
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             let replicas = &response.get_ref().replicas;
             if replicas.is_empty() {
@@ -531,6 +512,77 @@ async fn replica_stat(mut ctx: Context, _matches: &ArgMatches) -> crate::Result<
     Ok(())
 }
 
+async fn replica_trim(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let uuid = matches
+        .get_one::<String>("uuid")
+        .ok_or_else(|| ClientError::MissingValue {
+            field: "uuid".to_string(),
+        })?
+        .to_owned();
+
+    let response = json_call(
+        &mut ctx,
+        "replica_trim",
+        serde_json::json!({ "replica_uuid": uuid }),
+    )
+    .await?;
+
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response),
+        OutputFormat::Default => {
+            let reclaimed = response["reclaimed_bytes"].as_u64().unwrap_or_default();
+            println!(
+                "Replica {uuid} trimmed, {} zero clusters, {} reclaimed",
+                response["zero_clusters"].as_u64().unwrap_or_default(),
+                ctx.units(Byte::from_u64(reclaimed))
+            );
+        }
+    };
+
+    Ok(())
+}
+
+async fn replica_reclaimed(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let uuid = matches.get_one::<String>("uuid").cloned();
+
+    let response = json_call(
+        &mut ctx,
+        "replica_reclaim_stats",
+        serde_json::json!({ "replica_uuid": uuid }),
+    )
+    .await?;
+
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response),
+        OutputFormat::Default => {
+            let replicas = response.as_array().cloned().unwrap_or_default();
+            if replicas.is_empty() {
+                ctx.v1("No replicas found");
+                return Ok(());
+            }
+
+            let header = vec!["UUID", "THIN", ">ALLOCATED", ">RECLAIMED"];
+            let table = replicas
+                .iter()
+                .map(|r| {
+                    let bytes = |name: &str| {
+                        ctx.units(Byte::from_u64(r[name].as_u64().unwrap_or_default()))
+                    };
+                    vec![
+                        r["replica_uuid"].as_str().unwrap_or_default().to_string(),
+                        r["thin"].as_bool().unwrap_or_default().to_string(),
+                        bytes("allocated_bytes"),
+                        bytes("reclaimed_bytes"),
+                    ]
+                })
+                .collect();
+            ctx.print_list(header, table);
+        }
+    };
+
+    Ok(())
+}
+
 fn parse_replica_protocol(pcol: Option<&String>) -> Result<i32, Status> {
     match pcol.map(|s| s.as_str()) {
         None => Ok(v1_rpc::common::ShareProtocol::None as i32),
@@ -1,12 +1,12 @@
 //!
 //! methods to interact with snapshot management
 
+use super::jsonrpc_cli::json_call;
 use crate::{
     context::{Context, OutputFormat},
     ClientError, GrpcStatus,
 };
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::v1 as v1_rpc;
 use snafu::ResultExt;
 use tonic::Status;
@@ -19,6 +19,8 @@ pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         ("destroy", args) => destroy(ctx, args).await,
         ("create_clone", args) => create_clone(ctx, args).await,
         ("list_clone", args) => list_clone(ctx, args).await,
+        ("set_policy", args) => set_policy(ctx, args).await,
+        ("get_policy", args) => get_policy(ctx, args).await,
         (cmd, _) => {
             Err(Status::not_found(format!("command {cmd} does not exist"))).context(GrpcStatus)
         }
@@ -158,6 +160,62 @@ pub fn subcommands() -> Command {
             .index(1)
             .help("Snapshot uuid"),
     );
+    let set_policy = Command::new("set_policy")
+        .about("Set or clear the snapshot schedule and retention policy of a replica or nexus")
+        .arg(
+            Arg::new("replica_uuid")
+                .required(true)
+                .index(1)
+                .help("Replica uuid, or nexus uuid with --nexus"),
+        )
+        .arg(
+            Arg::new("nexus")
+                .long("nexus")
+                .action(clap::ArgAction::SetTrue)
+                .help("The uuid is the uuid of a nexus, whose replicas are snapshotted together"),
+        )
+        .arg(
+            Arg::new("interval")
+                .long("interval")
+                .required(false)
+                .value_parser(clap::value_parser!(u64))
+                .help("Take a snapshot every <interval> seconds"),
+        )
+        .arg(
+            Arg::new("keep-last")
+                .long("keep-last")
+                .required(false)
+                .value_parser(clap::value_parser!(u32))
+                .help("Keep the N most recent scheduled snapshots"),
+        )
+        .arg(
+            Arg::new("keep-daily")
+                .long("keep-daily")
+                .required(false)
+                .value_parser(clap::value_parser!(u32))
+                .help("Keep the newest scheduled snapshot of each of the last D days"),
+        )
+        .arg(
+            Arg::new("clear")
+                .long("clear")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["interval", "keep-last", "keep-daily"])
+                .help("Clear the policy of the replica or nexus"),
+        );
+    let get_policy = Command::new("get_policy")
+        .about("Get the snapshot schedule and retention policy of a replica or nexus")
+        .arg(
+            Arg::new("replica_uuid")
+                .required(true)
+                .index(1)
+                .help("Replica uuid, or nexus uuid with --nexus"),
+        )
+        .arg(
+            Arg::new("nexus")
+                .long("nexus")
+                .action(clap::ArgAction::SetTrue)
+                .help("The uuid is the uuid of a nexus, whose replicas are snapshotted together"),
+        );
     Command::new("snapshot")
         .subcommand_required(true)
         .arg_required_else_help(true)
@@ -168,6 +226,8 @@ pub fn subcommands() -> Command {
         .subcommand(destroy)
         .subcommand(create_clone)
         .subcommand(list_clone)
+        .subcommand(set_policy)
+        .subcommand(get_policy)
 }
 /// For multiple replicas, replica_uuid will be given in a single string,
 /// separated by comma. Same for snapshot_uuid. replica_uuid and snapshot_uuid
@@ -252,15 +312,7 @@ async fn create_for_nexus(mut ctx: Context, matches: &ArgMatches) -> crate::Resu
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let replica_done = &response.get_ref().replicas_done;
             let nexus = &response.get_ref().nexus;
@@ -342,15 +394,7 @@ async fn create_for_replica(mut ctx: Context, matches: &ArgMatches) -> crate::Re
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "Snapshot Created {}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             let snapshots = &response.get_ref().snapshot;
             let table = snapshots
@@ -415,15 +459,7 @@ async fn list(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let snapshots = &response.get_ref().snapshots;
             if snapshots.is_empty() {
@@ -501,7 +537,9 @@ async fn destroy(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {}
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(
+            &serde_json::json!({ "snapshot_uuid": snapshot_uuid, "deleted": true }),
+        ),
         OutputFormat::Default => {
             println!("snapshot: {} is deleted", &snapshot_uuid);
         }
@@ -544,15 +582,7 @@ async fn create_clone(mut ctx: Context, matches: &ArgMatches) -> crate::Result<(
         .context(GrpcStatus)?;
 
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let r = &response.get_ref();
             let data = vec![vec![
@@ -596,15 +626,7 @@ async fn list_clone(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()>
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let clones = &response.get_ref().replicas;
             if clones.is_empty() {
@@ -645,3 +667,124 @@ async fn list_clone(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()>
 
     Ok(())
 }
+
+async fn set_policy(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let replica_uuid = matches
+        .get_one::<String>("replica_uuid")
+        .ok_or_else(|| ClientError::MissingValue {
+            field: "replica_uuid".to_string(),
+        })?
+        .to_owned();
+    let policy = if matches.get_flag("clear") {
+        serde_json::Value::Null
+    } else {
+        serde_json::json!({
+            "interval_secs": matches.get_one::<u64>("interval"),
+            "keep_last": matches.get_one::<u32>("keep-last"),
+            "keep_daily": matches.get_one::<u32>("keep-daily"),
+        })
+    };
+
+    let response = if matches.get_flag("nexus") {
+        json_call(
+            &mut ctx,
+            "nexus_snapshot_policy_set",
+            serde_json::json!({
+                "nexus_uuid": replica_uuid,
+                "policy": policy,
+            }),
+        )
+        .await?
+    } else {
+        json_call(
+            &mut ctx,
+            "replica_snapshot_policy_set",
+            serde_json::json!({
+                "replica_uuid": replica_uuid,
+                "policy": policy,
+            }),
+        )
+        .await?
+    };
+    print_policy(&ctx, &response);
+
+    Ok(())
+}
+
+async fn get_policy(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
+    let replica_uuid = matches
+        .get_one::<String>("replica_uuid")
+        .ok_or_else(|| ClientError::MissingValue {
+            field: "replica_uuid".to_string(),
+        })?
+        .to_owned();
+
+    let response = if matches.get_flag("nexus") {
+        json_call(
+            &mut ctx,
+            "nexus_snapshot_policy_get",
+            serde_json::json!({ "nexus_uuid": replica_uuid }),
+        )
+        .await?
+    } else {
+        json_call(
+            &mut ctx,
+            "replica_snapshot_policy_get",
+            serde_json::json!({ "replica_uuid": replica_uuid }),
+        )
+        .await?
+    };
+    print_policy(&ctx, &response);
+
+    Ok(())
+}
+
+fn print_policy(ctx: &Context, response: &serde_json::Value) {
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response),
+        OutputFormat::Default => {
+            let policy = &response["policy"];
+            if policy.is_null() {
+                return;
+            }
+            let field = |name: &str| match &policy[name] {
+                serde_json::Value::Null => "".to_string(),
+                value => value.to_string(),
+            };
+            if let Some(nexus_uuid) = response["nexus_uuid"].as_str() {
+                ctx.print_list(
+                    vec![
+                        "NEXUS",
+                        ">INTERVAL",
+                        ">KEEP_LAST",
+                        ">KEEP_DAILY",
+                        "LAST_SNAPSHOT",
+                    ],
+                    vec![vec![
+                        nexus_uuid.to_string(),
+                        field("interval_secs"),
+                        field("keep_last"),
+                        field("keep_daily"),
+                        response["last_snapshot"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                    ]],
+                );
+                return;
+            }
+            ctx.print_list(
+                vec!["REPLICA", ">INTERVAL", ">KEEP_LAST", ">KEEP_DAILY"],
+                vec![vec![
+                    response["replica_uuid"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    field("interval_secs"),
+                    field("keep_last"),
+                    field("keep_daily"),
+                ]],
+            );
+        }
+    }
+}
@@ -6,7 +6,6 @@ use crate::{
     ClientError, GrpcStatus,
 };
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::{v1, v1::snapshot_rebuild::RebuildStatus};
 use snafu::ResultExt;
 use tonic::Status;
@@ -96,15 +95,7 @@ async fn create(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let uuid = response.into_inner().uuid;
             println!("Snapshot Rebuild {uuid} created");
@@ -130,7 +121,12 @@ async fn destroy(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         })
         .await
         .context(GrpcStatus)?;
-    println!("Snapshot Rebuild {uuid} deleted");
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => {
+            ctx.print_structured(&serde_json::json!({ "uuid": uuid, "deleted": true }))
+        }
+        OutputFormat::Default => println!("Snapshot Rebuild {uuid} deleted"),
+    }
 
     Ok(())
 }
@@ -149,15 +145,7 @@ async fn list(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&response.get_ref()),
         OutputFormat::Default => {
             let response = response.into_inner();
             if response.rebuilds.is_empty() {
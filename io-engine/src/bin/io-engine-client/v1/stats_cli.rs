@@ -1,35 +1,44 @@
 use crate::{
-    context::{Context, OutputFormat},
+    context::{watch_arg, watch_interval, Context, OutputFormat},
     GrpcStatus,
 };
 use byte_unit::Byte;
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
 use io_engine_api::v1 as v1rpc;
 use snafu::ResultExt;
+use std::time::{Duration, Instant};
 use tonic::Status;
 
 pub fn subcommands() -> Command {
-    let pool = Command::new("pool").about("Get Pool IO Stats").arg(
-        Arg::new("name")
-            .required(false)
-            .index(1)
-            .help("Storage pool name"),
-    );
+    let pool = Command::new("pool")
+        .about("Get Pool IO Stats")
+        .arg(
+            Arg::new("name")
+                .required(false)
+                .index(1)
+                .help("Storage pool name"),
+        )
+        .arg(watch_arg());
 
-    let nexus = Command::new("nexus").about("Get Nexus IO Stats").arg(
-        Arg::new("name")
-            .required(false)
-            .index(1)
-            .help("Volume target/nexus name"),
-    );
+    let nexus = Command::new("nexus")
+        .about("Get Nexus IO Stats")
+        .arg(
+            Arg::new("name")
+                .required(false)
+                .index(1)
+                .help("Volume target/nexus name"),
+        )
+        .arg(watch_arg());
 
-    let replica = Command::new("replica").about("Get Replica IO Stats").arg(
-        Arg::new("name")
-            .required(false)
-            .index(1)
-            .help("Replica name"),
-    );
+    let replica = Command::new("replica")
+        .about("Get Replica IO Stats")
+        .arg(
+            Arg::new("name")
+                .required(false)
+                .index(1)
+                .help("Replica name"),
+        )
+        .arg(watch_arg());
 
     let reset = Command::new("reset").about("Reset all resource IO Stats");
 
@@ -58,6 +67,9 @@ pub async fn handler(ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
 async fn pool(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
     ctx.v2("Requesting Pool metrics");
     let pool_name = matches.get_one::<String>("name");
+    if let Some(interval) = watch_interval(matches) {
+        return watch(ctx, Resource::Pool, pool_name.cloned(), interval).await;
+    }
     let response = ctx
         .v1
         .stats
@@ -67,15 +79,7 @@ async fn pool(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             let stats: &Vec<v1rpc::stats::IoStats> = &response.get_ref().stats;
             if stats.is_empty() {
@@ -141,6 +145,9 @@ async fn pool(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
 async fn nexus(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
     ctx.v2("Requesting Nexus metrics");
     let nexus_name = matches.get_one::<String>("name");
+    if let Some(interval) = watch_interval(matches) {
+        return watch(ctx, Resource::Nexus, nexus_name.cloned(), interval).await;
+    }
     let response = ctx
         .v1
         .stats
@@ -150,15 +157,7 @@ async fn nexus(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             let stats: &Vec<v1rpc::stats::IoStats> = &response.get_ref().stats;
             if stats.is_empty() {
@@ -224,6 +223,9 @@ async fn nexus(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
 async fn replica(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
     ctx.v2("Requesting Replica metrics");
     let replica_name = matches.get_one::<String>("name");
+    if let Some(interval) = watch_interval(matches) {
+        return watch(ctx, Resource::Replica, replica_name.cloned(), interval).await;
+    }
     let response = ctx
         .v1
         .stats
@@ -233,15 +235,7 @@ async fn replica(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
         .await
         .context(GrpcStatus)?;
     match ctx.output {
-        OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(response.get_ref())
-                    .unwrap()
-                    .to_colored_json_auto()
-                    .unwrap()
-            );
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(response.get_ref()),
         OutputFormat::Default => {
             let stats: &Vec<v1rpc::stats::ReplicaIoStats> = &response.get_ref().stats;
             if stats.is_empty() {
@@ -308,10 +302,150 @@ async fn replica(mut ctx: Context, matches: &ArgMatches) -> crate::Result<()> {
 async fn reset(mut ctx: Context) -> crate::Result<()> {
     ctx.v2("Resetting all metrics");
     let _ = ctx.v1.stats.reset_io_stats(()).await.context(GrpcStatus)?;
-    println!("Stats Reset Completed");
+    match ctx.output {
+        OutputFormat::Json | OutputFormat::Yaml => {
+            ctx.print_structured(&serde_json::json!({ "reset": true }))
+        }
+        OutputFormat::Default => println!("Stats Reset Completed"),
+    }
     Ok(())
 }
 
+/// Resource whose IO stats are requested.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Resource {
+    Pool,
+    Nexus,
+    Replica,
+}
+
+/// Get the IO stats of the resources of the given kind.
+pub(super) async fn io_stats(
+    ctx: &mut Context,
+    resource: Resource,
+    name: Option<String>,
+) -> crate::Result<Vec<v1rpc::stats::IoStats>> {
+    let option = v1rpc::stats::ListStatsOption { name };
+    let stats = match resource {
+        Resource::Pool => {
+            let response = ctx.v1.stats.get_pool_io_stats(option).await;
+            response.context(GrpcStatus)?.into_inner().stats
+        }
+        Resource::Nexus => {
+            let response = ctx.v1.stats.get_nexus_io_stats(option).await;
+            response.context(GrpcStatus)?.into_inner().stats
+        }
+        Resource::Replica => {
+            let response = ctx.v1.stats.get_replica_io_stats(option).await;
+            response
+                .context(GrpcStatus)?
+                .into_inner()
+                .stats
+                .into_iter()
+                .filter_map(|s| s.stats)
+                .collect()
+        }
+    };
+    Ok(stats)
+}
+
+/// IO rates of a resource between two samples of its IO stats.
+#[derive(Debug, Clone, serde::Serialize)]
+pub(super) struct IoRates {
+    pub(super) name: String,
+    pub(super) read_iops: f64,
+    pub(super) write_iops: f64,
+    pub(super) read_mbps: f64,
+    pub(super) write_mbps: f64,
+}
+
+impl IoRates {
+    /// Headers of the rate columns.
+    pub(super) const HEADERS: [&str; 4] =
+        [">READ_IOPS", ">WRITE_IOPS", ">READ_MB/s", ">WRITE_MB/s"];
+
+    fn new(
+        previous: &v1rpc::stats::IoStats,
+        current: &v1rpc::stats::IoStats,
+        elapsed: Duration,
+    ) -> Self {
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        let rate = |previous: u64, current: u64| current.saturating_sub(previous) as f64 / secs;
+        Self {
+            name: current.name.clone(),
+            read_iops: rate(previous.num_read_ops, current.num_read_ops),
+            write_iops: rate(previous.num_write_ops, current.num_write_ops),
+            read_mbps: rate(previous.bytes_read, current.bytes_read) / 1_000_000.0,
+            write_mbps: rate(previous.bytes_written, current.bytes_written) / 1_000_000.0,
+        }
+    }
+
+    /// Columns of the rates, matching the headers.
+    pub(super) fn columns(&self) -> Vec<String> {
+        vec![
+            format!("{:.0}", self.read_iops),
+            format!("{:.0}", self.write_iops),
+            format!("{:.2}", self.read_mbps),
+            format!("{:.2}", self.write_mbps),
+        ]
+    }
+}
+
+/// Rates of the resources present in both samples.
+pub(super) fn io_rates(
+    previous: &[v1rpc::stats::IoStats],
+    current: &[v1rpc::stats::IoStats],
+    elapsed: Duration,
+) -> Vec<IoRates> {
+    current
+        .iter()
+        .filter_map(|c| {
+            let p = previous.iter().find(|p| p.name == c.name)?;
+            Some(IoRates::new(p, c, elapsed))
+        })
+        .collect()
+}
+
+/// Refresh the IO stats on an interval, showing the rates since the last
+/// refresh.
+async fn watch(
+    mut ctx: Context,
+    resource: Resource,
+    name: Option<String>,
+    interval: Duration,
+) -> crate::Result<()> {
+    let mut previous = io_stats(&mut ctx, resource, name.clone()).await?;
+    let mut sampled_at = Instant::now();
+    loop {
+        tokio::time::sleep(interval).await;
+        let current = io_stats(&mut ctx, resource, name.clone()).await?;
+        let rates = io_rates(&previous, &current, sampled_at.elapsed());
+        sampled_at = Instant::now();
+        previous = current;
+
+        match ctx.output {
+            OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&rates),
+            OutputFormat::Default => {
+                if rates.is_empty() {
+                    ctx.v1(&format!("No {resource:?} IoStats found"));
+                    continue;
+                }
+                let table = rates
+                    .iter()
+                    .map(|r| {
+                        let mut row = vec![r.name.clone()];
+                        row.extend(r.columns());
+                        row
+                    })
+                    .collect();
+                let mut headers = vec!["NAME"];
+                headers.extend(IoRates::HEADERS);
+                ctx.print_list(headers, table);
+            }
+        }
+    }
+}
+
 fn adjust_bytes(bytes: u64) -> String {
     let byte = Byte::from_u64(bytes);
     let adjusted_byte = byte.get_appropriate_unit(byte_unit::UnitType::Binary);
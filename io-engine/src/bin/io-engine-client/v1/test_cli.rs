@@ -4,7 +4,6 @@ use crate::{
 };
 use byte_unit::Byte;
 use clap::{Arg, ArgMatches, Command};
-use colored_json::ToColoredJson;
 use futures::StreamExt;
 use io_engine_api::v1 as v1_rpc;
 use snafu::ResultExt;
@@ -157,9 +156,7 @@ async fn features(mut ctx: Context, _matches: &ArgMatches) -> crate::Result<()>
     let response = ctx.v1.test.get_features(()).await.context(GrpcStatus)?;
     let features = response.into_inner();
     match ctx.output {
-        OutputFormat::Json => {
-            println!("{}", serde_json::to_string_pretty(&features).unwrap());
-        }
+        OutputFormat::Json | OutputFormat::Yaml => ctx.print_structured(&features),
         OutputFormat::Default => {
             println!("{features:#?}");
         }
@@ -267,16 +264,10 @@ async fn replica_wipe(mut ctx: Context, matches: &ArgMatches) -> crate::Result<(
     }
 
     match ctx.output {
-        OutputFormat::Json => {
+        OutputFormat::Json | OutputFormat::Yaml => {
             while let Some(response) = resp.next().await {
                 let response = response.context(GrpcStatus)?;
-                println!(
-                    "{}",
-                    serde_json::to_string_pretty(&response)
-                        .unwrap()
-                        .to_colored_json_auto()
-                        .unwrap()
-                );
+                ctx.print_structured(&response);
             }
         }
         OutputFormat::Default => {
@@ -376,13 +367,7 @@ async fn list_injections(mut ctx: Context) -> crate::Result<()> {
         .await
         .context(GrpcStatus)?;
 
-    println!(
-        "{}",
-        serde_json::to_string_pretty(response.get_ref())
-            .unwrap()
-            .to_colored_json_auto()
-            .unwrap()
-    );
+    ctx.print_structured(response.get_ref());
 
     Ok(())
 }
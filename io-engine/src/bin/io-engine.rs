@@ -15,13 +15,16 @@ use io_engine::{
     core::{
         device_monitor_loop,
         diagnostics::process_diagnostics_cli,
+        handover,
         lock::{ProtectedSubsystems, ResourceLockManager, ResourceLockManagerConfig},
         reactor_monitor_loop, runtime, MayastorCliArgs, MayastorEnvironment, Mthread, Reactors,
     },
     eventing::Event,
     grpc, logger,
+    lvs::snapshot_policy_loop,
     persistent_store::PersistentStoreBuilder,
     subsys::Registration,
+    telemetry,
 };
 use version_info::fmt_package_info;
 
@@ -50,6 +53,10 @@ fn start_tokio_runtime(args: &MayastorCliArgs) {
     let api_versions = args.api_versions.clone();
     let node_name = grpc::node_name(&args.node_name);
     let node_nqn = args.make_hostnqn();
+    let registration_tls = args
+        .grpc_tls
+        .client_tls_config()
+        .expect("Invalid gRPC TLS configuration");
 
     let ps_endpoint = args.ps_endpoint.clone();
     let ps_timeout = args.ps_timeout;
@@ -57,6 +64,7 @@ fn start_tokio_runtime(args: &MayastorCliArgs) {
 
     let reactor_freeze_detection = args.reactor_freeze_detection;
     let reactor_freeze_timeout = args.reactor_freeze_timeout;
+    let handover_from = args.handover_from.clone();
 
     // Enable partial rebuild.
     if let Ok(v) = std::env::var("NEXUS_PARTIAL_REBUILD") {
@@ -97,10 +105,6 @@ fn start_tokio_runtime(args: &MayastorCliArgs) {
         warn!("RDMA is requested to be enabled for Mayastor NVMEoF target");
     }
 
-    unsafe {
-        spdk_rs::libspdk::spdk_blob_enable_cluster_unmap(args.bs_cluster_unmap);
-    }
-
     if args.bs_cluster_unmap {
         warn!("Blob store cluster release on UNMAP is enabled");
     } else {
@@ -130,7 +134,12 @@ fn start_tokio_runtime(args: &MayastorCliArgs) {
                     .await;
             }
 
+            if let Some(path) = handover_from {
+                runtime::spawn(handover::take_over(path));
+            }
+
             runtime::spawn(device_monitor_loop());
+            runtime::spawn(snapshot_policy_loop());
 
             // Launch reactor health monitor if diagnostics is enabled.
             if reactor_freeze_detection {
@@ -155,7 +164,9 @@ fn start_tokio_runtime(args: &MayastorCliArgs) {
                     &grpc_socket_addr.to_string(),
                     registration_addr,
                     api_versions,
-                );
+                    registration_tls,
+                )
+                .expect("Failed to configure the registration TLS");
                 futures.push(Registration::run().boxed());
             }
 
@@ -245,9 +256,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // automatically. trace maps to debug at FFI level. If RUST_LOG is
     // passed, we will use it regardless.
     if !args.log_components.is_empty() {
-        logger::init_ex("TRACE", log_format, args.events_url.clone());
+        logger::init_ex(
+            "TRACE",
+            log_format,
+            args.events_url.clone(),
+            args.otlp_endpoint.clone(),
+        );
     } else {
-        logger::init_ex("INFO", log_format, args.events_url.clone());
+        logger::init_ex(
+            "INFO",
+            log_format,
+            args.events_url.clone(),
+            args.otlp_endpoint.clone(),
+        );
     }
 
     info!("{}", fmt_package_info!());
@@ -295,5 +316,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     ms.fini();
     ms.event(EventAction::Start).generate();
+    telemetry::shutdown();
     Ok(())
 }
@@ -10,5 +10,8 @@ pub const NVME_NQN_PREFIX: &str = "nqn.2019-05.io.openebs";
 /// Target to filter eventing traces.
 pub const EVENTING_TARGET: &str = "mbus-events-target";
 
+/// Target of the spans exported to the OpenTelemetry collector.
+pub const TELEMETRY_TARGET: &str = "io-engine-telemetry";
+
 /// Service/ source component generating events for eventing.
 pub const SERVICE_NAME: &str = "io-engine";
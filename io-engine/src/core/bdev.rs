@@ -230,7 +230,7 @@ where
                         .context(ShareNvmf {})?;
                 }
             }
-            Some(Protocol::Off) | None => {}
+            Some(Protocol::Off | Protocol::Ublk | Protocol::Vhost) | None => {}
         }
 
         Ok(())
@@ -247,7 +247,7 @@ where
                     }
                 }
             }
-            Some(Protocol::Off) | None => {}
+            Some(Protocol::Off | Protocol::Ublk | Protocol::Vhost) | None => {}
         }
 
         Ok(())
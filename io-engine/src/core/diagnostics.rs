@@ -1,15 +1,128 @@
-use crate::core::{MayastorCliArgs, Reactor};
+use crate::{
+    bdev::nexus::{nexus_channels_io, NexusChannelIo},
+    core::{
+        lock::{LockState, ResourceLockManager},
+        MayastorCliArgs, MayastorEnvironment, Mthread, Reactor, Reactors,
+    },
+    jsonrpc::{jsonrpc_register, Code, RpcErrorCode},
+    rebuild::NexusRebuildJob,
+};
 use async_process::Command;
+use futures::{channel::oneshot, FutureExt};
 use rstack::TraceOptions;
-use std::env;
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu};
+use spdk_rs::libspdk::{
+    spdk_poller, spdk_poller_get_name, spdk_poller_get_stats, spdk_poller_stats,
+    spdk_thread_get_first_active_poller, spdk_thread_get_first_timed_poller,
+    spdk_thread_get_next_active_poller, spdk_thread_get_next_timed_poller, spdk_thread_get_stats,
+    spdk_thread_stats,
+};
+use std::{env, ffi::CStr, future::Future, path::PathBuf, pin::Pin, time::Duration};
+
+/// Maximum number of diagnostic bundles kept on disk. The oldest bundles are
+/// removed first.
+pub const MAX_BUNDLES: usize = 8;
+
+/// How long to wait for a reactor to report its state.
+const REACTOR_REPLY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Diagnostics errors.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)), context(suffix(false)))]
+#[allow(missing_docs)]
+pub enum DiagnosticsError {
+    #[snafu(display("No diagnostic bundle found"))]
+    NoBundles {},
+    #[snafu(display("Diagnostic bundle {name} not found"))]
+    BundleNotFound { name: String },
+    #[snafu(display("Failed to read diagnostic bundle {name}: {source}"))]
+    ReadBundle {
+        name: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Failed to parse diagnostic bundle {name}: {source}"))]
+    ParseBundle {
+        name: String,
+        source: serde_json::Error,
+    },
+}
+
+impl RpcErrorCode for DiagnosticsError {
+    fn rpc_error_code(&self) -> Code {
+        match self {
+            Self::NoBundles {} | Self::BundleNotFound { .. } => Code::NotFound,
+            _ => Code::InternalError,
+        }
+    }
+}
+
+/// Statistics of an SPDK poller.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PollerSnapshot {
+    pub name: String,
+    pub run_count: u64,
+    pub busy_count: u64,
+}
+
+/// Statistics of an SPDK thread and its pollers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadSnapshot {
+    pub name: String,
+    pub busy_tsc: u64,
+    pub idle_tsc: u64,
+    pub pollers: Vec<PollerSnapshot>,
+}
+
+/// State of a reactor. The SPDK threads of a reactor can only be inspected
+/// if the reactor is responsive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactorSnapshot {
+    pub core: u32,
+    pub tid: u64,
+    pub state: String,
+    pub responsive: bool,
+    pub threads: Vec<ThreadSnapshot>,
+}
+
+/// State of a nexus rebuild job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebuildSnapshot {
+    pub name: String,
+    pub src_uri: String,
+    pub dst_uri: String,
+    pub state: String,
+}
+
+/// Diagnostic bundle captured when a reactor freeze is detected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticBundle {
+    /// Name of the bundle, which is also its file name without extension.
+    pub name: String,
+    /// Time at which the bundle was captured (RFC 3339).
+    pub timestamp: String,
+    /// Core of the frozen reactor.
+    pub frozen_core: u32,
+    /// Stacks of all threads of the process, if they could be collected.
+    pub stacks: Option<String>,
+    /// State of all reactors.
+    pub reactors: Vec<ReactorSnapshot>,
+    /// In-flight I/Os of all nexus channels.
+    pub nexus_channels: Vec<NexusChannelIo>,
+    /// Nexus rebuild jobs, unless the main reactor is unresponsive.
+    pub rebuilds: Option<Vec<RebuildSnapshot>>,
+    /// State of the resource locks.
+    pub locks: Vec<LockState>,
+}
 
 /// Get command path from process CLI arguments.
 fn get_io_agent_path() -> String {
     env::args().next().as_ref().map(String::from).unwrap()
 }
 
-/// Dump stack for current I/O engine instance and log it.
-async fn dump_self_stack() {
+/// Dump stack for current I/O engine instance and log it. Returns the stack,
+/// if it could be collected.
+async fn dump_self_stack() -> Option<String> {
     let pid = std::process::id();
 
     info!(pid, "Collecting stack for I/O agent process");
@@ -26,6 +139,7 @@ async fn dump_self_stack() {
                 %error,
                 "Failed to collect process stack"
             );
+            None
         }
         Ok(output) => {
             let l = String::from_utf8(output.stdout).unwrap();
@@ -39,13 +153,13 @@ async fn dump_self_stack() {
                 }
             });
             info!("Process stack collected successfully");
+            Some(l)
         }
     }
 }
 
-/// Dump detailed diagnostic information for the frozen reactor.
-/// As of now print only basic information about the reactor,
-/// in the future might print stack traces too.
+/// Dump detailed diagnostic information for the frozen reactor, and capture
+/// a diagnostic bundle to disk.
 pub fn diagnose_reactor(reactor: &Reactor) {
     info!(
         core=reactor.core(),
@@ -54,10 +168,243 @@ pub fn diagnose_reactor(reactor: &Reactor) {
         "Reactor is frozen"
     );
 
+    let core = reactor.core();
+
     // Spawn a task to perform stack collection.
     tokio::spawn(async move {
-        dump_self_stack().await;
+        let stacks = dump_self_stack().await;
+        match capture_bundle(core, stacks).await {
+            Ok(path) => info!(?path, "Diagnostic bundle captured"),
+            Err(error) => error!(%error, "Failed to save diagnostic bundle"),
+        }
+    });
+}
+
+/// Collect the statistics of an SPDK thread and its pollers.
+/// Must be called on the reactor which polls the thread.
+fn thread_snapshot(thread: Mthread) -> ThreadSnapshot {
+    let mut snapshot = ThreadSnapshot {
+        name: thread.name().to_string(),
+        busy_tsc: 0,
+        idle_tsc: 0,
+        pollers: Vec::new(),
+    };
+
+    thread.with(|| {
+        let mut stats = spdk_thread_stats::default();
+        if unsafe { spdk_thread_get_stats(&mut stats) } == 0 {
+            snapshot.busy_tsc = stats.busy_tsc;
+            snapshot.idle_tsc = stats.idle_tsc;
+        }
+    });
+
+    let thread_ptr = thread.as_ptr();
+    unsafe {
+        snapshot.pollers = poller_snapshots(
+            spdk_thread_get_first_active_poller(thread_ptr),
+            spdk_thread_get_next_active_poller,
+        );
+        snapshot.pollers.extend(poller_snapshots(
+            spdk_thread_get_first_timed_poller(thread_ptr),
+            spdk_thread_get_next_timed_poller,
+        ));
+    }
+
+    snapshot
+}
+
+/// Collect the statistics of all pollers in a poller list, starting with
+/// `poller`.
+unsafe fn poller_snapshots(
+    mut poller: *mut spdk_poller,
+    next: unsafe extern "C" fn(*mut spdk_poller) -> *mut spdk_poller,
+) -> Vec<PollerSnapshot> {
+    let mut pollers = Vec::new();
+    while !poller.is_null() {
+        let mut stats = spdk_poller_stats::default();
+        spdk_poller_get_stats(poller, &mut stats);
+        pollers.push(PollerSnapshot {
+            name: CStr::from_ptr(spdk_poller_get_name(poller))
+                .to_string_lossy()
+                .into_owned(),
+            run_count: stats.run_count,
+            busy_count: stats.busy_count,
+        });
+        poller = next(poller);
+    }
+    pollers
+}
+
+/// Runs `f` on the reactor and waits for its result, unless the reactor is
+/// unresponsive.
+async fn on_reactor<F, R>(reactor: &Reactor, f: F) -> Option<R>
+where
+    F: FnOnce() -> R + 'static,
+    R: 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    reactor.send_future(async move {
+        sender.send(f()).ok();
     });
+    tokio::time::timeout(REACTOR_REPLY_TIMEOUT, receiver)
+        .await
+        .ok()
+        .and_then(Result::ok)
+}
+
+/// Collect the state of the reactor.
+async fn reactor_snapshot(reactor: &Reactor) -> ReactorSnapshot {
+    let threads = on_reactor(reactor, || {
+        Reactors::current()
+            .threads()
+            .into_iter()
+            .map(thread_snapshot)
+            .collect::<Vec<_>>()
+    })
+    .await;
+
+    ReactorSnapshot {
+        core: reactor.core(),
+        tid: reactor.tid(),
+        state: reactor.get_state().to_string(),
+        responsive: threads.is_some(),
+        threads: threads.unwrap_or_default(),
+    }
+}
+
+/// Collect the state of the nexus rebuild jobs.
+async fn rebuild_snapshot() -> Option<Vec<RebuildSnapshot>> {
+    on_reactor(Reactors::master(), || {
+        NexusRebuildJob::list()
+            .iter()
+            .map(|job| RebuildSnapshot {
+                name: job.name().to_owned(),
+                src_uri: job.src_uri().to_owned(),
+                dst_uri: job.dst_uri().to_owned(),
+                state: job.state().to_string(),
+            })
+            .collect()
+    })
+    .await
+}
+
+/// Capture a diagnostic bundle for the frozen reactor and save it to disk,
+/// returning the path of the bundle.
+/// Must not be called on a reactor, as it waits for all the reactors.
+pub async fn capture_bundle(
+    frozen_core: u32,
+    stacks: Option<String>,
+) -> Result<PathBuf, std::io::Error> {
+    let now = chrono::Utc::now();
+
+    let mut reactors = Vec::new();
+    for reactor in Reactors::iter() {
+        reactors.push(reactor_snapshot(reactor).await);
+    }
+
+    let bundle = DiagnosticBundle {
+        name: format!(
+            "freeze-{}-core{frozen_core}",
+            now.format("%Y%m%dT%H%M%S%.3fZ")
+        ),
+        timestamp: now.to_rfc3339(),
+        frozen_core,
+        stacks,
+        reactors,
+        nexus_channels: nexus_channels_io(),
+        rebuilds: rebuild_snapshot().await,
+        locks: ResourceLockManager::lock_states(),
+    };
+
+    save_bundle(&bundle)
+}
+
+/// Directory where diagnostic bundles are saved.
+fn bundle_dir() -> PathBuf {
+    MayastorEnvironment::global_or_default().diagnostics_dir()
+}
+
+/// Names of the saved diagnostic bundles, oldest first.
+pub fn bundle_names() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(bundle_dir()) else {
+        return Vec::new();
+    };
+
+    let mut names = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            e.file_name()
+                .to_str()
+                .and_then(|n| n.strip_suffix(".json"))
+                .map(ToOwned::to_owned)
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// Save the bundle, removing the oldest bundles beyond `MAX_BUNDLES`.
+fn save_bundle(bundle: &DiagnosticBundle) -> Result<PathBuf, std::io::Error> {
+    let dir = bundle_dir();
+    std::fs::create_dir_all(&dir)?;
+
+    let path = dir.join(&bundle.name).with_extension("json");
+    std::fs::write(&path, serde_json::to_vec_pretty(bundle)?)?;
+
+    let names = bundle_names();
+    let excess = names.len().saturating_sub(MAX_BUNDLES);
+    for name in &names[..excess] {
+        if let Err(error) = std::fs::remove_file(dir.join(name).with_extension("json")) {
+            warn!(%error, %name, "Failed to remove old diagnostic bundle");
+        }
+    }
+
+    Ok(path)
+}
+
+/// Load a saved diagnostic bundle, or the latest one if no name is given.
+pub fn load_bundle(name: Option<String>) -> Result<DiagnosticBundle, DiagnosticsError> {
+    let names = bundle_names();
+    let name = match name {
+        Some(name) => names
+            .into_iter()
+            .find(|n| *n == name)
+            .ok_or(DiagnosticsError::BundleNotFound { name })?,
+        None => names
+            .into_iter()
+            .last()
+            .ok_or(DiagnosticsError::NoBundles {})?,
+    };
+
+    let data = std::fs::read(bundle_dir().join(&name).with_extension("json"))
+        .context(ReadBundle { name: name.clone() })?;
+    serde_json::from_slice(&data).context(ParseBundle { name })
+}
+
+/// Arguments of the diagnostics_get json-rpc method.
+#[derive(Debug, Default, Deserialize)]
+struct DiagnosticsGetArgs {
+    /// Name of the bundle, the latest bundle if not given.
+    name: Option<String>,
+}
+
+type DiagnosticsFuture<R> = Pin<Box<dyn Future<Output = Result<R, DiagnosticsError>>>>;
+
+/// Register the diagnostics json-rpc methods.
+pub(crate) fn register_jsonrpc_methods() {
+    jsonrpc_register(
+        "diagnostics_list",
+        |_args: serde_json::Value| -> DiagnosticsFuture<Vec<String>> {
+            async move { Ok(bundle_names()) }.boxed_local()
+        },
+    );
+
+    jsonrpc_register(
+        "diagnostics_get",
+        |args: Option<DiagnosticsGetArgs>| -> DiagnosticsFuture<DiagnosticBundle> {
+            async move { load_bundle(args.unwrap_or_default().name) }.boxed_local()
+        },
+    );
 }
 
 /// Collect sracktraces for all stack frames for all threads in target process
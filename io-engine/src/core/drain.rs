@@ -0,0 +1,445 @@
+//! Drain, or maintenance mode, of the node.
+//!
+//! Draining prepares the node to be stopped, e.g. before a reboot:
+//! 1. new pools, replicas, nexuses and snapshots are refused.
+//! 2. the ANA state of the nexuses shared over NVMf is changed, so that the
+//!    hosts fail over to their other paths.
+//! 3. the rebuilds are given some time to finish, `DEFAULT_REBUILD_TIMEOUT`
+//!    unless given otherwise, and the remaining ones are paused.
+//! 4. the nexuses are shut down, which flags them as cleanly shut down within
+//!    the persistent store.
+//! 5. the snapshot rebuilds and replica imports are stopped, and the pools
+//!    are exported.
+//!
+//! Once drained, the node is safe to stop.
+//!
+//! A drain can be undone until the nexuses are shut down: the ANA state of the
+//! nexuses is restored, the paused rebuilds are resumed and the node serves
+//! again. Past that point, the io-engine must be restarted to serve again.
+
+use std::{
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use futures::{Future, FutureExt};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use snafu::Snafu;
+
+use crate::{
+    bdev::nexus::{self, nexus_iter, nexus_lookup, NvmeAnaState},
+    core::{handover::is_handing_over, MayastorFeatures, Protocol, Reactors, Share},
+    jsonrpc::{jsonrpc_register, Code, RpcErrorCode},
+    sleep::mayastor_sleep,
+};
+
+/// How often the rebuilds are polled while waiting for them to finish.
+const REBUILD_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long to wait for the rebuilds to finish before pausing them, unless
+/// given otherwise, in seconds.
+pub const DEFAULT_REBUILD_TIMEOUT: u64 = 300;
+
+/// Drain errors.
+#[derive(Debug, Snafu, Clone)]
+#[snafu(visibility(pub(crate)), context(suffix(false)))]
+#[allow(missing_docs)]
+pub enum DrainError {
+    #[snafu(display("The node is draining and no longer accepts new resources"))]
+    NodeDraining {},
+    #[snafu(display("The node is handing over to another io-engine instance"))]
+    HandingOver {},
+    #[snafu(display("The drain can no longer be undone, as it reached the {phase:?} phase"))]
+    DrainIrreversible { phase: DrainPhase },
+}
+
+impl RpcErrorCode for DrainError {
+    fn rpc_error_code(&self) -> Code {
+        match self {
+            Self::NodeDraining { .. } | Self::HandingOver { .. } => Code::InvalidRequest,
+            Self::DrainIrreversible { .. } => Code::InvalidParams,
+        }
+    }
+}
+
+/// ANA state the nexuses are changed to while draining.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DrainAnaState {
+    /// The paths through this node remain usable, but are not preferred.
+    NonOptimized,
+    /// The paths through this node can no longer be used.
+    #[default]
+    Inaccessible,
+}
+
+impl From<DrainAnaState> for NvmeAnaState {
+    fn from(state: DrainAnaState) -> Self {
+        match state {
+            DrainAnaState::NonOptimized => NvmeAnaState::NonOptimizedState,
+            DrainAnaState::Inaccessible => NvmeAnaState::InaccessibleState,
+        }
+    }
+}
+
+/// Arguments of a drain.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DrainArgs {
+    /// ANA state of the nexuses while draining.
+    #[serde(default)]
+    pub ana_state: DrainAnaState,
+    /// How long to wait for the rebuilds to finish before pausing them, in
+    /// seconds. Zero pauses them right away.
+    #[serde(default = "default_rebuild_timeout")]
+    pub rebuild_timeout: u64,
+}
+
+impl Default for DrainArgs {
+    fn default() -> Self {
+        Self {
+            ana_state: DrainAnaState::default(),
+            rebuild_timeout: DEFAULT_REBUILD_TIMEOUT,
+        }
+    }
+}
+
+fn default_rebuild_timeout() -> u64 {
+    DEFAULT_REBUILD_TIMEOUT
+}
+
+/// Phase of a drain.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DrainPhase {
+    /// The node is not draining.
+    #[default]
+    Serving,
+    /// Changing the ANA state of the nexuses.
+    Failover,
+    /// Waiting for the rebuilds to finish, or pausing them.
+    Rebuilds,
+    /// Shutting down the nexuses.
+    Nexuses,
+    /// Exporting the pools.
+    Pools,
+    /// The node is safe to stop.
+    Drained,
+    /// Undoing the drain: restoring the ANA state of the nexuses and resuming
+    /// the paused rebuilds.
+    Undraining,
+}
+
+/// Progress of a drain.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrainStatus {
+    /// Current phase.
+    pub phase: DrainPhase,
+    /// Number of nexuses when the drain started.
+    pub nexuses: u32,
+    /// Number of nexuses whose ANA state was changed.
+    pub failed_over: u32,
+    /// Number of rebuilds which are still running.
+    pub rebuilds: u32,
+    /// Number of rebuilds which were paused, as they didn't finish in time.
+    pub paused_rebuilds: u32,
+    /// Number of nexuses whose ANA state was restored by an undrain.
+    pub restored: u32,
+    /// Number of paused rebuilds which were resumed by an undrain.
+    pub resumed_rebuilds: u32,
+    /// Errors which didn't stop the drain.
+    pub errors: Vec<String>,
+    /// When the drain started, in RFC 3339 format.
+    pub started_at: Option<String>,
+    /// When the node was drained, in RFC 3339 format.
+    pub finished_at: Option<String>,
+}
+
+static DRAIN: Lazy<Mutex<DrainStatus>> = Lazy::new(Default::default);
+
+/// Whether the node is draining, or drained, in which case no new resources
+/// may be created.
+static DRAINING: AtomicBool = AtomicBool::new(false);
+
+/// Check if the node is draining, or drained.
+pub fn is_draining() -> bool {
+    DRAINING.load(Ordering::SeqCst)
+}
+
+/// Refuse new resources while the node is draining, or handing over to
+/// another io-engine instance.
+pub fn check_serving() -> Result<(), DrainError> {
+    if is_draining() {
+        return Err(DrainError::NodeDraining {});
+    }
+    if is_handing_over() {
+        return Err(DrainError::HandingOver {});
+    }
+    Ok(())
+}
+
+/// Get the progress of the drain.
+pub fn drain_status() -> DrainStatus {
+    DRAIN.lock().clone()
+}
+
+/// Update the progress of the drain.
+fn update(f: impl FnOnce(&mut DrainStatus)) {
+    f(&mut DRAIN.lock());
+}
+
+/// Start draining the node, unless it's already draining, and return the
+/// progress of the drain.
+pub fn start_drain(args: DrainArgs) -> DrainStatus {
+    if DRAINING.swap(true, Ordering::SeqCst) {
+        info!("Node is already draining");
+        return drain_status();
+    }
+
+    info!(?args, "Draining the node...");
+    update(|s| {
+        *s = DrainStatus {
+            phase: DrainPhase::Failover,
+            nexuses: nexus_iter().count() as u32,
+            started_at: Some(Utc::now().to_rfc3339()),
+            ..Default::default()
+        }
+    });
+    Reactors::master().send_future(drain(args));
+    drain_status()
+}
+
+/// Undo the drain, unless the nexuses are already being shut down, and return
+/// the progress of the drain.
+pub fn stop_drain() -> Result<DrainStatus, DrainError> {
+    let mut status = DRAIN.lock();
+    match status.phase {
+        DrainPhase::Serving | DrainPhase::Undraining => {}
+        DrainPhase::Failover | DrainPhase::Rebuilds => {
+            info!("Undraining the node...");
+            status.phase = DrainPhase::Undraining;
+        }
+        phase => return Err(DrainError::DrainIrreversible { phase }),
+    }
+    Ok(status.clone())
+}
+
+/// Check if an undrain was requested.
+fn undrain_requested() -> bool {
+    DRAIN.lock().phase == DrainPhase::Undraining
+}
+
+/// Move on to the given phase, unless an undrain was requested.
+fn advance(phase: DrainPhase) -> bool {
+    let mut status = DRAIN.lock();
+    if status.phase == DrainPhase::Undraining {
+        return false;
+    }
+    status.phase = phase;
+    true
+}
+
+/// Drain the node, see the module docs.
+async fn drain(args: DrainArgs) {
+    let failed_over = failover(args.ana_state).await;
+    if !advance(DrainPhase::Rebuilds) {
+        return undrain(failed_over, Vec::new()).await;
+    }
+
+    wait_rebuilds(Duration::from_secs(args.rebuild_timeout)).await;
+    let paused = pause_rebuilds().await;
+    if !advance(DrainPhase::Nexuses) {
+        return undrain(failed_over, paused).await;
+    }
+
+    nexus::shutdown_nexuses().await;
+
+    update(|s| s.phase = DrainPhase::Pools);
+    crate::rebuild::shutdown_snapshot_rebuilds().await;
+    crate::rebuild::shutdown_replica_imports().await;
+    crate::lvs::Lvs::export_all().await;
+    if MayastorFeatures::get().lvm() {
+        crate::lvm::VolumeGroup::export_all().await;
+    }
+
+    update(|s| {
+        s.phase = DrainPhase::Drained;
+        s.finished_at = Some(Utc::now().to_rfc3339());
+    });
+    info!("Node is drained and safe to stop");
+}
+
+/// Names of the current nexuses.
+fn nexus_names() -> Vec<String> {
+    nexus_iter().map(|n| n.name.clone()).collect()
+}
+
+/// Undo the drain: restore the ANA state of the nexuses and resume the paused
+/// rebuilds, then serve again.
+async fn undrain(failed_over: Vec<(String, NvmeAnaState)>, paused: Vec<(String, String)>) {
+    for (name, child) in paused {
+        let Some(nexus) = nexus_lookup(&name) else {
+            continue;
+        };
+        match nexus.resume_rebuild(&child).await {
+            Ok(_) => update(|s| s.resumed_rebuilds += 1),
+            Err(error) => {
+                error!(nexus = name, child, %error, "Failed to resume the rebuild");
+                update(|s| {
+                    s.errors
+                        .push(format!("Failed to resume the rebuild of {child}: {error}"))
+                });
+            }
+        }
+    }
+
+    for (name, ana_state) in failed_over {
+        let Some(nexus) = nexus_lookup(&name) else {
+            continue;
+        };
+        match nexus.set_ana_state(ana_state).await {
+            Ok(_) => update(|s| s.restored += 1),
+            Err(error) => {
+                error!(nexus = name, %error, "Failed to restore the nexus ANA state");
+                update(|s| {
+                    s.errors.push(format!(
+                        "Failed to restore the ANA state of {name}: {error}"
+                    ))
+                });
+            }
+        }
+    }
+
+    update(|s| {
+        s.phase = DrainPhase::Serving;
+        s.rebuilds = 0;
+        s.finished_at = Some(Utc::now().to_rfc3339());
+    });
+    DRAINING.store(false, Ordering::SeqCst);
+    info!("Node is undrained and serving again");
+}
+
+/// Change the ANA state of the nexuses shared over NVMf, and return their
+/// previous ANA state. Stops early if an undrain is requested.
+async fn failover(ana_state: DrainAnaState) -> Vec<(String, NvmeAnaState)> {
+    let mut failed_over = Vec::new();
+    for name in nexus_names() {
+        if undrain_requested() {
+            break;
+        }
+        let Some(nexus) = nexus_lookup(&name) else {
+            continue;
+        };
+        if nexus.shared() != Some(Protocol::Nvmf) {
+            continue;
+        }
+        let previous = match nexus.get_ana_state().await {
+            Ok(previous) => previous,
+            Err(error) => {
+                error!(nexus = name, %error, "Failed to get the nexus ANA state");
+                update(|s| {
+                    s.errors
+                        .push(format!("Failed to get the ANA state of {name}: {error}"))
+                });
+                continue;
+            }
+        };
+        match nexus.set_ana_state(ana_state.into()).await {
+            Ok(_) => {
+                failed_over.push((name, previous));
+                update(|s| s.failed_over += 1);
+            }
+            Err(error) => {
+                error!(nexus = name, %error, "Failed to change the nexus ANA state");
+                update(|s| {
+                    s.errors
+                        .push(format!("Failed to change the ANA state of {name}: {error}"))
+                });
+            }
+        }
+    }
+    failed_over
+}
+
+/// Count the running rebuilds.
+fn count_rebuilds() -> usize {
+    nexus_iter().map(|n| n.count_rebuild_jobs()).sum()
+}
+
+/// Wait for the rebuilds to finish, for up to the given timeout. Stops early
+/// if an undrain is requested.
+async fn wait_rebuilds(timeout: Duration) {
+    let started = Instant::now();
+    loop {
+        let rebuilds = count_rebuilds();
+        update(|s| s.rebuilds = rebuilds as u32);
+        if rebuilds == 0 || started.elapsed() >= timeout || undrain_requested() {
+            return;
+        }
+        mayastor_sleep(REBUILD_POLL_INTERVAL).await.ok();
+    }
+}
+
+/// Pause the rebuilds which are still running, unless an undrain is
+/// requested, and return the nexus and child of the paused ones.
+async fn pause_rebuilds() -> Vec<(String, String)> {
+    let mut paused = Vec::new();
+    if undrain_requested() {
+        return paused;
+    }
+    for name in nexus_names() {
+        let Some(nexus) = nexus_lookup(&name) else {
+            continue;
+        };
+        let children = nexus
+            .children_iter()
+            .filter(|c| c.rebuild_job().is_some())
+            .map(|c| c.uri().to_string())
+            .collect::<Vec<_>>();
+        for child in children {
+            match nexus.pause_rebuild(&child).await {
+                Ok(_) => {
+                    update(|s| s.paused_rebuilds += 1);
+                    paused.push((name.clone(), child));
+                }
+                Err(error) => {
+                    error!(nexus = name, child, %error, "Failed to pause the rebuild");
+                    update(|s| {
+                        s.errors
+                            .push(format!("Failed to pause the rebuild of {child}: {error}"))
+                    });
+                }
+            }
+        }
+    }
+    update(|s| s.rebuilds = 0);
+    paused
+}
+
+type DrainFuture<R> = Pin<Box<dyn Future<Output = Result<R, DrainError>>>>;
+
+/// Register the drain json-rpc methods.
+pub(crate) fn register_jsonrpc_methods() {
+    jsonrpc_register(
+        "node_drain",
+        |args: Option<DrainArgs>| -> DrainFuture<DrainStatus> {
+            async move { Ok(start_drain(args.unwrap_or_default())) }.boxed_local()
+        },
+    );
+
+    jsonrpc_register(
+        "node_undrain",
+        |_args: serde_json::Value| -> DrainFuture<DrainStatus> {
+            async move { stop_drain() }.boxed_local()
+        },
+    );
+
+    jsonrpc_register(
+        "node_drain_status",
+        |_args: serde_json::Value| -> DrainFuture<DrainStatus> {
+            async move { Ok(drain_status()) }.boxed_local()
+        },
+    );
+}
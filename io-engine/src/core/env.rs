@@ -149,6 +149,13 @@ pub struct MayastorCliArgs {
     #[clap(long)]
     /// Path to persistence through power loss nvme reservation base directory.
     pub ptpl_dir: Option<String>,
+    #[clap(long, default_value = "/var/tmp/io-engine/vhost")]
+    /// Directory where vhost-user-blk sockets are created.
+    pub vhost_socket_dir: String,
+    #[clap(long, env = "PTPL_STORE", requires = "ptpl_dir")]
+    /// Keep the nexus persistence through power loss state in the persistent
+    /// store, so that it follows the nexus to other nodes.
+    pub ptpl_store: bool,
     #[clap(short = 'P')]
     /// Path to pool config file.
     pub pool_config: Option<String>,
@@ -214,6 +221,20 @@ pub struct MayastorCliArgs {
     /// Timeout (in seconds) for reactor freeze detection.
     #[clap(long = "reactor-freeze-timeout", env = "REACTOR_FREEZE_TIMEOUT")]
     pub reactor_freeze_timeout: Option<u64>,
+    /// Directory where diagnostic bundles are captured on reactor freezes.
+    #[clap(
+        long,
+        env = "DIAGNOSTICS_DIR",
+        default_value = "/var/tmp/io-engine/diagnostics"
+    )]
+    pub diagnostics_dir: String,
+    /// Directory where the stream sources of replica imports are spooled.
+    #[clap(
+        long,
+        env = "IMPORT_SPOOL_DIR",
+        default_value = "/var/tmp/io-engine/import"
+    )]
+    pub import_spool_dir: String,
     /// Skip install of the signal handler which will trigger process graceful
     /// termination.
     #[clap(long, hide = true)]
@@ -227,6 +248,10 @@ pub struct MayastorCliArgs {
     /// Events message-bus endpoint url.
     #[clap(long)]
     pub events_url: Option<url::Url>,
+    /// OTLP collector endpoint url, to export the traces of the gRPC calls
+    /// to, e.g. http://otel-collector:4317.
+    #[clap(long, env = "OTEL_EXPORTER_OTLP_ENDPOINT")]
+    pub otlp_endpoint: Option<url::Url>,
     /// Enables additional nexus I/O channel debugging.
     #[clap(
         long = "enable-channel-dbg",
@@ -253,6 +278,13 @@ pub struct MayastorCliArgs {
     /// Enables globally blob store cluster release on unmap.
     #[clap(long, env = "ENABLE_BS_CLUSTER_UNMAP", hide = true)]
     pub bs_cluster_unmap: bool,
+    /// gRPC server TLS and authorisation.
+    #[clap(flatten)]
+    pub grpc_tls: grpc::GrpcTlsArgs,
+    /// Path to the handover manifest of a running io-engine instance to take
+    /// over from, for a live upgrade.
+    #[clap(long, env = "HANDOVER_FROM")]
+    pub handover_from: Option<String>,
 }
 
 fn delay_compat(s: &str) -> Result<bool, String> {
@@ -304,6 +336,8 @@ impl Default for MayastorCliArgs {
             log_format: None,
             mayastor_config: None,
             ptpl_dir: None,
+            vhost_socket_dir: "/var/tmp/io-engine/vhost".to_string(),
+            ptpl_store: false,
             pool_config: None,
             hugedir: None,
             core_list: None,
@@ -316,15 +350,20 @@ impl Default for MayastorCliArgs {
             diagnose_stack: None,
             reactor_freeze_detection: false,
             reactor_freeze_timeout: None,
+            diagnostics_dir: "/var/tmp/io-engine/diagnostics".to_string(),
+            import_spool_dir: "/var/tmp/io-engine/import".to_string(),
             skip_sig_handler: false,
             enable_io_all_thrd_nexus_channels: false,
             events_url: None,
+            otlp_endpoint: None,
             enable_nexus_channel_debug: false,
             lvm: false,
             snap_rebuild: false,
             developer_delay: false,
             rdma: false,
             bs_cluster_unmap: false,
+            grpc_tls: Default::default(),
+            handover_from: None,
         }
     }
 }
@@ -377,6 +416,10 @@ pub struct MayastorEnvironment {
     ps_retries: u8,
     mayastor_config: Option<String>,
     ptpl_dir: Option<String>,
+    vhost_socket_dir: String,
+    ptpl_store: bool,
+    diagnostics_dir: String,
+    import_spool_dir: String,
     pool_config: Option<String>,
     delay_subsystem_init: bool,
     enable_coredump: bool,
@@ -414,6 +457,9 @@ pub struct MayastorEnvironment {
     developer_delay: bool,
     rdma: bool,
     bs_cluster_unmap: bool,
+    /// gRPC server TLS and authorisation.
+    pub grpc_tls: grpc::GrpcTlsArgs,
+    handover_from: Option<String>,
 }
 
 impl Default for MayastorEnvironment {
@@ -428,6 +474,10 @@ impl Default for MayastorEnvironment {
             ps_retries: 30,
             mayastor_config: None,
             ptpl_dir: None,
+            vhost_socket_dir: "/var/tmp/io-engine/vhost".to_string(),
+            ptpl_store: false,
+            diagnostics_dir: "/var/tmp/io-engine/diagnostics".to_string(),
+            import_spool_dir: "/var/tmp/io-engine/import".to_string(),
             pool_config: None,
             delay_subsystem_init: false,
             enable_coredump: true,
@@ -464,6 +514,8 @@ impl Default for MayastorEnvironment {
             developer_delay: false,
             rdma: false,
             bs_cluster_unmap: false,
+            grpc_tls: Default::default(),
+            handover_from: None,
         }
     }
 }
@@ -503,6 +555,7 @@ async fn do_shutdown(arg: *mut c_void) {
     }
     nexus::shutdown_nexuses().await;
     crate::rebuild::shutdown_snapshot_rebuilds().await;
+    crate::rebuild::shutdown_replica_imports().await;
     crate::lvs::Lvs::export_all().await;
     if MayastorFeatures::get().lvm() {
         crate::lvm::VolumeGroup::export_all().await;
@@ -542,6 +595,16 @@ unsafe extern "C" fn signal_trampoline(_: *mut c_void) {
     mayastor_env_stop(0);
 }
 
+/// called on SIGHUP, reloads the configuration file on the master reactor
+fn mayastor_reload_handler() {
+    info!("Received SIGHUP, reloading the configuration file");
+    match Reactor::spawn_at_primary(async { Config::reload() }).map(futures::executor::block_on) {
+        Ok(Ok(Ok(_))) => {}
+        Ok(Ok(Err(error))) => error!("Failed to reload the configuration file: {error}"),
+        _ => error!("Failed to reload the configuration file: the master reactor is gone"),
+    }
+}
+
 /// called on SIGINT and SIGTERM
 extern "C" fn mayastor_signal_handler(signo: i32) {
     if SIG_RECEIVED.load(SeqCst) {
@@ -586,6 +649,10 @@ impl MayastorEnvironment {
             ),
             mayastor_config: args.mayastor_config,
             ptpl_dir: args.ptpl_dir,
+            vhost_socket_dir: args.vhost_socket_dir,
+            ptpl_store: args.ptpl_store,
+            diagnostics_dir: args.diagnostics_dir,
+            import_spool_dir: args.import_spool_dir,
             pool_config: args.pool_config,
             log_component: args.log_components,
             mem_size: args.mem_size,
@@ -604,6 +671,8 @@ impl MayastorEnvironment {
             developer_delay: args.developer_delay,
             rdma: args.rdma,
             bs_cluster_unmap: args.bs_cluster_unmap,
+            grpc_tls: args.grpc_tls,
+            handover_from: args.handover_from,
             enable_io_all_thrd_nexus_channels: args.enable_io_all_thrd_nexus_channels,
             ..Default::default()
         }
@@ -615,6 +684,33 @@ impl MayastorEnvironment {
         self.ptpl_dir.clone()
     }
 
+    /// Check if the nexus persistence through power loss state is kept in
+    /// the persistent store.
+    pub fn ptpl_store(&self) -> bool {
+        self.ptpl_store && self.ptpl_dir.is_some()
+    }
+
+    /// Get the vhost-user-blk socket directory.
+    pub fn vhost_socket_dir(&self) -> std::path::PathBuf {
+        std::path::PathBuf::from(&self.vhost_socket_dir)
+    }
+
+    /// Get the directory where diagnostic bundles are captured.
+    pub fn diagnostics_dir(&self) -> std::path::PathBuf {
+        std::path::PathBuf::from(&self.diagnostics_dir)
+    }
+
+    /// Get the directory where the stream sources of replica imports are
+    /// spooled.
+    pub fn import_spool_dir(&self) -> std::path::PathBuf {
+        std::path::PathBuf::from(&self.import_spool_dir)
+    }
+
+    /// Get the SPDK log flags enabled at startup.
+    pub fn log_components(&self) -> &[String] {
+        &self.log_component
+    }
+
     fn setup_static(self) -> Self {
         match MAYASTOR_DEFAULT_ENV.get() {
             None => {
@@ -651,6 +747,24 @@ impl MayastorEnvironment {
             })
         }
         .unwrap();
+
+        // SIGHUP reloads the configuration once the reactors are up, until
+        // then it's ignored instead of terminating the process.
+        unsafe { signal_hook::low_level::register(signal_hook::consts::SIGHUP, || {}) }.unwrap();
+    }
+
+    /// reload the configuration file on SIGHUP, which needs the reactors
+    fn install_reload_handler(&self) {
+        let mut signals =
+            signal_hook::iterator::Signals::new([signal_hook::consts::SIGHUP]).unwrap();
+        std::thread::Builder::new()
+            .name("sighup".into())
+            .spawn(move || {
+                for _ in signals.forever() {
+                    mayastor_reload_handler();
+                }
+            })
+            .unwrap();
     }
 
     /// construct an array of options to be passed to EAL and start it
@@ -805,6 +919,16 @@ impl MayastorEnvironment {
         self.rdma
     }
 
+    /// Check if blob store clusters are released on unmap.
+    pub fn bs_cluster_unmap(&self) -> bool {
+        self.bs_cluster_unmap
+    }
+
+    /// Get the handover manifest of the io-engine instance to take over from.
+    pub fn handover_from(&self) -> Option<&str> {
+        self.handover_from.as_deref()
+    }
+
     /// Detects IP address for NVMF target by the interface specified in CLI
     /// arguments.
     fn detect_nvmf_tgt_iface_ip(iface: &str) -> Result<String, String> {
@@ -1001,6 +1125,10 @@ impl MayastorEnvironment {
             }
         }
 
+        if let Err(error) = std::fs::create_dir_all(&self.vhost_socket_dir) {
+            tracing::error!(%error, "Failed to create vhost socket directory");
+        }
+
         let pool_config = self.load_pool_config();
 
         // bootstrap DPDK and its magic
@@ -1026,6 +1154,10 @@ impl MayastorEnvironment {
             nexus::ENABLE_IO_ALL_THRD_NX_CHAN.store(true, SeqCst);
         }
 
+        unsafe {
+            spdk_rs::libspdk::spdk_blob_enable_cluster_unmap(self.bs_cluster_unmap);
+        }
+
         // allocate a Reactor per core
         Reactors::init(self.developer_delay);
 
@@ -1070,6 +1202,10 @@ impl MayastorEnvironment {
             assert!(receiver.await.unwrap());
         });
 
+        if !self.skip_sig_handler {
+            self.install_reload_handler();
+        }
+
         // load any pools that need to be created
         if let Some(config) = pool_config {
             config.import_pools();
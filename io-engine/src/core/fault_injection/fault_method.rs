@@ -1,6 +1,9 @@
-use rand::RngCore;
+use rand::{Rng, RngCore};
 use regex::Regex;
-use std::fmt::{Debug, Display, Formatter};
+use std::{
+    fmt::{Debug, Display, Formatter},
+    time::Duration,
+};
 
 use spdk_rs::NvmeStatus;
 
@@ -15,6 +18,25 @@ pub enum FaultMethod {
     Status(IoCompletionStatus),
     /// Introduces data buffer corruption.
     Data,
+    /// Postpones completion of an affected operation by the given time.
+    Delay(Duration),
+    /// Never completes an affected operation, which is aborted once it times
+    /// out.
+    Drop,
+    /// Flips a random bit in every given number of bytes of the data buffer,
+    /// and at least one bit per operation.
+    BitFlip(u64),
+}
+
+/// Effect of an injected fault on the I/O.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InjectedFault {
+    /// Completes the I/O with the given status.
+    Status(IoCompletionStatus),
+    /// Completes the I/O after the given delay.
+    Delay(Duration),
+    /// The I/O is never completed, until it times out.
+    Drop,
 }
 
 impl Debug for FaultMethod {
@@ -24,6 +46,9 @@ impl Debug for FaultMethod {
                 write!(f, "Status[{s:?}]")
             }
             Self::Data => f.write_str("Data"),
+            Self::Delay(d) => write!(f, "Delay[{d:?}]"),
+            Self::Drop => f.write_str("Drop"),
+            Self::BitFlip(n) => write!(f, "BitFlip[1/{n}]"),
         }
     }
 }
@@ -56,6 +81,9 @@ impl Display for FaultMethod {
                 write!(f, "status-admin")
             }
             Self::Data => f.write_str("data"),
+            Self::Delay(d) => write!(f, "delay-{}", d.as_millis()),
+            Self::Drop => f.write_str("drop"),
+            Self::BitFlip(n) => write!(f, "bitflip-{n}"),
             _ => f.write_str("invalid"),
         }
     }
@@ -67,17 +95,28 @@ impl FaultMethod {
         NvmeStatus::DATA_TRANSFER_ERROR,
     ));
 
+    /// True if the method changes when the I/O completes rather than how.
+    pub fn is_deferring(&self) -> bool {
+        matches!(self, Self::Delay(_) | Self::Drop)
+    }
+
     /// TODO
     pub(super) fn inject(
         &self,
         state: &mut InjectionState,
         ctx: &InjectIoCtx,
-    ) -> Option<IoCompletionStatus> {
+    ) -> Option<InjectedFault> {
         match self {
-            FaultMethod::Status(status) => Some(*status),
+            FaultMethod::Status(status) => Some(InjectedFault::Status(*status)),
             FaultMethod::Data => {
                 self.inject_data_errors(state, ctx);
-                Some(IoCompletionStatus::Success)
+                Some(InjectedFault::Status(IoCompletionStatus::Success))
+            }
+            FaultMethod::Delay(d) => Some(InjectedFault::Delay(*d)),
+            FaultMethod::Drop => Some(InjectedFault::Drop),
+            FaultMethod::BitFlip(n) => {
+                self.inject_bit_flips(*n, state, ctx);
+                Some(InjectedFault::Status(IoCompletionStatus::Success))
             }
         }
     }
@@ -95,11 +134,51 @@ impl FaultMethod {
         }
     }
 
+    /// Flips one random bit per `rate` bytes of the data buffer.
+    fn inject_bit_flips(&self, rate: u64, s: &mut InjectionState, ctx: &InjectIoCtx) {
+        let Some(iovs) = ctx.iovs_mut() else {
+            return;
+        };
+
+        let total: u64 = iovs.iter().map(|iov| iov.len()).sum();
+        if total == 0 {
+            return;
+        }
+
+        let flips = (total / rate.max(1)).max(1);
+        for _ in 0..flips {
+            let mut pos = s.rng.gen_range(0..total);
+            for iov in iovs.iter_mut() {
+                if pos < iov.len() {
+                    iov[pos] ^= 1 << s.rng.gen_range(0..8);
+                    break;
+                }
+                pos -= iov.len();
+            }
+        }
+    }
+
     /// TODO
     pub fn parse(s: &str) -> Option<Self> {
         lazy_static::lazy_static! {
             static ref NVME_RE: Regex =
                 Regex::new(r"^status-nvme-([0-9a-f.]+)-([0-9a-f.]+)$").unwrap();
+            static ref DELAY_RE: Regex = Regex::new(r"^delay-([0-9]+)$").unwrap();
+            static ref BITFLIP_RE: Regex = Regex::new(r"^bitflip-([0-9]+)$").unwrap();
+        }
+
+        if let Some(cap) = DELAY_RE.captures(s) {
+            let ms = cap.get(1).unwrap().as_str().parse::<u64>().ok()?;
+            return Some(Self::Delay(Duration::from_millis(ms)));
+        }
+
+        if let Some(cap) = BITFLIP_RE.captures(s) {
+            let rate = cap.get(1).unwrap().as_str().parse::<u64>().ok()?;
+            return (rate > 0).then_some(Self::BitFlip(rate));
+        }
+
+        if s == "drop" {
+            return Some(Self::Drop);
         }
 
         if let Some(cap) = NVME_RE.captures(s) {
@@ -1,5 +1,6 @@
 #![cfg(feature = "fault-injection")]
 
+use rand::Rng;
 use spdk_rs::NvmeStatus;
 use std::{
     cell::RefCell,
@@ -13,7 +14,7 @@ use crate::core::IoCompletionStatus;
 
 use super::{
     FaultDomain, FaultInjectionError, FaultIoOperation, FaultIoStage, FaultMethod, InjectIoCtx,
-    InjectionState,
+    InjectedFault, InjectionState,
 };
 
 /// Fault injection.
@@ -41,6 +42,8 @@ pub struct Injection {
     pub block_range: Range<u64>,
     /// Number of retries.
     pub retries: u64,
+    /// Percentage of matching I/Os the fault applies to.
+    pub probability: u8,
     /// Injection state.
     #[builder(setter(skip))]
     state: RefCell<InjectionState>,
@@ -108,6 +111,7 @@ impl Debug for Injection {
                     &fmt_u64(self.block_range.end - self.block_range.start),
                 )
                 .field("retries", &fmt_u64(self.retries))
+                .field("probability", &self.probability)
                 .field("hits", &self.state.borrow().hits)
                 .field("started", &fmt_duration(&self.state.borrow().now()))
                 .finish()
@@ -152,9 +156,15 @@ impl Debug for Injection {
                 "".to_string()
             };
 
+            let probability = if self.probability < 100 {
+                format!(" | {p}% of I/Os", p = self.probability)
+            } else {
+                "".to_string()
+            };
+
             write!(
                 f,
-                "{info} on '{n}'{timed}{range}{retries}",
+                "{info} on '{n}'{timed}{range}{retries}{probability}",
                 n = self.device_name,
             )
         }
@@ -173,6 +183,7 @@ impl Default for Injection {
             time_range: Duration::ZERO..Duration::MAX,
             block_range: 0..u64::MAX,
             retries: u64::MAX,
+            probability: 100,
             state: Default::default(),
         }
     }
@@ -218,6 +229,7 @@ impl Injection {
                 "offset" => r.block_range.start = parse_num(&k, &v)?,
                 "num_blk" | "num_blocks" => r.block_range.end = parse_num(&k, &v)?,
                 "retries" => r.retries = parse_num(&k, &v)?,
+                "probability" => r.probability = parse_percent(&k, &v)?,
                 _ => {
                     return Err(FaultInjectionError::UnknownParameter {
                         name: k.to_string(),
@@ -285,6 +297,10 @@ impl Injection {
             opts.push(format!("retries={}", self.retries));
         }
 
+        if self.probability != d.probability {
+            opts.push(format!("probability={}", self.probability));
+        }
+
         format!(
             "inject://{name}?{opts}",
             name = self.device_name,
@@ -312,10 +328,9 @@ impl Injection {
 
     /// Injects an error for the given I/O context.
     /// If this injected fault does not apply to this context, returns `None`.
-    /// Otherwise, returns an operation status to be returned by the calling I/O
-    /// routine.
+    /// Otherwise, returns the fault to be applied by the calling I/O routine.
     #[inline]
-    pub fn inject(&self, stage: FaultIoStage, ctx: &InjectIoCtx) -> Option<IoCompletionStatus> {
+    pub fn inject(&self, stage: FaultIoStage, ctx: &InjectIoCtx) -> Option<InjectedFault> {
         if !ctx.is_valid()
             || !ctx.domain_ok(self.domain)
             || stage != self.io_stage
@@ -333,7 +348,13 @@ impl Injection {
             return None;
         }
 
-        self.method.inject(&mut self.state.borrow_mut(), ctx)
+        let mut state = self.state.borrow_mut();
+
+        if self.probability < 100 && state.rng.gen_range(0..100) >= self.probability {
+            return None;
+        }
+
+        self.method.inject(&mut state, ctx)
     }
 }
 
@@ -388,7 +409,6 @@ fn parse_fault_io_stage(k: &str, v: &str) -> Result<FaultIoStage, FaultInjection
 fn parse_method(k: &str, v: &str) -> Result<FaultMethod, FaultInjectionError> {
     match v {
         "status" | "Status" => Ok(FaultMethod::DATA_TRANSFER_ERROR),
-        // TODO: add data corruption methods.
         "data" | "Data" => Ok(FaultMethod::Data),
         _ => FaultMethod::parse(v).ok_or_else(|| FaultInjectionError::UnknownParameter {
             name: k.to_string(),
@@ -409,6 +429,17 @@ fn parse_timer(k: &str, v: &str) -> Result<Duration, FaultInjectionError> {
     Ok(Duration::from_millis(b))
 }
 
+/// Parses a percentage in the range 0..=100.
+fn parse_percent(k: &str, v: &str) -> Result<u8, FaultInjectionError> {
+    match v.parse::<u8>() {
+        Ok(p) if p <= 100 => Ok(p),
+        _ => Err(FaultInjectionError::BadParameterValue {
+            name: k.to_string(),
+            value: v.to_string(),
+        }),
+    }
+}
+
 /// TODO
 fn parse_num(k: &str, v: &str) -> Result<u64, FaultInjectionError> {
     v.parse::<u64>()
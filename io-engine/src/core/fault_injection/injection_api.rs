@@ -2,12 +2,21 @@
 
 use nix::errno::Errno;
 use once_cell::sync::OnceCell;
-use std::sync::atomic::{AtomicBool, Ordering};
+use spdk_rs::{libspdk::SPDK_NVME_SC_ABORTED_BY_REQUEST, NvmeStatus, Poller, PollerBuilder};
+use std::{
+    cell::RefCell,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
 
-use crate::core::{CoreError, IoCompletionStatus};
+use crate::{
+    bdev::nvmx::nvme_bdev_running_config,
+    core::{CoreError, IoCompletionStatus},
+};
 
 use super::{
-    add_bdev_io_injection, FaultDomain, FaultInjectionError, FaultIoStage, InjectIoCtx, Injection,
+    add_bdev_io_injection, FaultDomain, FaultInjectionError, FaultIoStage, InjectIoCtx,
+    InjectedFault, Injection,
 };
 
 /// A list of fault injections.
@@ -15,6 +24,10 @@ struct Injections {
     items: Vec<Injection>,
 }
 
+/// Time after which a dropped I/O is aborted when the NVMe I/O timeout is
+/// disabled.
+const DROPPED_IO_DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
 static INJECTIONS: OnceCell<parking_lot::Mutex<Injections>> = OnceCell::new();
 
 impl Injections {
@@ -31,6 +44,15 @@ impl Injections {
 
     /// Adds an injection.
     pub fn add(&mut self, inj: Injection) -> Result<(), FaultInjectionError> {
+        if inj.method.is_deferring()
+            && (inj.domain != FaultDomain::BlockDevice || inj.io_stage != FaultIoStage::Completion)
+        {
+            return Err(FaultInjectionError::InvalidInjection {
+                name: inj.device_name.clone(),
+                msg: "delay and drop are supported only for block device completions".to_string(),
+            });
+        }
+
         if inj.domain == FaultDomain::BdevIo {
             add_bdev_io_injection(&inj)?;
         }
@@ -55,7 +77,7 @@ impl Injections {
 
     /// TODO
     #[inline(always)]
-    fn inject(&self, stage: FaultIoStage, op: &InjectIoCtx) -> Option<IoCompletionStatus> {
+    fn inject(&self, stage: FaultIoStage, op: &InjectIoCtx) -> Option<InjectedFault> {
         self.items.iter().find_map(|inj| inj.inject(stage, op))
     }
 }
@@ -107,14 +129,15 @@ pub fn inject_submission_error(ctx: &InjectIoCtx) -> Result<(), CoreError> {
     }
 
     match Injections::get().inject(FaultIoStage::Submission, ctx) {
-        None => Ok(()),
-        Some(IoCompletionStatus::Success) => Ok(()),
-        Some(_) => Err(crate::bdev::device::io_type_to_err(
-            ctx.io_type,
-            Errno::ENXIO,
-            ctx.range.start,
-            ctx.range.end - ctx.range.start,
-        )),
+        Some(InjectedFault::Status(s)) if s != IoCompletionStatus::Success => {
+            Err(crate::bdev::device::io_type_to_err(
+                ctx.io_type,
+                Errno::ENXIO,
+                ctx.range.start,
+                ctx.range.end - ctx.range.start,
+            ))
+        }
+        _ => Ok(()),
     }
 }
 
@@ -127,12 +150,110 @@ pub fn inject_completion_error(
     ctx: &InjectIoCtx,
     status: IoCompletionStatus,
 ) -> IoCompletionStatus {
+    match inject_completion_fault(ctx, status) {
+        Some(InjectedFault::Status(s)) => s,
+        _ => status,
+    }
+}
+
+/// Finds and injects a fault for the given I/O context, at the completion I/O
+/// stage, and completes the I/O by calling `complete`.
+/// Unlike `inject_completion_error`, this also applies delay and drop faults:
+/// a delayed I/O is completed later on the current core, and a dropped I/O is
+/// aborted once the NVMe I/O timeout elapses, as a block device without an
+/// NVMe controller has no timeout handling of its own.
+/// The I/O must not refer to resources released before it completes, as the
+/// underlying bdev I/O.
+#[inline]
+pub fn inject_completion<F>(ctx: &InjectIoCtx, status: IoCompletionStatus, complete: F)
+where
+    F: FnOnce(IoCompletionStatus) + 'static,
+{
+    match inject_completion_fault(ctx, status) {
+        None => complete(status),
+        Some(InjectedFault::Status(s)) => complete(s),
+        Some(InjectedFault::Delay(d)) => defer_completion(d, Box::new(move || complete(status))),
+        Some(InjectedFault::Drop) => defer_completion(
+            dropped_io_timeout(),
+            Box::new(move || {
+                complete(IoCompletionStatus::NvmeError(NvmeStatus::Generic(
+                    SPDK_NVME_SC_ABORTED_BY_REQUEST,
+                )))
+            }),
+        ),
+    }
+}
+
+/// Finds a completion stage fault for the given I/O context, leaving it to
+/// the caller to apply it.
+#[inline]
+pub fn inject_completion_fault(
+    ctx: &InjectIoCtx,
+    status: IoCompletionStatus,
+) -> Option<InjectedFault> {
     if !injections_enabled() || !ctx.is_valid() || status != IoCompletionStatus::Success {
-        return status;
+        return None;
     }
 
-    match Injections::get().inject(FaultIoStage::Completion, ctx) {
-        Some(inj) => inj,
-        None => IoCompletionStatus::Success,
+    Injections::get().inject(FaultIoStage::Completion, ctx)
+}
+
+/// Time after which a dropped I/O of a block device without an NVMe
+/// controller is aborted: the NVMe I/O timeout, or its default if the timeout
+/// is disabled, so that the I/O is never leaked.
+fn dropped_io_timeout() -> Duration {
+    match nvme_bdev_running_config().timeout_us {
+        0 => DROPPED_IO_DEFAULT_TIMEOUT,
+        us => Duration::from_micros(us),
     }
 }
+
+/// I/O completion postponed by a delay injection.
+struct DeferredCompletion {
+    deadline: Instant,
+    complete: Box<dyn FnOnce()>,
+}
+
+thread_local! {
+    /// Completions postponed on the current core.
+    static DEFERRED: RefCell<Vec<DeferredCompletion>> = RefCell::new(Vec::new());
+    /// Poller that runs postponed completions once they are due.
+    static DEFERRED_POLLER: RefCell<Option<Poller<'static>>> = RefCell::new(None);
+}
+
+/// Postpones an I/O completion on the current core by the given time.
+fn defer_completion(delay: Duration, complete: Box<dyn FnOnce()>) {
+    DEFERRED.with(|d| {
+        d.borrow_mut().push(DeferredCompletion {
+            deadline: Instant::now() + delay,
+            complete,
+        })
+    });
+
+    DEFERRED_POLLER.with(|p| {
+        p.borrow_mut().get_or_insert_with(|| {
+            PollerBuilder::new()
+                .with_name("fault_injection_delay_poller")
+                .with_interval(Duration::from_micros(100))
+                .with_poll_fn(|_| run_deferred_completions())
+                .build()
+        });
+    });
+}
+
+/// Runs the postponed completions that are due.
+fn run_deferred_completions() -> i32 {
+    let now = Instant::now();
+
+    // Completions are run outside of the borrow, as they may issue new I/Os.
+    let due: Vec<DeferredCompletion> = DEFERRED.with(|d| {
+        let mut d = d.borrow_mut();
+        let (due, pending): (Vec<_>, Vec<_>) = d.drain(..).partition(|c| c.deadline <= now);
+        *d = pending;
+        due
+    });
+
+    let cnt = due.len();
+    due.into_iter().for_each(|c| (c.complete)());
+    cnt as i32
+}
@@ -15,12 +15,12 @@ mod injection_api;
 mod injection_state;
 
 use bdev_io_injection::add_bdev_io_injection;
-pub use fault_method::FaultMethod;
+pub use fault_method::{FaultMethod, InjectedFault};
 pub use inject_io_ctx::{InjectIoCtx, InjectIoDevice};
 pub use injection::{Injection, InjectionBuilder, InjectionBuilderError};
 pub use injection_api::{
-    add_fault_injection, inject_completion_error, inject_submission_error, list_fault_injections,
-    remove_fault_injection,
+    add_fault_injection, inject_completion, inject_completion_error, inject_completion_fault,
+    inject_submission_error, list_fault_injections, remove_fault_injection,
 };
 pub use injection_state::InjectionState;
 
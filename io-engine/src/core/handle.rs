@@ -11,8 +11,8 @@ use nix::errno::Errno;
 use spdk_rs::{
     libspdk::{
         spdk_bdev_desc, spdk_bdev_free_io, spdk_bdev_io, spdk_bdev_nvme_admin_passthru_ro,
-        spdk_bdev_read, spdk_bdev_reset, spdk_bdev_write, spdk_bdev_write_zeroes, spdk_io_channel,
-        spdk_nvme_cmd,
+        spdk_bdev_read, spdk_bdev_reset, spdk_bdev_unmap, spdk_bdev_write, spdk_bdev_write_zeroes,
+        spdk_io_channel, spdk_nvme_cmd,
     },
     nvme_admin_opc, BdevOps, DmaBuf, DmaError, IoChannelGuard, NvmeStatus,
 };
@@ -222,6 +222,37 @@ impl<T: BdevOps> BdevHandle<T> {
         }
     }
 
+    /// unmap the given byte range, with thin provisioned lvols this releases
+    /// the fully unmapped clusters back to the pool if cluster unmap is
+    /// enabled
+    pub async fn unmap_at(&self, offset: u64, len: u64) -> Result<(), CoreError> {
+        let (s, r) = oneshot::channel::<NvmeStatus>();
+        let errno = unsafe {
+            spdk_bdev_unmap(
+                self.desc.legacy_as_ptr(),
+                self.channel.legacy_as_ptr(),
+                offset,
+                len,
+                Some(Self::io_completion_cb),
+                cb_arg(s),
+            )
+        };
+
+        if errno != 0 {
+            return Err(CoreError::UnmapDispatch {
+                source: Errno::from_raw(errno.abs()),
+                offset,
+                len,
+            });
+        }
+
+        if r.await.expect("Failed awaiting unmap IO").is_success() {
+            Ok(())
+        } else {
+            Err(CoreError::UnmapFailed { offset, len })
+        }
+    }
+
     /// create a snapshot, only works for nvme bdev
     /// returns snapshot time as u64 seconds since Unix epoch
     pub async fn create_snapshot(&self, _snapshot: SnapshotParams) -> Result<u64, CoreError> {
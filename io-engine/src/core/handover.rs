@@ -0,0 +1,686 @@
+//! Live upgrade handover between two io-engine instances.
+//!
+//! The new instance is started alongside the running one, on a different
+//! reactor mask and gRPC endpoint, with `--handover-from <manifest>`. It holds
+//! back its NVMf listeners and refuses new resources until the handover
+//! completes. The running instance is then asked to hand over, through the
+//! `handover_start` json-rpc method, which:
+//! 1. refuses new resources, records the pools and the nexuses with their
+//!    healthy children within the manifest, pauses its NVMf subsystems and its
+//!    rebuilds, so that the hosts only see stalled I/O, and stops listening.
+//! 2. persists the clean shutdown of the nexuses, then shuts them down and
+//!    exports the pools, so that no blobstore is left loaded.
+//! 3. flags the manifest as ready.
+//!
+//! Once the manifest is ready, the new instance claims the handover, takes
+//! over the listener ports, imports the pools, which re-shares the replicas,
+//! and re-creates and re-publishes the nexuses with the same children, so that
+//! no rebuild is needed. The hosts, including the nexuses of other nodes,
+//! reconnect to the same address and NQN. It then flags the manifest as taken
+//! over, upon which the previous instance shuts down gracefully.
+//!
+//! Both waits are bounded: the previous instance takes its resources back if
+//! the handover is not claimed in time, and the new one starts on its own if
+//! the manifest is not ready in time. The claim is a lock file which only one
+//! of them creates.
+
+use std::{
+    fs::OpenOptions,
+    path::Path,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use futures::{Future, FutureExt};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use snafu::{ResultExt, Snafu};
+
+use crate::{
+    bdev::nexus::{
+        self, nexus_iter, nexus_lookup, nexus_lookup_mut, NexusNvmeParams, NexusNvmePreemption,
+        NvmeReservation, PersistOp,
+    },
+    core::{mayastor_env_stop, Protocol, Reactor, Reactors, Share, VerboseError},
+    jsonrpc::{jsonrpc_register, Code, RpcErrorCode},
+    pool_backend::{self, ListPoolArgs, PoolArgs, PoolBackend, PoolFactory},
+    sleep::mayastor_sleep,
+    subsys::{NvmfSubsystem, NvmfTarget, SubType},
+};
+
+/// How often the new instance polls the manifest.
+const MANIFEST_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How often the new instance retries to listen, until the ports are freed.
+const LISTEN_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+/// How many times the new instance retries to listen.
+const LISTEN_RETRIES: u32 = 50;
+/// How long the new instance waits for the manifest to be ready.
+const MANIFEST_READY_TIMEOUT: Duration = Duration::from_secs(600);
+/// How long the previous instance waits for the handover to be claimed,
+/// before resuming.
+const CLAIM_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long the previous instance waits for a claimed handover to complete,
+/// before exiting regardless, as its pools now belong to the new instance.
+const TAKE_OVER_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Handover errors.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)), context(suffix(false)))]
+#[allow(missing_docs)]
+pub enum HandoverError {
+    #[snafu(display("A handover is already in progress"))]
+    HandoverInProgress {},
+    #[snafu(display("Failed to write the handover manifest {path}: {source}"))]
+    HandoverWriteManifest {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Failed to read the handover manifest {path}: {source}"))]
+    HandoverReadManifest {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Failed to parse the handover manifest {path}: {source}"))]
+    HandoverParseManifest {
+        path: String,
+        source: serde_json::Error,
+    },
+    #[snafu(display("The handover manifest {path} was not ready in time"))]
+    HandoverTimeout { path: String },
+    #[snafu(display("The handover {path} was claimed by the other instance"))]
+    HandoverClaimed { path: String },
+    #[snafu(display("Failed to persist the shutdown of nexus {nexus}: {source}"))]
+    HandoverPersist { nexus: String, source: nexus::Error },
+    #[snafu(display("Failed to export pool {pool}: {source}"))]
+    HandoverExportPool {
+        pool: String,
+        source: pool_backend::Error,
+    },
+}
+
+impl RpcErrorCode for HandoverError {
+    fn rpc_error_code(&self) -> Code {
+        match self {
+            Self::HandoverInProgress { .. } => Code::InvalidParams,
+            _ => Code::InternalError,
+        }
+    }
+}
+
+/// A pool to import.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoverPool {
+    /// Name of the pool.
+    pub name: String,
+    /// Uuid of the pool.
+    pub uuid: String,
+    /// Disks of the pool.
+    pub disks: Vec<String>,
+    /// Backend of the pool.
+    pub backend: PoolBackend,
+}
+
+/// A nexus to re-create and re-publish.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoverNexus {
+    /// Name of the nexus.
+    pub name: String,
+    /// Uuid of the nexus.
+    pub uuid: String,
+    /// Size of the nexus in bytes.
+    pub size: u64,
+    /// Healthy children of the nexus.
+    pub children: Vec<String>,
+    /// Key of the nexus information within the persistent store.
+    pub nexus_info_key: Option<String>,
+    /// Minimum NVMe controller id.
+    pub min_cntlid: u16,
+    /// Maximum NVMe controller id.
+    pub max_cntlid: u16,
+    /// NVMe reservation key.
+    pub resv_key: u64,
+    /// NVMe preempt key.
+    pub preempt_key: Option<u64>,
+    /// NVMe reservation type.
+    pub resv_type: u8,
+    /// Whether to preempt the current reservation holder.
+    pub preempt_holder: bool,
+    /// Whether the nexus is published over NVMf.
+    pub published: bool,
+    /// Hosts allowed to connect to the nexus.
+    pub allowed_hosts: Vec<String>,
+}
+
+/// Manifest of the handover, written by the running instance.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HandoverManifest {
+    /// Pools to import.
+    pub pools: Vec<HandoverPool>,
+    /// Nexuses to re-create.
+    pub nexuses: Vec<HandoverNexus>,
+    /// The running instance has paused its I/O and stopped listening.
+    pub ready: bool,
+    /// The new instance has taken over, and the running instance may exit.
+    #[serde(default)]
+    pub taken_over: bool,
+}
+
+impl HandoverManifest {
+    /// Collect the pools and the nexuses of this instance.
+    async fn collect() -> Self {
+        let mut pools = Vec::new();
+        for factory in PoolFactory::factories() {
+            match factory.as_factory().list(&ListPoolArgs::default()).await {
+                Ok(list) => pools.extend(list.iter().map(|p| HandoverPool {
+                    name: p.name().to_string(),
+                    uuid: p.uuid(),
+                    disks: p.disks(),
+                    backend: p.pool_type(),
+                })),
+                Err(error) => error!("Failed to list the pools to hand over: {error}"),
+            }
+        }
+
+        let mut nexuses = Vec::new();
+        let names = nexus_iter().map(|n| n.name.clone()).collect::<Vec<_>>();
+        for nexus in names.iter().filter_map(|name| nexus_lookup(name)) {
+            let params = &nexus.nvme_params;
+            nexuses.push(HandoverNexus {
+                name: nexus.name.clone(),
+                uuid: nexus.uuid().to_string(),
+                size: nexus.req_size(),
+                children: nexus
+                    .children_iter()
+                    .filter(|c| c.is_healthy())
+                    .map(|c| c.uri().to_string())
+                    .collect(),
+                nexus_info_key: nexus.nexus_info_key().await,
+                min_cntlid: params.min_cntlid,
+                max_cntlid: params.max_cntlid,
+                resv_key: params.resv_key,
+                preempt_key: params.preempt_key.map(|k| k.get()),
+                resv_type: params.resv_type as u8,
+                preempt_holder: matches!(params.preempt_policy, NexusNvmePreemption::Holder),
+                published: nexus.shared() == Some(Protocol::Nvmf),
+                allowed_hosts: nexus.allowed_hosts(),
+            });
+        }
+
+        Self {
+            pools,
+            nexuses,
+            ready: false,
+            taken_over: false,
+        }
+    }
+
+    /// Write the manifest to the given path, atomically.
+    fn write(&self, path: &str) -> Result<(), HandoverError> {
+        let tmp = format!("{path}.tmp");
+        let json = serde_json::to_vec_pretty(self).expect("manifest is serializable");
+        std::fs::write(&tmp, json)
+            .and_then(|_| std::fs::rename(&tmp, path))
+            .context(HandoverWriteManifest { path })
+    }
+
+    /// Read the manifest from the given path.
+    fn read(path: &str) -> Result<Self, HandoverError> {
+        let json = std::fs::read(path).context(HandoverReadManifest { path })?;
+        serde_json::from_slice(&json).context(HandoverParseManifest { path })
+    }
+
+    /// Path to the lock file which claims the handover of the manifest at
+    /// the given path.
+    fn claim_path(path: &str) -> String {
+        format!("{path}.claim")
+    }
+
+    /// Claim the handover of the manifest at the given path, either to take
+    /// over or to abort it. Only the first claim succeeds.
+    fn claim(path: &str) -> Result<(), HandoverError> {
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(Self::claim_path(path))
+        {
+            Ok(_) => Ok(()),
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+                Err(HandoverError::HandoverClaimed {
+                    path: path.to_string(),
+                })
+            }
+            Err(source) => Err(HandoverError::HandoverWriteManifest {
+                path: Self::claim_path(path),
+                source,
+            }),
+        }
+    }
+}
+
+/// Role of this instance within a handover.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandoverRole {
+    /// No handover.
+    #[default]
+    None,
+    /// Handing over to a new instance.
+    Outgoing,
+    /// Taking over from a running instance.
+    Incoming,
+}
+
+/// Progress of a handover.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HandoverStatus {
+    /// Role of this instance.
+    pub role: HandoverRole,
+    /// Path to the manifest.
+    pub manifest: Option<String>,
+    /// Whether the handover has completed, for the incoming instance.
+    pub completed: bool,
+    /// Number of pools handed over.
+    pub pools: u32,
+    /// Number of nexuses handed over.
+    pub nexuses: u32,
+    /// Errors which didn't stop the handover.
+    pub errors: Vec<String>,
+}
+
+static HANDOVER: Lazy<Mutex<HandoverStatus>> = Lazy::new(Default::default);
+
+/// Whether a handover is in progress, in which case no new resources may be
+/// created.
+static HANDING_OVER: AtomicBool = AtomicBool::new(false);
+
+/// Check if a handover is in progress.
+pub fn is_handing_over() -> bool {
+    HANDING_OVER.load(Ordering::SeqCst)
+}
+
+/// Get the progress of the handover.
+pub fn handover_status() -> HandoverStatus {
+    HANDOVER.lock().clone()
+}
+
+/// Record an error which doesn't stop the handover.
+fn handover_error(error: String) {
+    error!("{error}");
+    HANDOVER.lock().errors.push(error);
+}
+
+/// Subsystems and rebuilds paused by the running instance, to resume if the
+/// handover is aborted.
+#[derive(Default)]
+struct Paused {
+    /// NQNs of the paused subsystems.
+    subsystems: Vec<String>,
+    /// Nexuses with the destination of their paused rebuilds.
+    rebuilds: Vec<(String, String)>,
+}
+
+/// Hand over to a new instance, which takes over from the manifest at the
+/// given path. This instance stops once the new one has taken over.
+pub async fn start_handover(path: String) -> Result<HandoverManifest, HandoverError> {
+    if HANDING_OVER.swap(true, Ordering::SeqCst) {
+        return Err(HandoverError::HandoverInProgress {});
+    }
+
+    info!(path, "Handing over to a new io-engine instance...");
+    let mut manifest = HandoverManifest::collect().await;
+    // A claim left by a previous handover.
+    std::fs::remove_file(HandoverManifest::claim_path(&path)).ok();
+
+    // Stall the I/O of the hosts until the new instance takes over.
+    let mut paused = Paused::default();
+    for subsystem in NvmfSubsystem::first().into_iter().flatten() {
+        if subsystem.subtype() != SubType::Nvme {
+            continue;
+        }
+        match subsystem.pause().await {
+            Ok(_) => paused.subsystems.push(subsystem.get_nqn()),
+            Err(error) => handover_error(format!(
+                "Failed to pause subsystem {}: {error}",
+                subsystem.get_nqn()
+            )),
+        }
+    }
+    // The rebuilt children are not handed over, but their replicas are, and
+    // must not be written to any longer.
+    let names = nexus_iter().map(|n| n.name.clone()).collect::<Vec<_>>();
+    for nexus in names.iter().filter_map(|name| nexus_lookup(name)) {
+        let rebuilding = nexus
+            .children_iter()
+            .filter(|c| c.is_rebuilding())
+            .map(|c| c.uri().to_string())
+            .collect::<Vec<_>>();
+        for uri in rebuilding {
+            match nexus.pause_rebuild(&uri).await {
+                Ok(_) => paused.rebuilds.push((nexus.name.clone(), uri)),
+                Err(error) => handover_error(format!(
+                    "Failed to pause the rebuild of {uri}: {}",
+                    error.verbose()
+                )),
+            }
+        }
+    }
+    NvmfTarget::stop_listen();
+
+    // The new instance re-creates the nexuses with the same children, which
+    // it may only trust if their shutdown is persisted.
+    for nexus in names.iter().filter_map(|name| nexus_lookup(name)) {
+        if let Err(source) = nexus.persist(PersistOp::Shutdown).await {
+            resume(paused).await;
+            return Err(HandoverError::HandoverPersist {
+                nexus: nexus.name.clone(),
+                source,
+            });
+        }
+    }
+
+    // Nothing of this instance may write to the pools once the new one has
+    // imported them.
+    nexus::shutdown_nexuses().await;
+    crate::rebuild::shutdown_snapshot_rebuilds().await;
+    crate::rebuild::shutdown_replica_imports().await;
+    if let Err(error) = export_pools().await {
+        restore(manifest).await;
+        return Err(error);
+    }
+
+    manifest.ready = true;
+    if let Err(error) = manifest.write(&path) {
+        restore(manifest).await;
+        return Err(error);
+    }
+    {
+        let mut status = HANDOVER.lock();
+        status.role = HandoverRole::Outgoing;
+        status.manifest = Some(path.clone());
+        status.pools = manifest.pools.len() as u32;
+        status.nexuses = manifest.nexuses.len() as u32;
+    }
+
+    Reactors::master().send_future(wait_taken_over(path, manifest.clone()));
+    Ok(manifest)
+}
+
+/// Export all the pools of this instance.
+async fn export_pools() -> Result<(), HandoverError> {
+    for factory in PoolFactory::factories() {
+        let pools = match factory.as_factory().list(&ListPoolArgs::default()).await {
+            Ok(pools) => pools,
+            Err(error) => {
+                handover_error(format!("Failed to list the pools to export: {error}"));
+                continue;
+            }
+        };
+        for pool in pools {
+            let name = pool.name().to_string();
+            pool.export()
+                .await
+                .context(HandoverExportPool { pool: name })?;
+        }
+    }
+    Ok(())
+}
+
+/// Wait for the new instance to take over from the manifest at the given
+/// path, and stop. Take the resources back if the handover isn't claimed in
+/// time.
+async fn wait_taken_over(path: String, manifest: HandoverManifest) {
+    let started = Instant::now();
+    let mut claimed = false;
+    loop {
+        mayastor_sleep(MANIFEST_POLL_INTERVAL).await.ok();
+
+        match HandoverManifest::read(&path) {
+            Ok(manifest) if manifest.taken_over => break,
+            Ok(_) => {}
+            Err(error) => warn!("{error}"),
+        }
+
+        if !claimed && started.elapsed() > CLAIM_TIMEOUT {
+            match HandoverManifest::claim(&path) {
+                Ok(_) => {
+                    handover_error(format!(
+                        "The handover {path} was not claimed in time, taking the resources back"
+                    ));
+                    std::fs::remove_file(&path).ok();
+                    restore(manifest).await;
+                    return;
+                }
+                Err(_) => claimed = true,
+            }
+        }
+        if started.elapsed() > CLAIM_TIMEOUT + TAKE_OVER_TIMEOUT {
+            handover_error(format!("The handover {path} did not complete in time"));
+            break;
+        }
+    }
+
+    std::fs::remove_file(&path).ok();
+    std::fs::remove_file(HandoverManifest::claim_path(&path)).ok();
+    info!(path, "Taken over by the new io-engine instance, stopping");
+    // The nexuses are persisted and the pools exported already, so the
+    // shutdown has nothing left to flush.
+    mayastor_env_stop(0);
+}
+
+/// Resume the I/O of a handover aborted before the nexuses were shut down.
+async fn resume(paused: Paused) {
+    if let Err(error) = NvmfTarget::listen_deferred() {
+        handover_error(format!("Failed to listen again: {error}"));
+    }
+    for subsystem in NvmfSubsystem::first().into_iter().flatten() {
+        if !paused.subsystems.contains(&subsystem.get_nqn()) {
+            continue;
+        }
+        if let Err(error) = subsystem.resume().await {
+            handover_error(format!(
+                "Failed to resume subsystem {}: {error}",
+                subsystem.get_nqn()
+            ));
+        }
+    }
+    for (name, uri) in paused.rebuilds {
+        if let Some(nexus) = nexus_lookup(&name) {
+            nexus.resume_rebuild(&uri).await.ok();
+        }
+    }
+    HANDOVER.lock().role = HandoverRole::None;
+    HANDING_OVER.store(false, Ordering::SeqCst);
+    info!("Handover aborted");
+}
+
+/// Take the resources of the manifest back, as the new instance would, for a
+/// handover aborted after the nexuses were shut down.
+async fn restore(manifest: HandoverManifest) {
+    take_over_resources(manifest).await;
+    HANDOVER.lock().role = HandoverRole::None;
+    HANDING_OVER.store(false, Ordering::SeqCst);
+    info!("Handover aborted, resources taken back");
+}
+
+/// Take over from the running instance, once its manifest is ready, or start
+/// on its own if it isn't ready in time.
+/// Runs on the tokio runtime, see `--handover-from`.
+pub async fn take_over(path: String) {
+    HANDING_OVER.store(true, Ordering::SeqCst);
+    *HANDOVER.lock() = HandoverStatus {
+        role: HandoverRole::Incoming,
+        manifest: Some(path.clone()),
+        ..Default::default()
+    };
+
+    info!(path, "Waiting for the handover manifest...");
+    match wait_manifest_ready(&path).await {
+        Ok(manifest) => {
+            info!(
+                pools = manifest.pools.len(),
+                nexuses = manifest.nexuses.len(),
+                "Taking over from the previous io-engine instance..."
+            );
+            match Reactor::spawn_at_primary(take_over_resources(manifest)) {
+                Ok(done) => {
+                    done.await.ok();
+                }
+                Err(error) => handover_error(format!("Failed to take over: {error}")),
+            }
+
+            // Let the previous instance exit.
+            let result = HandoverManifest::read(&path).and_then(|mut manifest| {
+                manifest.taken_over = true;
+                manifest.write(&path)
+            });
+            if let Err(error) = result {
+                handover_error(format!("Failed to complete the handover: {error}"));
+            }
+            HANDOVER.lock().completed = true;
+            info!("Handover completed");
+        }
+        Err(error) => {
+            handover_error(format!("Not taking over: {error}"));
+            match Reactor::spawn_at_primary(async {
+                if let Err(error) = NvmfTarget::listen_deferred() {
+                    handover_error(format!("Failed to listen: {error}"));
+                }
+            }) {
+                Ok(done) => {
+                    done.await.ok();
+                }
+                Err(error) => handover_error(format!("Failed to listen: {error}")),
+            }
+        }
+    }
+    HANDING_OVER.store(false, Ordering::SeqCst);
+}
+
+/// Wait for the manifest at the given path to be ready, and claim it.
+async fn wait_manifest_ready(path: &str) -> Result<HandoverManifest, HandoverError> {
+    let started = Instant::now();
+    loop {
+        if Path::new(path).exists() {
+            match HandoverManifest::read(path) {
+                Ok(manifest) if manifest.ready => {
+                    HandoverManifest::claim(path)?;
+                    return Ok(manifest);
+                }
+                Ok(_) => {}
+                Err(error) => warn!("{error}"),
+            }
+        }
+        if started.elapsed() > MANIFEST_READY_TIMEOUT {
+            return Err(HandoverError::HandoverTimeout {
+                path: path.to_string(),
+            });
+        }
+        tokio::time::sleep(MANIFEST_POLL_INTERVAL).await;
+    }
+}
+
+/// Listen, import the pools and re-create the nexuses of the manifest.
+async fn take_over_resources(manifest: HandoverManifest) {
+    // The ports are freed once the previous instance stops listening.
+    let mut retries = 0;
+    while let Err(error) = NvmfTarget::listen_deferred() {
+        retries += 1;
+        if retries >= LISTEN_RETRIES {
+            handover_error(format!("Failed to listen: {error}"));
+            break;
+        }
+        mayastor_sleep(LISTEN_RETRY_INTERVAL).await.ok();
+    }
+
+    for pool in manifest.pools {
+        let args = PoolArgs {
+            name: pool.name.clone(),
+            disks: pool.disks,
+            uuid: Some(pool.uuid),
+            cluster_size: None,
+            md_args: None,
+            backend: pool.backend,
+        };
+        match PoolFactory::new(pool.backend)
+            .as_factory()
+            .import(args)
+            .await
+        {
+            Ok(_) => HANDOVER.lock().pools += 1,
+            Err(error) => handover_error(format!("Failed to import pool {}: {error}", pool.name)),
+        }
+    }
+
+    for spec in manifest.nexuses {
+        match recreate_nexus(&spec).await {
+            Ok(_) => HANDOVER.lock().nexuses += 1,
+            Err(error) => handover_error(format!(
+                "Failed to re-create nexus {}: {}",
+                spec.name,
+                error.verbose()
+            )),
+        }
+    }
+}
+
+/// Re-create and re-publish a nexus.
+async fn recreate_nexus(spec: &HandoverNexus) -> Result<(), nexus::Error> {
+    let params = NexusNvmeParams {
+        min_cntlid: spec.min_cntlid,
+        max_cntlid: spec.max_cntlid,
+        resv_key: spec.resv_key,
+        preempt_key: spec.preempt_key.and_then(std::num::NonZeroU64::new),
+        resv_type: NvmeReservation::try_from(spec.resv_type)?,
+        preempt_policy: if spec.preempt_holder {
+            NexusNvmePreemption::Holder
+        } else {
+            NexusNvmePreemption::ArgKey
+        },
+    };
+    nexus::nexus_create_v2(
+        &spec.name,
+        spec.size,
+        &spec.uuid,
+        params,
+        &spec.children,
+        spec.nexus_info_key.clone(),
+    )
+    .await?;
+
+    if spec.published {
+        if let Some(nexus) = nexus_lookup_mut(&spec.name) {
+            nexus
+                .share_ext(Protocol::Nvmf, None, spec.allowed_hosts.clone())
+                .await?;
+        }
+    }
+    info!(nexus = spec.name, "Nexus taken over");
+    Ok(())
+}
+
+/// Arguments of the `handover_start` json-rpc method.
+#[derive(Debug, Deserialize)]
+struct HandoverArgs {
+    /// Path to write the manifest to.
+    manifest: String,
+}
+
+type HandoverFuture<R> = Pin<Box<dyn Future<Output = Result<R, HandoverError>>>>;
+
+/// Register the handover json-rpc methods.
+pub(crate) fn register_jsonrpc_methods() {
+    jsonrpc_register(
+        "handover_start",
+        |args: HandoverArgs| -> HandoverFuture<HandoverManifest> {
+            async move { start_handover(args.manifest).await }.boxed_local()
+        },
+    );
+
+    jsonrpc_register(
+        "handover_status",
+        |_args: serde_json::Value| -> HandoverFuture<HandoverStatus> {
+            async move { Ok(handover_status()) }.boxed_local()
+        },
+    );
+}
@@ -6,6 +6,7 @@ use std::{
 
 use futures::lock::{Mutex, MutexGuard};
 use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
 
 /// Common IO engine resource subsystems.
 pub struct ProtectedSubsystems;
@@ -85,6 +86,32 @@ impl ResourceSubsystem {
     }
 }
 
+/// Snapshot of the state of a lock, as reported by diagnostics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockState {
+    /// Subsystem the lock belongs to, `None` for the global lock.
+    pub subsystem: Option<String>,
+    /// Index of the resource lock, `None` for the subsystem lock.
+    pub resource: Option<usize>,
+    /// Whether the lock is currently held.
+    pub held: bool,
+    /// Number of times the lock has been acquired, unknown while it is held.
+    pub acquires: Option<usize>,
+}
+
+impl LockState {
+    /// Probe the lock without waiting for it.
+    fn probe(lock: &Mutex<LockStats>, subsystem: Option<&str>, resource: Option<usize>) -> Self {
+        let acquires = lock.try_lock().map(|stats| stats.num_acquires);
+        Self {
+            subsystem: subsystem.map(ToOwned::to_owned),
+            resource,
+            held: acquires.is_none(),
+            acquires,
+        }
+    }
+}
+
 /// Structure that holds per-lock statistics.
 #[derive(Debug, Default)]
 struct LockStats {
@@ -191,6 +218,28 @@ impl ResourceLockManager {
     pub fn get_instance() -> &'static ResourceLockManager {
         LOCK_MANAGER.get().expect("Lock Manager is not initialized")
     }
+
+    /// Get the state of the global and subsystem locks, and of the resource
+    /// locks which are currently held. Never waits for a lock, so it can be
+    /// used while a reactor is frozen.
+    pub fn lock_states() -> Vec<LockState> {
+        let Some(mgr) = LOCK_MANAGER.get() else {
+            return Vec::new();
+        };
+
+        let mut states = vec![LockState::probe(&mgr.mgr_lock, None, None)];
+        for s in &mgr.subsystems {
+            states.push(LockState::probe(&s.subsystem_lock, Some(&s.id), None));
+            states.extend(
+                s.object_locks
+                    .iter()
+                    .enumerate()
+                    .map(|(i, l)| LockState::probe(l, Some(&s.id), Some(i)))
+                    .filter(|l| l.held),
+            );
+        }
+        states
+    }
 }
 
 impl ResourceLockGuard<'_> {}
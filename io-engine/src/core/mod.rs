@@ -24,7 +24,9 @@ pub use env::{mayastor_env_stop, MayastorCliArgs, MayastorEnvironment, GLOBAL_RC
 pub use handle::{BdevHandle, UntypedBdevHandle};
 pub use io_device::IoDevice;
 pub use logical_volume::LogicalVolume;
-pub use reactor::{reactor_monitor_loop, Reactor, ReactorState, Reactors, REACTOR_LIST};
+pub use reactor::{
+    frozen_reactors, reactor_monitor_loop, Reactor, ReactorState, Reactors, REACTOR_LIST,
+};
 
 pub use lock::{
     ProtectedSubsystems, ResourceLockGuard, ResourceLockManager, ResourceLockManagerConfig,
@@ -39,8 +41,8 @@ pub use thread::Mthread;
 
 use crate::subsys::NvmfError;
 pub use snapshot::{
-    CloneParams, CloneXattrs, ISnapshotDescriptor, LvolSnapshotOps, SnapshotDescriptor,
-    SnapshotParams, SnapshotXattrs,
+    CloneParams, CloneXattrs, ISnapshotDescriptor, LvolSnapshotOps, ReplicaXattrs,
+    SnapshotDescriptor, SnapshotParams, SnapshotXattrs,
 };
 
 use spdk_rs::libspdk::SPDK_NVME_SC_CAPACITY_EXCEEDED;
@@ -51,9 +53,11 @@ mod descriptor;
 mod device_events;
 mod device_monitor;
 pub mod diagnostics;
+pub mod drain;
 mod env;
 pub mod fault_injection;
 mod handle;
+pub mod handover;
 mod io_device;
 pub mod io_driver;
 pub mod lock;
@@ -215,6 +219,11 @@ pub enum CoreError {
         offset: u64,
         len: u64,
     },
+    #[snafu(display("Unmap failed at offset {} length {}", offset, len))]
+    UnmapFailed {
+        offset: u64,
+        len: u64,
+    },
     #[snafu(display("NVMe Admin command {:x}h failed: {}", opcode, source))]
     NvmeAdminFailed {
         source: Errno,
@@ -304,6 +313,7 @@ impl ToErrno for CoreError {
             | Self::ReadingUnallocatedBlock { .. }
             | Self::ResetFailed { .. }
             | Self::WriteZeroesFailed { .. }
+            | Self::UnmapFailed { .. }
             | Self::NvmeIoPassthruFailed { .. }
             | Self::ShareNvmf { .. }
             | Self::UnshareNvmf { .. } => Errno::EIO,
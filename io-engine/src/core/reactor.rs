@@ -520,6 +520,11 @@ impl Reactor {
         self.add_incoming();
     }
 
+    /// Returns the SPDK threads polled by this reactor.
+    pub(crate) fn threads(&self) -> Vec<spdk_rs::Thread> {
+        self.threads.borrow().iter().copied().collect()
+    }
+
     fn add_incoming(&self) {
         while let Some(i) = self.incoming.pop() {
             self.threads.borrow_mut().push_back(i);
@@ -679,6 +684,17 @@ impl Future for &'static Reactor {
 /// Heartbeat timeout (in seconds) to classify a reactor as frozen.
 const REACTOR_HEARTBEAT_TIMEOUT: u64 = 3;
 
+/// Number of frozen reactors, as classified by the reactor health monitor.
+static FROZEN_REACTORS: OnceCell<std::sync::atomic::AtomicUsize> = OnceCell::new();
+
+/// Get the number of frozen reactors, if the reactor health monitor is
+/// running.
+pub fn frozen_reactors() -> Option<usize> {
+    FROZEN_REACTORS
+        .get()
+        .map(|frozen| frozen.load(std::sync::atomic::Ordering::Relaxed))
+}
+
 /// Monitor health for all reactors: all available reactors are constantly
 /// monitored for liveness.
 pub async fn reactor_monitor_loop(freeze_timeout: Option<u64>) {
@@ -700,6 +716,7 @@ pub async fn reactor_monitor_loop(freeze_timeout: Option<u64>) {
     static REACTOR_TICKS: OnceCell<Vec<AtomicU64>> = OnceCell::new();
 
     info!(num_cores, timeout, "Starting reactor health monitor loop");
+    let frozen_reactors = FROZEN_REACTORS.get_or_init(Default::default);
 
     // Intialize shared counters for heartbeat futures sent to reactors.
     let heartbeat_ticks = REACTOR_TICKS.get_or_init(|| {
@@ -752,6 +769,7 @@ pub async fn reactor_monitor_loop(freeze_timeout: Option<u64>) {
                 if tick - r.reactor_tick.load(Ordering::Relaxed) == 0 {
                     info!(core = r.core, "Reactor is healthy again");
                     r.frozen = false;
+                    frozen_reactors.fetch_sub(1, Ordering::Relaxed);
                     r.reactor.event(EventAction::ReactorUnfreeze).generate();
                 }
             } else {
@@ -759,6 +777,7 @@ pub async fn reactor_monitor_loop(freeze_timeout: Option<u64>) {
                 // assume it is frozen.
                 if tick - r.reactor_tick.load(Ordering::Relaxed) >= timeout {
                     r.frozen = true;
+                    frozen_reactors.fetch_add(1, Ordering::Relaxed);
                     r.reactor.event(EventAction::ReactorFreeze).generate();
                     crate::core::diagnostics::diagnose_reactor(r.reactor);
                 }
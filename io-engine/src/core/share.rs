@@ -12,6 +12,10 @@ pub enum Protocol {
     Off,
     /// shared as NVMe-oF TCP
     Nvmf,
+    /// exposed locally as a Linux ublk device (nexus only)
+    Ublk,
+    /// exposed locally as a vhost-user-blk socket (nexus only)
+    Vhost,
 }
 
 impl TryFrom<i32> for Protocol {
@@ -22,6 +26,10 @@ impl TryFrom<i32> for Protocol {
             0 => Ok(Self::Off),
             1 => Ok(Self::Nvmf),
             // 2 was for iSCSI
+            // ublk and vhost have no ShareProtocol value in the API yet, so
+            // they take the next free ones
+            3 => Ok(Self::Ublk),
+            4 => Ok(Self::Vhost),
             // the gRPC code does not validate enums so we have
             // to do it here
             _ => Err(LvsError::ReplicaShareProtocol { value }),
@@ -29,11 +37,24 @@ impl TryFrom<i32> for Protocol {
     }
 }
 
+impl From<Protocol> for i32 {
+    fn from(protocol: Protocol) -> Self {
+        match protocol {
+            Protocol::Off => 0,
+            Protocol::Nvmf => 1,
+            Protocol::Ublk => 3,
+            Protocol::Vhost => 4,
+        }
+    }
+}
+
 impl Display for Protocol {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let p = match self {
             Self::Off => "Not shared",
             Self::Nvmf => "NVMe-oF TCP",
+            Self::Ublk => "ublk",
+            Self::Vhost => "vhost-user-blk",
         };
         write!(f, "{p}")
     }
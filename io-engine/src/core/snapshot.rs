@@ -296,6 +296,25 @@ impl CloneXattrs {
     }
 }
 
+/// Replica attributes used to store its snapshot lifecycle configuration.
+#[derive(Debug, EnumCountMacro, EnumIter)]
+pub enum ReplicaXattrs {
+    /// The snapshot schedule and retention policy, serialized as json.
+    SnapshotPolicy,
+    /// The snapshot policy of the nexus the replica belongs to, serialized
+    /// as json.
+    NexusSnapshotPolicy,
+}
+
+impl ReplicaXattrs {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::SnapshotPolicy => "io-engine.snapshot_policy",
+            Self::NexusSnapshotPolicy => "io-engine.nexus_snapshot_policy",
+        }
+    }
+}
+
 pub use crate::lvs::LvolSnapshotOps;
 use crate::replica_backend::SnapshotOps;
 
@@ -0,0 +1,275 @@
+//! In-memory audit log of the mutating gRPC calls.
+//!
+//! Every mutating call which goes through the gRPC serializers is recorded in
+//! a bounded ring buffer, with the identity of its caller, its arguments, its
+//! result, how long it took and the resources it touched. The records are
+//! also logged under the `audit` target, and can be queried through the
+//! `audit_log` json-rpc method.
+//!
+//! Secrets, such as the nexus encryption key or the reservation keys, are
+//! redacted from the recorded arguments.
+
+use std::{collections::VecDeque, future::Future, pin::Pin, time::Instant};
+
+use chrono::Utc;
+use futures::FutureExt;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use regex::Regex;
+use snafu::Snafu;
+use tonic::Status;
+
+use super::GrpcClientContext;
+use crate::jsonrpc::{jsonrpc_register, Code, RpcErrorCode};
+
+/// Maximum number of audit records which are kept, the oldest ones are
+/// evicted first.
+const MAX_RECORDS: usize = 1024;
+
+/// Prefixes of the read-only gRPC methods, which are not audited.
+const READ_ONLY_PREFIXES: [&str; 4] = ["list_", "get_", "stat_", "find_"];
+
+/// Arguments whose values are redacted from the audit records, i.e. any
+/// argument whose name contains one of the secret words, such as `key`,
+/// `resv_key` or `preempt_key`.
+static SECRET_ARGS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"\b(\w*(?:key|secret|password|token|psk|dhchap)\w*): (Some\((?:"(?:[^"\\]|\\.)*"|[^)]*)\)|"(?:[^"\\]|\\.)*"|[^,})\]\s]+)"#,
+    )
+    .unwrap()
+});
+
+/// UUIDs of the resources touched by a call.
+static UUID: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}")
+        .unwrap()
+});
+
+/// The audit records, from the oldest to the newest.
+static RECORDS: Lazy<Mutex<AuditLog>> = Lazy::new(|| {
+    Mutex::new(AuditLog {
+        next_id: 1,
+        records: VecDeque::with_capacity(MAX_RECORDS),
+    })
+});
+
+/// Audit log errors.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)), context(suffix(false)))]
+#[allow(missing_docs)]
+pub enum AuditError {
+    #[snafu(display("Invalid audit filter: {reason}"))]
+    InvalidFilter { reason: String },
+}
+
+impl RpcErrorCode for AuditError {
+    fn rpc_error_code(&self) -> Code {
+        match self {
+            Self::InvalidFilter { .. } => Code::InvalidParams,
+        }
+    }
+}
+
+/// A recorded gRPC call.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditRecord {
+    /// Sequence number of the record.
+    pub id: u64,
+    /// When the call was received, in RFC 3339 format.
+    pub timestamp: String,
+    /// gRPC method of the call.
+    pub method: String,
+    /// Identity of the caller.
+    pub caller: String,
+    /// Client supplied operation id.
+    pub operation_id: Option<String>,
+    /// Arguments of the call, with the secrets redacted.
+    pub args: String,
+    /// Whether the call succeeded.
+    pub success: bool,
+    /// Result of the call, i.e. its gRPC status code and message.
+    pub result: String,
+    /// How long the call took, in milliseconds.
+    pub duration_ms: u64,
+    /// UUIDs of the resources touched by the call.
+    pub resources: Vec<String>,
+}
+
+/// Bounded log of the audit records.
+struct AuditLog {
+    next_id: u64,
+    records: VecDeque<AuditRecord>,
+}
+
+impl AuditLog {
+    fn push(&mut self, mut record: AuditRecord) {
+        record.id = self.next_id;
+        self.next_id += 1;
+        if self.records.len() == MAX_RECORDS {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+}
+
+/// An audited call, recorded once it completes, or when it's dropped before
+/// completing, e.g. because the gRPC call was cancelled.
+pub(crate) struct AuditEntry {
+    record: Option<AuditRecord>,
+    started: Instant,
+}
+
+impl AuditEntry {
+    /// Start auditing the call of the given context. Read-only calls are not
+    /// recorded.
+    pub(crate) fn start(ctx: &GrpcClientContext) -> Self {
+        let read_only = READ_ONLY_PREFIXES.iter().any(|p| ctx.id.starts_with(p));
+        let record = (!read_only).then(|| {
+            let args = redact(&ctx.args);
+            let mut resources = UUID
+                .find_iter(&args)
+                .map(|m| m.as_str().to_lowercase())
+                .collect::<Vec<_>>();
+            resources.sort();
+            resources.dedup();
+            AuditRecord {
+                id: 0,
+                timestamp: Utc::now().to_rfc3339(),
+                method: ctx.id.clone(),
+                caller: match &ctx.identity {
+                    Some(identity) => identity.to_string(),
+                    None => "unauthenticated client".to_string(),
+                },
+                operation_id: ctx.operation_id.clone(),
+                args,
+                success: false,
+                result: String::new(),
+                duration_ms: 0,
+                resources,
+            }
+        });
+
+        Self {
+            record,
+            started: Instant::now(),
+        }
+    }
+
+    /// Run the call and record its result.
+    pub(crate) async fn record<T, F>(mut self, f: F) -> Result<T, Status>
+    where
+        F: Future<Output = Result<T, Status>>,
+    {
+        let r = f.await;
+        self.finish(match &r {
+            Ok(_) => Ok(()),
+            Err(status) => Err(format!("{:?}: {}", status.code(), status.message())),
+        });
+        r
+    }
+
+    fn finish(&mut self, r: Result<(), String>) {
+        let Some(mut record) = self.record.take() else {
+            return;
+        };
+
+        record.duration_ms = self.started.elapsed().as_millis() as u64;
+        match r {
+            Ok(()) => {
+                record.success = true;
+                record.result = "Ok".to_string();
+            }
+            Err(result) => record.result = result,
+        }
+
+        info!(
+            target: "audit",
+            "{} by {}: {} in {}ms, args: {}",
+            record.method, record.caller, record.result, record.duration_ms, record.args
+        );
+        RECORDS.lock().push(record);
+    }
+}
+
+impl Drop for AuditEntry {
+    fn drop(&mut self) {
+        // The call was dropped before it completed, e.g. its gRPC future was
+        // cancelled while waiting for a lock.
+        self.finish(Err(format!("{:?}: call dropped", tonic::Code::Cancelled)));
+    }
+}
+
+/// Redact the values of the secret arguments.
+pub fn redact(args: &str) -> String {
+    SECRET_ARGS.replace_all(args, "$1: <redacted>").into_owned()
+}
+
+/// Filter of the audit records.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct AuditFilter {
+    /// Only the records of the last given seconds.
+    pub since_secs: Option<u64>,
+    /// Only the records of the given gRPC method.
+    pub method: Option<String>,
+    /// Only the records which touched the resource with the given UUID.
+    pub resource: Option<String>,
+    /// Only the records of the given caller.
+    pub caller: Option<String>,
+    /// Only the failed calls.
+    pub failed: bool,
+    /// Maximum number of records, the newest ones are returned.
+    pub limit: Option<usize>,
+}
+
+/// Get the audit records matching the filter, from the oldest to the newest.
+pub fn audit_log(filter: &AuditFilter) -> Result<Vec<AuditRecord>, AuditError> {
+    let since = match filter.since_secs {
+        Some(secs) => {
+            let secs = i64::try_from(secs).map_err(|_| AuditError::InvalidFilter {
+                reason: format!("since_secs {secs} is out of range"),
+            })?;
+            Some(Utc::now() - chrono::Duration::seconds(secs))
+        }
+        None => None,
+    };
+    let resource = filter.resource.as_ref().map(|r| r.to_lowercase());
+
+    let records = RECORDS
+        .lock()
+        .records
+        .iter()
+        .filter(|r| {
+            since.map_or(true, |since| {
+                chrono::DateTime::parse_from_rfc3339(&r.timestamp).map_or(true, |t| t >= since)
+            })
+        })
+        .filter(|r| filter.method.as_ref().map_or(true, |m| &r.method == m))
+        .filter(|r| resource.as_ref().map_or(true, |u| r.resources.contains(u)))
+        .filter(|r| {
+            filter
+                .caller
+                .as_ref()
+                .map_or(true, |c| r.caller.contains(c))
+        })
+        .filter(|r| !filter.failed || !r.success)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let skip = filter
+        .limit
+        .map_or(0, |limit| records.len().saturating_sub(limit));
+    Ok(records.into_iter().skip(skip).collect())
+}
+
+type AuditFuture<R> = Pin<Box<dyn Future<Output = Result<R, AuditError>>>>;
+
+/// Register the audit json-rpc methods.
+pub(crate) fn register_jsonrpc_methods() {
+    jsonrpc_register(
+        "audit_log",
+        |args: AuditFilter| -> AuditFuture<Vec<AuditRecord>> {
+            async move { audit_log(&args) }.boxed_local()
+        },
+    );
+}
@@ -0,0 +1,445 @@
+//! Transport security and per-client authorisation of the gRPC server.
+//!
+//! Given a certificate and a key, the gRPC server only accepts TLS
+//! connections, and given a CA it also requires the clients to present a
+//! certificate signed by it (mutual TLS). Clients are identified by the
+//! SHA-256 fingerprint of their certificate, which the roles file maps to one
+//! of the roles below. Each RPC requires a minimum role:
+//! - read-only: `List*`, `Get*`, `Stat*` and `Find*` methods.
+//! - operator: every other method, except for the test service.
+//! - test: the test service, which can wipe replicas, inject faults, etc.
+//!
+//! The JSON-RPC passthrough is authorised per JSON-RPC method instead: the
+//! io-engine methods which only query its state, and the SPDK `*_get_*`
+//! methods, require the read-only role, the other io-engine methods the
+//! operator role, and any other SPDK method the test role.
+//!
+//! With TLS enabled but without a roles file every client is granted the
+//! read-only role. Without TLS the clients can't be identified, and no
+//! authorisation takes place.
+
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    fs,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures::future::{ready, Either, Ready};
+use sha2::{Digest, Sha256};
+use snafu::{ResultExt, Snafu};
+use tonic::{
+    body::BoxBody,
+    codegen::empty_body,
+    transport::{
+        server::{TcpConnectInfo, TlsConnectInfo},
+        Certificate, ClientTlsConfig, Identity, ServerTlsConfig,
+    },
+    Code,
+};
+use tower::{Layer, Service};
+
+/// gRPC TLS and authorisation errors.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)), context(suffix(false)))]
+#[allow(missing_docs)]
+pub enum GrpcAuthError {
+    #[snafu(display("Failed to read {path}: {source}"))]
+    ReadFile {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Failed to parse the gRPC roles file {path}: {source}"))]
+    ParseRoles {
+        path: String,
+        source: serde_yaml::Error,
+    },
+    #[snafu(display("Both a gRPC TLS certificate and a key are required"))]
+    IncompleteIdentity {},
+}
+
+/// TLS and authorisation options of the gRPC server.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct GrpcTlsArgs {
+    /// Path to the PEM certificate of the gRPC server, which enables TLS.
+    /// It's also presented as the client certificate to the registration
+    /// server.
+    #[clap(long, env = "GRPC_TLS_CERT")]
+    pub grpc_tls_cert: Option<String>,
+    /// Path to the PEM private key of the gRPC server certificate.
+    #[clap(long, env = "GRPC_TLS_KEY")]
+    pub grpc_tls_key: Option<String>,
+    /// Path to the PEM CA certificate which the gRPC client certificates must
+    /// be signed by, which enables mutual TLS. It's also used to verify the
+    /// certificate of the registration server.
+    #[clap(long, env = "GRPC_TLS_CA")]
+    pub grpc_tls_ca: Option<String>,
+    /// Path to the YAML file mapping the gRPC client certificate fingerprints
+    /// to their roles.
+    #[clap(long, env = "GRPC_ROLES")]
+    pub grpc_roles: Option<String>,
+}
+
+/// Read a TLS or roles file.
+fn read_file(path: &str) -> Result<Vec<u8>, GrpcAuthError> {
+    fs::read(path).context(ReadFile { path })
+}
+
+impl GrpcTlsArgs {
+    /// Get the identity of this io-engine instance, if any.
+    fn identity(&self) -> Result<Option<Identity>, GrpcAuthError> {
+        match (&self.grpc_tls_cert, &self.grpc_tls_key) {
+            (Some(cert), Some(key)) => {
+                Ok(Some(Identity::from_pem(read_file(cert)?, read_file(key)?)))
+            }
+            (None, None) => Ok(None),
+            _ => Err(GrpcAuthError::IncompleteIdentity {}),
+        }
+    }
+
+    /// Get the CA certificate, if any.
+    fn ca(&self) -> Result<Option<Certificate>, GrpcAuthError> {
+        self.grpc_tls_ca
+            .as_deref()
+            .map(|ca| read_file(ca).map(Certificate::from_pem))
+            .transpose()
+    }
+
+    /// Get the TLS configuration of the gRPC server, if TLS is enabled.
+    pub(crate) fn server_tls_config(&self) -> Result<Option<ServerTlsConfig>, GrpcAuthError> {
+        let Some(identity) = self.identity()? else {
+            return Ok(None);
+        };
+        let config = ServerTlsConfig::new().identity(identity);
+        Ok(Some(match self.ca()? {
+            Some(ca) => config.client_ca_root(ca),
+            None => config,
+        }))
+    }
+
+    /// Get the TLS configuration of the gRPC clients of this instance, which
+    /// present its certificate as their identity.
+    pub fn client_tls_config(&self) -> Result<Option<ClientTlsConfig>, GrpcAuthError> {
+        let identity = self.identity()?;
+        let ca = self.ca()?;
+        if identity.is_none() && ca.is_none() {
+            return Ok(None);
+        }
+        let mut config = ClientTlsConfig::new();
+        if let Some(identity) = identity {
+            config = config.identity(identity);
+        }
+        if let Some(ca) = ca {
+            config = config.ca_certificate(ca);
+        }
+        Ok(Some(config))
+    }
+
+    /// Load the client roles, if a roles file is given. With TLS enabled the
+    /// clients are authorised regardless, with the least privileged role if
+    /// there is no roles file.
+    pub(crate) fn roles(&self) -> Result<Option<GrpcRoles>, GrpcAuthError> {
+        let Some(path) = &self.grpc_roles else {
+            if self.grpc_tls_cert.is_some() {
+                warn!("No gRPC roles file, every client is granted the read-only role");
+                return Ok(Some(GrpcRoles::default()));
+            }
+            return Ok(None);
+        };
+        let bytes = read_file(path)?;
+        GrpcRoles::from_yaml(&bytes)
+            .map(Some)
+            .context(ParseRoles { path })
+    }
+}
+
+/// Role of a gRPC client, from the least to the most privileged.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, strum_macros::Display,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum Role {
+    /// Can only query the state of the io-engine.
+    ReadOnly,
+    /// Can also manage pools, replicas, nexuses, etc.
+    Operator,
+    /// Can also call the destructive test methods.
+    Test,
+}
+
+impl Role {
+    /// Get the role required to call the gRPC method with the given path,
+    /// which is `/<package>.<service>/<method>`.
+    pub fn required_for(path: &str) -> Role {
+        const READ_ONLY: [&str; 4] = ["List", "Get", "Stat", "Find"];
+
+        let (service, method) = path
+            .trim_start_matches('/')
+            .split_once('/')
+            .unwrap_or_default();
+        if service.rsplit('.').next() == Some("TestRpc") {
+            Role::Test
+        } else if method == "JsonRpcCall" {
+            // The JSON-RPC method is authorised by the passthrough itself.
+            Role::ReadOnly
+        } else if READ_ONLY.iter().any(|p| method.starts_with(p)) {
+            Role::ReadOnly
+        } else {
+            Role::Operator
+        }
+    }
+
+    /// Get the role required to call the JSON-RPC method with the given name
+    /// through the gRPC passthrough. Only the listed methods are open to the
+    /// read-only and operator clients, any other method requires the most
+    /// privileged role.
+    pub fn required_for_json(method: &str) -> Role {
+        const READ_ONLY: [&str; 19] = [
+            "audit_log",
+            "bdev_get_bdevs",
+            "bdev_get_iostat",
+            "diagnostics_get",
+            "diagnostics_list",
+            "framework_get_reactors",
+            "handover_status",
+            "hello",
+            "log_get",
+            "nexus_snapshot_policy_get",
+            "node_drain_status",
+            "nvme_controller_timeouts",
+            "nvmf_get_subsystems",
+            "operation_get",
+            "operation_list",
+            "replica_import_list",
+            "replica_reclaim_stats",
+            "replica_snapshot_policy_get",
+            "thread_get_stats",
+        ];
+        const OPERATOR: [&str; 16] = [
+            "handover_start",
+            "log_reset",
+            "log_set",
+            "mayastor_config_export",
+            "mayastor_config_reload",
+            "nexus_rebuild_from_snapshot",
+            "nexus_share",
+            "nexus_snapshot_policy_set",
+            "node_drain",
+            "node_undrain",
+            "operation_cancel",
+            "pool_check",
+            "replica_import_create",
+            "replica_import_destroy",
+            "replica_snapshot_policy_set",
+            "replica_trim",
+        ];
+
+        if READ_ONLY.contains(&method) {
+            Role::ReadOnly
+        } else if OPERATOR.contains(&method) {
+            Role::Operator
+        } else {
+            // Any other SPDK method, which may as well destroy bdevs, etc.
+            Role::Test
+        }
+    }
+}
+
+/// Check that the client of the given JSON-RPC passthrough request has the
+/// role required by the JSON-RPC method.
+pub(crate) fn authorise_json<T>(
+    req: &tonic::Request<T>,
+    method: &str,
+) -> Result<(), tonic::Status> {
+    let Some(identity) = req.extensions().get::<ClientIdentity>() else {
+        return Ok(());
+    };
+    let required = Role::required_for_json(method);
+    if identity.role < required {
+        warn!("{identity} is not allowed to call {method}, which requires the {required} role");
+        return Err(tonic::Status::permission_denied(format!(
+            "{method} requires the {required} role"
+        )));
+    }
+    Ok(())
+}
+
+/// Roles of the gRPC clients, keyed by the SHA-256 fingerprint of their
+/// certificate, e.g.:
+/// ```yaml
+/// default: read-only
+/// clients:
+///   3a7bd3e2360a3d29eea436fcfb7e44c735d117c42d1c1835420b6b9942dd4f1b: operator
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GrpcRoles {
+    /// Role of the clients which are not listed, or have no certificate.
+    #[serde(default = "GrpcRoles::default_role")]
+    default: Role,
+    /// Roles of the known clients.
+    #[serde(default)]
+    clients: HashMap<String, Role>,
+}
+
+impl Default for GrpcRoles {
+    fn default() -> Self {
+        Self {
+            default: Self::default_role(),
+            clients: HashMap::new(),
+        }
+    }
+}
+
+impl GrpcRoles {
+    fn default_role() -> Role {
+        Role::ReadOnly
+    }
+
+    /// Parse the roles from YAML.
+    pub fn from_yaml(bytes: &[u8]) -> Result<Self, serde_yaml::Error> {
+        let mut roles: Self = serde_yaml::from_slice(bytes)?;
+        roles.clients = roles
+            .clients
+            .into_iter()
+            .map(|(fp, role)| (normalize_fingerprint(&fp), role))
+            .collect();
+        Ok(roles)
+    }
+
+    /// Get the role of the client with the given certificate fingerprint.
+    pub fn role(&self, fingerprint: Option<&str>) -> Role {
+        fingerprint
+            .and_then(|fp| self.clients.get(fp))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// Normalize a certificate fingerprint to lowercase hex without separators.
+fn normalize_fingerprint(fp: &str) -> String {
+    fp.chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Get the SHA-256 fingerprint of a DER encoded certificate.
+pub fn cert_fingerprint(der: &[u8]) -> String {
+    hex::encode(Sha256::digest(der))
+}
+
+/// Identity of the client of a gRPC request.
+#[derive(Debug, Clone)]
+pub struct ClientIdentity {
+    /// Fingerprint of the client certificate, if the client presented one.
+    pub fingerprint: Option<String>,
+    /// Role of the client.
+    pub role: Role,
+}
+
+impl Display for ClientIdentity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.fingerprint {
+            Some(fp) => write!(f, "client {fp} ({})", self.role),
+            None => write!(f, "anonymous client ({})", self.role),
+        }
+    }
+}
+
+impl ClientIdentity {
+    /// Get the identity of the client of the given request.
+    fn from_request<B>(req: &http::Request<B>, roles: Option<&GrpcRoles>) -> Self {
+        let fingerprint = req
+            .extensions()
+            .get::<TlsConnectInfo<TcpConnectInfo>>()
+            .and_then(|info| info.peer_certs())
+            .and_then(|certs| certs.first().map(|c| cert_fingerprint(c.as_ref())));
+        let role = match roles {
+            Some(roles) => roles.role(fingerprint.as_deref()),
+            None => Role::Test,
+        };
+        Self { fingerprint, role }
+    }
+}
+
+/// Layer which authorises the gRPC requests.
+#[derive(Debug, Clone)]
+pub(crate) struct AuthLayer {
+    roles: Option<Arc<GrpcRoles>>,
+}
+
+impl AuthLayer {
+    /// Create a new layer which authorises the requests with the given roles.
+    pub(crate) fn new(roles: Option<GrpcRoles>) -> Self {
+        Self {
+            roles: roles.map(Arc::new),
+        }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            roles: self.roles.clone(),
+        }
+    }
+}
+
+/// Service which rejects the requests of the clients lacking the role
+/// required by the method, and otherwise passes the client identity on to the
+/// method within the request extensions.
+#[derive(Debug, Clone)]
+pub(crate) struct AuthService<S> {
+    inner: S,
+    roles: Option<Arc<GrpcRoles>>,
+}
+
+impl<S, B> Service<http::Request<B>> for AuthService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        let identity = ClientIdentity::from_request(&req, self.roles.as_deref());
+        let path = req.uri().path();
+        let required = Role::required_for(path);
+        if identity.role < required {
+            warn!("{identity} is not allowed to call {path}, which requires the {required} role");
+            return Either::Right(ready(Ok(error_response(
+                Code::PermissionDenied,
+                &format!("{path} requires the {required} role"),
+            ))));
+        }
+        req.extensions_mut().insert(identity);
+        Either::Left(self.inner.call(req))
+    }
+}
+
+/// Build a gRPC error response, for the requests rejected before reaching
+/// their method.
+pub(super) fn error_response(code: Code, message: &str) -> http::Response<BoxBody> {
+    let mut response = http::Response::new(empty_body());
+    let headers = response.headers_mut();
+    headers.insert("grpc-status", (code as i32).into());
+    if let Ok(message) = http::HeaderValue::from_str(message) {
+        headers.insert("grpc-message", message);
+    }
+    headers.insert(
+        http::header::CONTENT_TYPE,
+        http::HeaderValue::from_static("application/grpc"),
+    );
+    response
+}
@@ -1,3 +1,4 @@
+pub use auth::{cert_fingerprint, ClientIdentity, GrpcAuthError, GrpcRoles, GrpcTlsArgs, Role};
 use futures::channel::oneshot::Receiver;
 use nix::errno::Errno;
 pub use server::MayastorGrpcServer;
@@ -8,6 +9,7 @@ use std::{
 };
 use tokio::sync::RwLock;
 use tonic::{Request, Response, Status};
+use tracing::Instrument;
 
 use crate::{
     bdev_api::BdevError,
@@ -46,7 +48,10 @@ impl From<CoreError> for tonic::Status {
     }
 }
 
+pub mod audit;
+mod auth;
 pub mod controller_grpc;
+pub mod operations;
 mod server;
 pub mod v0 {
     pub mod bdev_grpc;
@@ -83,6 +88,12 @@ pub(crate) struct GrpcClientContext {
     pub id: String,
     /// Method timeout.
     pub timeout: Duration,
+    /// Identity of the client.
+    pub identity: Option<ClientIdentity>,
+    /// Client supplied operation id.
+    pub operation_id: Option<String>,
+    /// Operation tracked by the operation id.
+    pub(crate) operation: Option<std::sync::Arc<operations::Operation>>,
 }
 
 impl GrpcClientContext {
@@ -95,6 +106,27 @@ impl GrpcClientContext {
             timeout: get_request_timeout(req),
             args: format!("{:?}", req.get_ref()),
             id: fid.to_string(),
+            identity: req.extensions().get::<ClientIdentity>().cloned(),
+            operation_id: operations::operation_id(req),
+            operation: None,
+        }
+    }
+
+    /// Wait for a resource lock of this request, failing as soon as its
+    /// operation is cancelled.
+    pub async fn queued<F: Future>(&self, lock: F) -> Result<F::Output, Status> {
+        match &self.operation {
+            Some(operation) => operation.queued(lock).await,
+            None => Ok(lock.await),
+        }
+    }
+
+    /// Mark the operation of this request as running, once it has acquired
+    /// its resource locks, failing if it was cancelled meanwhile.
+    pub fn start_operation(&self) -> Result<(), Status> {
+        match &self.operation {
+            Some(operation) => operation.start(),
+            None => Ok(()),
         }
     }
 }
@@ -139,7 +171,8 @@ where
     F: Future<Output = Result<R, E>> + 'static,
     R: Send + Debug + 'static,
 {
-    Reactor::spawn_at_primary(future).map_err(|_| Status::resource_exhausted("ENOMEM"))
+    Reactor::spawn_at_primary(future.in_current_span())
+        .map_err(|_| Status::resource_exhausted("ENOMEM"))
 }
 /// Submit rpc code to the primary reactor.
 /// Similar to `rpc_submit` but with a more generic response abstraction.
@@ -148,7 +181,8 @@ where
     F: Future<Output = R> + 'static,
     R: Send + Debug + 'static,
 {
-    Reactor::spawn_at_primary(future).map_err(|_| Status::resource_exhausted("ENOMEM"))
+    Reactor::spawn_at_primary(future.in_current_span())
+        .map_err(|_| Status::resource_exhausted("ENOMEM"))
 }
 
 /// Submit rpc code to the primary reactor.
@@ -159,7 +193,8 @@ where
     F: Future<Output = Result<R, tonic::Status>> + 'static,
     R: Send + Debug + 'static,
 {
-    Reactor::spawn_at_primary(future).map_err(|_| Status::resource_exhausted("ENOMEM"))
+    Reactor::spawn_at_primary(future.in_current_span())
+        .map_err(|_| Status::resource_exhausted("ENOMEM"))
 }
 
 /// Manage locks across multiple grpc services.
@@ -208,6 +243,13 @@ pub fn node_name(node_name: &Option<String>) -> String {
         .unwrap_or_else(|| std::env::var("HOSTNAME").unwrap_or_else(|_| "mayastor-node".into()))
 }
 
+/// Refuse to create new resources while the node is draining, or handing
+/// over to another io-engine instance.
+pub(crate) fn check_not_draining() -> Result<(), tonic::Status> {
+    crate::core::drain::check_serving()
+        .map_err(|error| tonic::Status::unavailable(error.to_string()))
+}
+
 const SECONDS_IN_HOUR: u64 = 60 * 60;
 const SECONDS_IN_MINUTE: u64 = 60;
 
@@ -0,0 +1,544 @@
+//! Idempotent, resumable gRPC operations.
+//!
+//! A client may tag a mutating request with an operation id, within the
+//! `operation-id` request metadata. The operation then runs detached from the
+//! request, so it completes even if the client times out, and it's tracked in
+//! a bounded table along with its result. A new operation is rejected while
+//! the table is full of operations which have not completed yet:
+//! - retrying the request with the same id waits for the running operation, or
+//!   returns the result of the completed one, instead of running it again.
+//! - the operation can be queried or cancelled by id, through the
+//!   `operation_get`, `operation_list` and `operation_cancel` json-rpc methods.
+//!
+//! An operation can only be cancelled while it's queued, ie waiting for its
+//! resource locks, as a running operation may have already changed the data
+//! plane. Cancelling it stops waiting for the locks right away, and completes
+//! the operation as cancelled.
+//!
+//! Only the mutating methods of the v1 pool, replica, nexus and snapshot
+//! services can be tracked, any other request with an operation id is
+//! rejected.
+
+use std::{
+    any::Any,
+    collections::{HashMap, VecDeque},
+    fmt::{Debug, Formatter},
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use chrono::{DateTime, Utc};
+use futures::{
+    future::{ready, Either, Ready},
+    FutureExt,
+};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use snafu::Snafu;
+use tokio::sync::watch;
+use tonic::{body::BoxBody, Code as GrpcCode, Response, Status};
+use tower::{Layer, Service};
+use tracing::Instrument;
+
+use super::{auth::error_response, GrpcClientContext, GrpcResult};
+use crate::jsonrpc::{jsonrpc_register, Code, RpcErrorCode};
+
+/// Request metadata key of the operation id.
+pub(crate) const OPERATION_ID_KEY: &str = "operation-id";
+
+/// Maximum number of operations which are tracked, the oldest completed
+/// operations are evicted first, and no new operation is accepted once the
+/// table is full of uncompleted ones.
+const MAX_OPERATIONS: usize = 1024;
+
+/// gRPC methods which can be tracked by an operation id, by service.
+const TRACKED_METHODS: [(&str, &[&str]); 4] = [
+    (
+        "PoolRpc",
+        &[
+            "CreatePool",
+            "DestroyPool",
+            "ExportPool",
+            "ImportPool",
+            "GrowPool",
+        ],
+    ),
+    (
+        "ReplicaRpc",
+        &[
+            "CreateReplica",
+            "DestroyReplica",
+            "ShareReplica",
+            "UnshareReplica",
+            "ResizeReplica",
+            "SetReplicaEntityId",
+        ],
+    ),
+    (
+        "NexusRpc",
+        &[
+            "CreateNexus",
+            "DestroyNexus",
+            "ResizeNexus",
+            "ShutdownNexus",
+            "AddChildNexus",
+            "RemoveChildNexus",
+            "FaultNexusChild",
+            "PublishNexus",
+            "UnpublishNexus",
+            "SetNvmeAnaState",
+            "ChildOperation",
+            "StartRebuild",
+            "StopRebuild",
+            "PauseRebuild",
+            "ResumeRebuild",
+        ],
+    ),
+    (
+        "SnapshotRpc",
+        &[
+            "CreateNexusSnapshot",
+            "CreateReplicaSnapshot",
+            "DestroySnapshot",
+            "CreateSnapshotClone",
+        ],
+    ),
+];
+
+/// Operation errors.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)), context(suffix(false)))]
+#[allow(missing_docs)]
+pub enum OperationError {
+    #[snafu(display("Operation {id} not found"))]
+    OperationNotFound { id: String },
+    #[snafu(display("Operation {id} is {state} and can no longer be cancelled"))]
+    OperationNotCancellable { id: String, state: OperationState },
+}
+
+impl RpcErrorCode for OperationError {
+    fn rpc_error_code(&self) -> Code {
+        match self {
+            Self::OperationNotFound { .. } => Code::NotFound,
+            Self::OperationNotCancellable { .. } => Code::InvalidParams,
+        }
+    }
+}
+
+/// State of an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, strum_macros::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum OperationState {
+    /// Waiting for its resource locks.
+    Queued,
+    /// Queued, but cancelled before it could run.
+    Cancelling,
+    /// Running.
+    Running,
+    /// Completed successfully.
+    Succeeded,
+    /// Failed.
+    Failed,
+    /// Cancelled before it could run.
+    Cancelled,
+}
+
+impl OperationState {
+    /// Whether the operation has completed.
+    pub fn is_done(&self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// Information about an operation.
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationInfo {
+    /// Id of the operation.
+    pub id: String,
+    /// gRPC method of the operation.
+    pub method: String,
+    /// State of the operation.
+    pub state: OperationState,
+    /// Error of the operation, if it failed.
+    pub error: Option<String>,
+    /// When the operation was started, in RFC 3339 format.
+    pub started_at: String,
+    /// When the operation completed, in RFC 3339 format.
+    pub finished_at: Option<String>,
+}
+
+/// Mutable part of an operation.
+struct OperationStatus {
+    state: OperationState,
+    error: Option<String>,
+    finished_at: Option<DateTime<Utc>>,
+    /// Result of the operation, a `Result<T, Status>`.
+    result: Option<Box<dyn Any + Send + Sync>>,
+}
+
+/// An operation tracked by its id.
+pub(crate) struct Operation {
+    id: String,
+    method: String,
+    args: String,
+    started_at: DateTime<Utc>,
+    status: Mutex<OperationStatus>,
+    done: watch::Sender<bool>,
+    cancelled: watch::Sender<bool>,
+}
+
+impl Debug for Operation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Operation")
+            .field("id", &self.id)
+            .field("method", &self.method)
+            .field("state", &self.status.lock().state)
+            .finish()
+    }
+}
+
+impl Operation {
+    fn new(id: &str, ctx: &GrpcClientContext) -> Self {
+        Self {
+            id: id.to_string(),
+            method: ctx.id.clone(),
+            args: ctx.args.clone(),
+            started_at: Utc::now(),
+            status: Mutex::new(OperationStatus {
+                state: OperationState::Queued,
+                error: None,
+                finished_at: None,
+                result: None,
+            }),
+            done: watch::channel(false).0,
+            cancelled: watch::channel(false).0,
+        }
+    }
+
+    /// Get the information about this operation.
+    fn info(&self) -> OperationInfo {
+        let status = self.status.lock();
+        OperationInfo {
+            id: self.id.clone(),
+            method: self.method.clone(),
+            state: status.state,
+            error: status.error.clone(),
+            started_at: self.started_at.to_rfc3339(),
+            finished_at: status.finished_at.map(|t| t.to_rfc3339()),
+        }
+    }
+
+    /// Mark the operation as running, unless it was cancelled.
+    pub(crate) fn start(&self) -> Result<(), Status> {
+        let mut status = self.status.lock();
+        match status.state {
+            OperationState::Queued => {
+                status.state = OperationState::Running;
+                Ok(())
+            }
+            OperationState::Cancelling => Err(self.cancelled_status()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Wait for a resource lock of the operation, failing as soon as the
+    /// operation is cancelled.
+    pub(crate) async fn queued<F: Future>(&self, lock: F) -> Result<F::Output, Status> {
+        let mut cancelled = self.cancelled.subscribe();
+        tokio::select! {
+            output = lock => Ok(output),
+            _ = cancelled.wait_for(|cancelled| *cancelled) => Err(self.cancelled_status()),
+        }
+    }
+
+    fn cancelled_status(&self) -> Status {
+        Status::cancelled(format!("Operation {} was cancelled", self.id))
+    }
+
+    /// Cancel the operation, if it's still queued.
+    fn cancel(&self) -> Result<OperationInfo, OperationError> {
+        {
+            let mut status = self.status.lock();
+            match status.state {
+                OperationState::Queued | OperationState::Cancelling => {
+                    status.state = OperationState::Cancelling;
+                }
+                state => {
+                    return Err(OperationError::OperationNotCancellable {
+                        id: self.id.clone(),
+                        state,
+                    })
+                }
+            }
+        }
+        self.cancelled.send_replace(true);
+        info!("Operation {} ({}) is cancelled", self.id, self.method);
+        Ok(self.info())
+    }
+
+    /// Complete the operation with the given result.
+    fn complete<T: Send + Sync + 'static>(&self, result: Result<T, Status>) {
+        {
+            let mut status = self.status.lock();
+            status.state = match &result {
+                Ok(_) => OperationState::Succeeded,
+                Err(_) if status.state == OperationState::Cancelling => OperationState::Cancelled,
+                Err(_) => OperationState::Failed,
+            };
+            status.error = result.as_ref().err().map(|e| e.message().to_string());
+            status.finished_at = Some(Utc::now());
+            status.result = Some(Box::new(result));
+        }
+        self.done.send_replace(true);
+    }
+
+    /// Wait for the operation to complete and get its result.
+    async fn wait<T: Clone + 'static>(&self) -> Result<T, Status> {
+        let mut done = self.done.subscribe();
+        done.wait_for(|done| *done).await.ok();
+
+        let status = self.status.lock();
+        match status
+            .result
+            .as_ref()
+            .and_then(|r| r.downcast_ref::<Result<T, Status>>())
+        {
+            Some(result) => result.clone(),
+            None => Err(Status::internal(format!(
+                "Operation {} has no result",
+                self.id
+            ))),
+        }
+    }
+}
+
+/// Bounded table of the operations.
+#[derive(Default)]
+struct OperationTable {
+    operations: HashMap<String, Arc<Operation>>,
+    /// Operation ids, from the oldest to the newest.
+    order: VecDeque<String>,
+}
+
+impl OperationTable {
+    /// Get the operation with the given id, or insert a new one.
+    /// Returns the operation and whether it was inserted.
+    fn get_or_insert(
+        &mut self,
+        id: &str,
+        ctx: &GrpcClientContext,
+    ) -> Result<(Arc<Operation>, bool), Status> {
+        if let Some(op) = self.operations.get(id) {
+            if op.method != ctx.id || op.args != ctx.args {
+                return Err(Status::already_exists(format!(
+                    "Operation {id} was used for another {} request",
+                    op.method
+                )));
+            }
+            return Ok((op.clone(), false));
+        }
+
+        self.evict();
+        if self.operations.len() >= MAX_OPERATIONS {
+            return Err(Status::resource_exhausted(format!(
+                "Operation {id} can't be tracked, {MAX_OPERATIONS} operations are in progress"
+            )));
+        }
+        let op = Arc::new(Operation::new(id, ctx));
+        self.operations.insert(id.to_string(), op.clone());
+        self.order.push_back(id.to_string());
+        Ok((op, true))
+    }
+
+    /// Evict the oldest completed operations, to make room for a new one.
+    fn evict(&mut self) {
+        let mut kept = VecDeque::new();
+        while self.operations.len() >= MAX_OPERATIONS {
+            let Some(id) = self.order.pop_front() else {
+                break;
+            };
+            match self.operations.get(&id) {
+                Some(op) if !op.status.lock().state.is_done() => kept.push_back(id),
+                _ => {
+                    self.operations.remove(&id);
+                }
+            }
+        }
+        while let Some(id) = kept.pop_back() {
+            self.order.push_front(id);
+        }
+    }
+}
+
+static OPERATIONS: Lazy<Mutex<OperationTable>> = Lazy::new(Default::default);
+
+/// Get the operation id of the given request, if any.
+pub(crate) fn operation_id<T>(req: &tonic::Request<T>) -> Option<String> {
+    req.metadata()
+        .get(OPERATION_ID_KEY)
+        .and_then(|id| id.to_str().ok())
+        .filter(|id| !id.is_empty())
+        .map(ToString::to_string)
+}
+
+/// Whether the gRPC method with the given path, which is
+/// `/<package>.<service>/<method>`, can be tracked by an operation id.
+pub fn is_tracked(path: &str) -> bool {
+    let (service, method) = path
+        .trim_start_matches('/')
+        .split_once('/')
+        .unwrap_or_default();
+    let service = service.rsplit('.').next().unwrap_or_default();
+    TRACKED_METHODS
+        .iter()
+        .any(|(s, methods)| *s == service && methods.contains(&method))
+}
+
+/// Run a gRPC method as an operation, if the request has an operation id,
+/// otherwise just run it.
+/// The method must wait for its resource locks with
+/// `GrpcClientContext::queued`, and call `GrpcClientContext::start_operation`
+/// once it has acquired them.
+pub(crate) async fn track_operation<T, F, Fut>(mut ctx: GrpcClientContext, f: F) -> GrpcResult<T>
+where
+    T: Clone + Send + Sync + 'static,
+    F: FnOnce(GrpcClientContext) -> Fut,
+    Fut: Future<Output = GrpcResult<T>> + Send + 'static,
+{
+    let Some(id) = ctx.operation_id.clone() else {
+        return f(ctx).await;
+    };
+
+    let (op, new) = OPERATIONS.lock().get_or_insert(&id, &ctx)?;
+    if new {
+        info!("Operation {id} ({}) is queued", op.method);
+        ctx.operation = Some(op.clone());
+        let fut = f(ctx);
+        let task_op = op.clone();
+        // Detach the operation from the request, so it completes even if the
+        // client goes away.
+        tokio::spawn(
+            async move {
+                let result = fut.await.map(Response::into_inner);
+                task_op.complete(result);
+            }
+            .in_current_span(),
+        );
+    } else {
+        info!("Operation {id} ({}) is resumed", op.method);
+    }
+
+    op.wait::<T>().await.map(Response::new)
+}
+
+/// Layer which rejects the requests with an operation id, unless their
+/// method can be tracked.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OperationLayer {}
+
+impl<S> Layer<S> for OperationLayer {
+    type Service = OperationService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        OperationService { inner }
+    }
+}
+
+/// Service which rejects the requests with an operation id, unless their
+/// method can be tracked, as the id would otherwise be silently ignored.
+#[derive(Debug, Clone)]
+pub(crate) struct OperationService<S> {
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for OperationService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let path = req.uri().path();
+        if req.headers().contains_key(OPERATION_ID_KEY) && !is_tracked(path) {
+            return Either::Right(ready(Ok(error_response(
+                GrpcCode::InvalidArgument,
+                &format!("{path} does not support operation ids"),
+            ))));
+        }
+        Either::Left(self.inner.call(req))
+    }
+}
+
+/// Get the information about the operation with the given id.
+pub fn operation_get(id: &str) -> Result<OperationInfo, OperationError> {
+    OPERATIONS
+        .lock()
+        .operations
+        .get(id)
+        .map(|op| op.info())
+        .ok_or_else(|| OperationError::OperationNotFound { id: id.to_string() })
+}
+
+/// List all the tracked operations, from the oldest to the newest.
+pub fn operation_list() -> Vec<OperationInfo> {
+    let table = OPERATIONS.lock();
+    table
+        .order
+        .iter()
+        .filter_map(|id| table.operations.get(id))
+        .map(|op| op.info())
+        .collect()
+}
+
+/// Cancel the queued operation with the given id.
+pub fn operation_cancel(id: &str) -> Result<OperationInfo, OperationError> {
+    let op = OPERATIONS
+        .lock()
+        .operations
+        .get(id)
+        .cloned()
+        .ok_or_else(|| OperationError::OperationNotFound { id: id.to_string() })?;
+    op.cancel()
+}
+
+/// Arguments of the operation json-rpc methods.
+#[derive(Debug, Deserialize)]
+struct OperationArgs {
+    /// Id of the operation.
+    id: String,
+}
+
+type OperationFuture<R> = Pin<Box<dyn Future<Output = Result<R, OperationError>>>>;
+
+/// Register the operation json-rpc methods.
+pub(crate) fn register_jsonrpc_methods() {
+    jsonrpc_register(
+        "operation_get",
+        |args: OperationArgs| -> OperationFuture<OperationInfo> {
+            async move { operation_get(&args.id) }.boxed_local()
+        },
+    );
+
+    jsonrpc_register(
+        "operation_list",
+        |_args: serde_json::Value| -> OperationFuture<Vec<OperationInfo>> {
+            async move { Ok(operation_list()) }.boxed_local()
+        },
+    );
+
+    jsonrpc_register(
+        "operation_cancel",
+        |args: OperationArgs| -> OperationFuture<OperationInfo> {
+            async move { operation_cancel(&args.id) }.boxed_local()
+        },
+    );
+}
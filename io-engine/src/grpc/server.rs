@@ -1,4 +1,6 @@
 use super::{
+    auth::AuthLayer,
+    operations::OperationLayer,
     v0::{bdev_grpc::BdevSvc, json_grpc::JsonRpcSvc, mayastor_grpc::MayastorSvc},
     v1::{
         bdev::BdevService, host::HostService, json::JsonService, nexus::NexusService,
@@ -15,7 +17,9 @@ use io_engine_api::{
     v1,
 };
 
-use crate::subsys::registration::registration_grpc::ApiVersion;
+use crate::{
+    core::MayastorEnvironment, subsys::registration::registration_grpc::ApiVersion, telemetry,
+};
 use futures::{select, FutureExt, StreamExt};
 use once_cell::sync::OnceCell;
 use std::{borrow::Cow, time::Duration};
@@ -68,7 +72,39 @@ impl MayastorGrpcServer {
             "{:?} gRPC server configured at address {}",
             api_versions, endpoint
         );
-        let svc = Server::builder()
+
+        let tls_args = MayastorEnvironment::global_or_default().grpc_tls;
+        let (tls, roles) = match tls_args.server_tls_config().and_then(|tls| {
+            let roles = tls_args.roles()?;
+            Ok((tls, roles))
+        }) {
+            Ok(config) => config,
+            Err(error) => {
+                error!("Invalid gRPC TLS configuration: {error}");
+                return Err(());
+            }
+        };
+        let mut builder = Server::builder().trace_fn(telemetry::grpc_span);
+        if let Some(tls) = tls {
+            info!(
+                "gRPC server TLS is enabled, client certificates are {}",
+                if tls_args.grpc_tls_ca.is_some() {
+                    "required"
+                } else {
+                    "not requested"
+                }
+            );
+            builder = builder.tls_config(tls).map_err(|error| {
+                error!("Failed to configure the gRPC server TLS: {error}");
+            })?;
+        }
+        if roles.is_some() {
+            info!("gRPC client authorisation is enabled");
+        }
+
+        let svc = builder
+            .layer(AuthLayer::new(roles))
+            .layer(OperationLayer::default())
             .add_optional_service(
                 enable_v1.map(|_| v1::bdev::BdevRpcServer::new(BdevService::new())),
             )
@@ -1,7 +1,7 @@
 //!
 //! gRPC method to proxy calls to (local) SPDK json-rpc service
 
-use crate::grpc::GrpcResult;
+use crate::grpc::{auth::authorise_json, GrpcResult};
 use io_engine_api::v0::{json_rpc_server::JsonRpc, JsonRpcReply, JsonRpcRequest};
 use jsonrpc::error::Error;
 use std::borrow::Cow;
@@ -25,6 +25,7 @@ impl JsonRpc for JsonRpcSvc {
     /// Invoke a json-rpc method and return the result
     #[instrument(level = "debug", err)]
     async fn json_rpc_call(&self, request: Request<JsonRpcRequest>) -> GrpcResult<JsonRpcReply> {
+        authorise_json(&request, &request.get_ref().method)?;
         let args = request.into_inner();
 
         let result = self
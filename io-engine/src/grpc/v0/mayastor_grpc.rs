@@ -22,16 +22,19 @@ use crate::{
         SnapshotParams, ToErrno, UntypedBdev,
     },
     grpc::{
+        audit::AuditEntry,
+        check_not_draining,
         controller_grpc::{controller_stats, list_controllers, NvmeControllerInfo},
         rpc_submit,
         v0::nexus_grpc::{nexus_add_child, nexus_destroy, nexus_lookup, uuid_to_name},
         GrpcClientContext, GrpcResult, Serializer,
     },
     host::{blk_device, resource},
-    lvs::{lvs_lvol::LvsLvol, BsError, Lvol, Lvs, LvsError},
+    lvs::{lock_pool, lvs_lvol::LvsLvol, BsError, Lvol, Lvs, LvsError},
     pool_backend::PoolArgs,
     rebuild::{RebuildState, RebuildStats},
     subsys::PoolConfig,
+    telemetry::operation_span,
 };
 
 use chrono::Utc;
@@ -45,6 +48,7 @@ use std::{
     time::Duration,
 };
 use tonic::{Request, Response, Status};
+use tracing::Instrument;
 
 use crate::core::{UpdateProps, VerboseError};
 use ::function_name::named;
@@ -67,37 +71,42 @@ where
     F: core::future::Future<Output = Result<T, Status>> + Send + 'static,
 {
     async fn locked(&self, ctx: GrpcClientContext, f: F) -> Result<T, Status> {
-        let mut guard = self.rw_lock.write().await;
-
-        // Store context as a marker of to detect abnormal termination of the
-        // request. Even though AssertUnwindSafe() allows us to
-        // intercept asserts in underlying method strategies, such a
-        // situation can still happen when the high-level future that
-        // represents gRPC call at the highest level (i.e. the one created
-        // by gRPC server) gets cancelled (due to timeout or somehow else).
-        // This can't be properly intercepted by 'locked' function itself in the
-        // first place, so the state needs to be cleaned up properly
-        // upon subsequent gRPC calls.
-        if let Some(c) = guard.replace(ctx) {
-            warn!("{}: gRPC method timed out, args: {}", c.id, c.args);
-        }
+        let audit = AuditEntry::start(&ctx);
+        audit
+            .record(async move {
+                let mut guard = self.rw_lock.write().await;
+
+                // Store context as a marker of to detect abnormal termination of the
+                // request. Even though AssertUnwindSafe() allows us to
+                // intercept asserts in underlying method strategies, such a
+                // situation can still happen when the high-level future that
+                // represents gRPC call at the highest level (i.e. the one created
+                // by gRPC server) gets cancelled (due to timeout or somehow else).
+                // This can't be properly intercepted by 'locked' function itself in the
+                // first place, so the state needs to be cleaned up properly
+                // upon subsequent gRPC calls.
+                if let Some(c) = guard.replace(ctx) {
+                    warn!("{}: gRPC method timed out, args: {}", c.id, c.args);
+                }
 
-        let fut = AssertUnwindSafe(f).catch_unwind();
-        let r = fut.await;
+                let fut = AssertUnwindSafe(f).catch_unwind();
+                let r = fut.await;
 
-        // Request completed, remove the marker.
-        let ctx = guard.take().expect("gRPC context disappeared");
+                // Request completed, remove the marker.
+                let ctx = guard.take().expect("gRPC context disappeared");
 
-        match r {
-            Ok(r) => r,
-            Err(_e) => {
-                warn!("{}: gRPC method panicked, args: {}", ctx.id, ctx.args);
-                Err(Status::cancelled(format!(
-                    "{}: gRPC method panicked",
-                    ctx.id
-                )))
-            }
-        }
+                match r {
+                    Ok(r) => r,
+                    Err(_e) => {
+                        warn!("{}: gRPC method panicked, args: {}", ctx.id, ctx.args);
+                        Err(Status::cancelled(format!(
+                            "{}: gRPC method panicked",
+                            ctx.id
+                        )))
+                    }
+                }
+            })
+            .await
     }
 }
 
@@ -127,7 +136,8 @@ impl MayastorSvc {
         // Schedule a Tokio task to detach it from the high-level gRPC future
         // and avoid task cancellation when the top-level gRPC future is
         // cancelled.
-        match tokio::spawn(async move {
+        let audit = AuditEntry::start(&ctx);
+        let task = audit.record(async move {
             // Grab global operation lock, if requested.
             let _global_guard = if global_operation {
                 match lock_manager.lock(Some(ctx.timeout), false).await {
@@ -167,9 +177,8 @@ impl MayastorSvc {
                     )))
                 }
             }
-        })
-        .await
-        {
+        });
+        match tokio::spawn(task.in_current_span()).await {
             Ok(r) => r,
             Err(_) => Err(Status::cancelled("gRPC call cancelled")),
         }
@@ -255,15 +264,6 @@ impl From<LvsError> for tonic::Status {
     }
 }
 
-impl From<Protocol> for i32 {
-    fn from(p: Protocol) -> Self {
-        match p {
-            Protocol::Off => 0,
-            Protocol::Nvmf => 1,
-        }
-    }
-}
-
 impl From<Lvs> for Pool {
     fn from(l: Lvs) -> Self {
         Self {
@@ -505,6 +505,7 @@ impl TryFrom<NvmePreemptionConv> for nexus::NexusNvmePreemption {
 impl mayastor_server::Mayastor for MayastorSvc {
     #[named]
     async fn create_pool(&self, request: Request<CreatePoolRequest>) -> GrpcResult<Pool> {
+        check_not_draining()?;
         self.locked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
@@ -592,11 +593,13 @@ impl mayastor_server::Mayastor for MayastorSvc {
 
     #[named]
     async fn create_replica(&self, request: Request<CreateReplicaRequest>) -> GrpcResult<Replica> {
+        check_not_draining()?;
         self.locked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 let rx = rpc_submit(async move {
                     let args = request.into_inner();
+                    let _lock_guard = lock_pool(&args.pool).await?;
 
                     if Lvs::lookup(&args.pool).is_none() {
                         return Err(LvsError::Invalid {
@@ -671,11 +674,13 @@ impl mayastor_server::Mayastor for MayastorSvc {
         &self,
         request: Request<CreateReplicaRequestV2>,
     ) -> GrpcResult<ReplicaV2> {
+        check_not_draining()?;
         self.locked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 let rx = rpc_submit(async move {
                     let args = request.into_inner();
+                    let _lock_guard = lock_pool(&args.pool).await?;
 
                     let lvs = match Lvs::lookup(&args.pool) {
                         Some(lvs) => lvs,
@@ -754,6 +759,7 @@ impl mayastor_server::Mayastor for MayastorSvc {
             let rx = rpc_submit::<_, _, LvsError>(async move {
                 if let Some(bdev) = UntypedBdev::lookup_by_name(&args.uuid) {
                     let lvol = Lvol::try_from(bdev)?;
+                    let _lock_guard = lock_pool(&lvol.pool_name()).await?;
                     lvol.destroy().await?;
                 }
                 Ok(Null {})
@@ -892,6 +898,11 @@ impl mayastor_server::Mayastor for MayastorSvc {
                                         })?);
                                     lvol.as_mut().share_nvmf(Some(props)).await?;
                                 }
+                                Protocol::Ublk | Protocol::Vhost => {
+                                    return Err(LvsError::ReplicaShareProtocol {
+                                        value: args.share,
+                                    });
+                                }
                             }
 
                             Ok(ShareReplicaReply {
@@ -919,6 +930,7 @@ impl mayastor_server::Mayastor for MayastorSvc {
 
     #[named]
     async fn create_nexus(&self, request: Request<CreateNexusRequest>) -> GrpcResult<Nexus> {
+        check_not_draining()?;
         let ctx = GrpcClientContext::new(&request, function_name!());
         let args = request.into_inner();
 
@@ -941,6 +953,7 @@ impl mayastor_server::Mayastor for MayastorSvc {
 
     #[named]
     async fn create_nexus_v2(&self, request: Request<CreateNexusV2Request>) -> GrpcResult<Nexus> {
+        check_not_draining()?;
         let ctx = GrpcClientContext::new(&request, function_name!());
         let args = request.into_inner();
 
@@ -1077,6 +1090,7 @@ impl mayastor_server::Mayastor for MayastorSvc {
 
     #[named]
     async fn add_child_nexus(&self, request: Request<AddChildNexusRequest>) -> GrpcResult<Child> {
+        check_not_draining()?;
         let ctx = GrpcClientContext::new(&request, function_name!());
         let args = request.into_inner();
 
@@ -1158,6 +1172,7 @@ impl mayastor_server::Mayastor for MayastorSvc {
         &self,
         request: Request<PublishNexusRequest>,
     ) -> GrpcResult<PublishNexusReply> {
+        check_not_draining()?;
         let ctx = GrpcClientContext::new(&request, function_name!());
         let args = request.into_inner();
 
@@ -1294,7 +1309,12 @@ impl mayastor_server::Mayastor for MayastorSvc {
 
                 match args.action {
                     0 => nexus.fault_child(&args.uri, FaultReason::Offline).await,
-                    1 => nexus.online_child(&args.uri).await,
+                    1 => {
+                        nexus
+                            .online_child(&args.uri)
+                            .instrument(operation_span("nexus.online_child", &args.uuid))
+                            .await
+                    }
                     2 => nexus.fault_child(&args.uri, FaultReason::IoError).await,
                     _ => Err(nexus::Error::InvalidKey {}),
                 }?;
@@ -1471,6 +1491,7 @@ impl mayastor_server::Mayastor for MayastorSvc {
         &self,
         request: Request<CreateSnapshotRequest>,
     ) -> GrpcResult<CreateSnapshotReply> {
+        check_not_draining()?;
         let ctx = GrpcClientContext::new(&request, function_name!());
         let args = request.into_inner();
 
@@ -1516,6 +1537,7 @@ impl mayastor_server::Mayastor for MayastorSvc {
 
                 let reply = nexus
                     .create_snapshot(snapshot, replicas)
+                    .instrument(operation_span("nexus.create_snapshot", &uuid))
                     .await
                     .map(|_r| CreateSnapshotReply { name })?;
                 info!("Created snapshot on nexus {}", uuid);
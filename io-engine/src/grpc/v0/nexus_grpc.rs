@@ -1,7 +1,9 @@
 //! Helpers related to nexus grpc methods.
+use crate::telemetry::operation_span;
 use io_engine_api::v0 as rpc;
 use rpc::{ChildState, ChildStateReason};
 use std::{convert::From, pin::Pin};
+use tracing::Instrument;
 use uuid::Uuid;
 
 use crate::{
@@ -205,7 +207,10 @@ pub async fn nexus_add_child(args: rpc::AddChildNexusRequest) -> Result<rpc::Chi
     // TODO: do not add child if it already exists (idempotency)
     // For that we need api to check existence of child by name (not uri that
     // contain parameters that may change).
-    n.as_mut().add_child(&args.uri, args.norebuild).await?;
+    n.as_mut()
+        .add_child(&args.uri, args.norebuild)
+        .instrument(operation_span("nexus.add_child", &args.uuid))
+        .await?;
     match n.child_mut(&args.uri) {
         Ok(child) => Ok(child.to_grpc().await),
         Err(error) => Err(error),
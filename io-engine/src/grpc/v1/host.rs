@@ -2,6 +2,7 @@ use crate::{
     bdev::{nexus, NvmeControllerState},
     core::{BlockDeviceIoStats, CoreError, MayastorBugFixes, MayastorFeatures},
     grpc::{
+        audit::AuditEntry,
         controller_grpc::{controller_stats, list_controllers, NvmeControllerInfo},
         rpc_submit, GrpcClientContext, GrpcResult, Serializer,
     },
@@ -38,37 +39,42 @@ where
     F: core::future::Future<Output = Result<T, Status>> + Send + 'static,
 {
     async fn locked(&self, ctx: GrpcClientContext, f: F) -> Result<T, Status> {
-        let mut context_guard = self.client_context.lock().await;
+        let audit = AuditEntry::start(&ctx);
+        audit
+            .record(async move {
+                let mut context_guard = self.client_context.lock().await;
 
-        // Store context as a marker of to detect abnormal termination of the
-        // request. Even though AssertUnwindSafe() allows us to
-        // intercept asserts in underlying method strategies, such a
-        // situation can still happen when the high-level future that
-        // represents gRPC call at the highest level (i.e. the one created
-        // by gRPC server) gets cancelled (due to timeout or somehow else).
-        // This can't be properly intercepted by 'locked' function itself in the
-        // first place, so the state needs to be cleaned up properly
-        // upon subsequent gRPC calls.
-        if let Some(c) = context_guard.replace(ctx) {
-            warn!("{}: gRPC method timed out, args: {}", c.id, c.args);
-        }
+                // Store context as a marker of to detect abnormal termination of the
+                // request. Even though AssertUnwindSafe() allows us to
+                // intercept asserts in underlying method strategies, such a
+                // situation can still happen when the high-level future that
+                // represents gRPC call at the highest level (i.e. the one created
+                // by gRPC server) gets cancelled (due to timeout or somehow else).
+                // This can't be properly intercepted by 'locked' function itself in the
+                // first place, so the state needs to be cleaned up properly
+                // upon subsequent gRPC calls.
+                if let Some(c) = context_guard.replace(ctx) {
+                    warn!("{}: gRPC method timed out, args: {}", c.id, c.args);
+                }
 
-        let fut = AssertUnwindSafe(f).catch_unwind();
-        let r = fut.await;
+                let fut = AssertUnwindSafe(f).catch_unwind();
+                let r = fut.await;
 
-        // Request completed, remove the marker.
-        let ctx = context_guard.take().expect("gRPC context disappeared");
+                // Request completed, remove the marker.
+                let ctx = context_guard.take().expect("gRPC context disappeared");
 
-        match r {
-            Ok(r) => r,
-            Err(_e) => {
-                warn!("{}: gRPC method panicked, args: {}", ctx.id, ctx.args);
-                Err(Status::cancelled(format!(
-                    "{}: gRPC method panicked",
-                    ctx.id
-                )))
-            }
-        }
+                match r {
+                    Ok(r) => r,
+                    Err(_e) => {
+                        warn!("{}: gRPC method panicked, args: {}", ctx.id, ctx.args);
+                        Err(Status::cancelled(format!(
+                            "{}: gRPC method panicked",
+                            ctx.id
+                        )))
+                    }
+                }
+            })
+            .await
     }
 }
 
@@ -240,6 +246,7 @@ impl host_rpc::HostRpc for HostService {
                 features: Some(MayastorFeatures::get().into()),
                 bugfixes: Some(MayastorBugFixes::get().into()),
                 version: Some(raw_version_string()),
+                summary: None,
             }),
         };
 
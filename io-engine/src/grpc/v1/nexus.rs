@@ -7,8 +7,12 @@ use crate::{
         lock::{ProtectedSubsystems, ResourceLockManager},
         Protocol, Share,
     },
-    grpc::{rpc_submit, GrpcClientContext, GrpcResult},
+    grpc::{
+        audit::AuditEntry, check_not_draining, operations::track_operation, rpc_submit,
+        GrpcClientContext, GrpcResult,
+    },
     rebuild::{HistoryRecord, RebuildState, RebuildStats},
+    telemetry::operation_span,
 };
 use futures::FutureExt;
 use std::{
@@ -19,6 +23,7 @@ use std::{
     pin::Pin,
 };
 use tonic::{Request, Response, Status};
+use tracing::Instrument;
 
 use io_engine_api::v1::nexus::*;
 
@@ -29,6 +34,12 @@ use std::panic::AssertUnwindSafe;
 
 use crate::eventing::Event;
 
+/// Child operation action which changes the I/O timeout policy of an NVMe-oF
+/// child, which has no `ChildAction` of its own. The timeout parameters to
+/// change are passed along with the uri of the child, e.g.
+/// `nvmf://host/nqn?uuid=<uuid>&io_timeout_us=30000000`.
+pub const CHILD_ACTION_SET_TIMEOUT_POLICY: i32 = 4;
+
 /// RPC service for mayastor nexus operations
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -51,12 +62,30 @@ impl NexusService {
         }
     }
 
+    /// Run the future with the nexus locks, as an operation if the request
+    /// has an operation id.
     async fn serialized<T, F>(
         &self,
         ctx: GrpcClientContext,
         nexus_uuid: String,
         global_operation: bool,
         f: F,
+    ) -> GrpcResult<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: core::future::Future<Output = GrpcResult<T>> + Send + 'static,
+    {
+        track_operation(ctx, move |ctx| {
+            Self::serialized_locked(ctx, nexus_uuid, global_operation, f)
+        })
+        .await
+    }
+
+    async fn serialized_locked<T, F>(
+        ctx: GrpcClientContext,
+        nexus_uuid: String,
+        global_operation: bool,
+        f: F,
     ) -> Result<T, Status>
     where
         T: Send + 'static,
@@ -68,10 +97,14 @@ impl NexusService {
         // Schedule a Tokio task to detach it from the high-level gRPC future
         // and avoid task cancellation when the top-level gRPC future is
         // cancelled.
-        match tokio::spawn(async move {
+        let audit = AuditEntry::start(&ctx);
+        let task = audit.record(async move {
             // Grab global operation lock, if requested.
             let _global_guard = if global_operation {
-                match lock_manager.lock(Some(ctx.timeout), false).await {
+                match ctx
+                    .queued(lock_manager.lock(Some(ctx.timeout), false))
+                    .await?
+                {
                     Some(g) => Some(g),
                     None => {
                         return Err(Status::deadline_exceeded(
@@ -84,11 +117,10 @@ impl NexusService {
             };
 
             // Grab per-object lock before executing the future.
-            let _resource_guard = match lock_manager
+            let resource_lock = lock_manager
                 .get_subsystem(ProtectedSubsystems::NEXUS)
-                .lock_resource(nexus_uuid, Some(ctx.timeout), false)
-                .await
-            {
+                .lock_resource(nexus_uuid, Some(ctx.timeout), false);
+            let _resource_guard = match ctx.queued(resource_lock).await? {
                 Some(g) => g,
                 None => {
                     return Err(Status::deadline_exceeded(
@@ -96,6 +128,9 @@ impl NexusService {
                     ))
                 }
             };
+            // Having acquired the locks, the operation can no longer be
+            // cancelled.
+            ctx.start_operation()?;
             let r = fut.await;
 
             match r {
@@ -108,9 +143,8 @@ impl NexusService {
                     )))
                 }
             }
-        })
-        .await
-        {
+        });
+        match tokio::spawn(task.in_current_span()).await {
             Ok(r) => r,
             Err(_) => Err(Status::cancelled("gRPC call cancelled")),
         }
@@ -359,7 +393,10 @@ async fn nexus_add_child(args: &AddChildNexusRequest) -> Result<Nexus, nexus::Er
     debug!("Adding child {} to nexus {} ...", args.uri, args.uuid);
     // For that we need api to check existence of child by name (not uri that
     // contain parameters that may change).
-    n.as_mut().add_child(&args.uri, args.norebuild).await?;
+    n.as_mut()
+        .add_child(&args.uri, args.norebuild)
+        .instrument(operation_span("nexus.add_child", &args.uuid))
+        .await?;
     Ok(n.into_grpc().await)
 }
 
@@ -370,6 +407,7 @@ impl NexusRpc for NexusService {
         &self,
         request: Request<CreateNexusRequest>,
     ) -> GrpcResult<CreateNexusResponse> {
+        check_not_draining()?;
         let ctx = GrpcClientContext::new(&request, function_name!());
         let args = request.into_inner();
 
@@ -547,6 +585,7 @@ impl NexusRpc for NexusService {
         &self,
         request: Request<AddChildNexusRequest>,
     ) -> GrpcResult<AddChildNexusResponse> {
+        check_not_draining()?;
         let ctx = GrpcClientContext::new(&request, function_name!());
         let args = request.into_inner();
 
@@ -633,6 +672,7 @@ impl NexusRpc for NexusService {
         &self,
         request: Request<PublishNexusRequest>,
     ) -> GrpcResult<PublishNexusResponse> {
+        check_not_draining()?;
         let ctx = GrpcClientContext::new(&request, function_name!());
         let args = request.into_inner();
 
@@ -661,7 +701,10 @@ impl NexusRpc for NexusService {
                 };
 
                 // error out if nbd or iscsi
-                if !matches!(share_protocol, Protocol::Off | Protocol::Nvmf) {
+                if !matches!(
+                    share_protocol,
+                    Protocol::Off | Protocol::Nvmf | Protocol::Ublk | Protocol::Vhost
+                ) {
                     return Err(nexus::Error::InvalidShareProtocol {
                         sp_value: args.share,
                     });
@@ -790,7 +833,13 @@ impl NexusRpc for NexusService {
                             .fault_child(&args.uri, FaultReason::Offline)
                             .await
                     }
-                    1 => nexus.as_mut().online_child(&args.uri).await,
+                    1 => {
+                        nexus
+                            .as_mut()
+                            .online_child(&args.uri)
+                            .instrument(operation_span("nexus.online_child", &args.nexus_uuid))
+                            .await
+                    }
                     2 => {
                         nexus
                             .as_mut()
@@ -803,6 +852,7 @@ impl NexusRpc for NexusService {
                             .fault_child(&args.uri, FaultReason::OfflinePermanent)
                             .await
                     }
+                    CHILD_ACTION_SET_TIMEOUT_POLICY => nexus.set_child_timeout_policy(&args.uri),
                     _ => Err(nexus::Error::InvalidKey {}),
                 }?;
 
@@ -1,12 +1,16 @@
 pub use crate::pool_backend::FindPoolArgs as PoolIdProbe;
 use crate::{
     core::{NvmfShareProps, ProtectedSubsystems, Protocol, ResourceLockGuard, ResourceLockManager},
-    grpc::{acquire_subsystem_lock, GrpcClientContext, GrpcResult, RWLock, RWSerializer},
+    grpc::{
+        acquire_subsystem_lock, audit::AuditEntry, check_not_draining, operations::track_operation,
+        GrpcClientContext, GrpcResult, RWLock, RWSerializer,
+    },
     lvs::{BsError, LvsError},
     pool_backend::{
         self, FindPoolArgs, IPoolFactory, ListPoolArgs, PoolArgs, PoolBackend, PoolFactory,
         PoolOps, ReplicaArgs,
     },
+    telemetry::operation_span,
 };
 use ::function_name::named;
 use futures::FutureExt;
@@ -15,6 +19,7 @@ use io_engine_api::v1::{
 };
 use std::{convert::TryFrom, fmt::Debug, ops::Deref, panic::AssertUnwindSafe};
 use tonic::{Request, Status};
+use tracing::Instrument;
 
 impl From<DestroyPoolRequest> for FindPoolArgs {
     fn from(value: DestroyPoolRequest) -> Self {
@@ -63,37 +68,44 @@ where
     F: core::future::Future<Output = Result<T, Status>> + Send + 'static,
 {
     async fn locked(&self, ctx: GrpcClientContext, f: F) -> Result<T, Status> {
-        let mut context_guard = self.client_context.write().await;
-
-        // Store context as a marker of to detect abnormal termination of the
-        // request. Even though AssertUnwindSafe() allows us to
-        // intercept asserts in underlying method strategies, such a
-        // situation can still happen when the high-level future that
-        // represents gRPC call at the highest level (i.e. the one created
-        // by gRPC server) gets cancelled (due to timeout or somehow else).
-        // This can't be properly intercepted by 'locked' function itself in the
-        // first place, so the state needs to be cleaned up properly
-        // upon subsequent gRPC calls.
-        if let Some(c) = context_guard.replace(ctx) {
-            warn!("{}: gRPC method timed out, args: {}", c.id, c.args);
-        }
+        let audit = AuditEntry::start(&ctx);
+        audit
+            .record(async move {
+                let mut context_guard = ctx.queued(self.client_context.write()).await?;
+                // Having acquired the lock, the operation can no longer be cancelled.
+                ctx.start_operation()?;
+
+                // Store context as a marker of to detect abnormal termination of the
+                // request. Even though AssertUnwindSafe() allows us to
+                // intercept asserts in underlying method strategies, such a
+                // situation can still happen when the high-level future that
+                // represents gRPC call at the highest level (i.e. the one created
+                // by gRPC server) gets cancelled (due to timeout or somehow else).
+                // This can't be properly intercepted by 'locked' function itself in the
+                // first place, so the state needs to be cleaned up properly
+                // upon subsequent gRPC calls.
+                if let Some(c) = context_guard.replace(ctx) {
+                    warn!("{}: gRPC method timed out, args: {}", c.id, c.args);
+                }
 
-        let fut = AssertUnwindSafe(f).catch_unwind();
-        let r = fut.await;
+                let fut = AssertUnwindSafe(f).catch_unwind();
+                let r = fut.await;
 
-        // Request completed, remove the marker.
-        let ctx = context_guard.take().expect("gRPC context disappeared");
+                // Request completed, remove the marker.
+                let ctx = context_guard.take().expect("gRPC context disappeared");
 
-        match r {
-            Ok(r) => r,
-            Err(_e) => {
-                warn!("{}: gRPC method panicked, args: {}", ctx.id, ctx.args);
-                Err(Status::cancelled(format!(
-                    "{}: gRPC method panicked",
-                    ctx.id
-                )))
-            }
-        }
+                match r {
+                    Ok(r) => r,
+                    Err(_e) => {
+                        warn!("{}: gRPC method panicked, args: {}", ctx.id, ctx.args);
+                        Err(Status::cancelled(format!(
+                            "{}: gRPC method panicked",
+                            ctx.id
+                        )))
+                    }
+                }
+            })
+            .await
     }
 
     async fn shared(&self, ctx: GrpcClientContext, f: F) -> Result<T, Status> {
@@ -264,6 +276,11 @@ impl PoolGrpc {
         args: io_engine_api::v1::replica::CreateReplicaRequest,
     ) -> Result<io_engine_api::v1::replica::Replica, Status> {
         let protocol = Protocol::try_from(args.share)?;
+        if matches!(protocol, Protocol::Ublk | Protocol::Vhost) {
+            return Err(Status::invalid_argument(format!(
+                "Replicas cannot be shared over {protocol}"
+            )));
+        }
         match self
             .pool
             .create_repl(ReplicaArgs {
@@ -358,6 +375,17 @@ impl PoolService {
             client_context: std::sync::Arc::new(tokio::sync::RwLock::new(None)),
         }
     }
+
+    /// Run a mutating method with the pool service lock, as an operation if the
+    /// request has an operation id.
+    async fn tracked<T, F>(&self, ctx: GrpcClientContext, f: F) -> GrpcResult<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: core::future::Future<Output = GrpcResult<T>> + Send + 'static,
+    {
+        let svc = self.clone();
+        track_operation(ctx, move |ctx| async move { svc.locked(ctx, f).await }).await
+    }
 }
 
 impl PoolBackend {
@@ -450,7 +478,8 @@ impl GrpcPoolFactory {
         for factory in Self::factories() {
             factory.ensure_not_found(&finder, args.backend).await?;
         }
-        let pool = self.as_factory().import(args).await?;
+        let span = operation_span("pool.import", &args.name);
+        let pool = self.as_factory().import(args).instrument(span).await?;
         Ok(pool.into())
     }
     fn as_factory(&self) -> &dyn IPoolFactory {
@@ -462,7 +491,8 @@ impl GrpcPoolFactory {
 impl PoolRpc for PoolService {
     #[named]
     async fn create_pool(&self, request: Request<CreatePoolRequest>) -> GrpcResult<Pool> {
-        self.locked(
+        check_not_draining()?;
+        self.tracked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 crate::spdk_submit!(async move {
@@ -481,7 +511,7 @@ impl PoolRpc for PoolService {
 
     #[named]
     async fn destroy_pool(&self, request: Request<DestroyPoolRequest>) -> GrpcResult<()> {
-        self.locked(
+        self.tracked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 crate::spdk_submit!(async move {
@@ -497,7 +527,7 @@ impl PoolRpc for PoolService {
 
     #[named]
     async fn export_pool(&self, request: Request<ExportPoolRequest>) -> GrpcResult<()> {
-        self.locked(
+        self.tracked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 crate::spdk_submit!(async move {
@@ -513,7 +543,8 @@ impl PoolRpc for PoolService {
 
     #[named]
     async fn import_pool(&self, request: Request<ImportPoolRequest>) -> GrpcResult<Pool> {
-        self.locked(
+        check_not_draining()?;
+        self.tracked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 crate::spdk_submit!(async move {
@@ -585,7 +616,7 @@ impl PoolRpc for PoolService {
 
     #[named]
     async fn grow_pool(&self, request: Request<GrowPoolRequest>) -> GrpcResult<GrowPoolResponse> {
-        self.locked(
+        self.tracked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 crate::spdk_submit!(async move {
@@ -7,6 +7,9 @@ use crate::{
     },
     grpc::{
         acquire_subsystem_lock,
+        audit::AuditEntry,
+        check_not_draining,
+        operations::track_operation,
         v1::pool::{GrpcPoolFactory, PoolGrpc, PoolIdProbe},
         GrpcClientContext, GrpcResult, RWLock, RWSerializer,
     },
@@ -36,37 +39,44 @@ where
     F: core::future::Future<Output = Result<T, Status>> + Send + 'static,
 {
     async fn locked(&self, ctx: GrpcClientContext, f: F) -> Result<T, Status> {
-        let mut context_guard = self.client_context.write().await;
-
-        // Store context as a marker of to detect abnormal termination of the
-        // request. Even though AssertUnwindSafe() allows us to
-        // intercept asserts in underlying method strategies, such a
-        // situation can still happen when the high-level future that
-        // represents gRPC call at the highest level (i.e. the one created
-        // by gRPC server) gets cancelled (due to timeout or somehow else).
-        // This can't be properly intercepted by 'locked' function itself in the
-        // first place, so the state needs to be cleaned up properly
-        // upon subsequent gRPC calls.
-        if let Some(c) = context_guard.replace(ctx) {
-            warn!("{}: gRPC method timed out, args: {}", c.id, c.args);
-        }
+        let audit = AuditEntry::start(&ctx);
+        audit
+            .record(async move {
+                let mut context_guard = ctx.queued(self.client_context.write()).await?;
+                // Having acquired the lock, the operation can no longer be cancelled.
+                ctx.start_operation()?;
+
+                // Store context as a marker of to detect abnormal termination of the
+                // request. Even though AssertUnwindSafe() allows us to
+                // intercept asserts in underlying method strategies, such a
+                // situation can still happen when the high-level future that
+                // represents gRPC call at the highest level (i.e. the one created
+                // by gRPC server) gets cancelled (due to timeout or somehow else).
+                // This can't be properly intercepted by 'locked' function itself in the
+                // first place, so the state needs to be cleaned up properly
+                // upon subsequent gRPC calls.
+                if let Some(c) = context_guard.replace(ctx) {
+                    warn!("{}: gRPC method timed out, args: {}", c.id, c.args);
+                }
 
-        let fut = AssertUnwindSafe(f).catch_unwind();
-        let r = fut.await;
+                let fut = AssertUnwindSafe(f).catch_unwind();
+                let r = fut.await;
 
-        // Request completed, remove the marker.
-        let ctx = context_guard.take().expect("gRPC context disappeared");
+                // Request completed, remove the marker.
+                let ctx = context_guard.take().expect("gRPC context disappeared");
 
-        match r {
-            Ok(r) => r,
-            Err(_e) => {
-                warn!("{}: gRPC method panicked, args: {}", ctx.id, ctx.args);
-                Err(Status::cancelled(format!(
-                    "{}: gRPC method panicked",
-                    ctx.id
-                )))
-            }
-        }
+                match r {
+                    Ok(r) => r,
+                    Err(_e) => {
+                        warn!("{}: gRPC method panicked, args: {}", ctx.id, ctx.args);
+                        Err(Status::cancelled(format!(
+                            "{}: gRPC method panicked",
+                            ctx.id
+                        )))
+                    }
+                }
+            })
+            .await
     }
     async fn shared(&self, ctx: GrpcClientContext, f: F) -> Result<T, Status> {
         let context_guard = self.client_context.read().await;
@@ -107,6 +117,19 @@ impl Default for ReplicaService {
     }
 }
 
+impl ReplicaService {
+    /// Run a mutating method with the replica service lock, as an operation if the
+    /// request has an operation id.
+    pub(crate) async fn tracked<T, F>(&self, ctx: GrpcClientContext, f: F) -> GrpcResult<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: core::future::Future<Output = GrpcResult<T>> + Send + 'static,
+    {
+        let svc = self.clone();
+        track_operation(ctx, move |ctx| async move { svc.locked(ctx, f).await }).await
+    }
+}
+
 impl From<destroy_replica_request::Pool> for PoolIdProbe {
     fn from(value: destroy_replica_request::Pool) -> Self {
         match value {
@@ -280,6 +303,12 @@ impl ReplicaGrpc {
             return Err(Status::invalid_argument("Invalid share protocol NONE"));
         }
 
+        if matches!(protocol, Protocol::Ublk | Protocol::Vhost) {
+            return Err(Status::invalid_argument(format!(
+                "Replicas cannot be shared over {protocol}"
+            )));
+        }
+
         let props = NvmfShareProps::new()
             .with_allowed_hosts(args.allowed_hosts)
             .with_ptpl(self.replica.create_ptpl()?);
@@ -331,7 +360,8 @@ impl From<ReplicaGrpc> for Replica {
 impl ReplicaRpc for ReplicaService {
     #[named]
     async fn create_replica(&self, request: Request<CreateReplicaRequest>) -> GrpcResult<Replica> {
-        self.locked(
+        check_not_draining()?;
+        self.tracked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 crate::spdk_submit!(async move {
@@ -361,7 +391,7 @@ impl ReplicaRpc for ReplicaService {
 
     #[named]
     async fn destroy_replica(&self, request: Request<DestroyReplicaRequest>) -> GrpcResult<()> {
-        self.locked(
+        self.tracked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 crate::spdk_submit!(async move {
@@ -382,9 +412,20 @@ impl ReplicaRpc for ReplicaService {
                         }
                         _else => _else,
                     }?;
-                    if let Some(pool) = &pool {
-                        replica.verify_pool(pool)?;
-                    }
+                    // The pool is locked by its finder, lock it here otherwise
+                    // just as for the replica creation.
+                    let _lock_guard = match &pool {
+                        Some(pool) => {
+                            replica.verify_pool(pool)?;
+                            None
+                        }
+                        None => {
+                            let pool_subsystem = ResourceLockManager::get_instance()
+                                .get_subsystem(ProtectedSubsystems::POOL);
+                            let pool_name = replica.replica.pool_name();
+                            Some(acquire_subsystem_lock(pool_subsystem, Some(&pool_name)).await?)
+                        }
+                    };
                     replica.destroy().await?;
                     Ok(())
                 })
@@ -432,7 +473,7 @@ impl ReplicaRpc for ReplicaService {
 
     #[named]
     async fn share_replica(&self, request: Request<ShareReplicaRequest>) -> GrpcResult<Replica> {
-        self.locked(
+        self.tracked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 crate::spdk_submit!(async move {
@@ -453,7 +494,7 @@ impl ReplicaRpc for ReplicaService {
         &self,
         request: Request<UnshareReplicaRequest>,
     ) -> GrpcResult<Replica> {
-        self.locked(
+        self.tracked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 crate::spdk_submit!(async move {
@@ -471,7 +512,7 @@ impl ReplicaRpc for ReplicaService {
 
     #[named]
     async fn resize_replica(&self, request: Request<ResizeReplicaRequest>) -> GrpcResult<Replica> {
-        self.locked(
+        self.tracked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 crate::spdk_submit!(async move {
@@ -492,7 +533,7 @@ impl ReplicaRpc for ReplicaService {
         &self,
         request: Request<SetReplicaEntityIdRequest>,
     ) -> GrpcResult<Replica> {
-        self.locked(
+        self.tracked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 crate::spdk_submit!(async move {
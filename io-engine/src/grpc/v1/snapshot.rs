@@ -9,10 +9,15 @@ use crate::{
         ResourceLockManager, UntypedBdev,
     },
     grpc::{
+        acquire_subsystem_lock,
+        audit::AuditEntry,
+        check_not_draining,
+        operations::track_operation,
         rpc_submit,
         v1::{nexus::nexus_lookup, replica::ReplicaGrpc},
         GrpcClientContext, GrpcResult, RWSerializer,
     },
+    telemetry::operation_span,
 };
 use ::function_name::named;
 use chrono::{DateTime, Utc};
@@ -20,6 +25,7 @@ use futures::FutureExt;
 use io_engine_api::v1::snapshot::*;
 use std::panic::AssertUnwindSafe;
 use tonic::{Request, Response, Status};
+use tracing::Instrument;
 
 /// Support for the snapshot's consumption as source, should be marked as true
 /// once we start supporting the feature.
@@ -119,12 +125,40 @@ impl SnapshotService {
             replica_svc,
         }
     }
+
+    /// Run a mutating method with the replica service lock, as an operation
+    /// if the request has an operation id.
+    async fn tracked<T, F>(&self, ctx: GrpcClientContext, f: F) -> GrpcResult<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: core::future::Future<Output = GrpcResult<T>> + Send + 'static,
+    {
+        self.replica_svc.tracked(ctx, f).await
+    }
+    /// Run the future with the nexus locks, as an operation if the request
+    /// has an operation id.
     async fn serialized<T, F>(
         &self,
         ctx: GrpcClientContext,
         nexus_uuid: String,
         global_operation: bool,
         f: F,
+    ) -> GrpcResult<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: core::future::Future<Output = GrpcResult<T>> + Send + 'static,
+    {
+        track_operation(ctx, move |ctx| {
+            Self::serialized_locked(ctx, nexus_uuid, global_operation, f)
+        })
+        .await
+    }
+
+    async fn serialized_locked<T, F>(
+        ctx: GrpcClientContext,
+        nexus_uuid: String,
+        global_operation: bool,
+        f: F,
     ) -> Result<T, Status>
     where
         T: Send + 'static,
@@ -136,10 +170,14 @@ impl SnapshotService {
         // Schedule a Tokio task to detach it from the high-level gRPC future
         // and avoid task cancellation when the top-level gRPC future is
         // cancelled.
-        match tokio::spawn(async move {
+        let audit = AuditEntry::start(&ctx);
+        let task = audit.record(async move {
             // Grab global operation lock, if requested.
             let _global_guard = if global_operation {
-                match lock_manager.lock(Some(ctx.timeout), false).await {
+                match ctx
+                    .queued(lock_manager.lock(Some(ctx.timeout), false))
+                    .await?
+                {
                     Some(g) => Some(g),
                     None => {
                         return Err(Status::deadline_exceeded(
@@ -152,11 +190,10 @@ impl SnapshotService {
             };
 
             // Grab per-object lock before executing the future.
-            let _resource_guard = match lock_manager
+            let resource_lock = lock_manager
                 .get_subsystem(ProtectedSubsystems::NEXUS)
-                .lock_resource(nexus_uuid, Some(ctx.timeout), false)
-                .await
-            {
+                .lock_resource(nexus_uuid, Some(ctx.timeout), false);
+            let _resource_guard = match ctx.queued(resource_lock).await? {
                 Some(g) => g,
                 None => {
                     return Err(Status::deadline_exceeded(
@@ -164,6 +201,9 @@ impl SnapshotService {
                     ))
                 }
             };
+            // Having acquired the locks, the operation can no longer be
+            // cancelled.
+            ctx.start_operation()?;
             let r = fut.await;
 
             match r {
@@ -176,9 +216,8 @@ impl SnapshotService {
                     )))
                 }
             }
-        })
-        .await
-        {
+        });
+        match tokio::spawn(task.in_current_span()).await {
             Ok(r) => r,
             Err(_) => Err(Status::cancelled("gRPC call cancelled")),
         }
@@ -261,7 +300,14 @@ impl ReplicaGrpc {
             )));
         }
 
-        match replica.create_snapshot(snap_config).await {
+        match replica
+            .create_snapshot(snap_config)
+            .instrument(operation_span(
+                "replica.create_snapshot",
+                &args.replica_uuid,
+            ))
+            .await
+        {
             Ok(snap_lvol) => {
                 info!("Create Snapshot Success for {replica:?}, {snap_lvol:?}");
 
@@ -320,6 +366,7 @@ impl SnapshotRpc for SnapshotService {
         &self,
         request: Request<NexusCreateSnapshotRequest>,
     ) -> GrpcResult<NexusCreateSnapshotResponse> {
+        check_not_draining()?;
         let ctx = GrpcClientContext::new(&request, function_name!());
         let args = request.into_inner();
 
@@ -344,8 +391,11 @@ impl SnapshotRpc for SnapshotService {
                     .map(NexusReplicaSnapshotDescriptor::from)
                     .collect::<Vec<_>>();
 
-                let res =
-                    nexus.as_mut().create_snapshot(snapshot, replicas).await?;
+                let res = nexus
+                    .as_mut()
+                    .create_snapshot(snapshot, replicas)
+                    .instrument(operation_span("nexus.create_snapshot", &args.nexus_uuid))
+                    .await?;
 
                 let replicas_done = res
                     .replicas_done
@@ -375,7 +425,8 @@ impl SnapshotRpc for SnapshotService {
         &self,
         request: Request<CreateReplicaSnapshotRequest>,
     ) -> GrpcResult<CreateReplicaSnapshotResponse> {
-        self.locked(
+        check_not_draining()?;
+        self.tracked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 crate::spdk_submit!(async move {
@@ -384,6 +435,12 @@ impl SnapshotRpc for SnapshotService {
 
                     let probe = FindReplicaArgs::new(&args.replica_uuid);
                     let mut replica = GrpcReplicaFactory::finder(&probe).await?;
+                    // Lock the pool as the scheduled snapshots do.
+                    let pool_subsystem = ResourceLockManager::get_instance()
+                        .get_subsystem(ProtectedSubsystems::POOL);
+                    let pool_name = replica.replica.pool_name();
+                    let _lock_guard =
+                        acquire_subsystem_lock(pool_subsystem, Some(&pool_name)).await?;
                     replica.create_snapshot(args).await
                 })
             },
@@ -421,7 +478,7 @@ impl SnapshotRpc for SnapshotService {
 
     #[named]
     async fn destroy_snapshot(&self, request: Request<DestroySnapshotRequest>) -> GrpcResult<()> {
-        self.locked(
+        self.tracked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 let args = request.into_inner();
@@ -433,9 +490,20 @@ impl SnapshotRpc for SnapshotService {
                     };
                     let probe = FindSnapshotArgs::new(args.snapshot_uuid);
                     let snapshot = SnapshotGrpc::finder(&probe).await?;
-                    if let Some(pool) = &pool {
-                        SnapshotGrpc::verify_pool(&snapshot, pool)?;
-                    }
+                    // The pool is locked by its finder, lock it here otherwise
+                    // as the scheduled snapshots do.
+                    let _lock_guard = match &pool {
+                        Some(pool) => {
+                            SnapshotGrpc::verify_pool(&snapshot, pool)?;
+                            None
+                        }
+                        None => {
+                            let pool_subsystem = ResourceLockManager::get_instance()
+                                .get_subsystem(ProtectedSubsystems::POOL);
+                            let pool_name = snapshot.0.pool_name();
+                            Some(acquire_subsystem_lock(pool_subsystem, Some(&pool_name)).await?)
+                        }
+                    };
 
                     snapshot.0.destroy_snapshot().await?;
                     Ok(())
@@ -450,7 +518,8 @@ impl SnapshotRpc for SnapshotService {
         &self,
         request: Request<CreateSnapshotCloneRequest>,
     ) -> GrpcResult<Replica> {
-        self.locked(
+        check_not_draining()?;
+        self.tracked(
             GrpcClientContext::new(&request, function_name!()),
             async move {
                 let args = request.into_inner();
@@ -474,6 +543,14 @@ impl SnapshotRpc for SnapshotService {
                         )));
                     }
 
+                    // Lock the pool as the snapshots do, so that a check of
+                    // the pool doesn't take the new clone for an orphaned
+                    // blob.
+                    let pool_subsystem = ResourceLockManager::get_instance()
+                        .get_subsystem(ProtectedSubsystems::POOL);
+                    let pool_name = snapshot.pool_name();
+                    let _lock_guard =
+                        acquire_subsystem_lock(pool_subsystem, Some(&pool_name)).await?;
                     let clone_config = match snapshot.prepare_clone_config(
                         &args.clone_name,
                         &args.clone_uuid,
@@ -1,6 +1,9 @@
 use crate::{
-    grpc::GrpcResult,
-    rebuild::{RebuildError, RebuildState, RebuildStats, SnapshotRebuildError, SnapshotRebuildJob},
+    grpc::{check_not_draining, GrpcResult},
+    rebuild::{
+        RebuildError, RebuildState, RebuildStats, ReplicaImportError, ReplicaImportJob,
+        SnapshotRebuildError, SnapshotRebuildJob,
+    },
 };
 use io_engine_api::v1::{
     snapshot_rebuild,
@@ -35,6 +38,7 @@ impl SnapshotRebuildRpc for SnapshotRebuildService {
         &self,
         request: Request<CreateSnapshotRebuildRequest>,
     ) -> GrpcResult<SnapshotRebuild> {
+        check_not_draining()?;
         let request = request.into_inner();
 
         crate::spdk_submit!(async move {
@@ -71,16 +75,23 @@ impl SnapshotRebuildRpc for SnapshotRebuildService {
             match args.replica_uuid {
                 None => {
                     let jobs = SnapshotRebuildJob::list();
-                    let mut rebuilds = Vec::with_capacity(jobs.len());
+                    let imports = ReplicaImportJob::list();
+                    let mut rebuilds = Vec::with_capacity(jobs.len() + imports.len());
                     for job in jobs {
                         rebuilds.push(SnapRebuild::from(job).await.into());
                     }
+                    for job in imports {
+                        rebuilds.push(import_rebuild(job).await);
+                    }
                     Ok(ListSnapshotRebuildResponse { rebuilds })
                 }
                 Some(uuid) => {
-                    let job = SnapRebuild::lookup(&uuid).await?;
+                    let rebuild = match ReplicaImportJob::lookup(&uuid) {
+                        Ok(job) => import_rebuild(job).await,
+                        Err(_) => SnapRebuild::lookup(&uuid).await?.into(),
+                    };
                     Ok(ListSnapshotRebuildResponse {
-                        rebuilds: vec![job.into()],
+                        rebuilds: vec![rebuild],
                     })
                 }
             }
@@ -148,6 +159,27 @@ impl From<SnapRebuild> for SnapshotRebuild {
     }
 }
 
+/// Replica imports are reported alongside the snapshot rebuilds, with the
+/// import source in place of the snapshot.
+async fn import_rebuild(job: Arc<ReplicaImportJob>) -> SnapshotRebuild {
+    let stats = job.stats().await;
+    SnapshotRebuild {
+        uuid: job.uuid().to_string(),
+        replica_uuid: job.replica_uuid().to_string(),
+        snapshot_uuid: "".to_string(),
+        replica_uri: job.replica_uri().to_string(),
+        snapshot_uri: job.source_uri().to_string(),
+        status: snapshot_rebuild::RebuildStatus::from(job.state()) as i32,
+        total: stats.blocks_total * stats.block_size,
+        rebuilt: stats.blocks_transferred * stats.block_size,
+        remaining: stats.blocks_remaining * stats.block_size,
+        persisted_checkpoint: 0,
+        start_timestamp: Some(stats.start_time.into()),
+        end_timestamp: stats.end_time.map(Into::into),
+        target_remote: false,
+    }
+}
+
 impl From<RebuildState> for snapshot_rebuild::RebuildStatus {
     fn from(value: RebuildState) -> Self {
         use snapshot_rebuild::RebuildStatus;
@@ -185,6 +217,11 @@ impl From<RebuildError> for tonic::Status {
                 // todo better error check here, what if bdev uri is invalid?
                 SnapshotRebuildError::UriBdevOpen { .. } => tonic::Status::not_found(message),
             },
+            RebuildError::ReplicaImport { source } => match source {
+                ReplicaImportError::ReplicaNotFound { .. } => tonic::Status::not_found(message),
+                ReplicaImportError::SourceBdevOpen { .. } => tonic::Status::not_found(message),
+                _ => tonic::Status::invalid_argument(message),
+            },
             _ => tonic::Status::internal(message),
         }
     }
@@ -1,6 +1,7 @@
 use crate::{
     core::lock::ResourceLockManager,
     grpc::{
+        audit::AuditEntry,
         rpc_submit,
         v1::{pool::PoolService, replica::ReplicaService},
         GrpcClientContext, GrpcResult, RWLock, Serializer,
@@ -37,29 +38,34 @@ where
     F: core::future::Future<Output = Result<T, Status>> + Send + 'static,
 {
     async fn locked(&self, ctx: GrpcClientContext, f: F) -> Result<T, Status> {
-        // Taking write lock of Stats service. This will hold off read ops.
-        let _statsvc_lock = self.client_context.write().await;
+        let audit = AuditEntry::start(&ctx);
+        audit
+            .record(async move {
+                // Taking write lock of Stats service. This will hold off read ops.
+                let _statsvc_lock = self.client_context.write().await;
 
-        // Takes read lock of Pool and Replica service.
-        let _pool_context = self.pool_svc.rw_lock().await.read().await;
-        let _replica_context = self.replica_svc.rw_lock().await.read().await;
+                // Takes read lock of Pool and Replica service.
+                let _pool_context = self.pool_svc.rw_lock().await.read().await;
+                let _replica_context = self.replica_svc.rw_lock().await.read().await;
 
-        let lock_manager = ResourceLockManager::get_instance();
-        // For nexus global lock.
-        let _global_guard = match lock_manager.lock(Some(ctx.timeout), false).await {
-            Some(g) => Some(g),
-            None => {
-                return Err(Status::deadline_exceeded(
-                    "Failed to acquire access to object within given timeout",
-                ))
-            }
-        };
-        let fut = AssertUnwindSafe(f).catch_unwind();
-        let r = fut.await;
-        r.unwrap_or_else(|_| {
-            warn!("{}: gRPC method panicked, args: {}", ctx.id, ctx.args);
-            Err(Status::cancelled("gRPC method panicked".to_string()))
-        })
+                let lock_manager = ResourceLockManager::get_instance();
+                // For nexus global lock.
+                let _global_guard = match lock_manager.lock(Some(ctx.timeout), false).await {
+                    Some(g) => Some(g),
+                    None => {
+                        return Err(Status::deadline_exceeded(
+                            "Failed to acquire access to object within given timeout",
+                        ))
+                    }
+                };
+                let fut = AssertUnwindSafe(f).catch_unwind();
+                let r = fut.await;
+                r.unwrap_or_else(|_| {
+                    warn!("{}: gRPC method panicked, args: {}", ctx.id, ctx.args);
+                    Err(Status::cancelled("gRPC method panicked".to_string()))
+                })
+            })
+            .await
     }
 }
 
@@ -249,6 +255,8 @@ impl From<BdevStats> for IoStats {
     }
 }
 /// Conversion fn to get gRPC type IOStat from BlockDeviceIoStats.
+/// The api has no field for the reclaimed bytes, which are reported by the
+/// `replica_reclaim_stats` json-rpc method instead.
 impl From<ReplicaBdevStats> for ReplicaIoStats {
     fn from(value: ReplicaBdevStats) -> Self {
         Self {
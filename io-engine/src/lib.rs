@@ -25,6 +25,7 @@ pub mod eventing;
 pub mod grpc;
 pub mod host;
 pub mod jsonrpc;
+pub mod log_control;
 pub mod logger;
 pub mod lvm;
 pub mod lvs;
@@ -36,6 +37,7 @@ pub mod sleep;
 pub mod store;
 pub mod subsys;
 pub mod target;
+pub mod telemetry;
 
 /// TODO
 #[macro_export]
@@ -51,4 +53,13 @@ pub extern "C" fn cps_init() {
     subsys::register_subsystem();
     bdev::nexus::register_module(true);
     bdev::null_ng::register();
+    bdev::nvmx::register_jsonrpc_methods();
+    rebuild::register_jsonrpc_methods();
+    lvs::register_jsonrpc_methods();
+    grpc::operations::register_jsonrpc_methods();
+    grpc::audit::register_jsonrpc_methods();
+    log_control::register_jsonrpc_methods();
+    core::drain::register_jsonrpc_methods();
+    core::handover::register_jsonrpc_methods();
+    core::diagnostics::register_jsonrpc_methods();
 }
@@ -0,0 +1,396 @@
+//! Runtime changes of the log settings.
+//!
+//! The tracing filter, the SPDK log level and log flags, and the I/O tracing
+//! of individual nexuses can be changed through the `log_set` json-rpc method,
+//! to debug an issue without restarting the io-engine and losing the state
+//! to be observed.
+//!
+//! The settings in effect before the first change are saved, and restored
+//! once the changes time out, or explicitly through the `log_reset` json-rpc
+//! method.
+
+use std::{
+    collections::BTreeSet,
+    ffi::{CStr, CString},
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use futures::FutureExt;
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use snafu::{ResultExt, Snafu};
+use spdk_rs::libspdk::{
+    spdk_log_clear_flag, spdk_log_get_first_flag, spdk_log_get_level, spdk_log_get_next_flag,
+    spdk_log_level, spdk_log_set_flag, spdk_log_set_level, spdk_log_set_print_level,
+    SPDK_LOG_DEBUG, SPDK_LOG_DISABLED, SPDK_LOG_ERROR, SPDK_LOG_INFO, SPDK_LOG_NOTICE,
+    SPDK_LOG_WARN,
+};
+
+use crate::{
+    bdev::nexus::nexus_iter,
+    core::{MayastorEnvironment, Reactors},
+    jsonrpc::{jsonrpc_register, Code, RpcErrorCode},
+    logger::{self, FilterError},
+    sleep::mayastor_sleep,
+};
+
+/// Default time after which the log changes are reverted.
+const DEFAULT_REVERT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Set when the I/Os of any nexus are traced, to keep the I/O path cheap
+/// otherwise.
+static NEXUS_IO_TRACING: AtomicBool = AtomicBool::new(false);
+
+/// Names of the nexuses whose I/Os are traced.
+static TRACED_NEXUSES: Lazy<RwLock<BTreeSet<String>>> = Lazy::new(Default::default);
+
+/// Runtime state of the log settings.
+static STATE: Lazy<Mutex<LogState>> = Lazy::new(Default::default);
+
+/// Log control errors.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)), context(suffix(false)))]
+#[allow(missing_docs)]
+pub enum LogControlError {
+    #[snafu(display("Failed to change the tracing filter"))]
+    Filter { source: FilterError },
+    #[snafu(display(
+        "Invalid SPDK log level '{level}', expected one of: disabled, error, warn, notice, \
+         info, debug"
+    ))]
+    InvalidSpdkLevel { level: String },
+    #[snafu(display("Unknown SPDK log flag '{flag}'"))]
+    UnknownSpdkFlag { flag: String },
+    #[snafu(display("Nexus '{name}' not found"))]
+    NexusNotFound { name: String },
+}
+
+impl RpcErrorCode for LogControlError {
+    fn rpc_error_code(&self) -> Code {
+        match self {
+            Self::Filter {
+                source: FilterError::NotInitialised {},
+            } => Code::InternalError,
+            Self::Filter { .. } => Code::InvalidParams,
+            Self::InvalidSpdkLevel { .. } => Code::InvalidParams,
+            Self::UnknownSpdkFlag { .. } => Code::InvalidParams,
+            Self::NexusNotFound { .. } => Code::NotFound,
+        }
+    }
+}
+
+/// The log settings.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LogSettings {
+    /// Directives of the tracing filter.
+    pub filter: Option<String>,
+    /// SPDK log level.
+    pub spdk_level: String,
+    /// Enabled SPDK log flags.
+    pub spdk_flags: Vec<String>,
+    /// Names of the nexuses whose I/Os are traced.
+    pub nexus_io_tracing: Vec<String>,
+    /// When the changes are reverted, in RFC 3339 format.
+    pub revert_at: Option<String>,
+}
+
+/// A change of the log settings.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct LogChange {
+    /// Directives of the tracing filter, e.g. `info,io_engine::bdev=debug`.
+    pub filter: Option<String>,
+    /// SPDK log level.
+    pub spdk_level: Option<String>,
+    /// SPDK log flags to enable.
+    pub enable_flags: Vec<String>,
+    /// SPDK log flags to disable.
+    pub disable_flags: Vec<String>,
+    /// Names or uuids of the nexuses whose I/Os are to be traced.
+    pub trace_nexus: Vec<String>,
+    /// Names or uuids of the nexuses whose I/Os are no longer to be traced.
+    pub untrace_nexus: Vec<String>,
+    /// Seconds after which the changes are reverted, 0 to keep them.
+    pub timeout_secs: Option<u64>,
+}
+
+/// Log settings saved before the first change.
+#[derive(Debug)]
+struct Baseline {
+    spdk_level: spdk_log_level,
+    spdk_flags: BTreeSet<String>,
+    nexus_io_tracing: BTreeSet<String>,
+}
+
+#[derive(Debug, Default)]
+struct LogState {
+    /// Enabled SPDK log flags, as SPDK can't list them.
+    spdk_flags: Option<BTreeSet<String>>,
+    /// Settings to restore, if any change is in effect.
+    baseline: Option<Baseline>,
+    /// Incremented on every change, to ignore the stale reverts.
+    generation: u64,
+    /// When the changes are reverted.
+    revert_at: Option<DateTime<Utc>>,
+}
+
+impl LogState {
+    fn spdk_flags(&mut self) -> &mut BTreeSet<String> {
+        self.spdk_flags.get_or_insert_with(|| {
+            MayastorEnvironment::global_or_default()
+                .log_components()
+                .iter()
+                .cloned()
+                .collect()
+        })
+    }
+}
+
+/// Check whether the I/Os of the given nexus are traced.
+#[inline]
+pub fn nexus_io_tracing(name: &str) -> bool {
+    NEXUS_IO_TRACING.load(Ordering::Relaxed) && TRACED_NEXUSES.read().contains(name)
+}
+
+fn set_nexus_io_tracing(names: BTreeSet<String>) {
+    let mut traced = TRACED_NEXUSES.write();
+    *traced = names;
+    NEXUS_IO_TRACING.store(!traced.is_empty(), Ordering::Relaxed);
+}
+
+fn spdk_level_name(level: spdk_log_level) -> &'static str {
+    match level {
+        SPDK_LOG_DISABLED => "disabled",
+        SPDK_LOG_ERROR => "error",
+        SPDK_LOG_WARN => "warn",
+        SPDK_LOG_NOTICE => "notice",
+        SPDK_LOG_INFO => "info",
+        SPDK_LOG_DEBUG => "debug",
+        _ => "unknown",
+    }
+}
+
+fn parse_spdk_level(level: &str) -> Result<spdk_log_level, LogControlError> {
+    match level.to_lowercase().as_str() {
+        "disabled" => Ok(SPDK_LOG_DISABLED),
+        "error" => Ok(SPDK_LOG_ERROR),
+        "warn" | "warning" => Ok(SPDK_LOG_WARN),
+        "notice" => Ok(SPDK_LOG_NOTICE),
+        "info" => Ok(SPDK_LOG_INFO),
+        "debug" => Ok(SPDK_LOG_DEBUG),
+        _ => Err(LogControlError::InvalidSpdkLevel {
+            level: level.to_string(),
+        }),
+    }
+}
+
+fn set_spdk_level(level: spdk_log_level) {
+    unsafe {
+        spdk_log_set_level(level);
+        spdk_log_set_print_level(level);
+    }
+}
+
+/// Check that the given SPDK log flag is registered, `all` standing for
+/// every registered flag.
+fn check_spdk_flag(flag: &str) -> Result<(), LogControlError> {
+    if flag == "all" {
+        return Ok(());
+    }
+    let mut next = unsafe { spdk_log_get_first_flag() };
+    while !next.is_null() {
+        let name = unsafe { CStr::from_ptr((*next).name) };
+        if name.to_bytes() == flag.as_bytes() {
+            return Ok(());
+        }
+        next = unsafe { spdk_log_get_next_flag(next) };
+    }
+    Err(LogControlError::UnknownSpdkFlag {
+        flag: flag.to_string(),
+    })
+}
+
+fn set_spdk_flag(flag: &str, enable: bool) -> Result<(), LogControlError> {
+    let unknown = || LogControlError::UnknownSpdkFlag {
+        flag: flag.to_string(),
+    };
+    let cflag = CString::new(flag).map_err(|_| unknown())?;
+    let rc = unsafe {
+        if enable {
+            spdk_log_set_flag(cflag.as_ptr())
+        } else {
+            spdk_log_clear_flag(cflag.as_ptr())
+        }
+    };
+    if rc != 0 {
+        return Err(unknown());
+    }
+    Ok(())
+}
+
+/// Find the name of the nexus with the given name or uuid.
+fn nexus_name(name: &str) -> Result<String, LogControlError> {
+    nexus_iter()
+        .find(|n| n.name == name || n.uuid().to_string() == name)
+        .map(|n| n.name.clone())
+        .ok_or_else(|| LogControlError::NexusNotFound {
+            name: name.to_string(),
+        })
+}
+
+/// Get the current log settings.
+pub fn log_get() -> LogSettings {
+    let mut state = STATE.lock();
+    LogSettings {
+        filter: logger::filter(),
+        spdk_level: spdk_level_name(unsafe { spdk_log_get_level() }).to_string(),
+        spdk_flags: state.spdk_flags().iter().cloned().collect(),
+        nexus_io_tracing: TRACED_NEXUSES.read().iter().cloned().collect(),
+        revert_at: state.revert_at.map(|t| t.to_rfc3339()),
+    }
+}
+
+/// Change the log settings, which are reverted once the change times out.
+pub fn log_set(change: LogChange) -> Result<LogSettings, LogControlError> {
+    // Validate the whole change before applying any of it.
+    let spdk_level = change
+        .spdk_level
+        .as_deref()
+        .map(parse_spdk_level)
+        .transpose()?;
+    let trace_nexus = change
+        .trace_nexus
+        .iter()
+        .map(|n| nexus_name(n))
+        .collect::<Result<Vec<_>, _>>()?;
+    let untrace_nexus = change
+        .untrace_nexus
+        .iter()
+        .map(|n| nexus_name(n))
+        .collect::<Result<Vec<_>, _>>()?;
+    for flag in change.enable_flags.iter().chain(&change.disable_flags) {
+        check_spdk_flag(flag)?;
+    }
+    if let Some(filter) = &change.filter {
+        logger::check_filter(filter).context(Filter)?;
+    }
+
+    let generation = {
+        let mut state = STATE.lock();
+        let baseline = state.baseline.is_none().then(|| Baseline {
+            spdk_level: unsafe { spdk_log_get_level() },
+            spdk_flags: state.spdk_flags().clone(),
+            nexus_io_tracing: TRACED_NEXUSES.read().clone(),
+        });
+
+        // Only the replacement of a valid filter can still fail, so it is
+        // done before anything else is changed.
+        if let Some(filter) = &change.filter {
+            logger::set_filter(filter).context(Filter)?;
+        }
+        if baseline.is_some() {
+            state.baseline = baseline;
+        }
+        if let Some(level) = spdk_level {
+            set_spdk_level(level);
+        }
+        for flag in &change.enable_flags {
+            set_spdk_flag(flag, true)?;
+            state.spdk_flags().insert(flag.clone());
+        }
+        for flag in &change.disable_flags {
+            set_spdk_flag(flag, false)?;
+            state.spdk_flags().remove(flag);
+        }
+        if !trace_nexus.is_empty() || !untrace_nexus.is_empty() {
+            let mut traced = TRACED_NEXUSES.read().clone();
+            traced.extend(trace_nexus);
+            untrace_nexus.iter().for_each(|n| {
+                traced.remove(n);
+            });
+            set_nexus_io_tracing(traced);
+        }
+
+        let timeout = change
+            .timeout_secs
+            .map_or(DEFAULT_REVERT_TIMEOUT, Duration::from_secs);
+        state.generation += 1;
+        state.revert_at = (!timeout.is_zero()).then(|| {
+            Utc::now()
+                + chrono::Duration::from_std(timeout).unwrap_or_else(|_| chrono::Duration::zero())
+        });
+        info!("Log settings changed: {change:?}");
+
+        (!timeout.is_zero()).then_some((state.generation, timeout))
+    };
+
+    if let Some((generation, timeout)) = generation {
+        Reactors::master().send_future(async move {
+            mayastor_sleep(timeout).await.ok();
+            if STATE.lock().generation == generation {
+                if let Err(error) = log_reset() {
+                    error!("Failed to revert the log settings: {error}");
+                }
+            }
+        });
+    }
+
+    Ok(log_get())
+}
+
+/// Restore the log settings in effect before the first change.
+pub fn log_reset() -> Result<LogSettings, LogControlError> {
+    {
+        let mut state = STATE.lock();
+        if state.baseline.is_some() {
+            // The baseline is kept until the filter is restored, which is
+            // the only step which may fail, so that the reset can be retried.
+            logger::reset_filter().context(Filter)?;
+            let baseline = state.baseline.take().expect("baseline must be set");
+            set_spdk_level(baseline.spdk_level);
+
+            let flags = state.spdk_flags().clone();
+            for flag in flags.difference(&baseline.spdk_flags) {
+                set_spdk_flag(flag, false)?;
+            }
+            for flag in baseline.spdk_flags.difference(&flags) {
+                set_spdk_flag(flag, true)?;
+            }
+            state.spdk_flags = Some(baseline.spdk_flags);
+
+            set_nexus_io_tracing(baseline.nexus_io_tracing);
+            info!("Log settings reverted");
+        }
+        state.generation += 1;
+        state.revert_at = None;
+    }
+
+    Ok(log_get())
+}
+
+type LogFuture<R> = Pin<Box<dyn Future<Output = Result<R, LogControlError>>>>;
+
+/// Register the log json-rpc methods.
+pub(crate) fn register_jsonrpc_methods() {
+    jsonrpc_register(
+        "log_get",
+        |_args: serde_json::Value| -> LogFuture<LogSettings> {
+            async move { Ok(log_get()) }.boxed_local()
+        },
+    );
+
+    jsonrpc_register("log_set", |args: LogChange| -> LogFuture<LogSettings> {
+        async move { log_set(args) }.boxed_local()
+    });
+
+    jsonrpc_register(
+        "log_reset",
+        |_args: serde_json::Value| -> LogFuture<LogSettings> {
+            async move { log_reset() }.boxed_local()
+        },
+    );
+}
@@ -1,5 +1,7 @@
 use ansi_term::{Colour, Style};
 use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use snafu::{ResultExt, Snafu};
 use std::{
     collections::HashMap,
     ffi::CStr,
@@ -12,22 +14,23 @@ use std::{
 };
 
 use crate::{
-    constants::{EVENTING_TARGET, SERVICE_NAME},
+    constants::{EVENTING_TARGET, SERVICE_NAME, TELEMETRY_TARGET},
     core::spawn,
+    telemetry,
 };
 use event_publisher::event_handler::EventHandle;
 use tracing::field::{Field, Visit};
 use tracing_core::{event::Event, Level, Metadata};
 use tracing_log::{LogTracer, NormalizeEvent};
 use tracing_subscriber::{
-    filter::{filter_fn, Targets},
+    filter::{filter_fn, EnvFilter, ParseError, Targets},
     fmt::{
         format::{FmtSpan, FormatEvent, FormatFields, Writer},
         FmtContext, FormattedFields,
     },
     layer::{Layer, SubscriberExt},
     registry::LookupSpan,
-    Registry,
+    reload, Registry,
 };
 
 /// Returns hostname.
@@ -40,6 +43,79 @@ fn get_hostname() -> String {
 
 static HOSTNAME_PREFIX: OnceCell<String> = OnceCell::new();
 
+/// Tracing filter, which can be replaced at runtime.
+type BoxedFilter = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Runtime state of the tracing filter.
+struct FilterState {
+    /// Handle to replace the filter.
+    handle: reload::Handle<BoxedFilter, Registry>,
+    /// Level the logger was initialised with.
+    level: String,
+    /// Directives of the current filter.
+    directives: String,
+}
+
+static FILTER: OnceCell<Mutex<FilterState>> = OnceCell::new();
+
+/// Errors of the runtime changes of the tracing filter.
+#[derive(Debug, Snafu)]
+#[snafu(context(suffix(false)))]
+pub enum FilterError {
+    #[snafu(display("The logger has not been initialised"))]
+    NotInitialised {},
+    #[snafu(display("Invalid tracing filter '{filter}'"))]
+    InvalidFilter { source: ParseError, filter: String },
+    #[snafu(display("Failed to replace the tracing filter"))]
+    ReloadFilter { source: reload::Error },
+}
+
+/// Directives of the current tracing filter.
+pub fn filter() -> Option<String> {
+    FILTER.get().map(|f| f.lock().directives.clone())
+}
+
+/// Check that the tracing filter can be replaced with the given directives.
+pub fn check_filter(directives: &str) -> Result<(), FilterError> {
+    EnvFilter::try_new(directives).context(InvalidFilter {
+        filter: directives.to_string(),
+    })?;
+    FILTER.get().ok_or(FilterError::NotInitialised {})?;
+    Ok(())
+}
+
+/// Replace the tracing filter with the given directives, e.g.
+/// `info,io_engine::bdev::nexus=trace`.
+pub fn set_filter(directives: &str) -> Result<(), FilterError> {
+    let filter = EnvFilter::try_new(directives).context(InvalidFilter {
+        filter: directives.to_string(),
+    })?;
+    let mut state = FILTER.get().ok_or(FilterError::NotInitialised {})?.lock();
+    state
+        .handle
+        .reload(Box::new(filter) as BoxedFilter)
+        .context(ReloadFilter)?;
+    state.directives = directives.to_string();
+    info!("Tracing filter set to '{directives}'");
+    Ok(())
+}
+
+/// Restore the tracing filter the logger was initialised with.
+pub fn reset_filter() -> Result<(), FilterError> {
+    let mut state = FILTER.get().ok_or(FilterError::NotInitialised {})?.lock();
+    let filter: BoxedFilter = Box::new(tracing_filter::rust_log_filter_ext(&state.level));
+    state.handle.reload(filter).context(ReloadFilter)?;
+    state.directives = initial_directives(&state.level);
+    info!("Tracing filter reset to '{}'", state.directives);
+    Ok(())
+}
+
+/// Directives of the filter created for the given level, which RUST_LOG
+/// overrides.
+fn initial_directives(level: &str) -> String {
+    std::env::var("RUST_LOG").unwrap_or_else(|_| level.to_string())
+}
+
 use spdk_rs::libspdk::{spdk_log_get_print_level, spdk_log_level};
 
 fn from_spdk_level(level: spdk_log_level) -> log::Level {
@@ -508,7 +584,14 @@ impl LogFormat {
 ///
 /// We might want to suppress certain messages, as some of them are redundant,
 /// in particular, the NOTICE messages as such, they are mapped to debug.
-pub fn init_ex(level: &str, format: LogFormat, events_url: Option<url::Url>) {
+///
+/// The spans are exported to the OTLP collector at `otlp_endpoint`, if any.
+pub fn init_ex(
+    level: &str,
+    format: LogFormat,
+    events_url: Option<url::Url>,
+    otlp_endpoint: Option<url::Url>,
+) {
     // Set up a "logger" that simply translates any "log" messages it receives
     // to trace events. This is for our custom spdk log messages, but also
     // for any other third party crates still using the logging facade.
@@ -521,11 +604,19 @@ pub fn init_ex(level: &str, format: LogFormat, events_url: Option<url::Url>) {
         .event_format(format)
         .with_filter(filter_fn(|metadata| {
             // Exclude spans or events that have the target
-            // "mbus-events-target".
-            metadata.target() != EVENTING_TARGET
+            // "mbus-events-target" or "io-engine-telemetry".
+            metadata.target() != EVENTING_TARGET && metadata.target() != TELEMETRY_TARGET
         }));
 
-    let filter = tracing_filter::rust_log_filter_ext(level);
+    let filter: BoxedFilter = Box::new(tracing_filter::rust_log_filter_ext(level));
+    let (filter, handle) = reload::Layer::new(filter);
+    FILTER
+        .set(Mutex::new(FilterState {
+            handle,
+            level: level.to_string(),
+            directives: initial_directives(level),
+        }))
+        .ok();
 
     // Get the optional eventing layer.
     let events_layer = match events_url {
@@ -539,14 +630,18 @@ pub fn init_ex(level: &str, format: LogFormat, events_url: Option<url::Url>) {
         None => None,
     };
 
+    // Get the optional OpenTelemetry layer.
+    let otlp_layer = otlp_endpoint.and_then(|endpoint| telemetry::layer(&endpoint));
+
     let subscriber = Registry::default()
         .with(filter)
         .with(Some(builder))
-        .with(events_layer);
+        .with(events_layer)
+        .with(otlp_layer);
 
     tracing::subscriber::set_global_default(subscriber).expect("failed to set default subscriber");
 }
 
 pub fn init(level: &str) {
-    init_ex(level, Default::default(), None)
+    init_ex(level, Default::default(), None, None)
 }
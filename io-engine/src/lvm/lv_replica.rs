@@ -458,7 +458,8 @@ impl LogicalVolume {
                     })?);
                 Self::bdev_share_nvmf(bdev, Some(props)).await?;
             }
-            Protocol::Off => {
+            // Replicas are never exposed over ublk or vhost.
+            Protocol::Off | Protocol::Ublk | Protocol::Vhost => {
                 Self::bdev_unshare(bdev).await?;
             }
         }
@@ -627,7 +628,7 @@ impl LogicalVolume {
                     .map_err(|source| Error::BdevShare { source })?;
                 bdev.share_uri().ok_or(Error::BdevShareUri {})
             }
-            Some(Protocol::Off) | None => bdev
+            Some(Protocol::Off | Protocol::Ublk | Protocol::Vhost) | None => bdev
                 .share_nvmf(props)
                 .await
                 .map_err(|source| Error::BdevShare { source }),
@@ -642,7 +643,7 @@ impl LogicalVolume {
                     .await
                     .map_err(|source| Error::BdevUnshare { source })?;
             }
-            Some(Protocol::Off) | None => {}
+            Some(Protocol::Off | Protocol::Ublk | Protocol::Vhost) | None => {}
         }
         Ok(bdev.share_uri())
     }
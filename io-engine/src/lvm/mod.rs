@@ -60,6 +60,7 @@ use crate::{
         FindReplicaArgs, FindSnapshotArgs, IReplicaFactory, ListCloneArgs, ListReplicaArgs,
         ListSnapshotArgs, ReplicaBdevStats, ReplicaOps, SnapshotOps,
     },
+    subsys::registration::NodeSummary,
 };
 use futures::channel::oneshot::Receiver;
 
@@ -115,6 +116,7 @@ impl PoolOps for VolumeGroup {
         &self,
         args: ReplicaArgs,
     ) -> Result<Box<dyn ReplicaOps>, crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         let replica = LogicalVolume::create(
             self.uuid(),
             &args.name,
@@ -129,11 +131,13 @@ impl PoolOps for VolumeGroup {
     }
 
     async fn destroy(self: Box<Self>) -> Result<(), crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         (*self).destroy().await?;
         Ok(())
     }
 
     async fn export(mut self: Box<Self>) -> Result<(), crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         VolumeGroup::export(&mut self).await?;
         Ok(())
     }
@@ -185,10 +189,12 @@ impl ReplicaOps for LogicalVolume {
     }
 
     async fn resize(&mut self, size: u64) -> Result<(), crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         self.resize(size).await.map_err(Into::into)
     }
 
     async fn destroy(self: Box<Self>) -> Result<(), crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         (*self).destroy().await.map_err(Into::into)
     }
 
@@ -329,11 +335,13 @@ pub struct PoolLvmFactory {}
 #[async_trait::async_trait(?Send)]
 impl IPoolFactory for PoolLvmFactory {
     async fn create(&self, args: PoolArgs) -> Result<Box<dyn PoolOps>, crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         let pool = VolumeGroup::create(args).await?;
         Ok(Box::new(pool))
     }
 
     async fn import(&self, args: PoolArgs) -> Result<Box<dyn PoolOps>, crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         let pool = VolumeGroup::import(args).await?;
         Ok(Box::new(pool))
     }
@@ -170,6 +170,8 @@ impl Protocol {
         match self {
             Protocol::Off => "off",
             Protocol::Nvmf => "nvmf",
+            Protocol::Ublk => "ublk",
+            Protocol::Vhost => "vhost",
         }
     }
     fn from_value(value: &str) -> Self {
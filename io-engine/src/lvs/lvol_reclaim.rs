@@ -0,0 +1,312 @@
+//! Space reclamation of thin provisioned replicas.
+//!
+//! With blob store cluster unmap enabled, the blob store releases the clusters
+//! of a thin provisioned lvol which an unmap covers entirely back to the pool,
+//! which shows in the allocated size of the lvol. This is all the release the
+//! unmaps of a nexus or of a host get: the unmaps of a nexus reach its
+//! replicas at the offset of the nexus data partition, which doesn't line up
+//! with the clusters of the replicas, so that the clusters they unmap in parts
+//! stay allocated, and the I/O path of the lvols is left as it is to track
+//! them. Instead, a trim of a replica scans its allocated clusters and unmaps
+//! the all-zero ones, releasing them, and the bytes released by the trims are
+//! accounted as reclaimed.
+//!
+//! A trim runs with the pool of the replica locked and the replica claimed,
+//! so that the replica can neither be shared nor added to a nexus, and no
+//! write can race with the scan of a cluster.
+
+use std::{collections::HashMap, convert::TryFrom, ops::Range, pin::Pin, sync::Arc};
+
+use futures::{Future, FutureExt};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use snafu::Snafu;
+use spdk_rs::libspdk::{spdk_blob, spdk_blob_get_next_allocated_io_unit};
+
+use super::{lock_pool, Lvol, LvsError, LvsLvol};
+use crate::{
+    core::{
+        Bdev, CoreError, LogicalVolume, MayastorEnvironment, Share, UntypedBdev, UntypedBdevHandle,
+        UntypedDescriptorGuard,
+    },
+    jsonrpc::{jsonrpc_register, Code, RpcErrorCode},
+};
+
+/// Reclaim errors.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)), context(suffix(false)))]
+#[allow(missing_docs)]
+pub enum ReclaimError {
+    #[snafu(display("Replica {uuid} not found"))]
+    ReclaimReplicaNotFound { uuid: String },
+    #[snafu(display("Replica {uuid} is not thin provisioned"))]
+    NotThinProvisioned { uuid: String },
+    #[snafu(display("Replica {uuid} is in use and cannot be trimmed"))]
+    ReplicaInUse { uuid: String },
+    #[snafu(display("Blob store cluster unmap is disabled"))]
+    ClusterUnmapDisabled {},
+    #[snafu(display("Failed to trim replica {uuid}: {source}"))]
+    TrimIo { uuid: String, source: CoreError },
+    #[snafu(display("Failed to lock the pool of replica {uuid}: {source}"))]
+    TrimPoolLock { uuid: String, source: LvsError },
+}
+
+impl RpcErrorCode for ReclaimError {
+    fn rpc_error_code(&self) -> Code {
+        match self {
+            Self::ReclaimReplicaNotFound { .. } => Code::NotFound,
+            Self::NotThinProvisioned { .. } | Self::ReplicaInUse { .. } => Code::InvalidParams,
+            Self::ClusterUnmapDisabled {} | Self::TrimIo { .. } | Self::TrimPoolLock { .. } => {
+                Code::InternalError
+            }
+        }
+    }
+}
+
+/// Clusters of the given blob which are allocated to the blob itself and
+/// which the given blocks cover entirely.
+fn allocated_clusters(blob: *mut spdk_blob, cluster_blocks: u64, blocks: &Range<u64>) -> Vec<u64> {
+    let mut clusters = Vec::new();
+    let end = blocks.end / cluster_blocks;
+    let mut cluster = (blocks.start + cluster_blocks - 1) / cluster_blocks;
+    while cluster < end {
+        let next = unsafe { spdk_blob_get_next_allocated_io_unit(blob, cluster * cluster_blocks) };
+        if next == u64::MAX {
+            break;
+        }
+        cluster = next / cluster_blocks;
+        if cluster < end {
+            clusters.push(cluster);
+        }
+        cluster += 1;
+    }
+    clusters
+}
+
+/// Bytes released back to the pool by trims, keyed by the replica uuid.
+static RECLAIMED: Lazy<Mutex<HashMap<String, u64>>> = Lazy::new(Default::default);
+
+/// Result of a replica trim.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct TrimStats {
+    /// Uuid of the trimmed replica.
+    pub replica_uuid: String,
+    /// Number of allocated clusters scanned.
+    pub clusters_scanned: u64,
+    /// Number of allocated all-zero clusters which were unmapped.
+    pub zero_clusters: u64,
+    /// Bytes released back to the pool by this trim.
+    pub reclaimed_bytes: u64,
+    /// Total bytes released back to the pool for this replica.
+    pub total_reclaimed_bytes: u64,
+}
+
+impl Lvol {
+    /// Get the total bytes released back to the pool by trims of this
+    /// replica.
+    pub fn reclaimed_bytes(&self) -> u64 {
+        RECLAIMED
+            .lock()
+            .get(&self.uuid())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Reset the reclaimed bytes of this replica.
+    pub(crate) fn reset_reclaimed_bytes(&self) {
+        RECLAIMED.lock().remove(&self.uuid());
+    }
+
+    /// Forget the reclaimed bytes of a destroyed replica.
+    pub(super) fn forget_reclaimed_bytes(uuid: &str) {
+        RECLAIMED.lock().remove(uuid);
+    }
+
+    /// Scan the allocated clusters of the replica for all-zero ones and unmap
+    /// them, releasing them back to the pool.
+    /// The replica must be thin provisioned and must not be in use, and the
+    /// caller must hold the lock of its pool. The replica is claimed for the
+    /// whole trim, otherwise a write racing with the scan of a cluster could
+    /// be lost.
+    pub async fn trim(&self) -> Result<TrimStats, ReclaimError> {
+        let uuid = self.uuid();
+        if !self.is_thin() {
+            return Err(ReclaimError::NotThinProvisioned { uuid });
+        }
+        if !MayastorEnvironment::global_or_default().bs_cluster_unmap() {
+            return Err(ReclaimError::ClusterUnmapDisabled {});
+        }
+        let bdev = self.as_bdev();
+        let desc = Bdev::open(&bdev, true).map_err(|source| ReclaimError::TrimIo {
+            uuid: uuid.clone(),
+            source,
+        })?;
+        // Sharing the replica or adding it to a nexus claims it as well, so
+        // that both fail until the claim is released.
+        if self.shared().is_some() || !desc.claim() {
+            return Err(ReclaimError::ReplicaInUse { uuid });
+        }
+        let desc = Arc::new(desc);
+        let result = self.trim_claimed(desc.clone()).await;
+        desc.unclaim();
+        result
+    }
+
+    /// Trim the replica through the given descriptor, which claims it.
+    async fn trim_claimed(
+        &self,
+        desc: Arc<UntypedDescriptorGuard>,
+    ) -> Result<TrimStats, ReclaimError> {
+        let uuid = self.uuid();
+        let bdev = self.as_bdev();
+        let allocated_before = self.allocated();
+
+        let hdl = UntypedBdevHandle::try_from(desc).map_err(|source| ReclaimError::TrimIo {
+            uuid: uuid.clone(),
+            source,
+        })?;
+        let cluster_size = self.usage().cluster_size;
+        let mut buf = hdl
+            .dma_malloc(cluster_size)
+            .map_err(|_| ReclaimError::TrimIo {
+                uuid: uuid.clone(),
+                source: CoreError::DmaAllocationFailed { size: cluster_size },
+            })?;
+
+        let mut stats = TrimStats {
+            replica_uuid: uuid.clone(),
+            ..Default::default()
+        };
+        // Clusters which were never allocated, or were released already,
+        // have nothing to release. A trailing partial cluster cannot be
+        // released.
+        let clusters = allocated_clusters(
+            self.blob_checked(),
+            cluster_size / bdev.block_len() as u64,
+            &(0..bdev.num_blocks()),
+        );
+        for cluster in clusters {
+            let offset = cluster * cluster_size;
+            hdl.read_at(offset, &mut buf)
+                .await
+                .map_err(|source| ReclaimError::TrimIo {
+                    uuid: uuid.clone(),
+                    source,
+                })?;
+            stats.clusters_scanned += 1;
+            if buf.as_slice().iter().any(|b| *b != 0) {
+                continue;
+            }
+            hdl.unmap_at(offset, cluster_size)
+                .await
+                .map_err(|source| ReclaimError::TrimIo {
+                    uuid: uuid.clone(),
+                    source,
+                })?;
+            stats.zero_clusters += 1;
+        }
+
+        // The replica is claimed, so that the clusters released are the ones
+        // the trim unmapped.
+        stats.reclaimed_bytes = allocated_before.saturating_sub(self.allocated());
+        stats.total_reclaimed_bytes = {
+            let mut reclaimed = RECLAIMED.lock();
+            let total = reclaimed.entry(uuid).or_default();
+            *total += stats.reclaimed_bytes;
+            *total
+        };
+        info!("{self:?}: trimmed: {stats:?}");
+        Ok(stats)
+    }
+}
+
+/// Arguments of the `replica_trim` json-rpc method.
+#[derive(Debug, Deserialize)]
+struct TrimArgs {
+    /// Uuid of the replica.
+    replica_uuid: String,
+}
+
+/// Arguments of the `replica_reclaim_stats` json-rpc method.
+#[derive(Debug, Deserialize)]
+struct ReclaimStatsArgs {
+    /// Uuid of the replica, all replicas if missing.
+    replica_uuid: Option<String>,
+}
+
+/// Reclaimed space of a replica, as returned by the json-rpc methods.
+#[derive(Debug, Serialize)]
+struct ReplicaReclaimStats {
+    replica_uuid: String,
+    thin: bool,
+    allocated_bytes: u64,
+    reclaimed_bytes: u64,
+}
+
+impl From<&Lvol> for ReplicaReclaimStats {
+    fn from(lvol: &Lvol) -> Self {
+        Self {
+            replica_uuid: lvol.uuid(),
+            thin: lvol.is_thin(),
+            reclaimed_bytes: lvol.reclaimed_bytes(),
+            allocated_bytes: lvol.allocated(),
+        }
+    }
+}
+
+type ReclaimFuture<R> = Pin<Box<dyn Future<Output = Result<R, ReclaimError>>>>;
+
+/// Find the replica with the given uuid.
+fn lookup_replica(uuid: &str) -> Result<Lvol, ReclaimError> {
+    UntypedBdev::lookup_by_uuid_str(uuid)
+        .and_then(|bdev| Lvol::try_from(bdev).ok())
+        .filter(|lvol| !lvol.is_snapshot())
+        .ok_or_else(|| ReclaimError::ReclaimReplicaNotFound {
+            uuid: uuid.to_string(),
+        })
+}
+
+/// Register the space reclamation json-rpc methods.
+pub(crate) fn register_jsonrpc_methods() {
+    jsonrpc_register(
+        "replica_trim",
+        |args: TrimArgs| -> ReclaimFuture<TrimStats> {
+            async move {
+                info!("{:?}", args);
+                let replica = lookup_replica(&args.replica_uuid)?;
+                let _lock_guard = lock_pool(&replica.pool_name()).await.map_err(|source| {
+                    ReclaimError::TrimPoolLock {
+                        uuid: args.replica_uuid.clone(),
+                        source,
+                    }
+                })?;
+                // The replica may have gone while waiting for the lock.
+                lookup_replica(&args.replica_uuid)?.trim().await
+            }
+            .boxed_local()
+        },
+    );
+
+    jsonrpc_register(
+        "replica_reclaim_stats",
+        |args: Option<ReclaimStatsArgs>| -> ReclaimFuture<Vec<ReplicaReclaimStats>> {
+            async move {
+                if let Some(uuid) = args.and_then(|a| a.replica_uuid) {
+                    let replica = lookup_replica(&uuid)?;
+                    return Ok(vec![ReplicaReclaimStats::from(&replica)]);
+                }
+                let Some(bdev) = UntypedBdev::bdev_first() else {
+                    return Ok(Vec::new());
+                };
+                Ok(bdev
+                    .into_iter()
+                    .filter(|b| b.driver() == "lvol")
+                    .filter_map(|b| Lvol::try_from(b).ok())
+                    .filter(|l| !l.is_snapshot())
+                    .map(|l| ReplicaReclaimStats::from(&l))
+                    .collect())
+            }
+            .boxed_local()
+        },
+    );
+}
@@ -142,8 +142,8 @@ pub trait LvolSnapshotOps {
     /// happened, it is possible that, last clone can be deleted, but linked
     /// snapshot marked as discarded still present in the system. As part of
     /// pool import, do the garbage collection to clean the discarded snapshots
-    /// leftout in the system.
-    async fn destroy_pending_discarded_snapshot();
+    /// leftout in the given pool, which the caller must have locked.
+    async fn destroy_pending_discarded_snapshot(pool_name: &str);
 
     /// If self is clone or a snapshot whose parent is clone, then do ancestor
     /// calculation for all snapshot linked to clone.
@@ -744,17 +744,27 @@ impl LvolSnapshotOps for Lvol {
     /// happened, it is possible that, last clone can be deleted, but linked
     /// snapshot marked as discarded still present in the system. As part of
     /// pool import, do the garbage collection to clean the discarded snapshots
-    /// leftout in the system.
-    async fn destroy_pending_discarded_snapshot() {
+    /// leftout in the given pool, which the caller must have locked.
+    async fn destroy_pending_discarded_snapshot(pool_name: &str) {
         let Some(bdev) = UntypedBdev::bdev_first() else {
             return; /* No devices available */
         };
         let snap_list = bdev
             .into_iter()
             .filter(|b| b.driver() == "lvol")
-            .map(|b| Lvol::try_from(b).unwrap())
+            .filter_map(|b| {
+                let name = b.name().to_string();
+                match Lvol::try_from(b) {
+                    Ok(lvol) => Some(lvol),
+                    Err(error) => {
+                        warn!("Skipping lvol {name} while collecting discarded snapshots: {error}");
+                        None
+                    }
+                }
+            })
             .filter(|b| {
-                b.is_snapshot()
+                b.pool_name() == pool_name
+                    && b.is_snapshot()
                     && b.is_discarded_snapshot()
                     && b.list_clones_by_snapshot_uuid().is_empty()
             })
@@ -0,0 +1,579 @@
+//! Metadata health check and repair of lvol stores.
+//!
+//! The check walks every blob of the blob store and cross-checks it with the
+//! lvols of the store and with the io-engine specific xattrs of the lvols:
+//! blobs which don't belong to any lvol are orphaned, allocated clusters which
+//! don't belong to any blob are leaked, and snapshot or clone xattrs which
+//! don't match the blob hierarchy are dangling.
+//! A pool which is not imported is checked offline, by importing it for the
+//! duration of the check only, without sharing its replicas.
+//! Blobs are only deleted when explicitly requested, as an orphaned blob may
+//! hold the only copy of data which is worth recovering. The pool is locked
+//! for the duration of the check, as for the creation and destruction of its
+//! replicas, so that a blob which is being created or destroyed is not
+//! mistaken for an orphaned one.
+//! The check can also release the all-zero clusters of the thin provisioned
+//! replicas, but it doesn't move allocated clusters, so it doesn't defragment
+//! the pool.
+
+use std::{
+    collections::{HashMap, HashSet},
+    os::raw::{c_char, c_void},
+    pin::Pin,
+};
+
+use futures::{channel::oneshot, Future, FutureExt};
+use snafu::Snafu;
+use spdk_rs::libspdk::{
+    spdk_blob, spdk_blob_calc_used_clusters, spdk_blob_get_id, spdk_blob_get_parent_snapshot,
+    spdk_blob_remove_xattr, spdk_blob_sync_md, spdk_bs_delete_blob, spdk_bs_free_cluster_count,
+    spdk_bs_iter_first, spdk_bs_iter_next, spdk_bs_total_data_cluster_count,
+};
+use strum::IntoEnumIterator;
+
+use super::{
+    BsError, Lvol, LvolSnapshotOps, Lvs, LvsError, LvsLvol, NexusSnapshotPolicy, ReclaimError,
+    SnapshotPolicy,
+};
+use crate::{
+    core::{
+        CloneXattrs, LogicalVolume, ProtectedSubsystems, ReplicaXattrs, ResourceLockManager,
+        SnapshotXattrs,
+    },
+    ffihelper::{cb_arg, done_cb, IntoCString},
+    jsonrpc::{jsonrpc_register, Code, RpcErrorCode},
+    pool_backend::{PoolArgs, PoolBackend},
+};
+
+/// Blob id which doesn't refer to any blob.
+const BLOBID_INVALID: u64 = u64::MAX;
+
+/// Pool check errors.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)), context(suffix(false)))]
+#[allow(missing_docs)]
+pub enum PoolCheckError {
+    #[snafu(display("Pool {name} is not imported and no disk was given"))]
+    CheckPoolNotFound { name: String },
+    #[snafu(display("Failed to lock pool {name}"))]
+    CheckPoolLock { name: String },
+    #[snafu(display("Failed to import pool {name} for an offline check: {source}"))]
+    CheckPoolImport { name: String, source: LvsError },
+    #[snafu(display("Failed to export pool {name} after an offline check: {source}"))]
+    CheckPoolExport { name: String, source: LvsError },
+}
+
+impl RpcErrorCode for PoolCheckError {
+    fn rpc_error_code(&self) -> Code {
+        match self {
+            Self::CheckPoolNotFound { .. } => Code::NotFound,
+            Self::CheckPoolLock { .. }
+            | Self::CheckPoolImport { .. }
+            | Self::CheckPoolExport { .. } => Code::InternalError,
+        }
+    }
+}
+
+/// Options of a pool check.
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct PoolCheckOptions {
+    /// Repair the issues which can be repaired, except for the orphaned
+    /// blobs which are only reported.
+    pub repair: bool,
+    /// Delete the orphaned blobs, and the discarded snapshots which have no
+    /// clones left.
+    pub delete_orphans: bool,
+    /// Release the all-zero clusters of the thin provisioned replicas which
+    /// are not in use. The allocated clusters are not moved, so this doesn't
+    /// defragment the pool.
+    pub release_zeroes: bool,
+}
+
+/// Kind of an issue found by a pool check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolIssueKind {
+    /// The metadata of a blob is incomplete or can't be parsed.
+    InvalidMetadata,
+    /// A blob which doesn't belong to any lvol, or a discarded snapshot which
+    /// has no clones left.
+    OrphanedBlob,
+    /// Allocated clusters which don't belong to any blob.
+    LeakedClusters,
+    /// Snapshot xattrs on a blob which is not a snapshot.
+    DanglingSnapshotXattr,
+    /// Clone xattrs which don't refer to an ancestor snapshot of the blob.
+    DanglingCloneXattr,
+}
+
+/// An issue found by a pool check.
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolIssue {
+    /// Kind of the issue.
+    pub kind: PoolIssueKind,
+    /// Id of the blob the issue pertains to, if any.
+    pub blob_id: Option<u64>,
+    /// Name of the lvol the issue pertains to, if any.
+    pub lvol: Option<String>,
+    /// Description of the issue.
+    pub description: String,
+    /// Whether the issue has been repaired.
+    pub repaired: bool,
+}
+
+/// Result of a pool check.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct PoolCheckReport {
+    /// Name of the pool.
+    pub pool_name: String,
+    /// Uuid of the pool.
+    pub pool_uuid: String,
+    /// Whether the pool was imported for the check.
+    pub offline: bool,
+    /// Number of blobs checked.
+    pub blobs_checked: u64,
+    /// Number of metadata pages.
+    pub md_pages: u64,
+    /// Number of metadata pages in use.
+    pub md_used_pages: u64,
+    /// Number of data clusters.
+    pub data_clusters: u64,
+    /// Number of free data clusters.
+    pub free_clusters: u64,
+    /// Number of clusters allocated to blobs.
+    pub blob_clusters: u64,
+    /// Number of allocated clusters which don't belong to any blob.
+    pub leaked_clusters: u64,
+    /// Bytes of all-zero clusters released back to the pool.
+    pub released_bytes: u64,
+    /// Issues found.
+    pub issues: Vec<PoolIssue>,
+}
+
+impl PoolCheckReport {
+    /// Whether no issues are left unrepaired.
+    pub fn is_healthy(&self) -> bool {
+        self.issues.iter().all(|i| i.repaired)
+    }
+}
+
+/// A blob found while walking the blob store.
+#[derive(Debug)]
+struct BlobEntry {
+    /// Id of the blob.
+    id: u64,
+    /// Id of the parent snapshot of the blob, if any.
+    parent: Option<u64>,
+    /// Number of clusters allocated to the blob.
+    used_clusters: u64,
+}
+
+/// Blob store iteration callback.
+extern "C" fn blob_iter_cb(arg: *mut c_void, blob: *mut spdk_blob, errno: i32) {
+    let s = unsafe { Box::from_raw(arg as *mut oneshot::Sender<(*mut spdk_blob, i32)>) };
+    s.send((blob, errno)).ok();
+}
+
+impl Lvs {
+    /// Check the metadata health of this pool, and repair the issues found if
+    /// requested.
+    pub async fn check(&self, opts: PoolCheckOptions) -> PoolCheckReport {
+        let bs = self.blob_store();
+        let mut report = PoolCheckReport {
+            pool_name: self.name().to_string(),
+            pool_uuid: self.uuid(),
+            md_pages: self.md_pages(),
+            md_used_pages: self.md_used_pages(),
+            data_clusters: unsafe { spdk_bs_total_data_cluster_count(bs) },
+            free_clusters: unsafe { spdk_bs_free_cluster_count(bs) },
+            ..Default::default()
+        };
+
+        let blobs = self.scan_blobs().await;
+        let parents = blobs
+            .iter()
+            .filter_map(|b| b.parent.map(|p| (b.id, p)))
+            .collect::<HashMap<_, _>>();
+        report.blobs_checked = blobs.len() as u64;
+        report.blob_clusters = blobs.iter().map(|b| b.used_clusters).sum();
+
+        // blobs of the lvols which failed to register a bdev are still owned
+        let owned = self.lvol_blob_ids();
+
+        let lvols = self
+            .lvols()
+            .map(|l| l.collect::<Vec<_>>())
+            .unwrap_or_default();
+        for lvol in lvols {
+            self.check_lvol(&lvol, &parents, opts, &mut report).await;
+        }
+        let in_use = parents.values().copied().collect::<HashSet<_>>();
+        for blob in blobs.iter().filter(|b| !owned.contains(&b.id)) {
+            let mut issue = PoolIssue {
+                kind: PoolIssueKind::OrphanedBlob,
+                blob_id: Some(blob.id),
+                lvol: None,
+                description: format!(
+                    "blob {:#x} with {} clusters doesn't belong to any lvol",
+                    blob.id, blob.used_clusters
+                ),
+                repaired: false,
+            };
+            if opts.delete_orphans && !in_use.contains(&blob.id) {
+                match self.delete_blob(blob.id).await {
+                    Ok(_) => issue.repaired = true,
+                    Err(error) => warn!("{self:?}: failed to delete orphaned blob: {error}"),
+                }
+            }
+            report.issues.push(issue);
+        }
+
+        let used_clusters = report.data_clusters.saturating_sub(report.free_clusters);
+        report.leaked_clusters = used_clusters.saturating_sub(report.blob_clusters);
+        if report.leaked_clusters > 0 {
+            report.issues.push(PoolIssue {
+                kind: PoolIssueKind::LeakedClusters,
+                blob_id: None,
+                lvol: None,
+                description: format!(
+                    "{} allocated clusters don't belong to any blob and can't \
+                    be released online",
+                    report.leaked_clusters
+                ),
+                repaired: false,
+            });
+        }
+
+        if opts.release_zeroes {
+            report.released_bytes = self.release_zero_clusters().await;
+        }
+
+        info!(
+            "{self:?}: checked {} blobs, {} issues found, {} repaired",
+            report.blobs_checked,
+            report.issues.len(),
+            report.issues.iter().filter(|i| i.repaired).count()
+        );
+        report
+    }
+
+    /// Check the metadata of the given lvol.
+    async fn check_lvol(
+        &self,
+        lvol: &Lvol,
+        parents: &HashMap<u64, u64>,
+        opts: PoolCheckOptions,
+        report: &mut PoolCheckReport,
+    ) {
+        let blob = lvol.blob_checked();
+        let blob_id = unsafe { spdk_blob_get_id(blob) };
+        let mut add_issue = |kind, description: String, repaired| {
+            report.issues.push(PoolIssue {
+                kind,
+                blob_id: Some(blob_id),
+                lvol: Some(lvol.name()),
+                description,
+                repaired,
+            })
+        };
+
+        let uuid = Lvol::get_blob_xattr(blob, "uuid");
+        if uuid
+            .as_deref()
+            .and_then(|u| uuid::Uuid::parse_str(u).ok())
+            .is_none()
+        {
+            add_issue(
+                PoolIssueKind::InvalidMetadata,
+                format!("invalid uuid xattr {uuid:?}"),
+                false,
+            );
+        }
+
+        if lvol.is_snapshot() {
+            for attr in SnapshotXattrs::iter()
+                .filter(|a| !matches!(a, SnapshotXattrs::DiscardedSnapshot))
+                .filter(|a| Lvol::get_blob_xattr(blob, a.name()).is_none())
+            {
+                add_issue(
+                    PoolIssueKind::InvalidMetadata,
+                    format!("snapshot is missing the {} xattr", attr.name()),
+                    false,
+                );
+            }
+            if lvol.is_discarded_snapshot() && lvol.list_clones_by_snapshot_uuid().is_empty() {
+                let repaired = opts.delete_orphans && {
+                    lvol.reset_snapshot_tree_usage_cache(false);
+                    lvol.clone().destroy().await.is_ok()
+                };
+                add_issue(
+                    PoolIssueKind::OrphanedBlob,
+                    "discarded snapshot has no clones left".to_string(),
+                    repaired,
+                );
+            }
+            return;
+        }
+
+        if let Some(policy) = Lvol::get_blob_xattr(blob, ReplicaXattrs::SnapshotPolicy.name()) {
+            if serde_json::from_str::<SnapshotPolicy>(&policy).is_err() {
+                let repaired = opts.repair && SnapshotPolicy::set(lvol, None).await.is_ok();
+                add_issue(
+                    PoolIssueKind::InvalidMetadata,
+                    format!("invalid snapshot policy {policy:?}"),
+                    repaired,
+                );
+            }
+        }
+        let attr = ReplicaXattrs::NexusSnapshotPolicy.name();
+        if let Some(policy) = Lvol::get_blob_xattr(blob, attr) {
+            if serde_json::from_str::<NexusSnapshotPolicy>(&policy).is_err() {
+                let repaired = opts.repair && lvol.remove_blob_attrs(&[attr]).await.is_ok();
+                add_issue(
+                    PoolIssueKind::InvalidMetadata,
+                    format!("invalid nexus snapshot policy {policy:?}"),
+                    repaired,
+                );
+            }
+        }
+
+        if !lvol.is_read_only() {
+            let dangling = [
+                SnapshotXattrs::TxId,
+                SnapshotXattrs::EntityId,
+                SnapshotXattrs::ParentId,
+                SnapshotXattrs::DiscardedSnapshot,
+            ]
+            .iter()
+            .map(|a| a.name())
+            .filter(|a| Lvol::get_blob_xattr(blob, a).is_some())
+            .collect::<Vec<_>>();
+            if !dangling.is_empty() {
+                let repaired = opts.repair && lvol.remove_blob_attrs(&dangling).await.is_ok();
+                add_issue(
+                    PoolIssueKind::DanglingSnapshotXattr,
+                    format!("writable lvol has snapshot xattrs {dangling:?}"),
+                    repaired,
+                );
+            }
+        }
+
+        let Some(source_uuid) = lvol.snapshot_uuid() else {
+            return;
+        };
+        let source_blob = self
+            .lvols()
+            .and_then(|mut l| l.find(|s| s.uuid() == source_uuid && s.is_snapshot()))
+            .map(|s| unsafe { spdk_blob_get_id(s.blob_checked()) });
+        let mut ancestors = std::iter::successors(parents.get(&blob_id), |id| parents.get(id));
+        if source_blob.map_or(false, |id| ancestors.any(|a| *a == id)) {
+            return;
+        }
+        // a clone which has been decoupled from its snapshot is a plain
+        // replica, any other mismatch needs a manual fix
+        let decoupled = !parents.contains_key(&blob_id);
+        let repaired = opts.repair
+            && decoupled
+            && lvol
+                .remove_blob_attrs(&[
+                    CloneXattrs::SourceUuid.name(),
+                    CloneXattrs::CloneCreateTime.name(),
+                ])
+                .await
+                .is_ok();
+        add_issue(
+            PoolIssueKind::DanglingCloneXattr,
+            format!("source snapshot {source_uuid} is not an ancestor of the clone"),
+            repaired,
+        );
+    }
+
+    /// Release the all-zero clusters of the thin provisioned replicas which
+    /// are not in use, returning the number of bytes released.
+    async fn release_zero_clusters(&self) -> u64 {
+        let mut released = 0;
+        let lvols = self
+            .lvols()
+            .map(|l| l.collect::<Vec<_>>())
+            .unwrap_or_default();
+        for lvol in lvols.iter().filter(|l| l.is_thin() && !l.is_snapshot()) {
+            match lvol.trim().await {
+                Ok(stats) => released += stats.reclaimed_bytes,
+                Err(ReclaimError::ReplicaInUse { .. }) => {}
+                Err(error) => {
+                    warn!("{lvol:?}: failed to release the zeroed clusters: {error}");
+                    if matches!(error, ReclaimError::ClusterUnmapDisabled {}) {
+                        break;
+                    }
+                }
+            }
+        }
+        released
+    }
+
+    /// Walk the blob store and collect all of its blobs.
+    async fn scan_blobs(&self) -> Vec<BlobEntry> {
+        let bs = self.blob_store();
+        let mut blobs = Vec::new();
+
+        let (s, r) = oneshot::channel::<(*mut spdk_blob, i32)>();
+        unsafe { spdk_bs_iter_first(bs, Some(blob_iter_cb), cb_arg(s)) };
+        let mut next = r.await.ok();
+
+        // the iteration ends with ENOENT
+        while let Some((blob, 0)) = next {
+            if blob.is_null() {
+                break;
+            }
+            let id = unsafe { spdk_blob_get_id(blob) };
+            let parent = unsafe { spdk_blob_get_parent_snapshot(bs, id) };
+            blobs.push(BlobEntry {
+                id,
+                parent: (parent != BLOBID_INVALID).then_some(parent),
+                used_clusters: unsafe { spdk_blob_calc_used_clusters(blob) },
+            });
+
+            let (s, r) = oneshot::channel::<(*mut spdk_blob, i32)>();
+            unsafe { spdk_bs_iter_next(bs, blob, Some(blob_iter_cb), cb_arg(s)) };
+            next = r.await.ok();
+        }
+        blobs
+    }
+
+    /// Ids of the blobs which belong to the lvols of this store, including
+    /// the super blob of the store.
+    fn lvol_blob_ids(&self) -> HashSet<u64> {
+        let lvs = unsafe { &*self.as_inner_ptr() };
+        let mut ids = HashSet::from([lvs.super_blob_id]);
+        let mut lvol = lvs.lvols.tqh_first;
+        while !lvol.is_null() {
+            unsafe {
+                ids.insert((*lvol).blob_id);
+                lvol = (*lvol).link.tqe_next;
+            }
+        }
+        ids
+    }
+
+    /// Delete the blob with the given id.
+    async fn delete_blob(&self, id: u64) -> Result<(), LvsError> {
+        let (s, r) = oneshot::channel::<i32>();
+        unsafe { spdk_bs_delete_blob(self.blob_store(), id, Some(done_cb), cb_arg(s)) };
+        match r.await.expect("delete blob callback disappeared") {
+            0 => Ok(()),
+            errno => Err(LvsError::Invalid {
+                source: BsError::from_i32(errno),
+                msg: format!("failed to delete blob {id:#x}"),
+            }),
+        }
+    }
+}
+
+impl Lvol {
+    /// Remove the given blob attributes and sync the metadata.
+    pub(super) async fn remove_blob_attrs(&self, attrs: &[&str]) -> Result<(), LvsError> {
+        for attr in attrs {
+            let name = attr.into_cstring();
+            let r = unsafe {
+                spdk_blob_remove_xattr(self.blob_checked(), name.as_ptr() as *const c_char)
+            };
+            // a missing attribute is already removed
+            if r != 0 && r != -libc::ENOENT {
+                return Err(LvsError::SetProperty {
+                    source: BsError::from_i32(r),
+                    prop: attr.to_string(),
+                    name: self.name(),
+                });
+            }
+        }
+
+        let (s, r) = oneshot::channel::<i32>();
+        unsafe { spdk_blob_sync_md(self.blob_checked(), Some(done_cb), cb_arg(s)) };
+        match r.await.expect("sync metadata callback disappeared") {
+            0 => Ok(()),
+            errno => Err(LvsError::SyncProperty {
+                source: BsError::from_i32(errno),
+                name: self.name(),
+            }),
+        }
+    }
+}
+
+/// Check the pool with the given name.
+/// A pool which is not imported is imported from the given disks for the
+/// duration of the check only.
+pub async fn check_pool(
+    name: &str,
+    disks: Option<Vec<String>>,
+    opts: PoolCheckOptions,
+) -> Result<PoolCheckReport, PoolCheckError> {
+    let pool_subsystem =
+        ResourceLockManager::get_instance().get_subsystem(ProtectedSubsystems::POOL);
+    let Some(_lock_guard) = pool_subsystem.lock_resource(name, None, false).await else {
+        return Err(PoolCheckError::CheckPoolLock {
+            name: name.to_string(),
+        });
+    };
+
+    if let Some(lvs) = Lvs::lookup(name) {
+        return Ok(lvs.check(opts).await);
+    }
+
+    let Some(disks) = disks.filter(|d| !d.is_empty()) else {
+        return Err(PoolCheckError::CheckPoolNotFound {
+            name: name.to_string(),
+        });
+    };
+    let lvs = Lvs::import_offline(PoolArgs {
+        name: name.to_string(),
+        disks,
+        uuid: None,
+        cluster_size: None,
+        md_args: None,
+        backend: PoolBackend::Lvs,
+    })
+    .await
+    .map_err(|source| PoolCheckError::CheckPoolImport {
+        name: name.to_string(),
+        source,
+    })?;
+
+    let mut report = lvs.check(opts).await;
+    report.offline = true;
+
+    lvs.export()
+        .await
+        .map_err(|source| PoolCheckError::CheckPoolExport {
+            name: name.to_string(),
+            source,
+        })?;
+    Ok(report)
+}
+
+/// Arguments of the `pool_check` json-rpc method.
+#[derive(Debug, Deserialize)]
+struct PoolCheckArgs {
+    /// Name of the pool.
+    name: String,
+    /// Disks of the pool, required to check a pool which is not imported.
+    #[serde(default)]
+    disks: Option<Vec<String>>,
+    /// Check options.
+    #[serde(flatten)]
+    opts: PoolCheckOptions,
+}
+
+/// Register the pool check json-rpc methods.
+pub(crate) fn register_jsonrpc_methods() {
+    jsonrpc_register(
+        "pool_check",
+        |args: PoolCheckArgs| -> Pin<Box<dyn Future<Output = Result<PoolCheckReport, PoolCheckError>>>> {
+            async move {
+                info!("{:?}", args);
+                check_pool(&args.name, args.disks, args.opts).await
+            }
+            .boxed_local()
+        },
+    );
+}
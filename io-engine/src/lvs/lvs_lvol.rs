@@ -821,6 +821,7 @@ impl LvsLvol for Lvol {
         let _ = Pin::new(&mut self).unshare().await;
 
         let name = self.name();
+        let uuid = self.uuid();
         let ptpl = self.ptpl();
 
         let (s, r) = pair::<i32>();
@@ -842,6 +843,8 @@ impl LvsLvol for Lvol {
             );
         }
 
+        Lvol::forget_reclaimed_bytes(&uuid);
+
         info!("destroyed lvol {name}");
         event.generate();
         Ok(name)
@@ -227,6 +227,12 @@ impl Lvs {
 
     /// imports a pool based on its name and base bdev name
     pub async fn import(name: &str, bdev: &str) -> Result<Lvs, LvsError> {
+        Self::import_ext(name, bdev, true).await
+    }
+
+    /// imports a pool based on its name and base bdev name, sharing its
+    /// shared lvols if requested
+    async fn import_ext(name: &str, bdev: &str, share: bool) -> Result<Lvs, LvsError> {
         let (sender, receiver) = pair::<ErrnoResult<Lvs>>();
 
         debug!("Trying to import lvs '{}' from '{}'...", name, bdev);
@@ -294,7 +300,9 @@ impl Lvs {
                 reason: ImportErrorReason::NameMismatch { name: pool_name },
             })
         } else {
-            lvs.share_all().await;
+            if share {
+                lvs.share_all().await;
+            }
             info!("{:?}: existing lvs imported successfully", lvs);
             Ok(lvs)
         }
@@ -303,6 +311,20 @@ impl Lvs {
     /// imports a pool based on its name, uuid and base bdev name
     #[tracing::instrument(level = "debug", err)]
     pub async fn import_from_args(args: PoolArgs) -> Result<Lvs, LvsError> {
+        Self::import_from_args_ext(args, true).await
+    }
+
+    /// imports a pool based on its name, uuid and base bdev name for an
+    /// offline operation, e.g. a check: its lvols are not shared, and its
+    /// pending discarded snapshots are left alone
+    #[tracing::instrument(level = "debug", err)]
+    pub(crate) async fn import_offline(args: PoolArgs) -> Result<Lvs, LvsError> {
+        Self::import_from_args_ext(args, false).await
+    }
+
+    /// imports a pool based on its name, uuid and base bdev name, serving it
+    /// if requested
+    async fn import_from_args_ext(args: PoolArgs, serve: bool) -> Result<Lvs, LvsError> {
         let disk = Self::parse_disk(args.disks.clone())?;
 
         let parsed = uri::parse(&disk).map_err(|e| LvsError::InvalidBdev {
@@ -347,10 +369,12 @@ impl Lvs {
             Ok(name) => Ok(name),
         }?;
 
-        let pool = Self::import(&args.name, &bdev).await?;
-        // Try to destroy the pending snapshots without catching
-        // the error.
-        Lvol::destroy_pending_discarded_snapshot().await;
+        let pool = Self::import_ext(&args.name, &bdev, serve).await?;
+        if serve {
+            // Try to destroy the pending snapshots without catching
+            // the error.
+            Lvol::destroy_pending_discarded_snapshot(&args.name).await;
+        }
         // if the uuid is provided for the import request check
         // for the pool uuid to make sure it is the correct one
         if let Some(uuid) = args.uuid {
@@ -13,28 +13,63 @@ use crate::{
         ReplicaOps, SnapshotOps,
     },
 };
+pub use lvol_reclaim::{ReclaimError, TrimStats};
 pub use lvol_snapshot::LvolSnapshotIter;
 pub use lvs_bdev::LvsBdev;
+pub use lvs_check::{
+    check_pool, PoolCheckError, PoolCheckOptions, PoolCheckReport, PoolIssue, PoolIssueKind,
+};
 pub use lvs_error::{BsError, ImportErrorReason, LvsError};
 pub use lvs_iter::{LvsBdevIter, LvsIter};
 pub use lvs_lvol::{Lvol, LvsLvol, PropName, PropValue};
 pub use lvs_store::Lvs;
+pub use snapshot_policy::{
+    evaluate_snapshot_policies, snapshot_policy_loop, NexusSnapshotPolicy, SnapshotPolicy,
+    SnapshotPolicyError, SnapshotPolicyOutcome,
+};
 use std::{convert::TryFrom, pin::Pin};
 
 mod lvol_iter;
+mod lvol_reclaim;
 mod lvol_snapshot;
 mod lvs_bdev;
+mod lvs_check;
 mod lvs_error;
 mod lvs_iter;
 pub mod lvs_lvol;
 mod lvs_store;
+mod snapshot_policy;
 
 use crate::{
-    core::{BdevStater, BdevStats, CoreError, UntypedBdev},
+    core::{
+        BdevStater, BdevStats, CoreError, ProtectedSubsystems, ResourceLockGuard,
+        ResourceLockManager, UntypedBdev,
+    },
     replica_backend::{FindSnapshotArgs, ReplicaBdevStats},
+    subsys::registration::NodeSummary,
 };
 pub use lvol_snapshot::{LvolResult, LvolSnapshotDescriptor, LvolSnapshotOps};
 
+/// Register the lvs json-rpc methods.
+pub(crate) fn register_jsonrpc_methods() {
+    snapshot_policy::register_jsonrpc_methods();
+    lvol_reclaim::register_jsonrpc_methods();
+    lvs_check::register_jsonrpc_methods();
+}
+
+/// Lock the pool with the given name, as done for the creation and
+/// destruction of its replicas, failing right away if it is already locked,
+/// e.g. while the pool is being checked.
+pub(crate) async fn lock_pool(name: &str) -> Result<ResourceLockGuard<'static>, LvsError> {
+    ResourceLockManager::get_instance()
+        .get_subsystem(ProtectedSubsystems::POOL)
+        .lock_resource(name, None, true)
+        .await
+        .ok_or_else(|| LvsError::ResourceLockFailed {
+            msg: format!("pool {name} is locked by another operation"),
+        })
+}
+
 #[async_trait::async_trait(?Send)]
 impl ReplicaOps for Lvol {
     fn shared(&self) -> Option<Protocol> {
@@ -72,6 +107,7 @@ impl ReplicaOps for Lvol {
     }
 
     async fn resize(&mut self, size: u64) -> Result<(), crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         self.resize_replica(size).await.map_err(Into::into)
     }
 
@@ -81,6 +117,7 @@ impl ReplicaOps for Lvol {
     }
 
     async fn destroy(self: Box<Self>) -> Result<(), crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         self.destroy_replica().await?;
         Ok(())
     }
@@ -89,6 +126,7 @@ impl ReplicaOps for Lvol {
         &mut self,
         params: SnapshotParams,
     ) -> Result<Box<dyn SnapshotOps>, Error> {
+        let _change = NodeSummary::change();
         let snapshot = LvolSnapshotOps::create_snapshot(self, params).await?;
         Ok(Box::new(snapshot))
     }
@@ -104,22 +142,30 @@ impl BdevStater for Lvol {
 
     async fn stats(&self) -> Result<ReplicaBdevStats, CoreError> {
         let stats = self.as_bdev().stats().await?;
-        Ok(ReplicaBdevStats::new(stats, self.entity_id()))
+        Ok(ReplicaBdevStats::new(
+            stats,
+            self.entity_id(),
+            self.reclaimed_bytes(),
+        ))
     }
 
     async fn reset_stats(&self) -> Result<(), CoreError> {
-        self.as_bdev().reset_stats().await
+        self.as_bdev().reset_stats().await?;
+        self.reset_reclaimed_bytes();
+        Ok(())
     }
 }
 
 #[async_trait::async_trait(?Send)]
 impl SnapshotOps for Lvol {
     async fn destroy_snapshot(self: Box<Self>) -> Result<(), Error> {
+        let _change = NodeSummary::change();
         LvolSnapshotOps::destroy_snapshot(*self).await?;
         Ok(())
     }
 
     async fn create_clone(&self, params: CloneParams) -> Result<Box<dyn ReplicaOps>, Error> {
+        let _change = NodeSummary::change();
         let clone = LvolSnapshotOps::create_clone(self, params).await?;
         Ok(Box::new(clone))
     }
@@ -138,21 +184,25 @@ impl PoolOps for Lvs {
         &self,
         args: ReplicaArgs,
     ) -> Result<Box<dyn ReplicaOps>, crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         let lvol = self.create_lvol_with_opts(args).await?;
         Ok(Box::new(lvol))
     }
 
     async fn destroy(self: Box<Self>) -> Result<(), crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         (*self).destroy().await?;
         Ok(())
     }
 
     async fn export(self: Box<Self>) -> Result<(), crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         (*self).export().await?;
         Ok(())
     }
 
     async fn grow(&self) -> Result<(), crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         (*self).grow().await?;
         Ok(())
     }
@@ -229,11 +279,13 @@ pub struct PoolLvsFactory {}
 #[async_trait::async_trait(?Send)]
 impl IPoolFactory for PoolLvsFactory {
     async fn create(&self, args: PoolArgs) -> Result<Box<dyn PoolOps>, crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         let lvs = Lvs::create_or_import(args).await?;
         Ok(Box::new(lvs))
     }
 
     async fn import(&self, args: PoolArgs) -> Result<Box<dyn PoolOps>, crate::pool_backend::Error> {
+        let _change = NodeSummary::change();
         let lvs = Lvs::import_from_args(args).await?;
         Ok(Box::new(lvs))
     }
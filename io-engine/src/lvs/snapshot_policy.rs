@@ -0,0 +1,701 @@
+//! Snapshot schedules and retention policies of replicas and nexuses.
+//!
+//! A replica policy is stored as json in the replica's blob xattrs, next to
+//! the snapshot xattrs, and is evaluated periodically by the io-engine itself.
+//! This way scheduled snapshots are taken and pruned even while the control
+//! plane is down.
+//!
+//! A nexus policy takes consistent snapshots of all the replicas of the
+//! nexus. It is stored in the xattrs of the replicas of the nexus which are
+//! local to the nexus, so that it outlives the nexus itself, and the
+//! retention rules are applied to these replicas as if they were their own.
+//! The snapshots which a nexus policy takes of the replicas of the nexus
+//! which live on other nodes are not pruned by it: they are kept until they
+//! are destroyed by the control plane, or by a policy set on the replica
+//! itself, which may have retention rules without a schedule. This way
+//! clearing a policy always stops its pruning.
+//!
+//! Snapshots are taken and pruned with the pool of the replica locked, as
+//! for the gRPC snapshot calls, and no policy is evaluated while the node is
+//! draining or handing over.
+
+use std::{collections::HashSet, convert::TryFrom, pin::Pin, time::Duration};
+
+use chrono::{DateTime, Utc};
+use futures::{Future, FutureExt};
+use snafu::Snafu;
+
+use super::{lock_pool, Lvol, LvolSnapshotOps, Lvs, LvsError, LvsLvol};
+use crate::{
+    bdev::nexus::{self, nexus_iter, nexus_lookup_uuid_mut, NexusReplicaSnapshotDescriptor},
+    core::{
+        drain::is_draining, handover::is_handing_over, ISnapshotDescriptor, LogicalVolume,
+        ProtectedSubsystems, Reactor, ReplicaXattrs, ResourceLockManager, SnapshotParams,
+        UntypedBdev,
+    },
+    jsonrpc::{jsonrpc_register, Code, RpcErrorCode},
+};
+
+/// How often the snapshot policies of all replicas are evaluated.
+const POLICY_EVAL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Transaction id prefix of the snapshots taken by the scheduler.
+/// Retention only ever considers snapshots carrying this prefix, so snapshots
+/// taken by the control plane are never pruned by the io-engine.
+const SCHED_TXN_PREFIX: &str = "io-engine-sched-";
+
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Snapshot policy errors.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)), context(suffix(false)))]
+#[allow(missing_docs)]
+pub enum SnapshotPolicyError {
+    #[snafu(display("Replica {uuid} not found"))]
+    PolicyReplicaNotFound { uuid: String },
+    #[snafu(display("Lvol {uuid} is not a replica"))]
+    PolicyNotAReplica { uuid: String },
+    #[snafu(display("Invalid snapshot policy for replica {uuid}: {reason}"))]
+    InvalidPolicy { uuid: String, reason: String },
+    #[snafu(display("Failed to update the snapshot policy of replica {uuid}: {source}"))]
+    PolicyUpdate { uuid: String, source: LvsError },
+    #[snafu(display("Nexus {uuid} not found"))]
+    PolicyNexusNotFound { uuid: String },
+    #[snafu(display("Nexus {uuid} has no local replica to keep its snapshot policy"))]
+    PolicyNoLocalReplica { uuid: String },
+    #[snafu(display(
+        "Failed to update the snapshot policy of nexus {uuid} on replica {replica}: {source}"
+    ))]
+    NexusPolicyUpdate {
+        uuid: String,
+        replica: String,
+        source: LvsError,
+    },
+}
+
+impl RpcErrorCode for SnapshotPolicyError {
+    fn rpc_error_code(&self) -> Code {
+        match self {
+            Self::PolicyReplicaNotFound { .. } | Self::PolicyNexusNotFound { .. } => Code::NotFound,
+            Self::PolicyNotAReplica { .. }
+            | Self::InvalidPolicy { .. }
+            | Self::PolicyNoLocalReplica { .. } => Code::InvalidParams,
+            Self::PolicyUpdate { .. } | Self::NexusPolicyUpdate { .. } => Code::InternalError,
+        }
+    }
+}
+
+/// Snapshot schedule and retention policy of a replica.
+/// Retention is only applied when at least one of the retention rules is
+/// set, in which case a scheduled snapshot is kept if any of the rules
+/// retains it.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SnapshotPolicy {
+    /// Take a snapshot every `interval_secs` seconds.
+    pub interval_secs: Option<u64>,
+    /// Keep the `keep_last` most recent scheduled snapshots.
+    pub keep_last: Option<u32>,
+    /// Keep the newest scheduled snapshot of each of the last `keep_daily`
+    /// days.
+    pub keep_daily: Option<u32>,
+}
+
+/// Outcome of a single policy evaluation of a replica.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SnapshotPolicyOutcome {
+    /// Uuid of the snapshot taken, if one was due.
+    pub created: Option<String>,
+    /// Uuids of the scheduled snapshots which are no longer retained.
+    pub destroyed: Vec<String>,
+}
+
+impl SnapshotPolicy {
+    /// Check the policy can be applied.
+    fn validate(&self) -> Result<(), String> {
+        if self.interval_secs == Some(0) {
+            return Err("interval_secs must be greater than 0".into());
+        }
+        if self.keep_last == Some(0) {
+            return Err("keep_last must be greater than 0".into());
+        }
+        if self.keep_daily == Some(0) {
+            return Err("keep_daily must be greater than 0".into());
+        }
+        Ok(())
+    }
+
+    /// Get the policy of the given replica, if any.
+    pub fn get(replica: &Lvol) -> Option<Self> {
+        let value =
+            Lvol::get_blob_xattr(replica.blob_checked(), ReplicaXattrs::SnapshotPolicy.name())?;
+        if value.is_empty() {
+            return None;
+        }
+        match serde_json::from_str(&value) {
+            Ok(policy) => Some(policy),
+            Err(error) => {
+                warn!("{replica:?}: ignoring invalid snapshot policy '{value}': {error}");
+                None
+            }
+        }
+    }
+
+    /// Store the given policy in the replica's metadata, or remove it if
+    /// `None`.
+    pub async fn set(replica: &Lvol, policy: Option<&Self>) -> Result<(), SnapshotPolicyError> {
+        let result = match policy {
+            Some(policy) => {
+                policy
+                    .validate()
+                    .map_err(|reason| SnapshotPolicyError::InvalidPolicy {
+                        uuid: replica.uuid(),
+                        reason,
+                    })?;
+                let value = serde_json::to_string(policy).unwrap_or_default();
+                replica
+                    .set_blob_attr(ReplicaXattrs::SnapshotPolicy.name(), value, true)
+                    .await
+            }
+            None => {
+                replica
+                    .remove_blob_attrs(&[ReplicaXattrs::SnapshotPolicy.name()])
+                    .await
+            }
+        };
+        result.map_err(|source| SnapshotPolicyError::PolicyUpdate {
+            uuid: replica.uuid(),
+            source,
+        })
+    }
+
+    /// Get the retention rules of this policy, if any.
+    fn retention(&self) -> Option<Self> {
+        (self.keep_last.is_some() || self.keep_daily.is_some()).then(|| Self {
+            interval_secs: None,
+            keep_last: self.keep_last,
+            keep_daily: self.keep_daily,
+        })
+    }
+
+    /// Take a snapshot of the replica if one is due and destroy the scheduled
+    /// snapshots which are no longer retained, as of `now`.
+    /// The pool of the replica must be locked by the caller.
+    pub async fn evaluate(
+        &self,
+        replica: &Lvol,
+        now: DateTime<Utc>,
+    ) -> Result<SnapshotPolicyOutcome, LvsError> {
+        let mut outcome = SnapshotPolicyOutcome::default();
+        let mut scheduled = Self::scheduled_snapshots(replica);
+
+        let due = match (self.interval_secs, scheduled.first()) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(interval), Some((last, _))) => now.timestamp() - last >= interval as i64,
+        };
+        if due {
+            let snapshot = Self::create_snapshot(replica, now).await?;
+            outcome.created = Some(snapshot.uuid());
+            scheduled.insert(0, (now.timestamp(), snapshot));
+        }
+
+        let expired = self.expired(
+            &scheduled.iter().map(|(ts, _)| *ts).collect::<Vec<_>>(),
+            now,
+        );
+        for (idx, (_, snapshot)) in scheduled.into_iter().enumerate() {
+            if !expired.contains(&idx) {
+                continue;
+            }
+            let uuid = snapshot.uuid();
+            match snapshot.destroy_snapshot().await {
+                Ok(()) => outcome.destroyed.push(uuid),
+                Err(error) => {
+                    error!("{replica:?}: failed to destroy expired snapshot {uuid}: {error}")
+                }
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    /// Get the indexes of the snapshots which are not retained by this
+    /// policy, given the snapshot timestamps sorted newest first.
+    fn expired(&self, timestamps: &[i64], now: DateTime<Utc>) -> HashSet<usize> {
+        if self.keep_last.is_none() && self.keep_daily.is_none() {
+            return HashSet::new();
+        }
+        let mut retained = HashSet::new();
+        if let Some(keep_last) = self.keep_last {
+            retained.extend(0..(keep_last as usize).min(timestamps.len()));
+        }
+        if let Some(keep_daily) = self.keep_daily {
+            let today = now.timestamp().div_euclid(SECS_PER_DAY);
+            let mut days = HashSet::new();
+            for (idx, ts) in timestamps.iter().enumerate() {
+                let day = ts.div_euclid(SECS_PER_DAY);
+                if today - day < keep_daily as i64 && days.insert(day) {
+                    retained.insert(idx);
+                }
+            }
+        }
+        (0..timestamps.len())
+            .filter(|idx| !retained.contains(idx))
+            .collect()
+    }
+
+    /// List the snapshots of the replica taken by the scheduler and not yet
+    /// discarded, along with their timestamps, sorted newest first.
+    fn scheduled_snapshots(replica: &Lvol) -> Vec<(i64, Lvol)> {
+        let mut snapshots = replica
+            .list_lvol_snapshot_by_source_uuid()
+            .into_iter()
+            .filter(|s| !s.snapshot_lvol().is_discarded_snapshot())
+            .filter_map(|s| {
+                let ts = parse_sched_txn_id(&s.snap_param.txn_id()?)?;
+                Some((ts, s.snapshot_lvol().clone()))
+            })
+            .collect::<Vec<_>>();
+        snapshots.sort_by(|a, b| b.0.cmp(&a.0));
+        snapshots
+    }
+
+    /// Take a scheduled snapshot of the replica.
+    async fn create_snapshot(replica: &Lvol, now: DateTime<Utc>) -> Result<Lvol, LvsError> {
+        let ts = now.timestamp();
+        let entity_id = replica.entity_id().unwrap_or_else(|| replica.uuid());
+        let params = SnapshotParams::prepare(
+            &format!("{}-sched-{ts}", replica.name()),
+            &entity_id,
+            &sched_txn_id(ts),
+            &uuid::Uuid::new_v4().to_string(),
+            replica.uuid(),
+        )
+        .ok_or_else(|| LvsError::SnapshotConfigFailed {
+            name: replica.name(),
+            msg: "failed to prepare scheduled snapshot".to_string(),
+        })?;
+        let snapshot = replica.create_snapshot(params).await?;
+        info!("{replica:?}: created scheduled snapshot {snapshot:?}");
+        Ok(snapshot)
+    }
+}
+
+/// Get the transaction id of a scheduled snapshot taken at the given time,
+/// e.g. `io-engine-sched-1704888000`.
+fn sched_txn_id(ts: i64) -> String {
+    format!("{SCHED_TXN_PREFIX}{ts}")
+}
+
+/// Parse the transaction id of a scheduled snapshot into its timestamp.
+fn parse_sched_txn_id(txn_id: &str) -> Option<i64> {
+    txn_id.strip_prefix(SCHED_TXN_PREFIX)?.parse::<i64>().ok()
+}
+
+/// Snapshot policy of a nexus, as kept in the xattrs of its local replicas.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NexusSnapshotPolicy {
+    /// Uuid of the nexus.
+    pub nexus_uuid: String,
+    /// The schedule and the retention rules of the snapshots.
+    pub policy: SnapshotPolicy,
+}
+
+impl NexusSnapshotPolicy {
+    /// Get the nexus policy stored in the given replica's metadata, if any.
+    pub fn get_replica(replica: &Lvol) -> Option<Self> {
+        let value = Lvol::get_blob_xattr(
+            replica.blob_checked(),
+            ReplicaXattrs::NexusSnapshotPolicy.name(),
+        )?;
+        if value.is_empty() {
+            return None;
+        }
+        match serde_json::from_str(&value) {
+            Ok(policy) => Some(policy),
+            Err(error) => {
+                warn!("{replica:?}: ignoring invalid nexus snapshot policy '{value}': {error}");
+                None
+            }
+        }
+    }
+
+    /// Get the replicas of the nexus with the given uuid which are local to
+    /// this node.
+    fn local_replicas(uuid: &str) -> Result<Vec<Lvol>, SnapshotPolicyError> {
+        let nexus = nexus_lookup_uuid_mut(uuid).ok_or_else(|| {
+            SnapshotPolicyError::PolicyNexusNotFound {
+                uuid: uuid.to_string(),
+            }
+        })?;
+        Ok(nexus
+            .children()
+            .iter()
+            .filter_map(|c| c.get_uuid())
+            .filter_map(|replica_uuid| lookup_replica(&replica_uuid).ok())
+            .collect())
+    }
+
+    /// Get the policy of the nexus with the given uuid, if any.
+    pub fn get(uuid: &str) -> Option<Self> {
+        Self::local_replicas(uuid)
+            .ok()?
+            .iter()
+            .filter_map(Self::get_replica)
+            .find(|p| p.nexus_uuid == uuid)
+    }
+
+    /// Set the policy of the nexus with the given uuid, or remove it if
+    /// `None`, in the metadata of all its local replicas.
+    pub async fn set(
+        uuid: &str,
+        policy: Option<&SnapshotPolicy>,
+    ) -> Result<(), SnapshotPolicyError> {
+        let replicas = Self::local_replicas(uuid)?;
+        let value = match policy {
+            Some(policy) => {
+                policy
+                    .validate()
+                    .map_err(|reason| SnapshotPolicyError::InvalidPolicy {
+                        uuid: uuid.to_string(),
+                        reason,
+                    })?;
+                if replicas.is_empty() {
+                    return Err(SnapshotPolicyError::PolicyNoLocalReplica {
+                        uuid: uuid.to_string(),
+                    });
+                }
+                let policy = Self {
+                    nexus_uuid: uuid.to_string(),
+                    policy: policy.clone(),
+                };
+                Some(serde_json::to_string(&policy).unwrap_or_default())
+            }
+            None => None,
+        };
+
+        for replica in replicas {
+            let _lock_guard = lock_pool(&replica.pool_name()).await.map_err(|source| {
+                SnapshotPolicyError::NexusPolicyUpdate {
+                    uuid: uuid.to_string(),
+                    replica: replica.uuid(),
+                    source,
+                }
+            })?;
+            let attr = ReplicaXattrs::NexusSnapshotPolicy.name();
+            match &value {
+                Some(value) => replica.set_blob_attr(attr, value.clone(), true).await,
+                None => replica.remove_blob_attrs(&[attr]).await,
+            }
+            .map_err(|source| SnapshotPolicyError::NexusPolicyUpdate {
+                uuid: uuid.to_string(),
+                replica: replica.uuid(),
+                source,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Get when the last scheduled snapshot of the nexus was taken, as the
+    /// newest scheduled snapshot of its local replicas.
+    pub fn last_snapshot(uuid: &str) -> Option<i64> {
+        Self::local_replicas(uuid)
+            .ok()?
+            .iter()
+            .filter_map(|r| {
+                SnapshotPolicy::scheduled_snapshots(r)
+                    .first()
+                    .map(|(ts, _)| *ts)
+            })
+            .max()
+    }
+
+    /// Take a snapshot of all the replicas of the nexus if one is due, as of
+    /// `now`, returning the time of the snapshot taken.
+    /// The snapshot is skipped if the nexus is locked by another operation.
+    pub async fn evaluate(
+        &self,
+        uuid: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<DateTime<Utc>>, nexus::Error> {
+        let due = match (self.policy.interval_secs, Self::last_snapshot(uuid)) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(interval), Some(last)) => now.timestamp() - last >= interval as i64,
+        };
+        if !due {
+            return Ok(None);
+        }
+
+        let Some(_lock_guard) = ResourceLockManager::get_instance()
+            .get_subsystem(ProtectedSubsystems::NEXUS)
+            .lock_resource(uuid, None, true)
+            .await
+        else {
+            debug!("Nexus {uuid} is locked, postponing its scheduled snapshot");
+            return Ok(None);
+        };
+        let mut nexus = nexus_lookup_uuid_mut(uuid).ok_or_else(|| nexus::Error::NexusNotFound {
+            name: uuid.to_string(),
+        })?;
+
+        let ts = now.timestamp();
+        let snapshot = SnapshotParams::new(
+            Some(uuid.to_string()),
+            Some(uuid.to_string()),
+            Some(sched_txn_id(ts)),
+            Some(format!("{}-sched-{ts}", nexus.name)),
+            None,
+            Some(now.to_string()),
+            false,
+        );
+        let replicas = nexus
+            .children()
+            .iter()
+            .filter_map(|c| c.get_uuid())
+            .map(|replica_uuid| NexusReplicaSnapshotDescriptor {
+                replica_uuid,
+                snapshot_uuid: Some(uuid::Uuid::new_v4().to_string()),
+                skip: false,
+            })
+            .collect::<Vec<_>>();
+        let status = nexus.as_mut().create_snapshot(snapshot, replicas).await?;
+        info!("Nexus {uuid}: created scheduled snapshot: {status:?}");
+        Ok(Some(now))
+    }
+}
+
+/// Evaluate the snapshot policies of all replicas and nexuses and garbage
+/// collect the discarded snapshots which no longer have any clones.
+/// Nothing is done while the node is draining or handing over.
+pub async fn evaluate_snapshot_policies() {
+    if is_draining() || is_handing_over() {
+        debug!("Node is draining or handing over, skipping the snapshot policies");
+        return;
+    }
+    let now = Utc::now();
+
+    let nexuses = nexus_iter()
+        .map(|n| n.uuid().to_string())
+        .collect::<Vec<_>>();
+    for uuid in nexuses {
+        let Some(policy) = NexusSnapshotPolicy::get(&uuid) else {
+            continue;
+        };
+        if let Err(error) = policy.evaluate(&uuid, now).await {
+            error!("Nexus {uuid}: failed to evaluate snapshot policy: {error}");
+        }
+    }
+
+    let Some(bdev) = UntypedBdev::bdev_first() else {
+        return;
+    };
+    let replicas = bdev
+        .into_iter()
+        .filter(|b| b.driver() == "lvol")
+        .filter_map(|b| Lvol::try_from(b).ok())
+        .filter(|l| !l.is_snapshot())
+        .map(|l| (l.uuid(), l.pool_name()))
+        .collect::<Vec<_>>();
+
+    for (uuid, pool_name) in replicas {
+        let Ok(_lock_guard) = lock_pool(&pool_name).await else {
+            debug!("Pool {pool_name} is locked, postponing the snapshot policy of {uuid}");
+            continue;
+        };
+        // The replica may have gone while waiting for the previous ones.
+        let Ok(replica) = lookup_replica(&uuid) else {
+            continue;
+        };
+        // A replica without a policy of its own may be a local replica of a
+        // nexus which has a policy, whose retention rules then apply.
+        let Some(policy) = SnapshotPolicy::get(&replica).or_else(|| {
+            NexusSnapshotPolicy::get_replica(&replica).and_then(|p| p.policy.retention())
+        }) else {
+            continue;
+        };
+        match policy.evaluate(&replica, now).await {
+            Ok(outcome) if outcome != SnapshotPolicyOutcome::default() => {
+                debug!("{replica:?}: snapshot policy evaluated: {outcome:?}");
+            }
+            Ok(_) => {}
+            Err(error) => {
+                error!("{replica:?}: failed to evaluate snapshot policy: {error}");
+            }
+        }
+    }
+
+    // The discarded snapshots are collected per pool, with the pool locked as
+    // for the replica destruction, which destroys them too.
+    let pools = Lvs::iter()
+        .map(|lvs| lvs.name().to_string())
+        .collect::<Vec<_>>();
+    for pool_name in pools {
+        let Ok(_lock_guard) = lock_pool(&pool_name).await else {
+            debug!("Pool {pool_name} is locked, postponing its discarded snapshots collection");
+            continue;
+        };
+        if Lvs::lookup(&pool_name).is_some() {
+            Lvol::destroy_pending_discarded_snapshot(&pool_name).await;
+        }
+    }
+}
+
+/// Periodically evaluate the snapshot policies on the primary reactor.
+pub async fn snapshot_policy_loop() {
+    let mut interval = tokio::time::interval(POLICY_EVAL_INTERVAL);
+    loop {
+        interval.tick().await;
+        match Reactor::spawn_at_primary(evaluate_snapshot_policies()) {
+            Ok(rx) => {
+                if let Err(error) = rx.await {
+                    error!("Failed to evaluate snapshot policies: {error}");
+                }
+            }
+            Err(error) => {
+                error!("Failed to schedule snapshot policies evaluation: {error}");
+            }
+        }
+    }
+}
+
+/// Arguments of the `replica_snapshot_policy_set` json-rpc method.
+#[derive(Debug, Deserialize)]
+struct SetPolicyArgs {
+    /// Uuid of the replica.
+    replica_uuid: String,
+    /// The policy to apply, clears the current policy if missing.
+    policy: Option<SnapshotPolicy>,
+}
+
+/// Arguments of the `replica_snapshot_policy_get` json-rpc method.
+#[derive(Debug, Deserialize)]
+struct GetPolicyArgs {
+    /// Uuid of the replica.
+    replica_uuid: String,
+}
+
+/// Snapshot policy of a replica, as returned by the json-rpc methods.
+#[derive(Debug, Serialize)]
+struct ReplicaSnapshotPolicy {
+    replica_uuid: String,
+    policy: Option<SnapshotPolicy>,
+}
+
+/// Arguments of the `nexus_snapshot_policy_set` json-rpc method.
+#[derive(Debug, Deserialize)]
+struct SetNexusPolicyArgs {
+    /// Uuid of the nexus.
+    nexus_uuid: String,
+    /// The policy to apply, clears the current policy if missing.
+    policy: Option<SnapshotPolicy>,
+}
+
+/// Arguments of the `nexus_snapshot_policy_get` json-rpc method.
+#[derive(Debug, Deserialize)]
+struct GetNexusPolicyArgs {
+    /// Uuid of the nexus.
+    nexus_uuid: String,
+}
+
+/// Snapshot policy of a nexus, as returned by the json-rpc methods.
+#[derive(Debug, Serialize)]
+struct NexusPolicyStatus {
+    nexus_uuid: String,
+    policy: Option<SnapshotPolicy>,
+    last_snapshot: Option<DateTime<Utc>>,
+}
+
+impl NexusPolicyStatus {
+    /// Get the status of the policy of the nexus with the given uuid.
+    fn get(uuid: &str) -> Result<Self, SnapshotPolicyError> {
+        if nexus_lookup_uuid_mut(uuid).is_none() {
+            return Err(SnapshotPolicyError::PolicyNexusNotFound {
+                uuid: uuid.to_string(),
+            });
+        }
+        Ok(Self {
+            nexus_uuid: uuid.to_string(),
+            last_snapshot: NexusSnapshotPolicy::last_snapshot(uuid)
+                .and_then(|ts| DateTime::from_timestamp(ts, 0)),
+            policy: NexusSnapshotPolicy::get(uuid).map(|p| p.policy),
+        })
+    }
+}
+
+type PolicyFuture<R> = Pin<Box<dyn Future<Output = Result<R, SnapshotPolicyError>>>>;
+
+/// Find the replica with the given uuid.
+fn lookup_replica(uuid: &str) -> Result<Lvol, SnapshotPolicyError> {
+    let bdev = UntypedBdev::lookup_by_uuid_str(uuid).ok_or_else(|| {
+        SnapshotPolicyError::PolicyReplicaNotFound {
+            uuid: uuid.to_string(),
+        }
+    })?;
+    match Lvol::try_from(bdev) {
+        Ok(lvol) if !lvol.is_snapshot() => Ok(lvol),
+        _ => Err(SnapshotPolicyError::PolicyNotAReplica {
+            uuid: uuid.to_string(),
+        }),
+    }
+}
+
+/// Register the snapshot policy json-rpc methods.
+pub(crate) fn register_jsonrpc_methods() {
+    jsonrpc_register(
+        "replica_snapshot_policy_set",
+        |args: SetPolicyArgs| -> PolicyFuture<ReplicaSnapshotPolicy> {
+            async move {
+                info!("{:?}", args);
+                let replica = lookup_replica(&args.replica_uuid)?;
+                let _lock_guard = lock_pool(&replica.pool_name()).await.map_err(|source| {
+                    SnapshotPolicyError::PolicyUpdate {
+                        uuid: replica.uuid(),
+                        source,
+                    }
+                })?;
+                SnapshotPolicy::set(&replica, args.policy.as_ref()).await?;
+                Ok(ReplicaSnapshotPolicy {
+                    replica_uuid: replica.uuid(),
+                    policy: SnapshotPolicy::get(&replica),
+                })
+            }
+            .boxed_local()
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_snapshot_policy_set",
+        |args: SetNexusPolicyArgs| -> PolicyFuture<NexusPolicyStatus> {
+            async move {
+                info!("{:?}", args);
+                NexusSnapshotPolicy::set(&args.nexus_uuid, args.policy.as_ref()).await?;
+                NexusPolicyStatus::get(&args.nexus_uuid)
+            }
+            .boxed_local()
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_snapshot_policy_get",
+        |args: GetNexusPolicyArgs| -> PolicyFuture<NexusPolicyStatus> {
+            async move { NexusPolicyStatus::get(&args.nexus_uuid) }.boxed_local()
+        },
+    );
+
+    jsonrpc_register(
+        "replica_snapshot_policy_get",
+        |args: GetPolicyArgs| -> PolicyFuture<ReplicaSnapshotPolicy> {
+            async move {
+                let replica = lookup_replica(&args.replica_uuid)?;
+                Ok(ReplicaSnapshotPolicy {
+                    replica_uuid: replica.uuid(),
+                    policy: SnapshotPolicy::get(&replica),
+                })
+            }
+            .boxed_local()
+        },
+    );
+}
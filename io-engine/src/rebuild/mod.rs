@@ -10,12 +10,13 @@ mod rebuild_state;
 mod rebuild_stats;
 mod rebuild_task;
 mod rebuilders;
+mod replica_import;
 mod snapshot_rebuild;
 
 pub use bdev_rebuild::BdevRebuildJob;
 pub use nexus_rebuild::{NexusRebuildJob, NexusRebuildJobStarter};
 use rebuild_descriptor::RebuildDescriptor;
-pub(crate) use rebuild_error::{RebuildError, SnapshotRebuildError};
+pub(crate) use rebuild_error::{RebuildError, ReplicaImportError, SnapshotRebuildError};
 use rebuild_job::RebuildOperation;
 pub use rebuild_job::{RebuildJob, RebuildJobOptions, RebuildVerifyMode};
 use rebuild_job_backend::{RebuildFBendChan, RebuildJobBackendManager, RebuildJobRequest};
@@ -25,6 +26,8 @@ use rebuild_state::RebuildStates;
 pub(crate) use rebuild_stats::HistoryRecord;
 pub use rebuild_stats::RebuildStats;
 use rebuild_task::{RebuildTasks, TaskResult};
+pub(crate) use rebuilders::SnapshotDeltaFn;
+pub use replica_import::{ImportSource, ReplicaImportJob};
 pub use snapshot_rebuild::SnapshotRebuildJob;
 
 /// Number of concurrent copy tasks per rebuild job
@@ -60,6 +63,23 @@ pub(crate) async fn shutdown_snapshot_rebuilds() {
     }
 }
 
+/// Shutdown all pending replica imports.
+pub(crate) async fn shutdown_replica_imports() {
+    ReplicaImportJob::cancel_all_spooling();
+    let jobs = ReplicaImportJob::list().into_iter();
+    for recv in jobs
+        .flat_map(|job| job.force_stop().left())
+        .collect::<Vec<_>>()
+    {
+        recv.await.ok();
+    }
+}
+
+/// Register the rebuild json-rpc methods.
+pub(crate) fn register_jsonrpc_methods() {
+    replica_import::register_jsonrpc_methods();
+}
+
 /// Parse the given url as string into a `url::Url`.
 pub fn parse_url(url: &str) -> Result<url::Url, RebuildError> {
     match url::Url::parse(url) {
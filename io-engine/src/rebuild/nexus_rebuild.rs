@@ -1,16 +1,22 @@
 use futures::channel::oneshot;
 use snafu::ResultExt;
 use spdk_rs::LbaRange;
-use std::ops::{Deref, Range};
+use std::{
+    ops::{Deref, Range},
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use crate::{
-    core::{DescriptorGuard, UntypedBdev},
+    core::{DescriptorGuard, ReadOptions, UntypedBdev},
     gen_rebuild_instances,
     rebuild::{
         rebuild_error::{RangeLockFailed, RangeUnlockFailed},
         rebuild_job_backend::RebuildJobManager,
         rebuild_task::{RebuildTask, RebuildTaskCopier},
-        rebuilders::{FullRebuild, PartialSeqCopier, PartialSeqRebuild, RangeRebuilder},
+        rebuilders::{
+            FullRebuild, PartialSeqCopier, PartialSeqRebuild, RangeRebuilder, SnapshotDeltaCopier,
+            SnapshotDeltaFn, SnapshotDeltaRebuild,
+        },
         RebuildMap, RebuildState,
     },
 };
@@ -32,6 +38,11 @@ use super::{
 /// is the one responsible for the read/writing of the data.
 pub struct NexusRebuildJob {
     job: RebuildJob,
+    /// The snapshot which the destination is first copied from, if any.
+    snapshot_uri: Option<String>,
+    /// Set while the destination is copied from the snapshot, before it joins
+    /// the write path.
+    copying_snapshot: AtomicBool,
 }
 
 /// Nexus supports both full and partial rebuilds. In case of a partial rebuild
@@ -93,13 +104,60 @@ impl NexusRebuildJob {
         Ok(NexusRebuildJobStarter {
             job: Some(Self {
                 job: RebuildJob::from_manager(&manager, &backend.descriptor),
+                snapshot_uri: None,
+                copying_snapshot: AtomicBool::new(false),
             }),
             manager,
             backend,
         })
     }
+
+    /// Get the uri of the snapshot this job rebuilds from, if any.
+    pub fn snapshot_uri(&self) -> Option<&str> {
+        self.snapshot_uri.as_deref()
+    }
+    /// Check if the destination is still being copied from the snapshot, in
+    /// which case it must be kept out of the write path.
+    pub fn is_copying_snapshot(&self) -> bool {
+        self.copying_snapshot.load(Ordering::SeqCst)
+    }
+    /// Marks the destination as copied from the snapshot.
+    pub(crate) fn snapshot_copied(&self) {
+        self.copying_snapshot.store(false, Ordering::SeqCst);
+    }
+    /// Get a list of all nexus rebuild jobs.
+    pub fn list() -> Vec<std::sync::Arc<NexusRebuildJob>> {
+        Self::get_instances().values().cloned().collect()
+    }
 }
 impl NexusRebuildJobStarter {
+    /// Copy the destination from the given snapshot first, so that only the
+    /// segments which have changed since the snapshot was taken are copied
+    /// from the source child.
+    /// Must be called before the job is stored.
+    pub async fn with_snapshot(mut self, snapshot_uri: &str) -> Result<Self, RebuildError> {
+        let common = &self.backend.descriptor.common;
+        let options = RebuildJobOptions {
+            verify_mode: common.options.verify_mode.clone(),
+            // the destination must read back the snapshot in full
+            read_opts: ReadOptions::None,
+            skip_zeroes: false,
+        };
+        let snapshot = RebuildDescriptor::new(
+            snapshot_uri,
+            &common.dst_uri,
+            Some(common.range.clone()),
+            options,
+        )
+        .await?;
+
+        self.backend.snapshot = Some(snapshot);
+        if let Some(job) = self.job.as_mut() {
+            job.snapshot_uri = Some(snapshot_uri.to_string());
+            job.copying_snapshot.store(true, Ordering::SeqCst);
+        }
+        Ok(self)
+    }
     /// Store the inner rebuild job in the rebuild job list.
     pub fn store(mut self) -> Result<Self, RebuildError> {
         if let Some(job) = self.job.take() {
@@ -130,6 +188,33 @@ impl NexusRebuildJobStarter {
         }
         job.start().await
     }
+    /// Schedules the job to start from the snapshot in a future and returns
+    /// a complete channel which can be waited on.
+    /// Once the snapshot has been copied, the delta provides the segments
+    /// which have been written since the snapshot was taken, and which are
+    /// then copied from the source child.
+    /// Without a snapshot, a full rebuild is scheduled.
+    pub async fn start_from_snapshot(
+        self,
+        job: std::sync::Arc<NexusRebuildJob>,
+        delta: SnapshotDeltaFn,
+    ) -> Result<oneshot::Receiver<RebuildState>, RebuildError> {
+        match self.backend.snapshot {
+            None => {
+                self.manager
+                    .into_backend(self.backend.into_full())
+                    .schedule()
+                    .await;
+            }
+            Some(_) => {
+                self.manager
+                    .into_backend(self.backend.into_snapshot_delta(delta))
+                    .schedule()
+                    .await;
+            }
+        }
+        job.start().await
+    }
 }
 
 gen_rebuild_instances!(NexusRebuildJob);
@@ -176,6 +261,8 @@ struct NexusRebuildJobBackendStarter {
     task_pool: RebuildTasks,
     /// A nexus rebuild specific descriptor.
     descriptor: NexusRebuildDescriptor,
+    /// Descriptor for copying from the snapshot, if any.
+    snapshot: Option<RebuildDescriptor>,
     /// Notification callback which existing nexus uses to sync
     /// with rebuild updates.
     notify_fn: fn(String, String) -> (),
@@ -203,6 +290,7 @@ impl NexusRebuildJobBackendStarter {
         };
         Ok(Self {
             descriptor,
+            snapshot: None,
             task_pool,
             notify_fn,
         })
@@ -223,6 +311,22 @@ impl NexusRebuildJobBackendStarter {
             _p: Default::default(),
         }
     }
+    fn into_snapshot_delta(
+        self,
+        delta: SnapshotDeltaFn,
+    ) -> NexusRebuildJobBackend<
+        SnapshotDeltaCopier<NexusRebuildDescriptor>,
+        SnapshotDeltaRebuild<NexusRebuildDescriptor>,
+    > {
+        let snapshot = self.snapshot.expect("Snapshot descriptor must be set");
+        NexusRebuildJobBackend {
+            task_pool: self.task_pool,
+            notify_fn: self.notify_fn,
+            nexus_name: self.descriptor.nexus_name.clone(),
+            copier: SnapshotDeltaRebuild::new(snapshot, delta, self.descriptor),
+            _p: Default::default(),
+        }
+    }
     fn into_full(
         self,
     ) -> NexusRebuildJobBackend<NexusRebuildDescriptor, FullRebuild<NexusRebuildDescriptor>> {
@@ -273,6 +377,15 @@ impl<T: RebuildTaskCopier + 'static, R: RangeRebuilder<T>> RebuildBackend
     async fn await_one_task(&mut self) -> Option<TaskResult> {
         self.task_pool.await_one_task().await
     }
+    async fn next_phase(&mut self) -> Result<bool, RebuildError> {
+        if !self.copier.next_phase().await? {
+            return Ok(false);
+        }
+        // The progress is that of the current phase.
+        self.task_pool.segments_done = 0;
+        self.task_pool.segments_transferred = 0;
+        Ok(true)
+    }
 }
 
 impl<T: RebuildTaskCopier + 'static, R: RangeRebuilder<T>> std::fmt::Debug
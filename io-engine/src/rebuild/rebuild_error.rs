@@ -1,6 +1,10 @@
 use snafu::Snafu;
 
-use crate::{bdev_api::BdevError, core::CoreError};
+use crate::{
+    bdev_api::BdevError,
+    core::{drain::DrainError, CoreError},
+    jsonrpc::{Code, RpcErrorCode},
+};
 use spdk_rs::{BdevDescError, DmaError};
 
 #[derive(Debug, Snafu, Clone)]
@@ -81,8 +85,32 @@ pub enum RebuildError {
         active
     ))]
     RebuildTasksChannel { active: usize },
+    #[snafu(display("The writes made since the snapshot was taken are no longer tracked"))]
+    SnapshotDeltaLost,
     #[snafu(display("Snapshot Rebuild: {source}"))]
     SnapshotRebuild { source: SnapshotRebuildError },
+    #[snafu(display("Replica Import: {source}"))]
+    ReplicaImport { source: ReplicaImportError },
+}
+
+impl RpcErrorCode for RebuildError {
+    fn rpc_error_code(&self) -> Code {
+        match self {
+            Self::JobNotFound { .. } => Code::NotFound,
+            Self::JobAlreadyExists { .. } => Code::AlreadyExists,
+            Self::SameBdev { .. } | Self::InvalidSrcDstRange {} | Self::BdevInvalidUri { .. } => {
+                Code::InvalidParams
+            }
+            Self::ReplicaImport { source } => match source {
+                ReplicaImportError::ReplicaNotFound { .. } => Code::NotFound,
+                ReplicaImportError::NotServing { source } => source.rpc_error_code(),
+                ReplicaImportError::SourceBdevOpen { .. }
+                | ReplicaImportError::SourceRead { .. } => Code::InternalError,
+                _ => Code::InvalidParams,
+            },
+            _ => Code::InternalError,
+        }
+    }
 }
 
 /// Various snapshot rebuild errors.
@@ -105,3 +133,36 @@ impl From<SnapshotRebuildError> for RebuildError {
         Self::SnapshotRebuild { source }
     }
 }
+
+/// Various replica import errors.
+#[derive(Debug, Snafu, Clone)]
+#[snafu(visibility(pub(crate)), context(suffix(false)))]
+#[allow(missing_docs)]
+pub enum ReplicaImportError {
+    #[snafu(display("Replica {uuid} not found"))]
+    ReplicaNotFound { uuid: String },
+    #[snafu(display("Bdev {uuid} is not a writable replica"))]
+    InvalidReplica { uuid: String },
+    #[snafu(display("Invalid import source uri {uri}"))]
+    InvalidSourceUri { uri: String },
+    #[snafu(display("Failed to open {uri} as a bdev: {source}"))]
+    SourceBdevOpen { uri: String, source: BdevError },
+    #[snafu(display("Failed to read the import source {uri}: {reason}"))]
+    SourceRead { uri: String, reason: String },
+    #[snafu(display(
+        "Import source {uri} of {size} bytes does not fit the replica of {replica_size} bytes"
+    ))]
+    SourceTooLarge {
+        uri: String,
+        size: u64,
+        replica_size: u64,
+    },
+    #[snafu(display("Refusing to import: {source}"))]
+    NotServing { source: DrainError },
+}
+
+impl From<ReplicaImportError> for RebuildError {
+    fn from(source: ReplicaImportError) -> Self {
+        Self::ReplicaImport { source }
+    }
+}
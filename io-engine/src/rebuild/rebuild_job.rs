@@ -32,6 +32,10 @@ pub enum RebuildVerifyMode {
 pub struct RebuildJobOptions {
     pub verify_mode: RebuildVerifyMode,
     pub read_opts: ReadOptions,
+    /// Skip writing segments which contain only zeroes.
+    /// This is only safe when the destination is known to read back zeroes
+    /// for unallocated blocks, eg: a freshly created thin replica.
+    pub skip_zeroes: bool,
 }
 impl RebuildJobOptions {
     /// Use the given `ReadOptions`.
@@ -39,6 +43,11 @@ impl RebuildJobOptions {
         self.read_opts = read_opts;
         self
     }
+    /// Skip the write of all-zero segments.
+    pub fn with_skip_zeroes(mut self, skip_zeroes: bool) -> Self {
+        self.skip_zeroes = skip_zeroes;
+        self
+    }
 }
 
 /// Operations used to control the state of the job.
@@ -14,7 +14,8 @@ use super::{
     TaskResult,
 };
 
-use crate::core::Reactors;
+use crate::{core::Reactors, telemetry::operation_span};
+use tracing::Instrument;
 
 /// Request between frontend and backend.
 #[derive(Debug)]
@@ -75,6 +76,12 @@ pub(super) trait RebuildBackend: std::fmt::Debug + std::fmt::Display {
     /// Each task's completion must be awaited, to ensure that no in-progress IO
     /// remains when we complete a rebuild.
     async fn await_one_task(&mut self) -> Option<TaskResult>;
+    /// Move on to the next phase of the rebuild, once all the segments of the
+    /// current phase have been rebuilt.
+    /// Returns false if there's no further phase, ie the rebuild is complete.
+    async fn next_phase(&mut self) -> Result<bool, RebuildError> {
+        Ok(false)
+    }
 }
 
 /// A rebuild job is responsible for managing a rebuild (copy) which reads
@@ -99,6 +106,8 @@ pub(super) struct RebuildJobBackendManager {
     /// The rebuild backend runner which implements the `RebuildBackend` and
     /// performs a specific type of rebuild copy.
     backend: Box<dyn RebuildBackend>,
+    /// Set once all the segments of the current phase have been rebuilt.
+    phase_done: bool,
 }
 
 impl Deref for RebuildJobBackendManager {
@@ -156,6 +165,7 @@ impl RebuildJobManager {
         RebuildJobBackendManager {
             manager: self,
             backend: Box::new(backend),
+            phase_done: false,
         }
     }
 }
@@ -169,15 +179,19 @@ impl RebuildJobBackendManager {
         let be = Self {
             manager: RebuildJobManager::new(),
             backend: Box::new(backend),
+            phase_done: false,
         };
         info!("{be}: backend created");
         be
     }
 
     /// Moves the rebuild job manager and runs until completion.
+    /// The rebuild is traced as a child of the current span, i.e. the span of
+    /// the request which started it.
     pub(super) async fn schedule(self) {
         let mut job = self;
-        Reactors::master().send_future(async move { job.run().await });
+        let span = operation_span("rebuild", &job.backend.common_desc().dst_uri);
+        Reactors::master().send_future(async move { job.run().await }.instrument(span));
     }
 
     /// Runs the management async task and listens for requests from the
@@ -205,6 +219,10 @@ impl RebuildJobBackendManager {
                     _ = self.manage_tasks().fuse() => {},
                 }
             }
+
+            if std::mem::take(&mut self.phase_done) {
+                self.next_phase().await;
+            }
         }
     }
 
@@ -265,6 +283,22 @@ impl RebuildJobBackendManager {
             .ok();
     }
 
+    /// Moves the backend on to its next phase, or completes the job if the
+    /// backend has no further phase.
+    async fn next_phase(&mut self) {
+        match self.backend.next_phase().await {
+            Ok(true) => {
+                let s = self.stats();
+                info!("{self}: moving on to the next phase; current stats: {s:?}");
+            }
+            Ok(false) => self.complete(),
+            Err(error) => {
+                error!("{self}: failed to move on to the next phase: {error}");
+                self.fail_with(error);
+            }
+        }
+    }
+
     /// Internal operations can bypass previous pending operations.
     fn exec_internal_op(&self, op: super::RebuildOperation) -> Result<bool, RebuildError> {
         self.states.write().exec_op(op, true)
@@ -354,9 +388,9 @@ impl RebuildJobBackendManager {
             }
         }
 
-        // Nothing to rebuild, in case we paused but the rebuild is complete
+        // Nothing to rebuild, in case we paused but the phase is complete
         if self.task_pool().active == 0 {
-            self.complete();
+            self.phase_done = true;
         }
 
         let s = self.stats();
@@ -368,7 +402,7 @@ impl RebuildJobBackendManager {
     fn start_task_by_id(&mut self, id: usize) -> bool {
         if !self.backend.schedule_task_by_id(id) {
             if self.task_pool().active == 0 {
-                self.complete();
+                self.phase_done = true;
             }
             false
         } else {
@@ -65,6 +65,12 @@ impl RebuildTask {
             // Segment is not allocated in the source, skip the write.
             return Ok(false);
         }
+
+        if desc.options.skip_zeroes && self.is_zero_segment(offset_blk, desc) {
+            // Destination already reads back zeroes, skip the write.
+            return Ok(false);
+        }
+
         desc.write_dst_segment(offset_blk, iovs).await?;
 
         if !matches!(desc.options.verify_mode, RebuildVerifyMode::None) {
@@ -73,6 +79,13 @@ impl RebuildTask {
 
         Ok(true)
     }
+
+    /// Checks if the segment which has just been read into the buffer is
+    /// made of zeroes only.
+    fn is_zero_segment(&self, offset_blk: u64, desc: &RebuildDescriptor) -> bool {
+        let len = (desc.get_segment_size_blks(offset_blk) * desc.block_size) as usize;
+        self.buffer.as_slice()[..len].iter().all(|b| *b == 0)
+    }
 }
 
 /// Pool of rebuild tasks and progress tracking.
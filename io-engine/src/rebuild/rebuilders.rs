@@ -7,6 +7,7 @@ use crate::{
     },
 };
 use bit_vec::BitVec;
+use futures::future::LocalBoxFuture;
 use std::{ops::Range, rc::Rc};
 
 /// A rebuild may rebuild a device by walking it differently, for example:
@@ -15,6 +16,10 @@ use std::{ops::Range, rc::Rc};
 /// 2. partial rebuild - walk the allocated segments only and copy them.
 /// 3. partial seq rebuild - walk the entire device range and copy only
 ///    allocated segments (current nexus partial rebuild behaviour).
+/// 4. snapshot delta rebuild - walk the entire device range and copy every
+///    segment from a snapshot, then walk it again and copy the segments which
+///    changed since the snapshot was taken from the source.
+#[async_trait::async_trait(?Send)]
 pub(super) trait RangeRebuilder<T: RebuildTaskCopier> {
     /// Fetch the next block to rebuild.
     fn next(&mut self) -> Option<u64>;
@@ -28,6 +33,11 @@ pub(super) trait RangeRebuilder<T: RebuildTaskCopier> {
     fn desc(&self) -> &RebuildDescriptor;
     /// Get the copier which can copy a segment.
     fn copier(&self) -> Rc<T>;
+    /// Move on to the next phase, once all the blocks have been rebuilt.
+    /// Returns false if there's no further phase.
+    async fn next_phase(&mut self) -> Result<bool, RebuildError> {
+        Ok(false)
+    }
 }
 
 /// The range is the full range of the request, in steps of segment size.
@@ -218,6 +228,126 @@ impl<T: RebuildTaskCopier> RebuildTaskCopier for PartialSeqCopier<T> {
     }
 }
 
+/// Stops tracking the writes made since the snapshot was taken, once the
+/// destination has been copied from the snapshot, and returns the map of the
+/// segments written in the meantime, if still available.
+pub(crate) type SnapshotDeltaFn = Box<dyn FnOnce() -> LocalBoxFuture<'static, Option<RebuildMap>>>;
+
+/// The range is the full range of the request, in steps of segment size,
+/// which is walked twice.
+/// First, every segment is copied from the snapshot, while the destination is
+/// kept out of the write path.
+/// Then, the range is walked again but only the delta, ie the segments written
+/// since the snapshot was taken, is copied from the live source, keeping the
+/// bulk of the read load off the source child.
+pub(super) struct SnapshotDeltaRebuild<T: RebuildTaskCopier> {
+    range: PeekableIterator<std::iter::StepBy<Range<u64>>>,
+    /// Provides the delta once the snapshot has been copied.
+    delta: Option<SnapshotDeltaFn>,
+    copier: Rc<SnapshotDeltaCopier<T>>,
+}
+impl<T: RebuildTaskCopier> SnapshotDeltaRebuild<T> {
+    /// Create a snapshot delta rebuild with the given snapshot descriptor,
+    /// delta and live copier.
+    pub(super) fn new(snapshot: RebuildDescriptor, delta: SnapshotDeltaFn, copier: T) -> Self {
+        let desc = copier.descriptor();
+        let range = desc.range.clone();
+        Self {
+            range: PeekableIterator::new(range.step_by(desc.segment_size_blks as usize)),
+            delta: Some(delta),
+            copier: Rc::new(SnapshotDeltaCopier {
+                snapshot,
+                map: parking_lot::Mutex::new(None),
+                copier,
+            }),
+        }
+    }
+}
+#[async_trait::async_trait(?Send)]
+impl<T: RebuildTaskCopier> RangeRebuilder<SnapshotDeltaCopier<T>> for SnapshotDeltaRebuild<T> {
+    fn next(&mut self) -> Option<u64> {
+        self.range.next()
+    }
+    fn peek_next(&self) -> Option<u64> {
+        self.range.peek().cloned()
+    }
+
+    fn blocks_remaining(&self) -> u64 {
+        match self.copier.map.lock().as_ref() {
+            Some(map) => map.count_dirty_blks(),
+            None => self
+                .peek_next()
+                .map(|r| self.desc().range.end.max(r) - r)
+                .unwrap_or_default(),
+        }
+    }
+    fn is_partial(&self) -> bool {
+        true
+    }
+
+    fn desc(&self) -> &RebuildDescriptor {
+        self.copier.descriptor()
+    }
+    fn copier(&self) -> Rc<SnapshotDeltaCopier<T>> {
+        self.copier.clone()
+    }
+
+    async fn next_phase(&mut self) -> Result<bool, RebuildError> {
+        let Some(delta) = self.delta.take() else {
+            return Ok(false);
+        };
+        let map = delta().await.ok_or(RebuildError::SnapshotDeltaLost)?;
+        *self.copier.map.lock() = Some(map);
+
+        let desc = self.desc();
+        let range = desc.range.clone().step_by(desc.segment_size_blks as usize);
+        self.range = PeekableIterator::new(range);
+        Ok(true)
+    }
+}
+/// The snapshot delta copier, which copies a segment from the snapshot until
+/// the delta is known, and from the live source afterwards, if the segment
+/// has been written since the snapshot was taken.
+pub(super) struct SnapshotDeltaCopier<T: RebuildTaskCopier> {
+    /// Descriptor for copying from the snapshot into the destination.
+    snapshot: RebuildDescriptor,
+    /// The segments written since the snapshot was taken, and not copied yet.
+    map: parking_lot::Mutex<Option<RebuildMap>>,
+    copier: T,
+}
+#[async_trait::async_trait(?Send)]
+impl<T: RebuildTaskCopier> RebuildTaskCopier for SnapshotDeltaCopier<T> {
+    fn descriptor(&self) -> &RebuildDescriptor {
+        self.copier.descriptor()
+    }
+
+    /// Copies one segment worth of data from either the snapshot or the live
+    /// source into destination.
+    async fn copy_segment(&self, blk: u64, task: &mut RebuildTask) -> Result<bool, RebuildError> {
+        let is_clean = self.map.lock().as_ref().map(|map| map.is_blk_clean(blk));
+
+        match is_clean {
+            // The destination is not in the write path yet, so there is no
+            // front-end I/O to synchronize with.
+            None => task.copy_one(blk, &self.snapshot).await,
+            Some(true) => Ok(false),
+            Some(false) => {
+                let result = self.copier.copy_segment(blk, task).await;
+
+                // In the case of success, mark the segment as already
+                // transferred.
+                if result.is_ok() {
+                    if let Some(map) = self.map.lock().as_mut() {
+                        map.blk_clean(blk);
+                    }
+                }
+
+                result
+            }
+        }
+    }
+}
+
 /// Adds peekable functionality to a generic iterator.
 /// > Note: the peekable from the std library is not sufficient here because it
 /// > requires a mutable reference to peek. We get around this limitation by
@@ -0,0 +1,723 @@
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    ops::Deref,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use chrono::{DateTime, Utc};
+use futures::{channel::oneshot, Future, FutureExt};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use super::{
+    rebuild_error::{RebuildError, ReplicaImportError},
+    snapshot_rebuild::Uri,
+    RebuildJob, RebuildJobOptions,
+};
+
+use crate::{
+    bdev::device_lookup,
+    bdev_api::bdev_get_name,
+    core::{
+        drain::check_serving, runtime, Bdev, LogicalVolume, MayastorEnvironment, Reactors,
+        ReadOptions,
+    },
+    gen_rebuild_instances,
+    jsonrpc::jsonrpc_register,
+    lvs::LvsLvol,
+    rebuild::{bdev_rebuild::BdevRebuildJobBuilder, BdevRebuildJob},
+};
+
+/// The source of a replica import.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportSource {
+    /// A raw image file on the local filesystem, eg: `file:///images/vol.img`.
+    File(PathBuf),
+    /// A raw image which can only be read sequentially, such as a named pipe,
+    /// eg: `stream:///run/import.fifo`.
+    /// It is spooled into a sparse file first, which is then imported.
+    Stream(PathBuf),
+    /// Any other device uri, eg: a snapshot or replica exported by another
+    /// io-engine over nvmf.
+    Uri(String),
+}
+
+impl ImportSource {
+    /// Parse the given uri into an `ImportSource`.
+    pub fn parse(uri: &str) -> Result<Self, ReplicaImportError> {
+        let url = url::Url::parse(uri).map_err(|_| ReplicaImportError::InvalidSourceUri {
+            uri: uri.to_string(),
+        })?;
+        match url.scheme() {
+            "file" if !url.path().is_empty() => Ok(Self::File(PathBuf::from(url.path()))),
+            "stream" if !url.path().is_empty() => Ok(Self::Stream(PathBuf::from(url.path()))),
+            "file" | "stream" => Err(ReplicaImportError::InvalidSourceUri {
+                uri: uri.to_string(),
+            }),
+            _ => Ok(Self::Uri(uri.to_string())),
+        }
+    }
+    /// Get the bdev uri which is used to read from this source, using the
+    /// given block size where the source does not define its own.
+    /// Streams are read from their spool file.
+    fn bdev_uri(&self, blk_size: u64, spool: Option<&SpoolFile>) -> String {
+        match (self, spool) {
+            (_, Some(spool)) => format!("aio://{}?blk_size={blk_size}", spool.path.display()),
+            (Self::File(path) | Self::Stream(path), None) => {
+                format!("aio://{}?blk_size={blk_size}", path.display())
+            }
+            (Self::Uri(uri), None) => uri.clone(),
+        }
+    }
+    /// Get the read options for this source.
+    /// Files are read in full. For exported devices only what is allocated
+    /// is copied, which is only correct when the unallocated segments of the
+    /// replica already read back zeroes: otherwise zeroes are read and
+    /// written as well, overwriting whatever the replica held.
+    fn read_opts(&self, dst_reads_zeroes: bool) -> ReadOptions {
+        match self {
+            Self::Uri(_) if dst_reads_zeroes => ReadOptions::UnwrittenFail,
+            _ => ReadOptions::None,
+        }
+    }
+}
+
+/// Sparse file into which a stream source is spooled, removed on drop.
+struct SpoolFile {
+    path: PathBuf,
+    /// Size of the stream.
+    size: u64,
+}
+
+/// Progress of the spooling of a stream source, shared with the blocking
+/// thread which reads the stream.
+#[derive(Default)]
+struct SpoolProgress {
+    /// Bytes of the stream spooled so far.
+    spooled: AtomicU64,
+    /// Set when the import is destroyed while spooling.
+    cancelled: AtomicBool,
+}
+
+impl SpoolFile {
+    /// Size of the chunks the stream is read in.
+    const CHUNK_SIZE: usize = 1024 * 1024;
+
+    /// Read the whole stream into a new spool file for the given import job,
+    /// seeking over the zeroed chunks so that they are not allocated.
+    /// The stream may not be larger than the replica, and the file is
+    /// padded to a whole number of blocks.
+    /// The file is created in the import spool directory with a unique name,
+    /// so that concurrent or retried imports never share it.
+    async fn spool(
+        stream: &Path,
+        job_uuid: &str,
+        replica_size: u64,
+        block_len: u64,
+        progress: Arc<SpoolProgress>,
+    ) -> Result<Self, ReplicaImportError> {
+        let uri = format!("stream://{}", stream.display());
+        let dir = MayastorEnvironment::global_or_default().import_spool_dir();
+        std::fs::create_dir_all(&dir).map_err(|error| ReplicaImportError::SourceRead {
+            uri: uri.clone(),
+            reason: format!("failed to create {}: {error}", dir.display()),
+        })?;
+        let mut spool = Self {
+            path: dir.join(format!(
+                "replica-import-{job_uuid}-{}.img",
+                uuid::Uuid::new_v4()
+            )),
+            size: 0,
+        };
+        let stream = stream.to_path_buf();
+        let path = spool.path.clone();
+
+        let (sender, receiver) = oneshot::channel();
+        runtime::spawn_blocking(move || {
+            let result = Self::copy(&stream, &path, replica_size, block_len, &progress);
+            sender.send(result).ok();
+        });
+        match receiver.await {
+            Ok(Ok(size)) => {
+                spool.size = size;
+                Ok(spool)
+            }
+            Ok(Err(error)) => Err(error),
+            Err(_) => Err(ReplicaImportError::SourceRead {
+                uri,
+                reason: "spooling was cancelled".to_string(),
+            }),
+        }
+    }
+
+    fn copy(
+        stream: &Path,
+        path: &Path,
+        replica_size: u64,
+        block_len: u64,
+        progress: &SpoolProgress,
+    ) -> Result<u64, ReplicaImportError> {
+        let uri = format!("stream://{}", stream.display());
+        let read_error = |error: std::io::Error| ReplicaImportError::SourceRead {
+            uri: uri.clone(),
+            reason: error.to_string(),
+        };
+
+        let mut input = File::open(stream).map_err(read_error)?;
+        let mut output = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(read_error)?;
+        let mut chunk = vec![0u8; Self::CHUNK_SIZE];
+        let mut size = 0u64;
+        loop {
+            if progress.cancelled.load(Ordering::Relaxed) {
+                return Err(ReplicaImportError::SourceRead {
+                    uri,
+                    reason: "the import was destroyed while spooling".to_string(),
+                });
+            }
+            let len = match input.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(read_error(error)),
+            };
+            if size + len as u64 > replica_size {
+                return Err(ReplicaImportError::SourceTooLarge {
+                    uri,
+                    size: size + len as u64,
+                    replica_size,
+                });
+            }
+            if chunk[..len].iter().all(|b| *b == 0) {
+                output
+                    .seek(SeekFrom::Current(len as i64))
+                    .map_err(read_error)?;
+            } else {
+                output.write_all(&chunk[..len]).map_err(read_error)?;
+            }
+            size += len as u64;
+            progress.spooled.store(size, Ordering::Relaxed);
+        }
+        if size == 0 {
+            return Err(ReplicaImportError::SourceRead {
+                uri,
+                reason: "the stream is empty".to_string(),
+            });
+        }
+        output
+            .set_len(size.div_ceil(block_len) * block_len)
+            .map_err(read_error)?;
+        Ok(size)
+    }
+}
+
+impl Drop for SpoolFile {
+    fn drop(&mut self) {
+        if let Err(error) = std::fs::remove_file(&self.path) {
+            if error.kind() != std::io::ErrorKind::NotFound {
+                warn!(
+                    "Failed to remove spool file {}: {error}",
+                    self.path.display()
+                );
+            }
+        }
+    }
+}
+
+/// A Replica import job is responsible for seeding a local replica with the
+/// contents of an external image, which may be a local file or a device
+/// exported by another io-engine.
+/// When the replica is thin and not yet allocated, segments made of zeroes
+/// only are not written, keeping the replica thin.
+pub struct ReplicaImportJob {
+    inner: BdevRebuildJob,
+    uuid: String,
+    replica_uuid: String,
+    source: ImportSource,
+    source_uri: Uri,
+    replica_uri: Uri,
+    /// Spool file of a stream source, removed along with the job.
+    spool: Option<SpoolFile>,
+}
+
+impl std::fmt::Debug for ReplicaImportJob {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplicaImportJob")
+            .field("uuid", &self.uuid)
+            .field("source", &self.source)
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+impl Deref for ReplicaImportJob {
+    type Target = RebuildJob;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// Builder for the `ReplicaImportJob`.
+#[derive(Default)]
+pub struct ReplicaImportJobBuilder {
+    bdev_builder: BdevRebuildJobBuilder,
+    uuid: Option<String>,
+    replica_uuid: String,
+    source: Option<ImportSource>,
+    spool: Option<SpoolFile>,
+}
+impl ReplicaImportJobBuilder {
+    fn builder() -> Self {
+        Default::default()
+    }
+    /// Specify a notification function.
+    pub fn with_notify_fn(mut self, notify_fn: fn(&str, &str) -> ()) -> Self {
+        self.bdev_builder = self.bdev_builder.with_notify_fn(notify_fn);
+        self
+    }
+    /// Specify the replica uuid.
+    pub fn with_replica_uuid(mut self, uuid: &str) -> Self {
+        self.replica_uuid = uuid.to_string();
+        if self.uuid.is_none() {
+            self.uuid = Some(uuid.to_string());
+        }
+        self
+    }
+    /// Specify the job's uuid.
+    pub fn with_uuid(mut self, uuid: &str) -> Self {
+        self.uuid = Some(uuid.to_string());
+        self
+    }
+    /// Specify the import source.
+    pub fn with_source(mut self, source: ImportSource) -> Self {
+        self.source = Some(source);
+        self
+    }
+    /// Specify the file a stream source has been spooled into.
+    fn with_spool(mut self, spool: SpoolFile) -> Self {
+        self.spool = Some(spool);
+        self
+    }
+    // todo: we have new backends, we shouldn't just use `Lvol` directly.
+    fn lookup_lvol(uuid: &str) -> Result<crate::lvs::Lvol, ReplicaImportError> {
+        let bdev =
+            Bdev::lookup_by_uuid_str(uuid).ok_or_else(|| ReplicaImportError::ReplicaNotFound {
+                uuid: uuid.to_string(),
+            })?;
+        let lvol =
+            crate::lvs::Lvol::try_from(bdev).map_err(|_| ReplicaImportError::InvalidReplica {
+                uuid: uuid.to_string(),
+            })?;
+        if lvol.is_snapshot() {
+            return Err(ReplicaImportError::InvalidReplica {
+                uuid: uuid.to_string(),
+            });
+        }
+        Ok(lvol)
+    }
+    /// Get the size of the source in blocks, checking that it fits the replica.
+    fn source_blocks(uri: &str, replica: &crate::lvs::Lvol) -> Result<u64, ReplicaImportError> {
+        let device = bdev_get_name(uri)
+            .ok()
+            .and_then(|name| device_lookup(&name))
+            .ok_or_else(|| ReplicaImportError::InvalidSourceUri {
+                uri: uri.to_string(),
+            })?;
+        let size = device.size_in_bytes();
+        if size > replica.size() {
+            return Err(ReplicaImportError::SourceTooLarge {
+                uri: uri.to_string(),
+                size,
+                replica_size: replica.size(),
+            });
+        }
+        Ok(size / device.block_len())
+    }
+
+    /// Builds a `ReplicaImportJob` which can be started and which will then
+    /// copy the source into the replica.
+    /// A stream source must have been spooled already, see
+    /// `ReplicaImportJob::create`.
+    pub async fn build(self) -> Result<ReplicaImportJob, RebuildError> {
+        let source = self
+            .source
+            .ok_or_else(|| ReplicaImportError::InvalidSourceUri { uri: "".into() })?;
+        let lvol = Self::lookup_lvol(&self.replica_uuid)?;
+        let block_len = lvol.as_bdev().block_len() as u64;
+        let uuid = self
+            .uuid
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        let spool = self.spool;
+        if let (ImportSource::Stream(path), None) = (&source, &spool) {
+            return Err(ReplicaImportError::SourceRead {
+                uri: format!("stream://{}", path.display()),
+                reason: "the stream has not been spooled".to_string(),
+            }
+            .into());
+        }
+
+        let source_uri = Uri::new(source.bdev_uri(block_len, spool.as_ref()), true)
+            .try_open_create()
+            .await
+            .map_err(|(uri, source)| ReplicaImportError::SourceBdevOpen { uri, source })?;
+        let replica_uri = Uri::new(format!("bdev:///{}", self.replica_uuid), false);
+
+        let blocks = match Self::source_blocks(&source_uri.uri, &lvol) {
+            Ok(blocks) => blocks,
+            Err(error) => {
+                source_uri.close().await.ok();
+                return Err(error.into());
+            }
+        };
+
+        // Zeroes can only be skipped if the replica reads back zeroes where
+        // nothing has been allocated yet, which is not the case for a clone
+        // as its unallocated clusters read back from its snapshot.
+        let reads_zeroes =
+            lvol.is_thin() && lvol.allocated() == 0 && lvol.is_snapshot_clone().is_none();
+        let options = RebuildJobOptions::default()
+            .with_read_opts(source.read_opts(reads_zeroes))
+            .with_skip_zeroes(reads_zeroes);
+
+        match self
+            .bdev_builder
+            .with_option(options)
+            .with_range(0..blocks)
+            .build(&source_uri.uri, &replica_uri.uri)
+            .await
+        {
+            Ok(inner) => Ok(ReplicaImportJob {
+                inner,
+                uuid,
+                replica_uuid: self.replica_uuid,
+                source,
+                source_uri,
+                replica_uri,
+                spool,
+            }),
+            Err(error) => {
+                source_uri.close().await.ok();
+                Err(error)
+            }
+        }
+    }
+}
+
+impl ReplicaImportJob {
+    /// Helps create a `Self` using a builder: `ReplicaImportJobBuilder`.
+    pub fn builder() -> ReplicaImportJobBuilder {
+        ReplicaImportJobBuilder::builder()
+    }
+    /// Get a list of all replica import jobs.
+    pub fn list() -> Vec<Arc<ReplicaImportJob>> {
+        Self::get_instances().values().cloned().collect()
+    }
+    /// Get the name of this import job.
+    pub fn name(&self) -> &str {
+        self.uuid()
+    }
+    /// Get the uuid of this import job.
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+    /// Get the replica uri.
+    pub fn replica_uri(&self) -> &str {
+        &self.replica_uri.uri
+    }
+    /// Get the replica uuid.
+    pub fn replica_uuid(&self) -> &str {
+        &self.replica_uuid
+    }
+    /// Get the import source.
+    pub fn source(&self) -> &ImportSource {
+        &self.source
+    }
+    /// Get the uri of the bdev the source is read from.
+    pub fn source_uri(&self) -> &str {
+        &self.source_uri.uri
+    }
+    /// Destroy this import job itself.
+    pub fn destroy(self: Arc<Self>) {
+        let _ = Self::remove(self.uuid());
+    }
+}
+
+gen_rebuild_instances!(ReplicaImportJob);
+
+/// A replica import whose stream source is being spooled, before its copy
+/// job is built and started.
+struct SpoolingImport {
+    uuid: String,
+    replica_uuid: String,
+    source_uri: String,
+    progress: Arc<SpoolProgress>,
+    /// Why the spooling failed, kept until the import is destroyed.
+    error: Mutex<Option<String>>,
+    start_time: DateTime<Utc>,
+}
+
+/// Replica imports which are spooling their stream source, by import uuid.
+static SPOOLING_IMPORTS: Lazy<Mutex<HashMap<String, Arc<SpoolingImport>>>> =
+    Lazy::new(Default::default);
+
+impl ReplicaImportJob {
+    /// Create and start a replica import job.
+    /// A stream source is first spooled within the import, in the background,
+    /// after which the copy job is built and started: the import may then be
+    /// listed and destroyed while it is spooling.
+    /// Creating an import which already exists is a no-op.
+    pub async fn create(
+        uuid: &str,
+        replica_uuid: &str,
+        source: ImportSource,
+    ) -> Result<(), RebuildError> {
+        if Self::lookup(uuid).is_ok() || SPOOLING_IMPORTS.lock().contains_key(uuid) {
+            return Ok(());
+        }
+        let builder = Self::builder()
+            .with_uuid(uuid)
+            .with_replica_uuid(replica_uuid)
+            .with_source(source.clone());
+
+        let ImportSource::Stream(path) = source else {
+            let job = builder.build().await?.store()?;
+            let _receiver = job.start().await?;
+            return Ok(());
+        };
+
+        let lvol = ReplicaImportJobBuilder::lookup_lvol(replica_uuid)?;
+        let (replica_size, block_len) = (lvol.size(), lvol.as_bdev().block_len() as u64);
+        let spooling = Arc::new(SpoolingImport {
+            uuid: uuid.to_string(),
+            replica_uuid: replica_uuid.to_string(),
+            source_uri: format!("stream://{}", path.display()),
+            progress: Default::default(),
+            error: Mutex::new(None),
+            start_time: Utc::now(),
+        });
+        SPOOLING_IMPORTS
+            .lock()
+            .insert(uuid.to_string(), spooling.clone());
+
+        Reactors::master().send_future(async move {
+            let result = async {
+                let spool = SpoolFile::spool(
+                    &path,
+                    &spooling.uuid,
+                    replica_size,
+                    block_len,
+                    spooling.progress.clone(),
+                )
+                .await?;
+                let job = builder.with_spool(spool).build().await?.store()?;
+                if SPOOLING_IMPORTS.lock().remove(&spooling.uuid).is_none() {
+                    // Destroyed while the job was being built.
+                    job.destroy();
+                    return Ok(());
+                }
+                let _receiver = job.start().await?;
+                Ok::<_, RebuildError>(())
+            }
+            .await;
+            if let Err(error) = result {
+                if !spooling.progress.cancelled.load(Ordering::Relaxed) {
+                    error!("Replica import {} failed: {error}", spooling.uuid);
+                    *spooling.error.lock() = Some(error.to_string());
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Cancel the import with the given uuid if it is still spooling its
+    /// source, returning whether it was found.
+    fn cancel_spooling(uuid: &str) -> bool {
+        match SPOOLING_IMPORTS.lock().remove(uuid) {
+            Some(spooling) => {
+                spooling.progress.cancelled.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancel all the imports which are still spooling their source.
+    pub(crate) fn cancel_all_spooling() {
+        for (_, spooling) in SPOOLING_IMPORTS.lock().drain() {
+            spooling.progress.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Arguments of the `replica_import_create` json-rpc method.
+#[derive(Debug, Deserialize)]
+struct CreateImportArgs {
+    /// Uuid of the import job, defaults to the replica uuid.
+    uuid: Option<String>,
+    /// Uuid of the replica to import into.
+    replica_uuid: String,
+    /// Uri of the source to import from.
+    source_uri: String,
+}
+
+/// Arguments of the `replica_import_list` json-rpc method.
+#[derive(Debug, Deserialize)]
+struct ListImportArgs {
+    /// Uuid of the import job.
+    uuid: Option<String>,
+}
+
+/// Arguments of the `replica_import_destroy` json-rpc method.
+#[derive(Debug, Deserialize)]
+struct DestroyImportArgs {
+    /// Uuid of the import job.
+    uuid: String,
+}
+
+/// Status of a replica import, as returned by the json-rpc methods.
+#[derive(Debug, Serialize)]
+pub(crate) struct ReplicaImportStatus {
+    uuid: String,
+    replica_uuid: String,
+    source_uri: String,
+    state: String,
+    total: u64,
+    imported: u64,
+    transferred: u64,
+    remaining: u64,
+    progress: u64,
+    /// Bytes of a stream source spooled so far.
+    spooled: u64,
+    /// Why the import failed, if it failed while spooling.
+    error: Option<String>,
+    start_time: DateTime<Utc>,
+    end_time: Option<DateTime<Utc>>,
+}
+
+impl ReplicaImportStatus {
+    /// Collect the status of the given spooling import.
+    fn spooling(spooling: &SpoolingImport) -> Self {
+        let error = spooling.error.lock().clone();
+        Self {
+            uuid: spooling.uuid.clone(),
+            replica_uuid: spooling.replica_uuid.clone(),
+            source_uri: spooling.source_uri.clone(),
+            state: if error.is_some() {
+                "failed"
+            } else {
+                "spooling"
+            }
+            .to_string(),
+            total: 0,
+            imported: 0,
+            transferred: 0,
+            remaining: 0,
+            progress: 0,
+            spooled: spooling.progress.spooled.load(Ordering::Relaxed),
+            error,
+            start_time: spooling.start_time,
+            end_time: None,
+        }
+    }
+    /// Collect the status of the import with the given uuid.
+    async fn lookup(uuid: &str) -> Result<Self, RebuildError> {
+        if let Some(spooling) = SPOOLING_IMPORTS.lock().get(uuid) {
+            return Ok(Self::spooling(spooling));
+        }
+        Ok(Self::collect(&*ReplicaImportJob::lookup(uuid)?).await)
+    }
+    /// Collect the status of the given import job.
+    async fn collect(job: &ReplicaImportJob) -> Self {
+        let stats = job.stats().await;
+        Self {
+            uuid: job.uuid().to_string(),
+            replica_uuid: job.replica_uuid().to_string(),
+            source_uri: job.source_uri().to_string(),
+            state: job.state().to_string(),
+            total: stats.blocks_total * stats.block_size,
+            imported: stats.blocks_recovered * stats.block_size,
+            transferred: stats.blocks_transferred * stats.block_size,
+            remaining: stats.blocks_remaining * stats.block_size,
+            progress: stats.progress,
+            spooled: job.spool.as_ref().map_or(0, |spool| spool.size),
+            error: None,
+            start_time: stats.start_time,
+            end_time: stats.end_time,
+        }
+    }
+}
+
+type ImportFuture<R> = Pin<Box<dyn Future<Output = Result<R, RebuildError>>>>;
+
+/// Register the replica import json-rpc methods.
+pub(crate) fn register_jsonrpc_methods() {
+    jsonrpc_register(
+        "replica_import_create",
+        |args: CreateImportArgs| -> ImportFuture<ReplicaImportStatus> {
+            async move {
+                info!("{:?}", args);
+                check_serving().map_err(|source| ReplicaImportError::NotServing { source })?;
+                let uuid = args.uuid.unwrap_or_else(|| args.replica_uuid.clone());
+                let source = ImportSource::parse(&args.source_uri)?;
+                ReplicaImportJob::create(&uuid, &args.replica_uuid, source).await?;
+                ReplicaImportStatus::lookup(&uuid).await
+            }
+            .boxed_local()
+        },
+    );
+
+    jsonrpc_register(
+        "replica_import_list",
+        |args: Option<ListImportArgs>| -> ImportFuture<Vec<ReplicaImportStatus>> {
+            async move {
+                if let Some(uuid) = args.and_then(|a| a.uuid) {
+                    return Ok(vec![ReplicaImportStatus::lookup(&uuid).await?]);
+                }
+                let mut imports = SPOOLING_IMPORTS
+                    .lock()
+                    .values()
+                    .map(|spooling| ReplicaImportStatus::spooling(spooling))
+                    .collect::<Vec<_>>();
+                for job in ReplicaImportJob::list() {
+                    imports.push(ReplicaImportStatus::collect(&job).await);
+                }
+                Ok(imports)
+            }
+            .boxed_local()
+        },
+    );
+
+    jsonrpc_register(
+        "replica_import_destroy",
+        |args: DestroyImportArgs| -> ImportFuture<()> {
+            async move {
+                info!("{:?}", args);
+                if ReplicaImportJob::cancel_spooling(&args.uuid) {
+                    info!("Replica import {} cancelled while spooling", args.uuid);
+                    return Ok(());
+                }
+                let job = ReplicaImportJob::lookup(&args.uuid)?;
+                let rx = match job.force_stop() {
+                    either::Either::Left(chan) => chan.await,
+                    either::Either::Right(stopped) => Ok(stopped),
+                };
+                info!("Replica import stopped: {rx:?}");
+                job.destroy();
+                Ok(())
+            }
+            .boxed_local()
+        },
+    );
+}
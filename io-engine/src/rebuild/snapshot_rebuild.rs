@@ -40,16 +40,18 @@ impl Deref for SnapshotRebuildJob {
     }
 }
 
+/// A uri which may have to be created before it can be opened, and which is
+/// destroyed on drop if created by this.
 #[derive(Default)]
-struct Uri {
+pub(super) struct Uri {
     create: bool,
     delete: bool,
-    uri: String,
+    pub(super) uri: String,
 }
 impl Uri {
     /// Return a new `Self` with flag indicating if it needs to be created
     /// before it can be opened.
-    pub fn new<I: Into<String>>(uri: I, create: bool) -> Self {
+    pub(super) fn new<I: Into<String>>(uri: I, create: bool) -> Self {
         Self {
             create,
             delete: false,
@@ -58,16 +60,20 @@ impl Uri {
     }
     /// Opens or Creates the uri.
     /// If created then the uri is also closed on drop.
-    async fn open_create(mut self) -> Result<Uri, SnapshotRebuildError> {
+    pub(super) async fn open_create(self) -> Result<Uri, SnapshotRebuildError> {
+        self.try_open_create()
+            .await
+            .map_err(|(uri, source)| SnapshotRebuildError::UriBdevOpen { uri, source })
+    }
+    /// Opens or Creates the uri, returning the uri and the `BdevError` on
+    /// failure.
+    pub(super) async fn try_open_create(mut self) -> Result<Uri, (String, BdevError)> {
         if self.create {
             if let Err(source) = device_create(&self.uri).await {
                 if matches!(source, BdevError::BdevExists { .. }) {
                     self.delete = false;
                 } else {
-                    return Err(SnapshotRebuildError::UriBdevOpen {
-                        uri: self.uri.clone(),
-                        source,
-                    });
+                    return Err((self.uri.clone(), source));
                 }
             } else {
                 self.delete = true;
@@ -76,7 +82,7 @@ impl Uri {
         Ok(self)
     }
     /// Closes the uri if it was created by this.
-    async fn close(mut self) -> Result<(), SnapshotRebuildError> {
+    pub(super) async fn close(mut self) -> Result<(), SnapshotRebuildError> {
         if self.delete {
             Self::destroy(&self.uri).await;
             self.delete = false;
@@ -188,11 +188,17 @@ pub trait IReplicaFactory {
 pub struct ReplicaBdevStats {
     pub stats: BdevStats,
     pub entity_id: Option<String>,
+    /// Bytes released back to the pool by trims of the replica.
+    pub reclaimed_bytes: u64,
 }
 impl ReplicaBdevStats {
     /// Create a new `Self` from the given parts.
-    pub fn new(stats: BdevStats, entity_id: Option<String>) -> Self {
-        Self { stats, entity_id }
+    pub fn new(stats: BdevStats, entity_id: Option<String>, reclaimed_bytes: u64) -> Self {
+        Self {
+            stats,
+            entity_id,
+            reclaimed_bytes,
+        }
     }
 }
 
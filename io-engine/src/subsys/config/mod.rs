@@ -25,16 +25,31 @@ use crate::{
 };
 
 #[derive(Debug, Clone, Snafu)]
-pub enum Error {}
+#[snafu(visibility(pub(crate)), context(suffix(false)))]
+#[allow(missing_docs)]
+pub enum Error {
+    #[snafu(display("No configuration file was loaded"))]
+    NoConfigFile {},
+    #[snafu(display("Failed to read the configuration file {file}: {reason}"))]
+    ReadConfig { file: String, reason: String },
+    #[snafu(display("Invalid configuration: {reason}"))]
+    InvalidConfig { reason: String },
+}
 
 impl RpcErrorCode for Error {
     fn rpc_error_code(&self) -> Code {
-        Code::InternalError
+        match self {
+            Self::NoConfigFile {} => Code::NotFound,
+            Self::ReadConfig { .. } | Self::InvalidConfig { .. } => Code::InvalidParams,
+        }
     }
 }
 
 pub(crate) mod opts;
 pub(crate) mod pool;
+mod reload;
+
+pub use reload::ReloadReport;
 
 pub static CONFIG: OnceCell<Config> = OnceCell::new();
 
@@ -72,6 +87,12 @@ impl ConfigSubsystem {
             f.boxed_local()
         });
 
+        // re-read the config file and apply the settings which can be
+        // changed at runtime, reporting the ones which need a restart.
+        jsonrpc_register::<(), _, _, Error>("mayastor_config_reload", |_| {
+            async move { Config::reload() }.boxed_local()
+        });
+
         unsafe { spdk_subsystem_init_next(0) };
     }
 
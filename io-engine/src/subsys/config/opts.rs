@@ -330,7 +330,7 @@ impl From<NvmfTransportOpts> for spdk_nvmf_transport_opts {
 }
 
 /// generic settings for the NVMe bdev (all our replicas)
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct NvmeBdevOpts {
     /// action take on timeout
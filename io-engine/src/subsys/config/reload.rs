@@ -0,0 +1,203 @@
+//! Reloading of the configuration file at runtime.
+//!
+//! The configuration file is read again and validated, and the settings which
+//! are safe to change while running are applied:
+//! - the I/O timeouts and the timeout action of the NVMe controllers, and the
+//!   bdev retry count, which take effect right away.
+//! - the keep-alive timeout and the transport retry count of the NVMe
+//!   controllers, and the Command Retry Delay Times of the nvmf target, which
+//!   are only used by the controllers created from now on.
+//!
+//! Any other setting which differs from the one in use only takes effect
+//! after a restart, and is reported as such.
+
+use std::{collections::BTreeMap, fs, sync::Arc};
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{
+    opts::{GetOpts, NvmeBdevOpts, TARGET_CRDT_LEN},
+    Config, Error,
+};
+use crate::{
+    bdev::nvmx::nvme_bdev_reconfigure_timeouts, core::DeviceTimeoutAction,
+    subsys::nvmf::set_target_crdt,
+};
+
+/// NVMe bdev options which take effect without a restart.
+const LIVE_NVME_BDEV_OPTS: [&str; 4] = [
+    "action_on_timeout",
+    "timeout_us",
+    "timeout_admin_us",
+    "bdev_retry_count",
+];
+
+/// Settings which can be changed without a restart, but which are only used
+/// by the NVMe controllers created from now on.
+const NEW_CONTROLLER_OPTS: [&str; 3] = [
+    "nvme_bdev_opts.keep_alive_timeout_ms",
+    "nvme_bdev_opts.transport_retry_count",
+    "nvmf_tgt_conf.crdt",
+];
+
+/// NVMe bdev options in use, which differ from the loaded ones once the
+/// configuration has been reloaded.
+static NVME_BDEV_OPTS: Lazy<RwLock<Arc<NvmeBdevOpts>>> =
+    Lazy::new(|| RwLock::new(Arc::new(Config::get().nvme_bdev_opts.clone())));
+
+/// Command Retry Delay Times of the nvmf target in use.
+static NVMF_TGT_CRDT: Lazy<RwLock<[u16; TARGET_CRDT_LEN]>> =
+    Lazy::new(|| RwLock::new(Config::get().nvmf_tgt_conf.crdt));
+
+/// Outcome of a configuration reload.
+#[derive(Debug, Default, Serialize)]
+pub struct ReloadReport {
+    /// Settings which have been changed, and are in effect.
+    pub applied: Vec<String>,
+    /// Settings which have been changed, but are only used by the NVMe
+    /// controllers created from now on.
+    pub applied_to_new_controllers: Vec<String>,
+    /// Settings which differ from the ones in use, but which only take effect
+    /// after a restart.
+    pub restart_required: Vec<String>,
+}
+
+impl Config {
+    /// The NVMe bdev options in use.
+    pub fn nvme_bdev_opts() -> Arc<NvmeBdevOpts> {
+        NVME_BDEV_OPTS.read().clone()
+    }
+
+    /// Read the configuration file again, and apply the settings which can
+    /// be changed without a restart. This must be called from the master
+    /// reactor.
+    pub fn reload() -> Result<ReloadReport, Error> {
+        let current = Config::get();
+        let Some(file) = current.source.as_ref() else {
+            return Err(Error::NoConfigFile {});
+        };
+
+        // A missing file is not taken as the default configuration here.
+        fs::metadata(file).map_err(|e| Error::ReadConfig {
+            file: file.clone(),
+            reason: e.to_string(),
+        })?;
+        let new = Config::read(file).map_err(|e| Error::ReadConfig {
+            file: file.clone(),
+            reason: e.to_string(),
+        })?;
+        new.validate()?;
+
+        let crdt = new.nvmf_tgt_conf.crdt;
+        let mut running = to_value(current);
+        running["nvme_bdev_opts"] = to_value(Config::nvme_bdev_opts().as_ref());
+        running["nvmf_tgt_conf"]["crdt"] = to_value(&*NVMF_TGT_CRDT.read());
+        let new = to_value(&new);
+
+        let mut report = ReloadReport::default();
+        let mut opts = running["nvme_bdev_opts"].clone();
+        for setting in changes(&running, &new) {
+            if NEW_CONTROLLER_OPTS.contains(&setting.as_str()) {
+                report.applied_to_new_controllers.push(setting);
+                continue;
+            }
+            match setting.strip_prefix("nvme_bdev_opts.") {
+                Some(field) if LIVE_NVME_BDEV_OPTS.contains(&field) => report.applied.push(setting),
+                _ => report.restart_required.push(setting),
+            }
+        }
+        for setting in report
+            .applied
+            .iter()
+            .chain(&report.applied_to_new_controllers)
+        {
+            if let Some(field) = setting.strip_prefix("nvme_bdev_opts.") {
+                opts[field] = new["nvme_bdev_opts"][field].clone();
+            }
+        }
+
+        if *NVMF_TGT_CRDT.read() != crdt {
+            set_target_crdt(crdt);
+            *NVMF_TGT_CRDT.write() = crdt;
+        }
+
+        if opts != running["nvme_bdev_opts"] {
+            let mut opts: NvmeBdevOpts =
+                serde_json::from_value(opts).expect("NVMe bdev options must deserialize");
+            // SPDK only accepts new options while it has no NVMe controllers
+            // of its own, ours use the options in use regardless. The bdev
+            // retry count is only used by SPDK though.
+            if !opts.set() {
+                let setting = "nvme_bdev_opts.bdev_retry_count".to_string();
+                if let Some(i) = report.applied.iter().position(|s| *s == setting) {
+                    report.applied.remove(i);
+                    report.restart_required.push(setting);
+                    report.restart_required.sort();
+                }
+                opts.bdev_retry_count = Config::nvme_bdev_opts().bdev_retry_count;
+            }
+            *NVME_BDEV_OPTS.write() = Arc::new(opts);
+            nvme_bdev_reconfigure_timeouts();
+        }
+
+        info!(
+            "Reloaded configuration file {file}, applied: {:?}, applied to new NVMe \
+            controllers: {:?}, restart required: {:?}",
+            report.applied, report.applied_to_new_controllers, report.restart_required
+        );
+        Ok(report)
+    }
+
+    /// Check the settings which are not checked when parsing.
+    fn validate(&self) -> Result<(), Error> {
+        DeviceTimeoutAction::try_from(self.nvme_bdev_opts.action_on_timeout)
+            .map_err(|reason| Error::InvalidConfig { reason })?;
+        Ok(())
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).expect("configuration must serialize")
+}
+
+/// Get the dotted paths of the settings which differ, except for the source
+/// of the configuration.
+fn changes(running: &Value, new: &Value) -> Vec<String> {
+    let mut running_settings = BTreeMap::new();
+    let mut new_settings = BTreeMap::new();
+    flatten("", running, &mut running_settings);
+    flatten("", new, &mut new_settings);
+
+    let mut settings = running_settings
+        .keys()
+        .chain(new_settings.keys())
+        .filter(|s| *s != "source")
+        .filter(|s| running_settings.get(*s) != new_settings.get(*s))
+        .cloned()
+        .collect::<Vec<_>>();
+    settings.sort();
+    settings.dedup();
+    settings
+}
+
+/// Collect the settings by their dotted path.
+fn flatten(path: &str, value: &Value, settings: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                flatten(&path, value, settings);
+            }
+        }
+        value => {
+            settings.insert(path.to_string(), value.clone());
+        }
+    }
+}
@@ -4,11 +4,15 @@
 pub use config::{
     opts::{NexusOpts, NvmeBdevOpts},
     pool::PoolConfig,
-    Config, ConfigSubsystem,
+    Config, ConfigSubsystem, ReloadReport,
+};
+pub(crate) use nvmf::{
+    flush_sync_reservations, listen_reservations, restore_reservations, stop_sync_reservations,
+    sync_reservations, unlisten_reservations,
 };
 pub use nvmf::{
-    set_snapshot_time, Error as NvmfError, NvmeCpl, NvmfReq, NvmfSubsystem, SubType,
-    Target as NvmfTarget,
+    set_snapshot_time, Error as NvmfError, NvmeCpl, NvmfReq, NvmfSubsystem, PtplRegistrant,
+    PtplState, SubType, Target as NvmfTarget,
 };
 use spdk_rs::libspdk::{spdk_add_subsystem, spdk_add_subsystem_depend, spdk_subsystem_depend};
 use std::mem::zeroed;
@@ -200,6 +200,16 @@ async fn create_remote_snapshot(bdev: UntypedBdev, params: SnapshotParams, nvmf_
     let owner = replica_ops.entity_id().unwrap_or("unknown".to_string());
     let replica = replica_ops.uuid();
     info!(owner, replica, ?params, "Creating a remote snapshot");
+    // The pool is locked as for any other snapshot, so that a check of the
+    // pool doesn't take the new snapshot for an orphaned blob.
+    let _lock_guard = match crate::lvs::lock_pool(&replica_ops.pool_name()).await {
+        Ok(guard) => guard,
+        Err(error) => {
+            error!(?error, owner, replica, "Failed to lock the replica pool");
+            nvmf_req.complete_error(error.to_errno() as i32);
+            return;
+        }
+    };
     match replica_ops.create_snapshot(params).await {
         Ok(_) => {
             info!(
@@ -15,17 +15,23 @@ use snafu::Snafu;
 
 pub use admin_cmd::{set_snapshot_time, NvmeCpl, NvmfReq};
 use poll_groups::PollGroup;
+pub(crate) use reservation::{
+    flush_sync_reservations, listen_reservations, restore_reservations, stop_sync_reservations,
+    sync_reservations, unlisten_reservations,
+};
+pub use reservation::{PtplRegistrant, PtplState};
 use spdk_rs::libspdk::{spdk_subsystem, spdk_subsystem_fini_next, spdk_subsystem_init_next};
 pub use subsystem::{NvmfSubsystem, SubType};
 pub use target::Target;
 
 use crate::{
     jsonrpc::{Code, RpcErrorCode},
-    subsys::{nvmf::target::NVMF_TGT, Config},
+    subsys::{config::opts::TARGET_CRDT_LEN, nvmf::target::NVMF_TGT, Config},
 };
 
 mod admin_cmd;
 mod poll_groups;
+mod reservation;
 mod subsystem;
 mod target;
 mod transport;
@@ -78,6 +84,15 @@ thread_local! {
     pub (crate) static NVMF_PGS: RefCell<Vec<PollGroup>> = const { RefCell::new(Vec::new()) };
 }
 
+/// Set the Command Retry Delay Times of the nvmf target, which are reported
+/// by the controllers created from now on. This must be called from the
+/// master reactor.
+pub(crate) fn set_target_crdt(crdt: [u16; TARGET_CRDT_LEN]) {
+    if Config::get().nexus_opts.nvmf_enable {
+        NVMF_TGT.with(|tgt| tgt.borrow().set_crdt(crdt));
+    }
+}
+
 impl Nvmf {
     /// initialize a new subsystem that handles NVMF (confusing names, cannot
     /// help it)
@@ -88,6 +103,8 @@ impl Nvmf {
 
         // set up custom NVMe Admin command handler
         admin_cmd::setup_create_snapshot_hdlr();
+        // keep the reservations of the namespaces through power loss
+        reservation::setup_reservation_ops();
 
         if Config::get().nexus_opts.nvmf_enable {
             NVMF_TGT.with(|tgt| tgt.borrow_mut().next_state());
@@ -0,0 +1,455 @@
+//! Persistence of the NVMe reservations of the shared namespaces through
+//! power loss.
+//!
+//! SPDK calls the custom reservation operations registered here on every
+//! reservation change of a namespace added with a PTPL file, and when such a
+//! namespace is added. The reservation state is kept in the PTPL file, in the
+//! same format as SPDK does. The state of a namespace can also be kept in the
+//! persistent store, so that a nexus re-created on another node restores the
+//! reservations of its hosts:
+//! 1. the state is fetched from the store before the namespace is added, see
+//!    `restore_reservations`, and takes precedence over the PTPL file.
+//! 2. every reservation change is then saved to the store, see
+//!    `sync_reservations`, from the master reactor, with the saves of a
+//!    namespace being serialized and coalesced.
+//!
+//! SPDK expects the reservation operations to complete synchronously, while
+//! the store is remote, so a reservation command completes once its change is
+//! in the PTPL file, and before it's saved to the store. A nexus re-created on
+//! another node after a crash may thus miss the latest changes, as it would
+//! with the PTPL file of another node. A nexus which is unshared, e.g. to move
+//! it, waits for the pending saves first, see `flush_sync_reservations`.
+//!
+//! The state of a namespace can also be handed to a listener, see
+//! `listen_reservations`, once loaded and on every change. SPDK reports every
+//! registration change, but the acquires and releases only while the
+//! reservations persist through power loss, so they're kept persisting on the
+//! namespaces with a listener, whatever the hosts ask for. This way, every
+//! reservation command of the hosts reaches the listener, before it completes.
+//!
+//! SPDK only allows custom reservation operations for all the namespaces, so
+//! the replica namespaces, which are added with a PTPL file too, use them as
+//! well. Without a listener nor a persistent store, they keep the PTPL file in
+//! the format of SPDK, exactly as SPDK would, so that the existing files of
+//! the replicas are still loaded, and the reservations persist only as the
+//! hosts ask for.
+
+use std::{
+    collections::HashMap,
+    ffi::CStr,
+    os::raw::c_char,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use futures::{channel::oneshot, future::Either};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use spdk_rs::libspdk::{
+    spdk_nvmf_ns, spdk_nvmf_ns_get_bdev, spdk_nvmf_ns_reservation_ops, spdk_nvmf_reservation_info,
+    spdk_nvmf_set_custom_ns_reservation_ops,
+};
+
+use crate::{
+    core::{Reactors, UntypedBdev},
+    persistent_store::PersistentStore,
+    sleep::mayastor_sleep,
+};
+
+/// How long to wait before retrying to save a reservation state to the
+/// persistent store.
+const STORE_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reservation state of a namespace, in the format of the SPDK PTPL files.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PtplState {
+    /// Whether the reservations persist through power loss.
+    pub ptpl: bool,
+    /// Reservation type.
+    #[serde(default)]
+    pub rtype: u32,
+    /// Key of the reservation holder.
+    #[serde(default)]
+    pub crkey: u64,
+    /// Uuid of the namespace bdev.
+    pub bdev_uuid: String,
+    /// Host uuid of the reservation holder.
+    #[serde(default)]
+    pub holder_uuid: String,
+    /// Registered hosts.
+    #[serde(default)]
+    pub registrants: Vec<PtplRegistrant>,
+}
+
+/// A registered host.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PtplRegistrant {
+    /// Registration key.
+    pub rkey: u64,
+    /// Host uuid.
+    pub host_uuid: String,
+}
+
+/// Get a string from a nul terminated C char array.
+fn from_chars(chars: &[c_char]) -> String {
+    let bytes = chars
+        .iter()
+        .take_while(|c| **c != 0)
+        .map(|c| *c as u8)
+        .collect::<Vec<_>>();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Copy a string into a C char array, truncating it if needed.
+fn to_chars(s: &str, chars: &mut [c_char]) {
+    chars.fill(0);
+    let len = s.len().min(chars.len() - 1);
+    for (c, b) in chars.iter_mut().zip(&s.as_bytes()[..len]) {
+        *c = *b as c_char;
+    }
+}
+
+impl From<&spdk_nvmf_reservation_info> for PtplState {
+    fn from(info: &spdk_nvmf_reservation_info) -> Self {
+        Self {
+            ptpl: info.ptpl_activated,
+            rtype: info.rtype as u32,
+            crkey: info.crkey,
+            bdev_uuid: from_chars(&info.bdev_uuid),
+            holder_uuid: from_chars(&info.holder_uuid),
+            registrants: info
+                .registrants
+                .iter()
+                .take(info.num_regs as usize)
+                .map(|r| PtplRegistrant {
+                    rkey: r.rkey,
+                    host_uuid: from_chars(&r.host_uuid),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl PtplState {
+    /// Fill the given reservation information, which SPDK restores.
+    fn fill(&self, info: &mut spdk_nvmf_reservation_info) {
+        info.ptpl_activated = self.ptpl;
+        info.rtype = self.rtype as _;
+        info.crkey = self.crkey;
+        to_chars(&self.bdev_uuid, &mut info.bdev_uuid);
+        to_chars(&self.holder_uuid, &mut info.holder_uuid);
+        let registrants = self.registrants.iter().take(info.registrants.len());
+        info.num_regs = registrants.len() as u32;
+        for (reg, r) in info.registrants.iter_mut().zip(registrants) {
+            reg.rkey = r.rkey;
+            to_chars(&r.host_uuid, &mut reg.host_uuid);
+        }
+    }
+
+    /// Read the state from the given PTPL file, if it exists.
+    fn read(path: &Path) -> Result<Option<Self>, std::io::Error> {
+        let json = match std::fs::read(path) {
+            Ok(json) => json,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        serde_json::from_slice(&json).map(Some).map_err(Into::into)
+    }
+
+    /// Write the state to the given PTPL file, atomically.
+    fn write(&self, path: &Path) -> Result<(), std::io::Error> {
+        let tmp = path.with_extension("tmp");
+        let json = serde_json::to_vec_pretty(self).expect("PTPL state is serializable");
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&tmp, json).and_then(|_| std::fs::rename(&tmp, path))
+    }
+}
+
+/// States fetched from the persistent store, keyed by PTPL file, which are
+/// restored when the namespace is added.
+static RESTORED: Lazy<Mutex<HashMap<PathBuf, PtplState>>> = Lazy::new(Default::default);
+
+/// Saving of the reservation state of a namespace to the persistent store.
+struct StoreSync {
+    /// Key of the state in the persistent store.
+    key: String,
+    /// Latest state which isn't saved yet.
+    pending: Option<PtplState>,
+    /// Whether a save is in progress.
+    saving: bool,
+    /// Signalled once the save in progress is done, when stopping.
+    stopped: Option<oneshot::Sender<()>>,
+    /// Signalled once no save is left, when flushing.
+    flushed: Vec<oneshot::Sender<()>>,
+}
+
+/// PTPL files whose reservation state is saved to the persistent store.
+static STORE_SYNC: Lazy<Mutex<HashMap<PathBuf, StoreSync>>> = Lazy::new(Default::default);
+
+/// Called with the reservation state of a namespace, from the thread of its
+/// nvmf subsystem.
+pub(crate) type ReservationListener = Box<dyn Fn(&PtplState) + Send>;
+
+/// Listeners of the reservation state, keyed by PTPL file.
+static LISTENERS: Lazy<Mutex<HashMap<PathBuf, ReservationListener>>> = Lazy::new(Default::default);
+
+/// Restore the given reservation state, fetched from the persistent store,
+/// when the namespace with the given PTPL file is added, instead of the state
+/// within the PTPL file. Without a state, the PTPL file is used.
+pub(crate) fn restore_reservations(path: PathBuf, state: Option<PtplState>) {
+    let mut restored = RESTORED.lock();
+    match state {
+        Some(state) => restored.insert(path, state),
+        None => restored.remove(&path),
+    };
+}
+
+/// Save the reservation state of the namespace with the given PTPL file to the
+/// persistent store, under the given key, on every reservation change.
+/// Saving an already saved namespace only updates the key.
+pub(crate) fn sync_reservations(path: PathBuf, key: String) {
+    STORE_SYNC
+        .lock()
+        .entry(path)
+        .and_modify(|sync| sync.key.clone_from(&key))
+        .or_insert(StoreSync {
+            key,
+            pending: None,
+            saving: false,
+            stopped: None,
+            flushed: Vec::new(),
+        });
+}
+
+/// Stop saving the reservation state of the namespace with the given PTPL
+/// file to the persistent store, waiting for the save in progress, if any, so
+/// that it doesn't outlive a removal of the state from the store.
+pub(crate) async fn stop_sync_reservations(path: &Path) {
+    RESTORED.lock().remove(path);
+    let stopped = {
+        let mut syncs = STORE_SYNC.lock();
+        match syncs.get_mut(path) {
+            Some(sync) if sync.saving => {
+                let (sender, receiver) = oneshot::channel();
+                sync.pending = None;
+                sync.stopped = Some(sender);
+                receiver
+            }
+            _ => {
+                syncs.remove(path);
+                return;
+            }
+        }
+    };
+    stopped.await.ok();
+}
+
+/// Wait, up to the given timeout, for the reservation changes of the
+/// namespace with the given PTPL file to be saved to the persistent store.
+/// Returns whether they're all saved.
+pub(crate) async fn flush_sync_reservations(path: &Path, timeout: Duration) -> bool {
+    let flushed = {
+        let mut syncs = STORE_SYNC.lock();
+        match syncs.get_mut(path) {
+            Some(sync) if sync.saving => {
+                let (sender, receiver) = oneshot::channel();
+                sync.flushed.push(sender);
+                receiver
+            }
+            _ => return true,
+        }
+    };
+    matches!(
+        futures::future::select(flushed, mayastor_sleep(timeout)).await,
+        Either::Left((Ok(()), _))
+    )
+}
+
+/// Call the given listener with the reservation state of the namespace with
+/// the given PTPL file, once loaded and on every change, replacing any
+/// previous listener.
+pub(crate) fn listen_reservations(path: PathBuf, listener: ReservationListener) {
+    LISTENERS.lock().insert(path, listener);
+}
+
+/// Stop listening to the reservation state of the namespace with the given
+/// PTPL file.
+pub(crate) fn unlisten_reservations(path: &Path) {
+    LISTENERS.lock().remove(path);
+}
+
+/// Check if the namespace with the given PTPL file has a listener.
+fn is_listened(path: &Path) -> bool {
+    LISTENERS.lock().contains_key(path)
+}
+
+/// Hand the given state to the listener of the namespace with the given PTPL
+/// file, if any.
+fn notify_listener(path: &Path, state: &PtplState) {
+    if let Some(listener) = LISTENERS.lock().get(path) {
+        listener(state);
+    }
+}
+
+/// Queue the save of the given state to the persistent store, if the
+/// namespace with the given PTPL file is saved there. The save happens after
+/// the reservation command completes, see the module docs.
+fn queue_store_sync(path: &Path, state: PtplState) {
+    let mut syncs = STORE_SYNC.lock();
+    let Some(sync) = syncs.get_mut(path).filter(|sync| sync.stopped.is_none()) else {
+        return;
+    };
+    sync.pending = Some(state);
+    if sync.saving {
+        // The save in progress picks up the latest state once done.
+        return;
+    }
+    sync.saving = true;
+    Reactors::master().send_future(save_to_store(path.to_path_buf()));
+}
+
+/// Save the pending states of the namespace with the given PTPL file, one at
+/// a time, until none is left.
+async fn save_to_store(path: PathBuf) {
+    loop {
+        let (key, state) = {
+            let mut syncs = STORE_SYNC.lock();
+            let Some(sync) = syncs.get_mut(&path) else {
+                return;
+            };
+            let Some(state) = sync.pending.take() else {
+                sync.saving = false;
+                for flushed in sync.flushed.drain(..) {
+                    flushed.send(()).ok();
+                }
+                if let Some(stopped) = sync.stopped.take() {
+                    syncs.remove(&path);
+                    stopped.send(()).ok();
+                }
+                return;
+            };
+            (sync.key.clone(), state)
+        };
+
+        if let Err(error) = PersistentStore::put(&key, &state).await {
+            warn!(
+                "Failed to save the reservation state of {path:?} to the persistent store: {error}"
+            );
+            // Retry, unless a newer state is pending or the saving stops.
+            let retry = match STORE_SYNC.lock().get_mut(&path) {
+                Some(sync) if sync.stopped.is_none() => {
+                    sync.pending.get_or_insert(state);
+                    true
+                }
+                _ => false,
+            };
+            if retry {
+                mayastor_sleep(STORE_RETRY_INTERVAL).await.ok();
+            }
+        }
+    }
+}
+
+/// Get the PTPL file of the given namespace.
+fn ptpl_file(ns: *const spdk_nvmf_ns) -> Option<PathBuf> {
+    let file = unsafe { (*ns).ptpl_file };
+    if file.is_null() {
+        return None;
+    }
+    let file = unsafe { CStr::from_ptr(file) };
+    Some(PathBuf::from(file.to_string_lossy().into_owned()))
+}
+
+/// Check if the reservations of the given namespace persist through power
+/// loss, i.e. if it was added with a PTPL file.
+extern "C" fn is_ptpl_capable(ns: *const spdk_nvmf_ns) -> bool {
+    ptpl_file(ns).is_some()
+}
+
+/// Get the uuid of the bdev of the given namespace.
+fn ns_bdev_uuid(ns: *const spdk_nvmf_ns) -> String {
+    UntypedBdev::checked_from_ptr(unsafe { spdk_nvmf_ns_get_bdev(ns as *mut _) })
+        .map(|bdev| bdev.uuid_as_string())
+        .unwrap_or_default()
+}
+
+/// Persist the reservation state of the given namespace, which just changed.
+/// Called by SPDK from the reservation command which changed it, on the
+/// thread of the nvmf subsystem, before the command completes. Only the PTPL
+/// file is written before then, the persistent store being saved to later.
+extern "C" fn update(ns: *const spdk_nvmf_ns, info: *const spdk_nvmf_reservation_info) -> i32 {
+    let Some(path) = ptpl_file(ns) else {
+        return -libc::EINVAL;
+    };
+    let mut state = PtplState::from(unsafe { &*info });
+    if !state.ptpl && is_listened(&path) {
+        // A host cleared the persistence, which would hide the next
+        // reservation changes from the listener, see the module docs.
+        // SPDK only reads the flag of the namespace from its own thread,
+        // which this is.
+        unsafe { (*(ns as *mut spdk_nvmf_ns)).ptpl_activated = true };
+        state.ptpl = true;
+    }
+    // The change is made whether or not it persists.
+    notify_listener(&path, &state);
+    if let Err(error) = state.write(&path) {
+        error!("Failed to write the reservation state to {path:?}: {error}");
+        return -error.raw_os_error().unwrap_or(libc::EIO);
+    }
+    queue_store_sync(&path, state);
+    0
+}
+
+/// Load the reservation state of the given namespace, which is being added.
+extern "C" fn load(ns: *const spdk_nvmf_ns, info: *mut spdk_nvmf_reservation_info) -> i32 {
+    let Some(path) = ptpl_file(ns) else {
+        return -libc::EINVAL;
+    };
+
+    let restored = RESTORED.lock().remove(&path);
+    let mut state = match restored {
+        Some(state) => {
+            // Keep the PTPL file in line with the restored state.
+            if let Err(error) = state.write(&path) {
+                warn!("Failed to write the restored reservation state to {path:?}: {error}");
+            }
+            info!("Restoring the reservation state of {path:?} from the persistent store");
+            state
+        }
+        None => match PtplState::read(&path) {
+            Ok(Some(state)) => state,
+            Ok(None) if is_listened(&path) => PtplState {
+                bdev_uuid: ns_bdev_uuid(ns),
+                ..Default::default()
+            },
+            Ok(None) => return 0,
+            Err(error) => {
+                error!("Failed to read the reservation state from {path:?}: {error}");
+                return -libc::EINVAL;
+            }
+        },
+    };
+    if is_listened(&path) {
+        // Keep every reservation change reported, see the module docs.
+        state.ptpl = true;
+    }
+    state.fill(unsafe { &mut *info });
+    notify_listener(&path, &state);
+    0
+}
+
+/// Custom reservation operations, which replace the SPDK ones for all the
+/// namespaces, see the module docs.
+static RESERVATION_OPS: spdk_nvmf_ns_reservation_ops = spdk_nvmf_ns_reservation_ops {
+    is_ptpl_capable: Some(is_ptpl_capable),
+    update: Some(update),
+    load: Some(load),
+};
+
+/// Register the custom reservation operations, before any namespace is
+/// added.
+pub(super) fn setup_reservation_ops() {
+    unsafe { spdk_nvmf_set_custom_ns_reservation_ops(&RESERVATION_OPS) };
+}
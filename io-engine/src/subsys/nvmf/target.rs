@@ -20,7 +20,7 @@ use crate::{
     core::{Cores, MayastorEnvironment, Mthread, Reactors},
     ffihelper::{AsStr, FfiResult},
     subsys::{
-        config::opts::NvmfTgtTransport,
+        config::opts::{NvmfTgtTransport, TARGET_CRDT_LEN},
         nvmf::{
             poll_groups::PollGroup,
             subsystem::NvmfSubsystem,
@@ -133,6 +133,16 @@ impl Target {
             }
             TargetState::AddListener => {
                 self.next_state = TargetState::Running;
+                if MayastorEnvironment::global_or_default()
+                    .handover_from()
+                    .is_some()
+                {
+                    // The listener ports are still used by the io-engine
+                    // instance we take over from.
+                    info!("nvmf target listeners are deferred until the handover completes");
+                    self.next_state();
+                    return;
+                }
                 self.listen()
                     .map_err(|e| {
                         error!("failed to listen on address {}", e);
@@ -211,9 +221,52 @@ impl Target {
         });
     }
 
+    /// Listen for incoming connections and advance to the next state.
+    fn listen(&mut self) -> Result<()> {
+        self.add_listeners()?;
+        self.next_state();
+        Ok(())
+    }
+
+    /// Add the listeners which were deferred until the handover from the
+    /// previous io-engine instance, see `TargetState::AddListener`.
+    pub(crate) fn listen_deferred() -> Result<()> {
+        NVMF_TGT.with(|t| t.borrow_mut().add_listeners())
+    }
+
+    /// Stop accepting new connections, so that the io-engine instance which
+    /// takes over can listen. The connected hosts stay connected.
+    pub(crate) fn stop_listen() {
+        NVMF_TGT.with(|t| t.borrow().remove_listeners())
+    }
+
+    /// Stop listening on the addresses of the target.
+    fn remove_listeners(&self) {
+        let cfg = Config::get();
+        let trid_nexus = TransportId::new(cfg.nexus_opts.nvmf_nexus_port, NvmfTgtTransport::Tcp);
+        let trid_replica =
+            TransportId::new(cfg.nexus_opts.nvmf_replica_port, NvmfTgtTransport::Tcp);
+        let mut trid_vec = vec![trid_nexus, trid_replica];
+        // todo: handle by fetching current listeners dynamically here.
+        if self.rdma {
+            trid_vec.push(TransportId::new(
+                cfg.nexus_opts.nvmf_nexus_port,
+                NvmfTgtTransport::Rdma,
+            ));
+            trid_vec.push(TransportId::new(
+                cfg.nexus_opts.nvmf_replica_port,
+                NvmfTgtTransport::Rdma,
+            ));
+        }
+
+        for trid in trid_vec {
+            unsafe { spdk_nvmf_tgt_stop_listen(self.tgt.as_ptr(), trid.as_ptr()) };
+        }
+    }
+
     /// Listen for incoming connections, by default we only listen on the
     /// replica port i.e. NVMF_PORT_REPLICA.
-    fn listen(&mut self) -> Result<()> {
+    fn add_listeners(&mut self) -> Result<()> {
         let cfg = Config::get();
         let trid_nexus = TransportId::new(cfg.nexus_opts.nvmf_nexus_port, NvmfTgtTransport::Tcp);
         let mut opts = spdk_nvmf_listen_opts {
@@ -272,7 +325,6 @@ impl Target {
                 });
         }
 
-        self.next_state();
         Ok(())
     }
 
@@ -414,6 +466,15 @@ impl Target {
         })
     }
 
+    /// Set the Command Retry Delay Times of the target, which are reported
+    /// by the controllers created from now on.
+    pub(crate) fn set_crdt(&self, crdt: [u16; TARGET_CRDT_LEN]) {
+        if self.next_state == TargetState::Init {
+            return;
+        }
+        unsafe { (*self.tgt.as_ptr()).crdt = crdt };
+    }
+
     /// Shutdown procedure.
     fn shutdown(&mut self) {
         extern "C" fn destroy_cb(_arg: *mut c_void, _status: i32) {
@@ -431,29 +492,7 @@ impl Target {
                   use-after-free error"
             );
         } else {
-            let cfg = Config::get();
-            let trid_nexus =
-                TransportId::new(cfg.nexus_opts.nvmf_nexus_port, NvmfTgtTransport::Tcp);
-            let trid_replica =
-                TransportId::new(cfg.nexus_opts.nvmf_replica_port, NvmfTgtTransport::Tcp);
-            let mut trid_vec = vec![trid_nexus, trid_replica];
-            // todo: handle by fetching current listeners dynamically here.
-            // Since this is shutdown path we're good this way for
-            // now.
-            if self.rdma {
-                trid_vec.push(TransportId::new(
-                    cfg.nexus_opts.nvmf_nexus_port,
-                    NvmfTgtTransport::Rdma,
-                ));
-                trid_vec.push(TransportId::new(
-                    cfg.nexus_opts.nvmf_replica_port,
-                    NvmfTgtTransport::Rdma,
-                ));
-            }
-
-            for trid in trid_vec {
-                unsafe { spdk_nvmf_tgt_stop_listen(self.tgt.as_ptr(), trid.as_ptr()) };
-            }
+            self.remove_listeners();
         }
 
         unsafe { spdk_nvmf_tgt_destroy(self.tgt.as_ptr(), Some(destroy_cb), std::ptr::null_mut()) }
@@ -1,9 +1,12 @@
 //! A Registration subsystem is used to keep control-plane in the loop
 //! about the lifecycle of mayastor instances.
 
+mod node_summary;
 /// Module for grpc registration implementation
 pub mod registration_grpc;
 
+pub use node_summary::{FeatureSummary, NodeSummary, PoolSummary, ReactorSummary};
+
 use crate::core::MayastorEnvironment;
 use http::Uri;
 use registration_grpc::Registration;
@@ -0,0 +1,205 @@
+//! Compact summary of the node, which is sent along with every registration
+//! heartbeat so that the control plane knows what the node hosts without
+//! having to list every resource after each (re)start.
+//! As the registration api has no room for it, the summary is sent as JSON
+//! within the `node-summary-bin` request metadata.
+//!
+//! Listing the pools and replicas is not cheap, e.g. for lvm pools, so they
+//! are cached and only listed again once the pool and replica backends have
+//! changed a pool or a replica, or once the cache is older than
+//! `RESOURCES_MAX_AGE`, which catches the changes made otherwise and the
+//! growth of the thin provisioned replicas.
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::{
+    bdev::nexus::nexus_iter,
+    core::{
+        drain::{drain_status, DrainPhase},
+        frozen_reactors, Cores, MayastorFeatures, Reactor,
+    },
+    pool_backend::{ListPoolArgs, PoolFactory},
+    replica_backend::{ListReplicaArgs, ReplicaFactory},
+};
+
+/// Request metadata key of the node summary.
+pub(super) const NODE_SUMMARY_KEY: &str = "node-summary-bin";
+
+/// Maximum age of the cached pools and replicas.
+const RESOURCES_MAX_AGE: Duration = Duration::from_secs(60);
+
+/// Pools and number of replicas, as last listed.
+#[derive(Debug, Clone)]
+struct Resources {
+    listed_at: Instant,
+    pools: Vec<PoolSummary>,
+    replica_count: u32,
+}
+
+/// The cached pools and replicas.
+static RESOURCES: Lazy<Mutex<Option<Resources>>> = Lazy::new(|| Mutex::new(None));
+
+/// Whether the pools or replicas may have changed since they were listed.
+static RESOURCES_CHANGED: AtomicBool = AtomicBool::new(true);
+
+/// Change of a pool or a replica, which marks the cached pools and replicas
+/// as stale once it is dropped, i.e. once the change has completed, whether
+/// it succeeded or not.
+#[must_use]
+pub(crate) struct ResourcesChange(());
+
+impl Drop for ResourcesChange {
+    fn drop(&mut self) {
+        NodeSummary::invalidate();
+    }
+}
+
+/// Capacity of a pool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolSummary {
+    /// Name of the pool.
+    pub name: String,
+    /// Uuid of the pool.
+    pub uuid: String,
+    /// Capacity of the pool in bytes.
+    pub capacity: u64,
+    /// Bytes allocated from the pool.
+    pub used: u64,
+    /// Bytes committed to the replicas of the pool.
+    pub committed: u64,
+}
+
+/// Health of the reactors.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReactorSummary {
+    /// Number of reactors.
+    pub count: u32,
+    /// Number of frozen reactors, if freeze detection is enabled.
+    pub frozen: Option<u32>,
+}
+
+/// Features enabled on the node.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureSummary {
+    /// Lvm pools are enabled.
+    pub lvm: bool,
+    /// Snapshot rebuilds are enabled.
+    pub snap_rebuild: bool,
+    /// The nvmf target supports RDMA.
+    pub rdma: bool,
+}
+
+/// Summary of the node, see the module docs.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeSummary {
+    /// Pools of the node.
+    pub pools: Vec<PoolSummary>,
+    /// Number of nexuses.
+    pub nexus_count: u32,
+    /// Number of replicas, not counting the snapshots.
+    pub replica_count: u32,
+    /// Health of the reactors.
+    pub reactors: ReactorSummary,
+    /// Enabled features.
+    pub features: FeatureSummary,
+    /// Drain phase of the node.
+    pub drain: DrainPhase,
+}
+
+impl NodeSummary {
+    /// Mark the cached pools and replicas as stale, e.g. once a pool or a
+    /// replica may have been created, changed or destroyed.
+    pub fn invalidate() {
+        RESOURCES_CHANGED.store(true, Ordering::Release);
+    }
+
+    /// Start changing a pool or a replica, see `ResourcesChange`.
+    pub(crate) fn change() -> ResourcesChange {
+        ResourcesChange(())
+    }
+
+    /// Collect the summary of the node, listing the pools and replicas only
+    /// if the cached ones are stale.
+    /// Must be called from the primary reactor, see `collect_at_primary`.
+    pub async fn collect() -> Self {
+        let cached = RESOURCES.lock().clone().filter(|r| {
+            r.listed_at.elapsed() < RESOURCES_MAX_AGE && !RESOURCES_CHANGED.load(Ordering::Acquire)
+        });
+        let resources = match cached {
+            Some(resources) => resources,
+            None => {
+                let resources = Self::list_resources().await;
+                *RESOURCES.lock() = Some(resources.clone());
+                resources
+            }
+        };
+        let features = MayastorFeatures::get();
+
+        Self {
+            pools: resources.pools,
+            nexus_count: nexus_iter().count() as u32,
+            replica_count: resources.replica_count,
+            reactors: ReactorSummary {
+                count: Cores::count().into_iter().count() as u32,
+                frozen: frozen_reactors().map(|f| f as u32),
+            },
+            features: FeatureSummary {
+                lvm: features.logical_volume_manager,
+                snap_rebuild: features.snapshot_rebuild,
+                rdma: features.rdma_capable_io_engine,
+            },
+            drain: drain_status().phase,
+        }
+    }
+
+    /// List the pools and count the replicas.
+    async fn list_resources() -> Resources {
+        // Changes made from now on are caught by the next listing.
+        RESOURCES_CHANGED.store(false, Ordering::Release);
+        let listed_at = Instant::now();
+
+        let mut pools = Vec::new();
+        let mut replica_count = 0;
+        for factory in PoolFactory::factories() {
+            match factory.as_factory().list(&ListPoolArgs::default()).await {
+                Ok(list) => pools.extend(list.iter().map(|p| PoolSummary {
+                    name: p.name().to_string(),
+                    uuid: p.uuid(),
+                    capacity: p.capacity(),
+                    used: p.used(),
+                    committed: p.committed(),
+                })),
+                Err(error) => warn!("Failed to list pools for the node summary: {error}"),
+            }
+        }
+        for factory in ReplicaFactory::factories() {
+            match factory.as_factory().list(&ListReplicaArgs::default()).await {
+                Ok(list) => replica_count += list.iter().filter(|r| !r.is_snapshot()).count(),
+                Err(error) => warn!("Failed to list replicas for the node summary: {error}"),
+            }
+        }
+
+        Resources {
+            listed_at,
+            pools,
+            replica_count: replica_count as u32,
+        }
+    }
+
+    /// Collect the summary of the node on the primary reactor.
+    pub async fn collect_at_primary() -> Option<Self> {
+        match Reactor::spawn_at_primary(Self::collect()) {
+            Ok(summary) => summary.await.ok(),
+            Err(error) => {
+                warn!("Failed to collect the node summary: {error}");
+                None
+            }
+        }
+    }
+}
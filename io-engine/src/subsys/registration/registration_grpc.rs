@@ -1,6 +1,10 @@
 #![warn(missing_docs)]
 
-use crate::core::{MayastorBugFixes, MayastorFeatures};
+use super::node_summary::{NodeSummary, NODE_SUMMARY_KEY};
+use crate::core::{
+    drain::{start_drain, DrainArgs},
+    MayastorBugFixes, MayastorFeatures, Reactor,
+};
 use futures::{select, FutureExt, StreamExt};
 use http::Uri;
 use io_engine_api::v1::registration::{
@@ -8,12 +12,19 @@ use io_engine_api::v1::registration::{
 };
 use once_cell::sync::OnceCell;
 use std::{env, str::FromStr, time::Duration};
+use strum_macros::{Display, EnumString};
+use tonic::{
+    metadata::{MetadataMap, MetadataValue},
+    transport::ClientTlsConfig,
+};
 use version_info::raw_version_string;
 
 /// Mayastor sends registration messages in this interval (kind of heart-beat)
 const HB_INTERVAL_SEC: Duration = Duration::from_secs(5);
 /// How long we wait to send a registration message before timing out
 const HB_TIMEOUT_SEC: Duration = Duration::from_secs(5);
+/// Response metadata key of the control plane commands.
+const NODE_COMMANDS_KEY: &str = "node-commands";
 /// The http2 keep alive interval.
 const HTTP_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(10);
 /// The http2 keep alive TIMEOUT.
@@ -40,6 +51,48 @@ impl FromStr for ApiVersion {
     }
 }
 
+/// Commands which the control plane sends to the node, as a comma separated
+/// list within the `node-commands` metadata of the registration response.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum RegistrationCommand {
+    /// Re-register right away, with an up to date node summary.
+    Resync,
+    /// Drain the node, with the default drain arguments.
+    Drain,
+}
+
+impl RegistrationCommand {
+    /// Parse the commands from the registration response metadata, ignoring
+    /// the unknown ones.
+    pub fn from_metadata(metadata: &MetadataMap) -> Vec<Self> {
+        metadata
+            .get_all(NODE_COMMANDS_KEY)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|cmd| !cmd.is_empty())
+            .filter_map(|cmd| match Self::from_str(cmd) {
+                Ok(cmd) => Some(cmd),
+                Err(_) => {
+                    warn!("Ignoring unknown registration command '{cmd}'");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Whether the command disrupts the node, and so may only be accepted
+    /// from an authenticated control plane.
+    pub fn is_disruptive(&self) -> bool {
+        match self {
+            Self::Resync => false,
+            Self::Drain => true,
+        }
+    }
+}
+
 #[derive(Clone)]
 struct Configuration {
     /// Id of the node that mayastor is running on
@@ -57,6 +110,10 @@ struct Configuration {
     /// Uuid that is randomly generated on process start.
     /// It's used to identify process restarts.
     instance_uuid: uuid::Uuid,
+    /// Whether the control plane is authenticated, i.e. reached over https
+    /// with a TLS configuration, in which case its disruptive commands are
+    /// accepted.
+    authenticated: bool,
 }
 
 /// Registration component for registering dataplane to controlplane
@@ -66,10 +123,10 @@ pub struct Registration {
     config: Configuration,
     /// Registration client
     client: registration_client::RegistrationClient<tonic::transport::Channel>,
-    /// Receive channel for messages and termination
-    rcv_chan: async_channel::Receiver<()>,
-    /// Termination channel
-    fini_chan: async_channel::Sender<()>,
+    /// Receive channel for commands and termination
+    rcv_chan: async_channel::Receiver<RegistrationCommand>,
+    /// Command and termination channel
+    fini_chan: async_channel::Sender<RegistrationCommand>,
 }
 
 static GRPC_REGISTRATION: OnceCell<Registration> = OnceCell::new();
@@ -81,28 +138,35 @@ impl Registration {
         grpc_endpoint: &str,
         registration_addr: Uri,
         api_versions: Vec<ApiVersion>,
-    ) {
-        GRPC_REGISTRATION.get_or_init(|| {
+        tls: Option<ClientTlsConfig>,
+    ) -> Result<(), tonic::transport::Error> {
+        GRPC_REGISTRATION.get_or_try_init(|| {
             Registration::new(
                 node,
                 node_nqn,
                 grpc_endpoint,
                 registration_addr,
                 api_versions,
+                tls,
             )
-        });
+        })?;
+        Ok(())
     }
 
-    /// Create a new registration instance
+    /// Create a new registration instance.
+    /// The TLS configuration, which may present a client certificate, is only
+    /// used with an https registration address, and must be valid as the
+    /// registration never falls back to plaintext.
     pub fn new(
         node: &str,
         node_nqn: &Option<String>,
         grpc_endpoint: &str,
         registration_addr: Uri,
         api_versions: Vec<ApiVersion>,
-    ) -> Self {
-        let (msg_sender, msg_receiver) = async_channel::unbounded::<()>();
-        let config = Configuration {
+        tls: Option<ClientTlsConfig>,
+    ) -> Result<Self, tonic::transport::Error> {
+        let (msg_sender, msg_receiver) = async_channel::unbounded::<RegistrationCommand>();
+        let mut config = Configuration {
             api_versions,
             node: node.to_owned(),
             node_nqn: node_nqn.to_owned(),
@@ -116,19 +180,25 @@ impl Registration {
                 _ => HB_TIMEOUT_SEC,
             },
             instance_uuid: uuid::Uuid::new_v4(),
+            authenticated: false,
         };
-        let endpoint = tonic::transport::Endpoint::from(registration_addr)
+        let https = registration_addr.scheme_str() == Some("https");
+        let mut endpoint = tonic::transport::Endpoint::from(registration_addr)
             .connect_timeout(config.hb_timeout_sec)
             .timeout(config.hb_timeout_sec)
             .http2_keep_alive_interval(HTTP_KEEP_ALIVE_INTERVAL)
             .keep_alive_timeout(HTTP_KEEP_ALIVE_TIMEOUT);
+        if let Some(tls) = tls.filter(|_| https) {
+            endpoint = endpoint.tls_config(tls)?;
+            config.authenticated = true;
+        }
         let channel = endpoint.connect_lazy();
-        Self {
+        Ok(Self {
             config,
             client: registration_client::RegistrationClient::new(channel),
             rcv_chan: msg_receiver,
             fini_chan: msg_sender,
-        }
+        })
     }
 
     /// Get the instance uuid.
@@ -146,8 +216,14 @@ impl Registration {
         self.fini_chan.close();
     }
 
-    /// Register a new node over rpc
-    pub async fn register(&mut self) -> Result<(), tonic::Status> {
+    /// Queue a command, as if it was sent by the control plane.
+    pub fn command(&self, command: RegistrationCommand) {
+        self.fini_chan.try_send(command).ok();
+    }
+
+    /// Register a new node over rpc, along with the node summary, and return
+    /// the commands sent back by the control plane.
+    pub async fn register(&mut self) -> Result<Vec<RegistrationCommand>, tonic::Status> {
         let api_versions = self.config.api_versions.iter();
         let api_versions = api_versions.map(|v| ApiVersionGrpc::from(*v) as i32);
         let register = RegisterRequest {
@@ -160,10 +236,53 @@ impl Registration {
             bugfixes: Some(MayastorBugFixes::get().into()),
             version: Some(raw_version_string()),
         };
+        let mut request = tonic::Request::new(register);
+        if let Some(summary) = NodeSummary::collect_at_primary().await {
+            match serde_json::to_vec(&summary) {
+                Ok(summary) => {
+                    request
+                        .metadata_mut()
+                        .insert_bin(NODE_SUMMARY_KEY, MetadataValue::from_bytes(&summary));
+                }
+                Err(error) => warn!("Failed to serialize the node summary: {error}"),
+            }
+        }
         self.client
-            .register(tonic::Request::new(register))
+            .register(request)
             .await
-            .map(|_| ())
+            .map(|response| RegistrationCommand::from_metadata(response.metadata()))
+    }
+
+    /// Check if the disruptive commands of the control plane are accepted.
+    pub fn is_authenticated(&self) -> bool {
+        self.config.authenticated
+    }
+
+    /// Handle a command of the control plane, or a queued one, which is
+    /// trusted. The disruptive commands of an unauthenticated control plane
+    /// are ignored.
+    /// Returns whether to re-register right away.
+    fn handle_command(&self, command: RegistrationCommand, trusted: bool) -> bool {
+        info!("Received registration command '{command}'");
+        if command.is_disruptive() && !trusted {
+            warn!(
+                "Ignoring registration command '{command}', as the control plane is not \
+                authenticated, it must be reached over https with a TLS configuration"
+            );
+            return false;
+        }
+        match command {
+            RegistrationCommand::Resync => true,
+            RegistrationCommand::Drain => {
+                if let Err(error) = Reactor::spawn_at_primary(async {
+                    start_drain(DrainArgs::default());
+                }) {
+                    error!("Failed to start draining the node: {error}");
+                }
+                // Report the drain within the node summary right away.
+                true
+            }
+        }
     }
 
     /// Deregister a node over rpc
@@ -205,9 +324,14 @@ impl Registration {
             self.config.node, self.config.grpc_endpoint
         );
         let mut rcv_chan = Box::pin(self.rcv_chan.clone());
+        let mut resynced = false;
         loop {
+            let mut resync = false;
             match self.register().await {
-                Ok(_) => {
+                Ok(commands) => {
+                    for command in commands {
+                        resync |= self.handle_command(command, self.config.authenticated);
+                    }
                     if !show_error {
                         info!(
                             "Re-registered '{:?}' with grpc server {} ...",
@@ -223,11 +347,18 @@ impl Registration {
                     }
                 }
             };
+            // don't let the control plane keep us in a tight loop
+            resynced = resync && !resynced;
+            if resynced {
+                continue;
+            }
             select! {
                 _ = tokio::time::sleep(self.config.hb_interval_sec).fuse() => continue,
                 msg = rcv_chan.next().fuse() => {
                     match msg {
-                        Some(_) => info!("Messages have not been implemented yet"),
+                        Some(command) => {
+                            self.handle_command(command, true);
+                        }
                         _ => {
                             info!("Terminating the registration handler");
                             break;
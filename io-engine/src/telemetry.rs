@@ -0,0 +1,107 @@
+//! OpenTelemetry tracing of the control-plane operations.
+//!
+//! When an OTLP endpoint is configured, a span is created for every gRPC
+//! request and exported to the collector, along with the spans of the long
+//! running operations started by the request, such as adding a child to a
+//! nexus or rebuilding it. The span of a request is a child of the W3C trace
+//! context found in the request metadata, if any, so that a control-plane
+//! request can be followed into the io-engine.
+//!
+//! These spans use their own target, which is not logged.
+
+use once_cell::sync::OnceCell;
+use opentelemetry::{global, propagation::Extractor, trace::TracerProvider as _, KeyValue};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{propagation::TraceContextPropagator, runtime, trace, Resource};
+use tracing::{Span, Subscriber};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::constants::{SERVICE_NAME, TELEMETRY_TARGET};
+
+/// The tracer provider, kept to flush the spans on shutdown.
+static PROVIDER: OnceCell<trace::TracerProvider> = OnceCell::new();
+
+/// Reads the trace context from the headers of a gRPC request.
+struct HeaderExtractor<'a>(&'a http::HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+/// Create the layer which exports the spans to the OTLP collector at the
+/// given endpoint. This is called before the logger is set up, so errors are
+/// printed to stderr.
+pub(crate) fn layer<S>(endpoint: &url::Url) -> Option<OpenTelemetryLayer<S, trace::Tracer>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    // The exporter and its batch processor run on the tokio runtime.
+    let _rt = crate::core::runtime::enter();
+
+    let exporter = match SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint.as_str())
+        .build()
+    {
+        Ok(exporter) => exporter,
+        Err(error) => {
+            eprintln!("Failed to create the OTLP exporter for {endpoint}: {error}");
+            return None;
+        }
+    };
+
+    let provider = trace::TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new([KeyValue::new("service.name", SERVICE_NAME)]))
+        .build();
+    let tracer = provider.tracer(SERVICE_NAME);
+
+    PROVIDER.set(provider.clone()).ok();
+    global::set_tracer_provider(provider);
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    Some(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// Flush the pending spans and stop exporting them.
+pub fn shutdown() {
+    if let Some(provider) = PROVIDER.get() {
+        if let Err(error) = provider.shutdown() {
+            warn!("Failed to flush the OTLP spans: {error}");
+        }
+    }
+}
+
+/// Create the span of a gRPC request, as a child of the trace context of the
+/// request, if any.
+pub fn grpc_span(request: &http::Request<()>) -> Span {
+    let span = tracing::info_span!(
+        target: TELEMETRY_TARGET,
+        "grpc",
+        otel.name = request.uri().path(),
+        otel.kind = "server",
+        rpc.system = "grpc",
+    );
+    let parent =
+        global::get_text_map_propagator(|p| p.extract(&HeaderExtractor(request.headers())));
+    span.set_parent(parent);
+    span
+}
+
+/// Create the span of a long running operation on the given resource, as a
+/// child of the current span.
+pub fn operation_span(name: &str, resource: &str) -> Span {
+    tracing::info_span!(
+        target: TELEMETRY_TARGET,
+        "operation",
+        otel.name = name,
+        resource,
+    )
+}
@@ -0,0 +1,88 @@
+use common::MayastorTest;
+use io_engine::{core::MayastorCliArgs, subsys::Config};
+
+pub mod common;
+
+static CONFIG_FILE: &str = "/tmp/io-engine-config-reload.yaml";
+
+#[tokio::test]
+async fn config_reload() {
+    std::fs::write(
+        CONFIG_FILE,
+        "nvme_bdev_opts:\n  timeout_us: 5000000\n  keep_alive_timeout_ms: 10000\n",
+    )
+    .unwrap();
+
+    let ms = MayastorTest::new(MayastorCliArgs {
+        mayastor_config: Some(CONFIG_FILE.to_string()),
+        ..Default::default()
+    });
+
+    // Nothing has changed.
+    ms.spawn(async {
+        let report = Config::reload().unwrap();
+        assert!(report.applied.is_empty());
+        assert!(report.restart_required.is_empty());
+    })
+    .await;
+
+    // The timeouts and retry counts are applied, the keep-alive timeout and
+    // the retry delays only for new controllers, and the number of namespaces
+    // needs a restart.
+    std::fs::write(
+        CONFIG_FILE,
+        "nvme_bdev_opts:\n  timeout_us: 2000000\n  keep_alive_timeout_ms: 3000\n  \
+         bdev_retry_count: 2\n  transport_retry_count: 4\n\
+         nvmf_tgt_conf:\n  max_namespaces: 64\n  crdt: [5, 0, 0]\n",
+    )
+    .unwrap();
+    ms.spawn(async {
+        let report = Config::reload().unwrap();
+        assert_eq!(
+            report.applied,
+            vec![
+                "nvme_bdev_opts.bdev_retry_count",
+                "nvme_bdev_opts.timeout_us"
+            ]
+        );
+        assert_eq!(
+            report.applied_to_new_controllers,
+            vec![
+                "nvme_bdev_opts.keep_alive_timeout_ms",
+                "nvme_bdev_opts.transport_retry_count",
+                "nvmf_tgt_conf.crdt"
+            ]
+        );
+        assert_eq!(
+            report.restart_required,
+            vec!["nvmf_tgt_conf.max_namespaces"]
+        );
+
+        let opts = Config::nvme_bdev_opts();
+        assert_eq!(opts.timeout_us, 2_000_000);
+        assert_eq!(opts.keep_alive_timeout_ms, 3_000);
+        assert_eq!(opts.bdev_retry_count, 2);
+        assert_eq!(opts.transport_retry_count, 4);
+        assert_eq!(Config::get().nvme_bdev_opts.timeout_us, 5_000_000);
+
+        // The applied settings are in use, only the restart is left.
+        let report = Config::reload().unwrap();
+        assert!(report.applied.is_empty());
+        assert!(report.applied_to_new_controllers.is_empty());
+        assert_eq!(
+            report.restart_required,
+            vec!["nvmf_tgt_conf.max_namespaces"]
+        );
+    })
+    .await;
+
+    // An invalid file is rejected, and nothing is applied.
+    std::fs::write(CONFIG_FILE, "nvme_bdev_opts:\n  timeout: 1\n").unwrap();
+    ms.spawn(async {
+        assert!(Config::reload().is_err());
+        assert_eq!(Config::nvme_bdev_opts().timeout_us, 2_000_000);
+    })
+    .await;
+
+    std::fs::remove_file(CONFIG_FILE).unwrap();
+}
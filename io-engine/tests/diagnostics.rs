@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+
+pub mod common;
+use common::compose::MayastorTest;
+use io_engine::core::{
+    diagnostics::{bundle_names, capture_bundle, load_bundle, DiagnosticBundle, MAX_BUNDLES},
+    MayastorCliArgs,
+};
+
+static MAYASTOR: OnceCell<MayastorTest> = OnceCell::new();
+
+const RPC_SOCKET: &str = "/tmp/diagnostics.sock";
+const DIAGNOSTICS_DIR: &str = "/tmp/io-engine-diagnostics-test";
+
+fn get_ms() -> &'static MayastorTest<'static> {
+    MAYASTOR.get_or_init(|| {
+        std::fs::remove_dir_all(DIAGNOSTICS_DIR).ok();
+        MayastorTest::new(MayastorCliArgs {
+            rpc_address: RPC_SOCKET.to_string(),
+            diagnostics_dir: DIAGNOSTICS_DIR.to_string(),
+            ..Default::default()
+        })
+    })
+}
+
+/// The bundles share a directory, so the cases are run one after the other.
+#[tokio::test]
+async fn diagnostics() {
+    get_ms();
+
+    bundle_capture().await;
+    bundle_eviction().await;
+    diagnostics_get().await;
+}
+
+async fn capture() -> String {
+    let path = capture_bundle(0, Some("thread 1 - main".to_string()))
+        .await
+        .unwrap();
+    // bundle names have a millisecond resolution
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    path.file_stem().unwrap().to_string_lossy().into_owned()
+}
+
+async fn bundle_capture() {
+    assert!(bundle_names().is_empty());
+    assert!(load_bundle(None).is_err());
+
+    let name = capture().await;
+    assert!(name.starts_with("freeze-"));
+    assert!(name.ends_with("-core0"));
+    assert_eq!(bundle_names(), vec![name.clone()]);
+
+    let bundle = load_bundle(None).unwrap();
+    assert_eq!(bundle.name, name);
+    assert_eq!(bundle.frozen_core, 0);
+    assert_eq!(bundle.stacks.as_deref(), Some("thread 1 - main"));
+    assert!(!bundle.reactors.is_empty());
+    assert!(bundle.reactors.iter().all(|r| r.responsive));
+    assert!(bundle
+        .reactors
+        .iter()
+        .flat_map(|r| &r.threads)
+        .any(|t| !t.pollers.is_empty()));
+    assert_eq!(bundle.rebuilds.map(|r| r.len()), Some(0));
+
+    assert!(load_bundle(Some("freeze-unknown".to_string())).is_err());
+}
+
+async fn bundle_eviction() {
+    let first = bundle_names();
+    let mut captured = Vec::new();
+    for _ in 0..MAX_BUNDLES {
+        captured.push(capture().await);
+    }
+
+    // the oldest bundles are removed first
+    let names = bundle_names();
+    assert_eq!(names.len(), MAX_BUNDLES);
+    assert_eq!(names, captured);
+    assert!(first.iter().all(|n| !names.contains(n)));
+}
+
+async fn diagnostics_get() {
+    let names: Vec<String> = jsonrpc::call(RPC_SOCKET, "diagnostics_list", None::<()>)
+        .await
+        .unwrap();
+    assert_eq!(names, bundle_names());
+
+    let latest: DiagnosticBundle = jsonrpc::call(RPC_SOCKET, "diagnostics_get", None::<()>)
+        .await
+        .unwrap();
+    assert_eq!(Some(&latest.name), names.last());
+
+    let oldest: DiagnosticBundle = jsonrpc::call(
+        RPC_SOCKET,
+        "diagnostics_get",
+        Some(serde_json::json!({ "name": names[0] })),
+    )
+    .await
+    .unwrap();
+    assert_eq!(oldest.name, names[0]);
+
+    let missing = jsonrpc::call::<_, DiagnosticBundle>(
+        RPC_SOCKET,
+        "diagnostics_get",
+        Some(serde_json::json!({ "name": "freeze-unknown" })),
+    )
+    .await;
+    assert!(missing.is_err());
+}
@@ -0,0 +1,124 @@
+use once_cell::sync::OnceCell;
+use std::time::Duration;
+
+pub mod common;
+use common::compose::MayastorTest;
+use io_engine::core::{
+    drain::{
+        check_serving, drain_status, is_draining, start_drain, stop_drain, DrainArgs, DrainError,
+        DrainPhase, DEFAULT_REBUILD_TIMEOUT,
+    },
+    MayastorCliArgs,
+};
+use io_engine_tests::pool::PoolBuilderLocal;
+
+static MAYASTOR: OnceCell<MayastorTest> = OnceCell::new();
+
+fn get_ms() -> &'static MayastorTest<'static> {
+    MAYASTOR.get_or_init(|| MayastorTest::new(MayastorCliArgs::default()))
+}
+
+/// Poll the drain progress until it reaches the given phase.
+async fn wait_phase(ms: &MayastorTest<'static>, phase: DrainPhase) -> bool {
+    for _ in 0..20 {
+        if ms.spawn(async { drain_status() }).await.phase == phase {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    false
+}
+
+/// The rebuilds are given some time to finish, unless told otherwise.
+#[test]
+fn drain_args() {
+    assert_eq!(
+        DrainArgs::default().rebuild_timeout,
+        DEFAULT_REBUILD_TIMEOUT
+    );
+    let args: DrainArgs = serde_json::from_str("{}").unwrap();
+    assert_eq!(args.rebuild_timeout, DEFAULT_REBUILD_TIMEOUT);
+    let args: DrainArgs = serde_json::from_str(r#"{"rebuild_timeout": 0}"#).unwrap();
+    assert_eq!(args.rebuild_timeout, 0);
+}
+
+/// The drain state is global, so the cases are run one after the other.
+#[tokio::test]
+async fn drain_node() {
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        PoolBuilderLocal::malloc("md_drain", 64).await.unwrap();
+        assert!(!is_draining());
+        assert!(check_serving().is_ok());
+        assert_eq!(drain_status().phase, DrainPhase::Serving);
+        // Undraining a serving node is a no-op.
+        assert_eq!(stop_drain().unwrap().phase, DrainPhase::Serving);
+    })
+    .await;
+
+    undrain_node(ms).await;
+    drain_to_completion(ms).await;
+}
+
+async fn undrain_node(ms: &MayastorTest<'static>) {
+    ms.spawn(async move {
+        let status = start_drain(DrainArgs::default());
+        assert!(is_draining());
+        assert!(matches!(check_serving(), Err(DrainError::NodeDraining {})));
+        assert_eq!(status.phase, DrainPhase::Failover);
+
+        // The drain task didn't get to shut down the nexuses yet.
+        assert_eq!(stop_drain().unwrap().phase, DrainPhase::Undraining);
+    })
+    .await;
+
+    assert!(
+        wait_phase(ms, DrainPhase::Serving).await,
+        "the node should be serving again"
+    );
+
+    ms.spawn(async {
+        let status = drain_status();
+        assert!(status.errors.is_empty());
+        assert!(!is_draining());
+        assert!(check_serving().is_ok());
+        assert!(io_engine::lvs::Lvs::lookup("md_drain").is_some());
+    })
+    .await;
+}
+
+async fn drain_to_completion(ms: &MayastorTest<'static>) {
+    ms.spawn(async move {
+        let status = start_drain(DrainArgs::default());
+        assert!(is_draining());
+        assert_ne!(status.phase, DrainPhase::Serving);
+        assert!(status.started_at.is_some());
+    })
+    .await;
+
+    assert!(
+        wait_phase(ms, DrainPhase::Drained).await,
+        "the node should be drained"
+    );
+
+    ms.spawn(async {
+        let status = drain_status();
+        assert!(status.errors.is_empty());
+        assert!(status.finished_at.is_some());
+
+        // Draining again is a no-op.
+        assert_eq!(start_drain(DrainArgs::default()).phase, DrainPhase::Drained);
+        assert!(io_engine::lvs::Lvs::lookup("md_drain").is_none());
+
+        // Once the nexuses are shut down, the drain can no longer be undone.
+        assert!(matches!(
+            stop_drain(),
+            Err(DrainError::DrainIrreversible {
+                phase: DrainPhase::Drained
+            })
+        ));
+        assert!(is_draining());
+    })
+    .await;
+}
@@ -0,0 +1,163 @@
+pub mod common;
+
+use common::compose::{
+    rpc::v1::{
+        json::JsonRpcRequest,
+        pool::{CreatePoolRequest, DestroyPoolRequest, ListPoolOptions},
+        GrpcConnect, RpcHandle,
+    },
+    Builder,
+};
+use io_engine::grpc::audit::redact;
+use serde_json::{json, Value};
+use tonic::{Code, Status};
+
+#[derive(Debug)]
+#[allow(dead_code)]
+struct ReserveRequest {
+    uuid: String,
+    resv_key: u64,
+    preempt_key: Option<u64>,
+    key: String,
+}
+
+/// Call a json-rpc method of the io-engine.
+async fn json_rpc(rpc: &mut RpcHandle, method: &str, params: Value) -> Result<Value, Status> {
+    let response = rpc
+        .json
+        .json_rpc_call(JsonRpcRequest {
+            method: method.to_string(),
+            params: params.to_string(),
+        })
+        .await?
+        .into_inner();
+    Ok(serde_json::from_str(&response.result).unwrap())
+}
+
+/// Get the audit records matching the given filter.
+async fn audit_log(rpc: &mut RpcHandle, filter: Value) -> Vec<Value> {
+    let records = json_rpc(rpc, "audit_log", filter).await.unwrap();
+    records.as_array().unwrap().clone()
+}
+
+#[test]
+fn audit_redact() {
+    let args = format!(
+        "{:?}",
+        ReserveRequest {
+            uuid: "5a4e3c5c-8e0b-4c5b-9c3e-1f2d3c4b5a69".into(),
+            resv_key: 0x1234,
+            preempt_key: Some(0x5678),
+            key: "secret \"value\"".into(),
+        }
+    );
+    assert_eq!(
+        redact(&args),
+        "ReserveRequest { uuid: \"5a4e3c5c-8e0b-4c5b-9c3e-1f2d3c4b5a69\", \
+         resv_key: <redacted>, preempt_key: <redacted>, key: <redacted> }"
+    );
+    assert_eq!(
+        redact("CreatePoolRequest { name: \"p0\", disks: [\"aio:///dev/sda\"] }"),
+        "CreatePoolRequest { name: \"p0\", disks: [\"aio:///dev/sda\"] }"
+    );
+    assert_eq!(
+        redact("Connect { dhchap_secret: \"abc\", psk: None }"),
+        "Connect { dhchap_secret: <redacted>, psk: <redacted> }"
+    );
+}
+
+#[tokio::test]
+async fn audit_log_filter() {
+    common::composer_init();
+
+    let test = Builder::new()
+        .name("grpc_audit")
+        .network("10.1.0.0/16")
+        .unwrap()
+        .add_container_dbg("ms1")
+        .with_clean(true)
+        .build()
+        .await
+        .unwrap();
+    let mut rpc = GrpcConnect::new(&test).grpc_handle("ms1").await.unwrap();
+
+    let uuid = "0dd2bbd2-3fbc-4b3b-bb1c-1c1f0b6b6e6c";
+    let other = "9b1e6ad8-7f9c-4d84-a6e5-8e0d76a4b8a9";
+
+    // Read-only calls are not recorded.
+    rpc.pool
+        .list_pools(ListPoolOptions {
+            name: None,
+            pooltype: None,
+            uuid: None,
+        })
+        .await
+        .unwrap();
+    assert!(audit_log(&mut rpc, json!({ "method": "list_pools" }))
+        .await
+        .is_empty());
+
+    rpc.pool
+        .create_pool(CreatePoolRequest {
+            name: "p0".into(),
+            uuid: Some(uuid.into()),
+            pooltype: 0,
+            disks: vec!["malloc:///p0?size_mb=64".into()],
+            cluster_size: None,
+            md_args: None,
+        })
+        .await
+        .unwrap();
+    for _ in 0..2 {
+        let status = rpc
+            .pool
+            .destroy_pool(DestroyPoolRequest {
+                name: "nopool".into(),
+                uuid: Some(other.into()),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+    }
+
+    let records = audit_log(&mut rpc, json!({})).await;
+    assert_eq!(records.len(), 3);
+    assert!(records
+        .windows(2)
+        .all(|r| r[0]["id"].as_u64() < r[1]["id"].as_u64()));
+    assert_eq!(records[0]["method"], "create_pool");
+    assert_eq!(records[0]["success"], true);
+    assert_eq!(records[0]["result"], "Ok");
+    assert_eq!(records[0]["resources"], json!([uuid]));
+    assert_eq!(records[1]["method"], "destroy_pool");
+    assert_eq!(records[1]["success"], false);
+    assert!(records[1]["result"]
+        .as_str()
+        .unwrap()
+        .starts_with("NotFound: "));
+
+    let records = audit_log(&mut rpc, json!({ "resource": other.to_uppercase() })).await;
+    assert_eq!(records.len(), 2);
+
+    let records = audit_log(&mut rpc, json!({ "failed": true, "limit": 1 })).await;
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["method"], "destroy_pool");
+
+    let filter = json!({
+        "method": "create_pool",
+        "since_secs": 3600,
+        "caller": "unauthenticated",
+    });
+    assert_eq!(audit_log(&mut rpc, filter).await.len(), 1);
+
+    let filter = json!({ "since_secs": u64::MAX });
+    assert!(json_rpc(&mut rpc, "audit_log", filter).await.is_err());
+
+    rpc.pool
+        .destroy_pool(DestroyPoolRequest {
+            name: "p0".into(),
+            uuid: None,
+        })
+        .await
+        .unwrap();
+}
@@ -0,0 +1,329 @@
+use std::{
+    net::TcpStream,
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use io_engine::grpc::{cert_fingerprint, GrpcRoles, Role};
+use tonic::{
+    transport::{Certificate, Channel, ClientTlsConfig, Identity},
+    Code,
+};
+
+pub mod common;
+use common::compose::{
+    rpc::v1::{
+        json::{JsonRpcClient, JsonRpcRequest},
+        pool::{DestroyPoolRequest, ListPoolOptions, PoolRpcClient},
+        test::{ListFaultInjectionsRequest, TestRpcClient},
+    },
+    Binary, Builder,
+};
+
+#[test]
+fn grpc_required_roles() {
+    assert_eq!(
+        Role::required_for("/mayastor.v1.PoolRpc/ListPools"),
+        Role::ReadOnly
+    );
+    assert_eq!(
+        Role::required_for("/mayastor.v1.PoolRpc/DestroyPool"),
+        Role::Operator
+    );
+    assert_eq!(
+        Role::required_for("/mayastor.v1.TestRpc/WipeReplica"),
+        Role::Test
+    );
+    assert_eq!(
+        Role::required_for("/mayastor.v1.TestRpc/ListFaultInjections"),
+        Role::Test
+    );
+    assert_eq!(
+        Role::required_for("/mayastor.Mayastor/GetMayastorInfo"),
+        Role::ReadOnly
+    );
+    assert_eq!(
+        Role::required_for("/mayastor.v1.JsonRpc/JsonRpcCall"),
+        Role::ReadOnly
+    );
+    assert_eq!(Role::required_for_json("operation_list"), Role::ReadOnly);
+    assert_eq!(
+        Role::required_for_json("nvmf_get_subsystems"),
+        Role::ReadOnly
+    );
+    // a query which is not listed is not open to the readers either
+    assert_eq!(
+        Role::required_for_json("bdev_nvme_get_transport_statistics"),
+        Role::Test
+    );
+    assert_eq!(Role::required_for_json("node_drain"), Role::Operator);
+    assert_eq!(Role::required_for_json("log_set"), Role::Operator);
+    assert_eq!(Role::required_for_json("bdev_malloc_delete"), Role::Test);
+    assert!(Role::ReadOnly < Role::Operator && Role::Operator < Role::Test);
+}
+
+#[test]
+fn grpc_client_roles() {
+    let operator = cert_fingerprint(b"operator");
+    let test = cert_fingerprint(b"test");
+    let yaml = format!(
+        "default: read-only\nclients:\n  {operator}: operator\n  {}: test\n",
+        test.to_uppercase()
+    );
+    let roles = GrpcRoles::from_yaml(yaml.as_bytes()).unwrap();
+
+    assert_eq!(roles.role(Some(&operator)), Role::Operator);
+    assert_eq!(roles.role(Some(&test)), Role::Test);
+    assert_eq!(
+        roles.role(Some(&cert_fingerprint(b"other"))),
+        Role::ReadOnly
+    );
+    assert_eq!(roles.role(None), Role::ReadOnly);
+
+    let roles = GrpcRoles::from_yaml(b"clients: {}").unwrap();
+    assert_eq!(roles.role(None), Role::ReadOnly);
+    assert_eq!(GrpcRoles::default().role(Some(&operator)), Role::ReadOnly);
+    assert!(GrpcRoles::from_yaml(b"default: admin").is_err());
+}
+
+static CERT_DIR: &str = "/tmp/grpc-mtls";
+
+/// Run openssl within the certificate directory.
+fn openssl(args: &[&str]) {
+    let status = Command::new("openssl")
+        .args(args)
+        .current_dir(CERT_DIR)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(status.success(), "openssl {args:?} failed");
+}
+
+/// Generate a key and a certificate signed by the CA of the test.
+fn issue_cert(name: &str, extension: &str) {
+    openssl(&[
+        "req",
+        "-newkey",
+        "rsa:2048",
+        "-nodes",
+        "-keyout",
+        &format!("{name}.key"),
+        "-out",
+        &format!("{name}.csr"),
+        "-subj",
+        &format!("/CN={name}"),
+    ]);
+    std::fs::write(format!("{CERT_DIR}/{name}.ext"), extension).unwrap();
+    openssl(&[
+        "x509",
+        "-req",
+        "-in",
+        &format!("{name}.csr"),
+        "-CA",
+        "ca.pem",
+        "-CAkey",
+        "ca.key",
+        "-CAcreateserial",
+        "-days",
+        "1",
+        "-extfile",
+        &format!("{name}.ext"),
+        "-out",
+        &format!("{name}.pem"),
+    ]);
+}
+
+fn read(name: &str) -> Vec<u8> {
+    std::fs::read(format!("{CERT_DIR}/{name}")).unwrap()
+}
+
+/// Fingerprint of the certificate with the given name.
+fn fingerprint(name: &str) -> String {
+    openssl(&[
+        "x509",
+        "-in",
+        &format!("{name}.pem"),
+        "-outform",
+        "der",
+        "-out",
+        &format!("{name}.der"),
+    ]);
+    cert_fingerprint(&read(&format!("{name}.der")))
+}
+
+/// Connect to the io-engine as the client with the given certificate.
+async fn connect(endpoint: &str, client: Option<&str>) -> Result<Channel, tonic::transport::Error> {
+    let mut tls = ClientTlsConfig::new()
+        .ca_certificate(Certificate::from_pem(read("ca.pem")))
+        .domain_name("ms1");
+    if let Some(client) = client {
+        tls = tls.identity(Identity::from_pem(
+            read(&format!("{client}.pem")),
+            read(&format!("{client}.key")),
+        ));
+    }
+    Channel::from_shared(endpoint.to_string())
+        .unwrap()
+        .tls_config(tls)?
+        .connect()
+        .await
+}
+
+fn list_pools() -> ListPoolOptions {
+    ListPoolOptions {
+        name: None,
+        pooltype: None,
+        uuid: None,
+    }
+}
+
+#[tokio::test]
+async fn grpc_mtls_roles() {
+    common::composer_init();
+
+    let _ = std::fs::remove_dir_all(CERT_DIR);
+    std::fs::create_dir_all(CERT_DIR).unwrap();
+    openssl(&[
+        "req",
+        "-x509",
+        "-newkey",
+        "rsa:2048",
+        "-nodes",
+        "-keyout",
+        "ca.key",
+        "-out",
+        "ca.pem",
+        "-days",
+        "1",
+        "-subj",
+        "/CN=io-engine-test-ca",
+    ]);
+    issue_cert(
+        "ms1",
+        "subjectAltName=DNS:ms1\nextendedKeyUsage=serverAuth\n",
+    );
+    issue_cert("operator", "extendedKeyUsage=clientAuth\n");
+    issue_cert("reader", "extendedKeyUsage=clientAuth\n");
+    std::fs::write(
+        format!("{CERT_DIR}/roles.yaml"),
+        format!("clients:\n  {}: operator\n", fingerprint("operator")),
+    )
+    .unwrap();
+
+    let cert = format!("/host{CERT_DIR}/ms1.pem");
+    let key = format!("/host{CERT_DIR}/ms1.key");
+    let ca = format!("/host{CERT_DIR}/ca.pem");
+    let roles = format!("/host{CERT_DIR}/roles.yaml");
+    let test = Builder::new()
+        .name("grpc_mtls")
+        .network("10.1.0.0/16")
+        .unwrap()
+        .add_container_bin(
+            "ms1",
+            Binary::from_dbg("io-engine")
+                .with_args(vec![
+                    "-l",
+                    "1",
+                    "--grpc-tls-cert",
+                    cert.as_str(),
+                    "--grpc-tls-key",
+                    key.as_str(),
+                    "--grpc-tls-ca",
+                    ca.as_str(),
+                    "--grpc-roles",
+                    roles.as_str(),
+                ])
+                .with_bind("/tmp", "/host/tmp"),
+        )
+        .with_clean(true)
+        .build()
+        .await
+        .unwrap();
+
+    let ip = test.containers().get("ms1").unwrap().1;
+    let endpoint = format!("https://{ip}:10124");
+    let addr = format!("{ip}:10124").parse().unwrap();
+    let start = Instant::now();
+    while TcpStream::connect_timeout(&addr, Duration::from_millis(100)).is_err() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "gRPC server is down"
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    // Plaintext and clients without a certificate are turned away.
+    if let Ok(mut client) = PoolRpcClient::connect(format!("http://{ip}:10124")).await {
+        assert!(client.list_pools(list_pools()).await.is_err());
+    }
+    if let Ok(channel) = connect(&endpoint, None).await {
+        assert!(PoolRpcClient::new(channel)
+            .list_pools(list_pools())
+            .await
+            .is_err());
+    }
+
+    // A client which is not listed only gets the read-only role.
+    let mut reader = PoolRpcClient::new(connect(&endpoint, Some("reader")).await.unwrap());
+    reader.list_pools(list_pools()).await.unwrap();
+    let status = reader
+        .destroy_pool(DestroyPoolRequest {
+            name: "nopool".into(),
+            uuid: None,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    // The operator can manage pools, but neither use the test service nor the
+    // SPDK methods of the JSON-RPC passthrough which are not queries.
+    let channel = connect(&endpoint, Some("operator")).await.unwrap();
+    let status = PoolRpcClient::new(channel.clone())
+        .destroy_pool(DestroyPoolRequest {
+            name: "nopool".into(),
+            uuid: None,
+        })
+        .await
+        .unwrap_err();
+    assert_ne!(status.code(), Code::PermissionDenied);
+    let status = TestRpcClient::new(channel.clone())
+        .list_fault_injections(ListFaultInjectionsRequest {})
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+    let mut json = JsonRpcClient::new(channel);
+    json.json_rpc_call(JsonRpcRequest {
+        method: "nvmf_get_subsystems".into(),
+        params: "{}".into(),
+    })
+    .await
+    .unwrap();
+    json.json_rpc_call(JsonRpcRequest {
+        method: "operation_list".into(),
+        params: "{}".into(),
+    })
+    .await
+    .unwrap();
+    let status = json
+        .json_rpc_call(JsonRpcRequest {
+            method: "bdev_malloc_delete".into(),
+            params: r#"{"name": "nomalloc"}"#.into(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    // The reader can only query through the JSON-RPC passthrough.
+    let channel = connect(&endpoint, Some("reader")).await.unwrap();
+    let status = JsonRpcClient::new(channel)
+        .json_rpc_call(JsonRpcRequest {
+            method: "node_drain".into(),
+            params: "{}".into(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    std::fs::remove_dir_all(CERT_DIR).unwrap();
+}
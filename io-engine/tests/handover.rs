@@ -0,0 +1,195 @@
+//! Live upgrade handover between two io-engine instances of the same node,
+//! while a nexus of another node keeps writing to a replica of the node.
+
+pub mod common;
+
+use std::{process::Command, time::Duration};
+
+use common::{
+    compose::{
+        rpc::v1::{json::JsonRpcRequest, nexus::ChildState, GrpcConnect, RpcHandle},
+        Binary, Builder,
+    },
+    fio::{FioBuilder, FioJobBuilder},
+    nexus::{test_fio_to_nexus, NexusBuilder},
+    pool::PoolBuilder,
+    replica::{list_replicas, ReplicaBuilder},
+};
+use io_engine::core::handover::{HandoverManifest, HandoverRole, HandoverStatus};
+
+const TEST_NAME: &str = "handover";
+const IO_ENGINE: &str = env!("CARGO_BIN_EXE_io-engine");
+const DISK: &str = "/tmp/handover.img";
+const MANIFEST: &str = "/tmp/handover.json";
+/// gRPC port of the new io-engine instance.
+const NEW_GRPC_PORT: u16 = 10125;
+
+/// Run a command within the given container.
+fn docker_exec(container: &str, detach: bool, args: &[&str]) {
+    let mut cmd = Command::new("docker");
+    cmd.arg("exec");
+    if detach {
+        cmd.arg("-d");
+    }
+    let output = cmd.arg(container).args(args).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{args:?}: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Call a json-rpc method of the io-engine.
+async fn json_rpc<T: serde::de::DeserializeOwned>(
+    rpc: &mut RpcHandle,
+    method: &str,
+    params: serde_json::Value,
+) -> T {
+    let response = rpc
+        .json
+        .json_rpc_call(JsonRpcRequest {
+            method: method.to_string(),
+            params: params.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    serde_json::from_str(&response.result).unwrap()
+}
+
+/// Wait for the handover of the new io-engine instance to complete.
+async fn wait_handover_completed(rpc: &mut RpcHandle) -> HandoverStatus {
+    for _ in 0..300 {
+        let status: HandoverStatus = json_rpc(rpc, "handover_status", serde_json::json!({})).await;
+        if status.completed {
+            return status;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("The handover did not complete");
+}
+
+/// Get the state of the nexus child of the given replica.
+async fn child_state(nex: &NexusBuilder, repl: &ReplicaBuilder) -> ChildState {
+    nex.get_nexus_replica_child(repl).await.unwrap().state()
+}
+
+#[tokio::test]
+async fn handover() {
+    common::composer_init();
+
+    // The io-engine of the node runs in the background, so that the
+    // container outlives it and the new instance takes over within it, on the
+    // same address.
+    let test = Builder::new()
+        .name(TEST_NAME)
+        .add_container_bin(
+            "ms_node",
+            Binary::from_path("/bin/sh").with_args(vec!["-c", &format!("{IO_ENGINE} -l 1 & wait")]),
+        )
+        .add_container_bin(
+            "ms_nex",
+            Binary::from_dbg("io-engine").with_args(vec!["-l", "2"]),
+        )
+        .with_clean(true)
+        .build()
+        .await
+        .unwrap();
+
+    let conn = GrpcConnect::new(&test);
+    let ms_node = conn.grpc_handle_shared("ms_node").await.unwrap();
+    let ms_nex = conn.grpc_handle_shared("ms_nex").await.unwrap();
+
+    // The pool must outlive the io-engine instance.
+    docker_exec("ms_node", false, &["truncate", "-s", "128M", DISK]);
+    let mut pool = PoolBuilder::new(ms_node.clone())
+        .with_name("pool0")
+        .with_new_uuid()
+        .with_bdev(&format!("aio://{DISK}?blk_size=512"));
+    let mut repl = ReplicaBuilder::new(ms_node.clone())
+        .with_pool(&pool)
+        .with_name("r0")
+        .with_new_uuid()
+        .with_size_mb(64)
+        .with_thin(true);
+    pool.create().await.unwrap();
+    repl.create().await.unwrap();
+    repl.share().await.unwrap();
+
+    let mut nex = NexusBuilder::new(ms_nex.clone())
+        .with_name("nexus0")
+        .with_new_uuid()
+        .with_size_mb(64)
+        .with_replica(&repl);
+    nex.create().await.unwrap();
+    nex.publish().await.unwrap();
+
+    // The new instance waits for the manifest.
+    docker_exec(
+        "ms_node",
+        true,
+        &[
+            IO_ENGINE,
+            "-l",
+            "2",
+            "--grpc-port",
+            &NEW_GRPC_PORT.to_string(),
+            "-r",
+            "/var/tmp/mayastor-new.sock",
+            "--env-context=--file-prefix=handover",
+            "--handover-from",
+            MANIFEST,
+        ],
+    );
+
+    // Hand over while the host writes through the nexus of the other node.
+    let fio = tokio::spawn({
+        let nex = nex.clone();
+        async move {
+            test_fio_to_nexus(
+                &nex,
+                FioBuilder::new()
+                    .with_job(
+                        FioJobBuilder::new()
+                            .with_rw("randwrite")
+                            .with_runtime(15)
+                            .with_bs(4096)
+                            .with_iodepth(8)
+                            .with_verify("crc32")
+                            .with_verify_fatal(true)
+                            .build(),
+                    )
+                    .build(),
+            )
+            .await
+        }
+    });
+    tokio::time::sleep(Duration::from_secs(3)).await;
+
+    let manifest: HandoverManifest = json_rpc(
+        &mut *ms_node.lock().await,
+        "handover_start",
+        serde_json::json!({ "manifest": MANIFEST }),
+    )
+    .await;
+    assert!(manifest.ready);
+    assert_eq!(manifest.pools.len(), 1);
+    assert_eq!(manifest.pools[0].name, pool.name());
+
+    let ms_new = conn
+        .grpc_handle_shared_at("ms_node", NEW_GRPC_PORT)
+        .await
+        .unwrap();
+    let status = wait_handover_completed(&mut *ms_new.lock().await).await;
+    assert_eq!(status.role, HandoverRole::Incoming);
+    assert_eq!(status.pools, 1);
+    assert!(status.errors.is_empty(), "{:?}", status.errors);
+
+    // The host I/O went through, and the nexus didn't lose its replica.
+    fio.await.unwrap().unwrap();
+    assert_eq!(child_state(&nex, &repl).await, ChildState::Online);
+
+    // The replica is served by the new instance.
+    let replicas = list_replicas(ms_new).await.unwrap();
+    assert!(replicas.iter().any(|r| r.uuid == repl.uuid()));
+}
@@ -227,3 +227,31 @@ async fn test_lock_timed_subsystem() {
 async fn test_lock_timed_resource() {
     test_lock_timed_level(LockLevel::Resource).await
 }
+
+#[tokio::test]
+async fn test_lock_states() {
+    let lock_mgr = get_lock_manager();
+
+    let guard = lock_mgr
+        .get_subsystem(TEST_SUBSYSTEM)
+        .lock_resource("item2", None, false)
+        .await;
+    assert!(guard.is_some(), "Failed to acquire the lock");
+
+    let states = ResourceLockManager::lock_states();
+    assert!(
+        states
+            .iter()
+            .any(|s| s.subsystem.is_none() && s.resource.is_none()),
+        "Global lock is not reported"
+    );
+    assert!(
+        states.iter().any(|s| {
+            s.subsystem.as_deref() == Some(TEST_SUBSYSTEM)
+                && s.resource.is_some()
+                && s.held
+                && s.acquires.is_none()
+        }),
+        "Held resource lock is not reported"
+    );
+}
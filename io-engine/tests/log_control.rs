@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+
+pub mod common;
+use common::compose::MayastorTest;
+use io_engine::{
+    bdev::nexus::{nexus_create, nexus_lookup_mut},
+    core::MayastorCliArgs,
+    log_control::{log_get, log_reset, log_set, nexus_io_tracing, LogChange, LogControlError},
+};
+
+static MAYASTOR: OnceCell<MayastorTest> = OnceCell::new();
+
+fn get_ms() -> &'static MayastorTest<'static> {
+    MAYASTOR.get_or_init(|| MayastorTest::new(MayastorCliArgs::default()))
+}
+
+static NEXUS_NAME: &str = "log_control_nexus";
+
+/// The log settings are global, so the cases are run one after the other.
+#[tokio::test]
+async fn log_control() {
+    let ms = get_ms();
+
+    ms.spawn(log_set_and_reset()).await;
+    ms.spawn(log_spdk_flags()).await;
+    ms.spawn(log_nexus_io_tracing()).await;
+    log_auto_revert(ms).await;
+}
+
+async fn log_set_and_reset() {
+    let initial = log_get();
+
+    let settings = log_set(LogChange {
+        filter: Some("debug,h2=info".to_string()),
+        spdk_level: Some("debug".to_string()),
+        timeout_secs: Some(0),
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(settings.filter.as_deref(), Some("debug,h2=info"));
+    assert_eq!(settings.spdk_level, "debug");
+    assert_eq!(settings.revert_at, None);
+
+    assert!(matches!(
+        log_set(LogChange {
+            filter: Some("info,io_engine=verbose".to_string()),
+            ..Default::default()
+        }),
+        Err(LogControlError::Filter { .. })
+    ));
+    assert!(matches!(
+        log_set(LogChange {
+            spdk_level: Some("verbose".to_string()),
+            ..Default::default()
+        }),
+        Err(LogControlError::InvalidSpdkLevel { .. })
+    ));
+    assert_eq!(log_get().filter.as_deref(), Some("debug,h2=info"));
+
+    let settings = log_reset().unwrap();
+    assert_eq!(settings.filter, initial.filter);
+    assert_eq!(settings.spdk_level, initial.spdk_level);
+    assert_eq!(settings.revert_at, None);
+
+    // A rejected change doesn't change anything.
+    assert!(log_set(LogChange {
+        filter: Some("info,io_engine=verbose".to_string()),
+        spdk_level: Some("debug".to_string()),
+        ..Default::default()
+    })
+    .is_err());
+    let settings = log_get();
+    assert_eq!(settings.spdk_level, initial.spdk_level);
+    assert_eq!(settings.revert_at, None);
+}
+
+async fn log_spdk_flags() {
+    let initial = log_get();
+    assert!(!initial.spdk_flags.contains(&"bdev".to_string()));
+
+    // An unknown flag is rejected before any other flag is enabled.
+    assert!(matches!(
+        log_set(LogChange {
+            enable_flags: vec!["bdev".to_string(), "no_such_flag".to_string()],
+            ..Default::default()
+        }),
+        Err(LogControlError::UnknownSpdkFlag { .. })
+    ));
+    assert_eq!(log_get().spdk_flags, initial.spdk_flags);
+
+    let settings = log_set(LogChange {
+        enable_flags: vec!["bdev".to_string()],
+        timeout_secs: Some(0),
+        ..Default::default()
+    })
+    .unwrap();
+    assert!(settings.spdk_flags.contains(&"bdev".to_string()));
+
+    let settings = log_reset().unwrap();
+    assert_eq!(settings.spdk_flags, initial.spdk_flags);
+}
+
+async fn log_nexus_io_tracing() {
+    nexus_create(
+        NEXUS_NAME,
+        32 * 1024 * 1024,
+        None,
+        &["malloc:///log_control_malloc?size_mb=64".to_string()],
+    )
+    .await
+    .unwrap();
+    assert!(!nexus_io_tracing(NEXUS_NAME));
+
+    let settings = log_set(LogChange {
+        trace_nexus: vec![NEXUS_NAME.to_string()],
+        timeout_secs: Some(0),
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(settings.nexus_io_tracing, vec![NEXUS_NAME.to_string()]);
+    assert!(nexus_io_tracing(NEXUS_NAME));
+
+    assert!(matches!(
+        log_set(LogChange {
+            untrace_nexus: vec![NEXUS_NAME.to_string(), "no_such_nexus".to_string()],
+            ..Default::default()
+        }),
+        Err(LogControlError::NexusNotFound { .. })
+    ));
+    assert!(nexus_io_tracing(NEXUS_NAME));
+
+    let settings = log_reset().unwrap();
+    assert!(settings.nexus_io_tracing.is_empty());
+    assert!(!nexus_io_tracing(NEXUS_NAME));
+
+    nexus_lookup_mut(NEXUS_NAME)
+        .unwrap()
+        .destroy()
+        .await
+        .unwrap();
+}
+
+async fn log_auto_revert(ms: &MayastorTest<'static>) {
+    let initial = ms.spawn(async { log_get() }).await;
+
+    let settings = ms
+        .spawn(async {
+            log_set(LogChange {
+                spdk_level: Some("debug".to_string()),
+                enable_flags: vec!["bdev".to_string()],
+                timeout_secs: Some(1),
+                ..Default::default()
+            })
+            .unwrap()
+        })
+        .await;
+    assert_eq!(settings.spdk_level, "debug");
+    assert!(settings.revert_at.is_some());
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    let settings = ms.spawn(async { log_get() }).await;
+    assert_eq!(settings.spdk_level, initial.spdk_level);
+    assert_eq!(settings.spdk_flags, initial.spdk_flags);
+    assert_eq!(settings.revert_at, None);
+}
@@ -0,0 +1,160 @@
+use io_engine::{
+    bdev::{
+        nexus::{nexus_create, nexus_lookup_mut, FaultReason},
+        nvme_controller_timeouts,
+    },
+    constants::NVME_NQN_PREFIX,
+    core::{DeviceTimeoutAction, MayastorCliArgs},
+};
+
+pub mod common;
+use common::{
+    compose::{
+        rpc::v0::{
+            mayastor::{BdevShareRequest, BdevUri, Null},
+            GrpcConnect,
+        },
+        Builder,
+    },
+    MayastorTest,
+};
+
+static NEXUS_NAME: &str = "child_timeout_nexus";
+
+#[tokio::test]
+async fn child_timeout_policy() {
+    common::composer_init();
+
+    let test = Builder::new()
+        .name("child_timeout_test")
+        .network("10.1.0.0/16")
+        .unwrap()
+        .add_container_dbg("ms1")
+        .with_clean(true)
+        .build()
+        .await
+        .unwrap();
+
+    let grpc = GrpcConnect::new(&test);
+    let mut hdls = grpc.grpc_handles().await.unwrap();
+
+    // Create and share a bdev over nvmf
+    hdls[0].bdev.list(Null {}).await.unwrap();
+    hdls[0]
+        .bdev
+        .create(BdevUri {
+            uri: "malloc:///disk0?size_mb=100".into(),
+        })
+        .await
+        .unwrap();
+    hdls[0]
+        .bdev
+        .share(BdevShareRequest {
+            name: "disk0".into(),
+            proto: "nvmf".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let local_uri = "malloc:///malloc0?blk_size=512&size_mb=100".to_string();
+    let remote_uri = format!(
+        "nvmf://{}:8420/{NVME_NQN_PREFIX}:disk0",
+        hdls[0].endpoint.ip()
+    );
+
+    let mayastor = MayastorTest::new(MayastorCliArgs::default());
+    mayastor
+        .spawn(async move {
+            // The remote child overrides the timeout policy in its URI.
+            let child_uri =
+                format!("{remote_uri}?io_timeout_us=20000000&timeout_action=abort&retry_count=3");
+            nexus_create(
+                NEXUS_NAME,
+                1024 * 1024 * 50,
+                None,
+                &[local_uri.clone(), child_uri.clone()],
+            )
+            .await
+            .unwrap();
+
+            let nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+            let device = nexus.children()[1].get_device_name().unwrap();
+
+            let timeouts = nvme_controller_timeouts(&device).unwrap();
+            assert_eq!(timeouts.io_timeout_us, 20_000_000);
+            assert_eq!(timeouts.action, DeviceTimeoutAction::Abort);
+            assert!(timeouts.handler_registered);
+            assert_eq!(timeouts.retry_count, 3);
+            assert_eq!(timeouts.pending_retry_count, None);
+            // as reported by the nvme_controller_timeouts json-rpc method
+            assert_eq!(
+                serde_json::to_value(timeouts).unwrap(),
+                serde_json::json!({
+                    "io_timeout_us": 20_000_000,
+                    "admin_timeout_us": timeouts.admin_timeout_us,
+                    "action": "abort",
+                    "handler_registered": true,
+                    "retry_count": 3,
+                    "pending_retry_count": null,
+                })
+            );
+
+            // Only the given settings are changed, the retry count only for
+            // the queue pairs connected from now on.
+            nexus
+                .set_child_timeout_policy(&format!(
+                    "{remote_uri}?io_timeout_us=60000000&timeout_action=reset&retry_count=5"
+                ))
+                .unwrap();
+            let timeouts = nvme_controller_timeouts(&device).unwrap();
+            assert_eq!(timeouts.io_timeout_us, 60_000_000);
+            assert_eq!(timeouts.action, DeviceTimeoutAction::Reset);
+            assert_eq!(timeouts.retry_count, 3);
+            assert_eq!(timeouts.pending_retry_count, Some(5));
+
+            // A zero I/O timeout unregisters the timeout handler.
+            nexus
+                .set_child_timeout_policy(&format!("{remote_uri}?io_timeout_us=0"))
+                .unwrap();
+            let timeouts = nvme_controller_timeouts(&device).unwrap();
+            assert_eq!(timeouts.io_timeout_us, 0);
+            assert_eq!(timeouts.action, DeviceTimeoutAction::Ignore);
+            assert!(!timeouts.handler_registered);
+
+            nexus
+                .set_child_timeout_policy(&format!("{remote_uri}?io_timeout_us=60000000"))
+                .unwrap();
+            let timeouts = nvme_controller_timeouts(&device).unwrap();
+            assert_eq!(timeouts.action, DeviceTimeoutAction::Reset);
+            assert!(timeouts.handler_registered);
+
+            // The policy of a local child can not be changed.
+            assert!(nexus
+                .set_child_timeout_policy(&format!("{local_uri}&io_timeout_us=60000000"))
+                .is_err());
+            assert!(nexus
+                .set_child_timeout_policy(&format!("{remote_uri}?timeout_action=panic"))
+                .is_err());
+
+            // The changes are kept when the device of the child is re-created.
+            nexus_lookup_mut(NEXUS_NAME)
+                .unwrap()
+                .fault_child(&child_uri, FaultReason::Offline)
+                .await
+                .unwrap();
+            nexus_lookup_mut(NEXUS_NAME)
+                .unwrap()
+                .online_child(&child_uri)
+                .await
+                .unwrap();
+            let nexus = nexus_lookup_mut(NEXUS_NAME).unwrap();
+            let device = nexus.children()[1].get_device_name().unwrap();
+            let timeouts = nvme_controller_timeouts(&device).unwrap();
+            assert_eq!(timeouts.io_timeout_us, 60_000_000);
+            assert_eq!(timeouts.action, DeviceTimeoutAction::Reset);
+            assert_eq!(timeouts.retry_count, 5);
+            assert_eq!(timeouts.pending_retry_count, None);
+        })
+        .await;
+}
@@ -21,7 +21,7 @@ use common::{
     },
     file_io::DataSize,
     fio::{spawn_fio_task, FioBuilder, FioJobBuilder},
-    nexus::{test_write_to_nexus, NexusBuilder},
+    nexus::{test_fio_to_nexus, test_write_to_nexus, NexusBuilder},
     nvme::{find_mayastor_nvme_device_path, NmveConnectGuard},
     pool::PoolBuilder,
     replica::ReplicaBuilder,
@@ -371,6 +371,37 @@ async fn injection_uri_creation() {
     assert_eq!(src.retries, res.retries);
 }
 
+#[tokio::test]
+async fn injection_uri_new_methods() {
+    let methods = [
+        ("delay-250", FaultMethod::Delay(Duration::from_millis(250))),
+        ("drop", FaultMethod::Drop),
+        ("bitflip-4096", FaultMethod::BitFlip(4096)),
+    ];
+
+    for (name, method) in methods {
+        let uri = format!("inject://dev0?domain=block&stage=compl&method={name}&probability=25");
+        let res = Injection::from_uri(&uri).unwrap();
+        assert_eq!(res.method, method);
+        assert_eq!(res.probability, 25);
+
+        let src = InjectionBuilder::default()
+            .with_domain(FaultDomain::BlockDevice)
+            .with_device_name("dev0".to_string())
+            .with_io_stage(FaultIoStage::Completion)
+            .with_method(method)
+            .with_probability(25)
+            .build()
+            .unwrap();
+        let res = Injection::from_uri(&src.as_uri()).unwrap();
+        assert_eq!(src.method, res.method);
+        assert_eq!(src.probability, res.probability);
+    }
+
+    assert!(Injection::from_uri("inject://dev0?method=bitflip-0").is_err());
+    assert!(Injection::from_uri("inject://dev0?probability=101").is_err());
+}
+
 #[tokio::test]
 async fn replica_bdev_io_injection() {
     common::composer_init();
@@ -469,3 +500,155 @@ async fn replica_bdev_io_injection() {
 
     assert_eq!(r.kind(), std::io::ErrorKind::Other);
 }
+
+/// Adds a block device fault injection at the write completion of the given
+/// child device of the nexus.
+async fn add_write_completion_injection(nex: &NexusBuilder, dev_name: &str, inj_part: &str) {
+    let inj_uri = format!("inject://{dev_name}?domain=block&op=write&stage=compl&{inj_part}");
+    add_fault_injection(nex.rpc(), &inj_uri).await.unwrap();
+}
+
+/// Writes a block to the nexus, returning how long the write took.
+async fn timed_write_to_nexus(nex: &NexusBuilder) -> Duration {
+    let start = std::time::Instant::now();
+    test_write_to_nexus(nex, DataSize::from_bytes(0), 1, DataSize::from_kb(1))
+        .await
+        .unwrap();
+    start.elapsed()
+}
+
+#[tokio::test]
+async fn nexus_fault_injection_delay() {
+    let test = create_compose_test().await;
+    let StorageBuilder { nex_0, .. } = create_test_storage(&test).await;
+
+    let children = nex_0.get_nexus().await.unwrap().children;
+    let dev_name = children[0].device_name.clone().unwrap();
+
+    add_write_completion_injection(&nex_0, &dev_name, "method=delay-1000").await;
+
+    // The write completes once the delayed child write does, and the child
+    // stays healthy.
+    let elapsed = timed_write_to_nexus(&nex_0).await;
+    assert!(elapsed >= Duration::from_millis(1000), "{elapsed:?}");
+
+    let children = nex_0.get_nexus().await.unwrap().children;
+    assert_eq!(children[0].state, ChildState::Online as i32);
+}
+
+#[tokio::test]
+async fn nexus_fault_injection_drop() {
+    const IO_TIMEOUT: Duration = Duration::from_secs(2);
+
+    let test = create_compose_test().await;
+    let StorageBuilder {
+        repl_0,
+        repl_1,
+        mut nex_0,
+        ..
+    } = create_test_storage(&test).await;
+
+    // Re-create the nexus with children that abort a command once it times
+    // out.
+    nex_0.destroy().await.unwrap();
+    let with_timeout = |uri: String| {
+        let sep = if uri.contains('?') { '&' } else { '?' };
+        format!(
+            "{uri}{sep}io_timeout_us={}&timeout_action=abort",
+            IO_TIMEOUT.as_micros()
+        )
+    };
+    let mut nex_0 = NexusBuilder::new(nex_0.rpc())
+        .with_name("nexus0")
+        .with_new_uuid()
+        .with_size_mb(REPL_SIZE)
+        .with_bdev(&with_timeout(nex_0.replica_uri(&repl_0)))
+        .with_bdev(&with_timeout(nex_0.replica_uri(&repl_1)));
+    nex_0.create().await.unwrap();
+    nex_0.publish().await.unwrap();
+
+    let children = nex_0.get_nexus().await.unwrap().children;
+    let dev_name = children[0].device_name.clone().unwrap();
+
+    add_write_completion_injection(&nex_0, &dev_name, "method=drop").await;
+
+    // The dropped child write is aborted once it times out, rather than
+    // hanging: the child is faulted and the write completes on the other
+    // child.
+    let elapsed = tokio::time::timeout(IO_TIMEOUT * 10, timed_write_to_nexus(&nex_0))
+        .await
+        .expect("a dropped I/O must time out");
+    assert!(elapsed >= IO_TIMEOUT, "{elapsed:?}");
+
+    let children = nex_0.get_nexus().await.unwrap().children;
+    assert_eq!(children[0].state, ChildState::Faulted as i32);
+    assert_eq!(children[1].state, ChildState::Online as i32);
+}
+
+#[tokio::test]
+async fn nexus_fault_injection_bitflip() {
+    let test = create_compose_test().await;
+    let StorageBuilder { nex_0, .. } = create_test_storage(&test).await;
+
+    let fio = FioBuilder::new()
+        .with_job(
+            FioJobBuilder::new()
+                .with_bs(DataSize::from_kb(4))
+                .with_size(DataSize::from_mb(4))
+                .with_rw("write")
+                .with_verify("crc32")
+                .with_verify_fatal(true)
+                .build(),
+        )
+        .build();
+
+    // Without the injection, the data reads back as written.
+    test_fio_to_nexus(&nex_0, fio.clone())
+        .await
+        .expect("This FIO job must succeed");
+
+    // Flip bits on the reads of both children: the reads complete
+    // successfully, but the data no longer verifies.
+    for child in nex_0.get_nexus().await.unwrap().children {
+        let dev_name = child.device_name.unwrap();
+        let inj_uri =
+            format!("inject://{dev_name}?domain=block&op=read&stage=compl&method=bitflip-512");
+        add_fault_injection(nex_0.rpc(), &inj_uri).await.unwrap();
+    }
+
+    test_fio_to_nexus(&nex_0, fio)
+        .await
+        .expect_err("This FIO job must fail to verify");
+
+    let children = nex_0.get_nexus().await.unwrap().children;
+    assert!(children
+        .iter()
+        .all(|c| c.state == ChildState::Online as i32));
+}
+
+#[tokio::test]
+async fn nexus_fault_injection_probability() {
+    const WRITES: usize = 40;
+
+    let test = create_compose_test().await;
+    let StorageBuilder { nex_0, .. } = create_test_storage(&test).await;
+
+    let children = nex_0.get_nexus().await.unwrap().children;
+    let dev_name = children[0].device_name.clone().unwrap();
+
+    // Delay half of the writes, which are told apart by their latency.
+    add_write_completion_injection(&nex_0, &dev_name, "method=delay-250&probability=50").await;
+
+    let mut delayed = 0;
+    for _ in 0..WRITES {
+        if timed_write_to_nexus(&nex_0).await >= Duration::from_millis(250) {
+            delayed += 1;
+        }
+    }
+
+    // The chance of falling outside of these bounds is below 1e-4.
+    assert!(
+        (8..=32).contains(&delayed),
+        "{delayed} of {WRITES} writes delayed"
+    );
+}
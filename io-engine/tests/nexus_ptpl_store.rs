@@ -0,0 +1,167 @@
+//! The nexus PTPL state is kept in the persistent store, so that the host
+//! reservations follow the nexus to another node.
+
+pub mod common;
+
+use std::time::Duration;
+
+use common::{
+    compose::{rpc::v1::GrpcConnect, Binary, Builder, ContainerSpec},
+    nexus::NexusBuilder,
+    nvme::{get_nvme_resv_report, nvme_resv_acquire, nvme_resv_register, nvme_resv_release},
+};
+use etcd_client::Client;
+use io_engine::subsys::PtplState;
+
+static ETCD_ENDPOINT: &str = "0.0.0.0:2379";
+const TEST_NAME: &str = "nexus_ptpl_store";
+const NEXUS_NAME: &str = "nexus_ptpl";
+const NEXUS_UUID: &str = "5b0cf1c7-4e1a-4f55-9d3c-4c1f5d0e8a21";
+const RESV_KEY: u64 = 0x1234_5678_abcd_ef00;
+/// Write Exclusive reservation type.
+const RESV_TYPE: u8 = 1;
+
+fn store_key() -> String {
+    format!("ptpl/nexus/{NEXUS_UUID}")
+}
+
+/// Wait for the PTPL state of the nexus in the persistent store to match.
+async fn wait_store_state(etcd: &mut Client, f: impl Fn(&PtplState) -> bool) -> PtplState {
+    for _ in 0..50 {
+        let response = etcd.get(store_key(), None).await.unwrap();
+        if let Some(kv) = response.kvs().first() {
+            let state: PtplState = serde_json::from_slice(kv.value()).unwrap();
+            if f(&state) {
+                return state;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("The PTPL state of the nexus was not saved to the persistent store");
+}
+
+#[tokio::test]
+async fn nexus_ptpl_store() {
+    common::composer_init();
+
+    let etcd_endpoint = format!("http://etcd.{TEST_NAME}:2379");
+    // Each io-engine has its own PTPL directory, so only the persistent store
+    // can carry the reservations from one to the other.
+    let io_engine = || {
+        Binary::from_dbg("io-engine").with_args(vec![
+            "-p",
+            &etcd_endpoint,
+            "--ptpl-dir",
+            "/tmp/ptpl",
+            "--ptpl-store",
+        ])
+    };
+    let test = Builder::new()
+        .name(TEST_NAME)
+        .add_container_spec(
+            ContainerSpec::from_binary(
+                "etcd",
+                Binary::from_path(env!("ETCD_BIN")).with_args(vec![
+                    "--data-dir",
+                    "/tmp/etcd-data",
+                    "--advertise-client-urls",
+                    "http://0.0.0.0:2379",
+                    "--listen-client-urls",
+                    "http://0.0.0.0:2379",
+                ]),
+            )
+            .with_portmap("2379", "2379")
+            .with_portmap("2380", "2380"),
+        )
+        .add_container_bin("ms1", io_engine())
+        .add_container_bin("ms2", io_engine())
+        .with_clean(true)
+        .build()
+        .await
+        .unwrap();
+
+    let conn = GrpcConnect::new(&test);
+    let ms1 = conn.grpc_handle_shared("ms1").await.unwrap();
+    let ms2 = conn.grpc_handle_shared("ms2").await.unwrap();
+    let mut etcd = Client::connect([ETCD_ENDPOINT], None).await.unwrap();
+
+    let mut nex_1 = NexusBuilder::new(ms1)
+        .with_name(NEXUS_NAME)
+        .with_uuid(NEXUS_UUID)
+        .with_size_mb(32)
+        .with_bdev("malloc:///mem0?size_mb=64");
+    nex_1.create().await.unwrap();
+    nex_1.publish().await.unwrap();
+
+    {
+        let (_cg, path) = nex_1.nvmf_location().open().unwrap();
+        let dev = path.to_str().unwrap();
+        nvme_resv_register(dev, RESV_KEY);
+        nvme_resv_acquire(dev, RESV_KEY, RESV_TYPE);
+    }
+
+    // The reservation changes are saved to the persistent store.
+    let state = wait_store_state(&mut etcd, |s| s.rtype == RESV_TYPE as u32).await;
+    assert!(state.ptpl);
+    assert_eq!(state.crkey, RESV_KEY);
+    assert_eq!(state.registrants.len(), 1);
+    assert_eq!(state.registrants[0].rkey, RESV_KEY);
+
+    // Unsharing the nexus flushes the pending saves, so the latest state is
+    // in the store as soon as the nexus is unpublished.
+    {
+        let (_cg, path) = nex_1.nvmf_location().open().unwrap();
+        nvme_resv_release(path.to_str().unwrap(), RESV_KEY, RESV_TYPE);
+    }
+    nex_1.unpublish().await.unwrap();
+    let response = etcd.get(store_key(), None).await.unwrap();
+    let state: PtplState = serde_json::from_slice(response.kvs()[0].value()).unwrap();
+    assert_eq!(state.rtype, 0);
+    assert_eq!(state.registrants.len(), 1);
+
+    nex_1.publish().await.unwrap();
+    {
+        let (_cg, path) = nex_1.nvmf_location().open().unwrap();
+        nvme_resv_acquire(path.to_str().unwrap(), RESV_KEY, RESV_TYPE);
+    }
+    wait_store_state(&mut etcd, |s| s.rtype == RESV_TYPE as u32).await;
+
+    // The node of the nexus is lost, and the nexus is re-created on another
+    // node, which restores the reservations before the host reconnects.
+    test.stop("ms1").await.unwrap();
+
+    let mut nex_2 = NexusBuilder::new(ms2)
+        .with_name(NEXUS_NAME)
+        .with_uuid(NEXUS_UUID)
+        .with_size_mb(32)
+        .with_bdev("malloc:///mem0?size_mb=64");
+    nex_2.create().await.unwrap();
+    nex_2.publish().await.unwrap();
+
+    {
+        let (_cg, path) = nex_2.nvmf_location().open().unwrap();
+        let dev = path.to_str().unwrap();
+
+        let v = get_nvme_resv_report(dev);
+        assert_eq!(v["rtype"], RESV_TYPE, "should have write exclusive access");
+        assert_eq!(v["regctl"], 1, "should have 1 registered controller");
+        assert_eq!(
+            v["ptpls"], 1,
+            "should have Persist Through Power Loss State enabled"
+        );
+        assert_eq!(
+            v["regctlext"][0]["rkey"], RESV_KEY,
+            "should have the registered key of the host"
+        );
+
+        // The changes on the new node are saved too.
+        nvme_resv_release(dev, RESV_KEY, RESV_TYPE);
+        let state = wait_store_state(&mut etcd, |s| s.rtype == 0).await;
+        assert_eq!(state.registrants.len(), 1);
+    }
+
+    // Destroying the nexus removes its PTPL state from the store.
+    nex_2.destroy().await.unwrap();
+    let response = etcd.get(store_key(), None).await.unwrap();
+    assert!(response.kvs().is_empty());
+}
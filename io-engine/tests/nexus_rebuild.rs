@@ -1,17 +1,23 @@
 use std::{sync::Mutex, time::Duration};
 
+use chrono::Utc;
 use crossbeam::channel::unbounded;
 use once_cell::sync::{Lazy, OnceCell};
 use tracing::error;
 
 use io_engine::{
-    bdev::{device_create, device_destroy, device_open, nexus::nexus_lookup_mut},
-    core::{MayastorCliArgs, Mthread, Protocol},
+    bdev::{
+        device_create, device_destroy, device_open,
+        nexus::{nexus_lookup_mut, ChildState, Error, FaultReason, NexusReplicaSnapshotDescriptor},
+    },
+    core::{MayastorCliArgs, Mthread, Protocol, SnapshotParams},
+    lvs::Lvs,
     rebuild::{BdevRebuildJob, NexusRebuildJob, RebuildState},
 };
 
 pub mod common;
 use common::{compose::MayastorTest, reactor_poll, wait_for_rebuild};
+use io_engine_tests::pool::PoolBuilderLocal;
 
 // each test `should` use a different nexus name to prevent clashing with
 // one another. This allows the failed tests to `panic gracefully` improving
@@ -109,13 +115,19 @@ async fn nexus_share() -> String {
     device
 }
 
-#[allow(deprecated)]
 async fn wait_for_replica_rebuild(src_replica: &str, new_replica: &str) {
+    wait_for_child_rebuild(new_replica, src_replica, new_replica).await;
+}
+
+/// Waits for the rebuild of the given child, then checks the data of its
+/// device against the one of the source device.
+#[allow(deprecated)]
+async fn wait_for_child_rebuild(child: &str, src_replica: &str, new_replica: &str) {
     let ms = get_ms();
 
     // 1. Wait for rebuild to complete.
     loop {
-        let replica_name = new_replica.to_string();
+        let replica_name = child.to_string();
         let complete = ms
             .spawn(async move {
                 let nexus = nexus_lookup_mut(nexus_name()).unwrap();
@@ -314,6 +326,271 @@ async fn rebuild_replica() {
     .await;
 }
 
+const SNAP_POOL_SIZE_MB: u64 = 256;
+const SNAP_REPLICA_SIZE: u64 = 64 * 1024 * 1024;
+const SNAP_NEXUS_SIZE: u64 = 32 * 1024 * 1024;
+const SNAP_REPLICA_UUIDS: [&str; 2] = [
+    "4a6ab1f5-8d7e-4b0e-9a53-1c2f4a9e7d01",
+    "4a6ab1f5-8d7e-4b0e-9a53-1c2f4a9e7d02",
+];
+const SNAP_UUID: &str = "4a6ab1f5-8d7e-4b0e-9a53-1c2f4a9e7d10";
+
+fn snap_child(number: usize) -> String {
+    let uuid = SNAP_REPLICA_UUIDS[number];
+    format!("bdev:///{uuid}?uuid={uuid}")
+}
+
+fn snap_uri() -> String {
+    format!("bdev:///{SNAP_UUID}?uuid={SNAP_UUID}")
+}
+
+/// Creates a nexus over two replicas of a local pool named after the nexus
+/// and fills it with random data, returning the device of the nexus.
+async fn snap_nexus_create() -> String {
+    let pool = PoolBuilderLocal::default()
+        .with_builder(|b| {
+            b.with_name(nexus_name())
+                .with_new_uuid()
+                .with_malloc(nexus_name(), SNAP_POOL_SIZE_MB)
+        })
+        .create()
+        .await
+        .unwrap();
+    for uuid in SNAP_REPLICA_UUIDS {
+        pool.create_lvol(uuid, SNAP_REPLICA_SIZE, Some(uuid), true, None)
+            .await
+            .unwrap();
+    }
+
+    io_engine::bdev::nexus::nexus_create(
+        nexus_name(),
+        SNAP_NEXUS_SIZE,
+        None,
+        &[snap_child(0), snap_child(1)],
+    )
+    .await
+    .unwrap();
+
+    let device = nexus_share().await;
+    let nexus_device = device.clone();
+    let (s, r) = unbounded::<i32>();
+    Mthread::spawn_unaffinitized(move || s.send(common::dd_urandom_blkdev(&nexus_device)));
+    let dd_result: i32;
+    reactor_poll!(r, dd_result);
+    assert_eq!(dd_result, 0, "Failed to fill nexus with random data");
+
+    device
+}
+
+/// Destroys the nexus and the pool of its replicas.
+async fn snap_nexus_destroy() {
+    nexus_lookup_mut(nexus_name())
+        .unwrap()
+        .destroy()
+        .await
+        .unwrap();
+    Lvs::lookup(nexus_name()).unwrap().destroy().await.unwrap();
+}
+
+/// Takes a snapshot of the first replica of the nexus through the nexus,
+/// skipping the second one.
+async fn snap_nexus_snapshot() {
+    let nexus = nexus_lookup_mut(nexus_name()).unwrap();
+    let params = SnapshotParams::new(
+        Some("e1".to_string()),
+        Some(SNAP_REPLICA_UUIDS[0].to_string()),
+        Some("t1".to_string()),
+        Some("snap".to_string()),
+        Some(SNAP_UUID.to_string()),
+        Some(Utc::now().to_string()),
+        false,
+    );
+    let replicas = vec![
+        NexusReplicaSnapshotDescriptor {
+            replica_uuid: SNAP_REPLICA_UUIDS[0].to_string(),
+            snapshot_uuid: Some(SNAP_UUID.to_string()),
+            skip: false,
+        },
+        NexusReplicaSnapshotDescriptor {
+            replica_uuid: SNAP_REPLICA_UUIDS[1].to_string(),
+            snapshot_uuid: None,
+            skip: true,
+        },
+    ];
+    let res = nexus.create_snapshot(params, replicas).await.unwrap();
+    assert_eq!(res.replicas_done.len(), 1);
+    assert_eq!(res.replicas_done[0].status, 0);
+}
+
+async fn snap_fault_child() {
+    let mut nexus = nexus_lookup_mut(nexus_name()).unwrap();
+    nexus
+        .as_mut()
+        .fault_child(&snap_child(1), FaultReason::Offline)
+        .await
+        .unwrap();
+    reactor_poll!(200);
+    assert_eq!(
+        nexus.child(&snap_child(1)).unwrap().state(),
+        ChildState::Faulted(FaultReason::Offline)
+    );
+}
+
+#[tokio::test]
+async fn rebuild_from_snapshot() {
+    // size of the writes made after the snapshot was taken
+    const DELTA_SIZE: u64 = 4 * 1024 * 1024;
+
+    test_ini("rebuild_from_snapshot");
+
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        let device = snap_nexus_create().await;
+
+        // the child goes out of sync, and its I/O log starts
+        snap_fault_child().await;
+
+        // the snapshot is taken afterwards, and then more data is written
+        snap_nexus_snapshot().await;
+        let (s, r) = unbounded::<String>();
+        Mthread::spawn_unaffinitized(move || {
+            s.send(common::dd_urandom_file_size(&device, DELTA_SIZE))
+        });
+        reactor_poll!(r);
+
+        nexus_lookup_mut(nexus_name())
+            .unwrap()
+            .online_child_from_snapshot(&snap_child(1), &snap_uri())
+            .await
+            .unwrap();
+        let job = NexusRebuildJob::lookup(&snap_child(1)).unwrap();
+        assert_eq!(job.snapshot_uri(), Some(snap_uri().as_str()));
+    })
+    .await;
+
+    // the snapshot and the delta make up for the whole child
+    wait_for_child_rebuild(&snap_child(1), SNAP_REPLICA_UUIDS[0], SNAP_REPLICA_UUIDS[1]).await;
+
+    ms.spawn(async move {
+        let nexus = nexus_lookup_mut(nexus_name()).unwrap();
+        assert_eq!(
+            nexus.child(&snap_child(1)).unwrap().state(),
+            ChildState::Open
+        );
+
+        // only the delta was read from the source child
+        let history = nexus.rebuild_history();
+        let record = history.last().unwrap();
+        assert_eq!(record.state, RebuildState::Completed);
+        assert!(record.is_partial);
+        assert!(record.blocks_transferred > 0);
+        assert!(record.blocks_transferred <= DELTA_SIZE / 512 + record.blocks_per_task);
+
+        snap_nexus_destroy().await;
+        test_fini();
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn rebuild_from_snapshot_no_io_log() {
+    test_ini("rebuild_from_snapshot_no_io_log");
+
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        nexus_create(NEXUS_SIZE, 1, true).await;
+        let mut nexus = nexus_lookup_mut(nexus_name()).unwrap();
+        nexus.as_mut().add_child(&get_dev(1), true).await.unwrap();
+
+        // a newly added child has never been in sync, so there is no I/O log
+        // to tell the segments which changed since the snapshot, and the
+        // child is fully rebuilt from the source child instead
+        nexus
+            .start_rebuild_from_snapshot(&get_dev(1), "malloc:///snap?size_mb=256")
+            .await
+            .unwrap();
+        let job = NexusRebuildJob::lookup(&get_dev(1)).unwrap();
+        assert_eq!(job.snapshot_uri(), None);
+    })
+    .await;
+
+    wait_for_replica_rebuild(&get_dev(0), &get_dev(1)).await;
+
+    ms.spawn(async move {
+        let nexus = nexus_lookup_mut(nexus_name()).unwrap();
+        let history = nexus.rebuild_history();
+        let record = history.last().unwrap();
+        assert_eq!(record.state, RebuildState::Completed);
+        assert!(!record.is_partial);
+
+        nexus.destroy().await.unwrap();
+        test_fini();
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn rebuild_from_snapshot_too_old() {
+    test_ini("rebuild_from_snapshot_too_old");
+
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        snap_nexus_create().await;
+        snap_nexus_snapshot().await;
+        snap_fault_child().await;
+
+        // the writes made between the snapshot and the start of the I/O log
+        // of the child would be lost
+        let mut nexus = nexus_lookup_mut(nexus_name()).unwrap();
+        let result = nexus
+            .as_mut()
+            .online_child_from_snapshot(&snap_child(1), &snap_uri())
+            .await;
+        assert!(matches!(result, Err(Error::SnapshotBeforeIoLog { .. })));
+        assert!(NexusRebuildJob::lookup(&snap_child(1)).is_err());
+
+        snap_nexus_destroy().await;
+        test_fini();
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn rebuild_from_snapshot_not_of_nexus() {
+    test_ini("rebuild_from_snapshot_not_of_nexus");
+
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        nexus_create(NEXUS_SIZE, 2, false).await;
+        let mut nexus = nexus_lookup_mut(nexus_name()).unwrap();
+        nexus
+            .as_mut()
+            .fault_child(&get_dev(1), FaultReason::Offline)
+            .await
+            .unwrap();
+        reactor_poll!(200);
+
+        // nothing tells which writes a snapshot the nexus did not take holds
+        let snapshot_uri = "malloc:///snap?size_mb=256";
+        device_create(snapshot_uri).await.unwrap();
+        let result = nexus
+            .as_mut()
+            .online_child_from_snapshot(&get_dev(1), snapshot_uri)
+            .await;
+        assert!(matches!(result, Err(Error::SnapshotNotOfNexus { .. })));
+        assert!(NexusRebuildJob::lookup(&get_dev(1)).is_err());
+
+        nexus.destroy().await.unwrap();
+        device_destroy(snapshot_uri).await.unwrap();
+        test_fini();
+    })
+    .await;
+}
+
 #[tokio::test]
 async fn rebuild_bdev() {
     test_ini("rebuild_bdev");
@@ -0,0 +1,260 @@
+//! The reservations of the hosts of a nexus are kept per host by its nvmf
+//! subsystem, and mirrored onto its replicas, whose own reservations persist
+//! through the custom reservation operations of the nvmf subsystems too.
+
+pub mod common;
+
+use std::time::Duration;
+
+use common::{
+    compose::{rpc::v1::GrpcConnect, Binary, Builder},
+    nexus::NexusBuilder,
+    nvme::NvmeHostConnection,
+    pool::PoolBuilder,
+    replica::ReplicaBuilder,
+};
+
+const TEST_NAME: &str = "nexus_reservation";
+const HOSTNQN_A: &str = "nqn.2019-05.io.openebs:host-a";
+const HOSTID_A: &str = "3e4c6b4a-4a9c-4b8a-9c44-0a6f4c1e7a01";
+const HOSTNQN_B: &str = "nqn.2019-05.io.openebs:host-b";
+const HOSTID_B: &str = "3e4c6b4a-4a9c-4b8a-9c44-0a6f4c1e7a02";
+/// Host which checks the replicas, and competes with the nexus for them.
+const HOSTNQN_R: &str = "nqn.2019-05.io.openebs:host-r";
+const HOSTID_R: &str = "3e4c6b4a-4a9c-4b8a-9c44-0a6f4c1e7a03";
+const KEY_A: u64 = 0xa;
+const KEY_B: u64 = 0xb;
+const KEY_R: u64 = 0xf;
+/// Write Exclusive reservation type.
+const WRITE_EXCLUSIVE: u8 = 1;
+
+/// Connect the replica checking host to the given replica.
+fn connect_replica(repl: &ReplicaBuilder) -> NvmeHostConnection {
+    let loc = repl.nvmf_location();
+    NvmeHostConnection::connect(&loc.addr.ip().to_string(), &loc.nqn, HOSTNQN_R, HOSTID_R)
+}
+
+/// Wait for the reservation of the nexus on the replica to match, returning
+/// the registration of the nexus.
+async fn wait_replica_resv(repl: &NvmeHostConnection, key: u64, rtype: u8) -> serde_json::Value {
+    for _ in 0..50 {
+        let v = repl.resv_report();
+        let nexus = v["regctlext"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|r| r["hostid"].as_str() != Some(HOSTID_R.replace('-', "").as_str()))
+            .cloned();
+        if let Some(nexus) = nexus {
+            if nexus["rkey"] == key && v["rtype"] == rtype {
+                return nexus;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("The reservation of the replica does not match: {key:x}h, type {rtype}");
+}
+
+#[tokio::test]
+async fn nexus_reservation_multi_host() {
+    common::composer_init();
+
+    let test = Builder::new()
+        .name(TEST_NAME)
+        .add_container_bin(
+            "ms_repl",
+            Binary::from_dbg("io-engine").with_args(vec!["-l", "1"]),
+        )
+        .add_container_bin(
+            "ms_nex",
+            Binary::from_dbg("io-engine").with_args(vec!["-l", "2", "--ptpl-dir", "/tmp/ptpl"]),
+        )
+        .with_clean(true)
+        .build()
+        .await
+        .unwrap();
+
+    let conn = GrpcConnect::new(&test);
+    let ms_repl = conn.grpc_handle_shared("ms_repl").await.unwrap();
+    let ms_nex = conn.grpc_handle_shared("ms_nex").await.unwrap();
+
+    let mut pool = PoolBuilder::new(ms_repl.clone())
+        .with_name("pool0")
+        .with_new_uuid()
+        .with_malloc("mem0", 128);
+    pool.create().await.unwrap();
+
+    let mut replicas = Vec::new();
+    for i in 0..3 {
+        let mut repl = ReplicaBuilder::new(ms_repl.clone())
+            .with_pool(&pool)
+            .with_name(&format!("r{i}"))
+            .with_new_uuid()
+            .with_size_mb(32)
+            .with_thin(false);
+        repl.create().await.unwrap();
+        repl.share().await.unwrap();
+        replicas.push(repl);
+    }
+
+    let mut nex = NexusBuilder::new(ms_nex.clone())
+        .with_name("nexus0")
+        .with_new_uuid()
+        .with_size_mb(32)
+        .with_replicas(&replicas[..2]);
+    nex.create().await.unwrap();
+    nex.publish().await.unwrap();
+
+    let loc = nex.nvmf_location();
+    let addr = loc.addr.ip().to_string();
+    let host_a = NvmeHostConnection::connect(&addr, &loc.nqn, HOSTNQN_A, HOSTID_A);
+    let host_b = NvmeHostConnection::connect(&addr, &loc.nqn, HOSTNQN_B, HOSTID_B);
+    let r0 = connect_replica(&replicas[0]);
+    let r1 = connect_replica(&replicas[1]);
+
+    // Each host has its own registration on the nexus, while the replicas
+    // see the nexus registered with the key of the first host.
+    // The second host doesn't ask for its reservations to persist through
+    // power loss, which the nexus keeps doing to mirror all of them.
+    assert!(host_a.resv_register(KEY_A));
+    assert!(host_b.resv_register_volatile(KEY_B));
+    let v = host_b.resv_report();
+    assert_eq!(v["regctl"], 2, "should have 2 registered hosts");
+    assert_eq!(v["ptpls"], 1, "should persist through power loss");
+    for (host, key) in [(HOSTID_A, KEY_A), (HOSTID_B, KEY_B)] {
+        let reg = v["regctlext"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["hostid"].as_str() == Some(host.replace('-', "").as_str()))
+            .unwrap_or_else(|| panic!("host {host} should be registered"));
+        assert_eq!(reg["rkey"], key);
+    }
+    for r in [&r0, &r1] {
+        wait_replica_resv(r, KEY_A, 0).await;
+    }
+
+    // Another host holds the reservation of the second replica, so the
+    // reservation of the first host can't be mirrored onto it, and is rolled
+    // back on the first replica.
+    assert!(r1.resv_register(KEY_R));
+    assert!(r1.resv_acquire(KEY_R, WRITE_EXCLUSIVE));
+    assert!(host_a.resv_acquire(KEY_A, WRITE_EXCLUSIVE));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(r0.resv_report()["rtype"], 0, "should be rolled back");
+    assert_eq!(r1.resv_report()["rtype"], WRITE_EXCLUSIVE);
+
+    // The host reservations are still kept per host by the nexus.
+    let v = host_b.resv_report();
+    assert_eq!(v["rtype"], WRITE_EXCLUSIVE);
+    assert!(
+        !host_b.resv_acquire(KEY_B, WRITE_EXCLUSIVE),
+        "should conflict with the reservation of the first host"
+    );
+
+    // Once the other host is gone, the reservation is mirrored onto both.
+    assert!(r1.resv_release(KEY_R, WRITE_EXCLUSIVE));
+    for r in [&r0, &r1] {
+        let nexus = wait_replica_resv(r, KEY_A, WRITE_EXCLUSIVE).await;
+        assert_eq!(nexus["rcsts"], 1, "the nexus should hold the reservation");
+    }
+
+    // The second host preempts the first one, on the nexus and the replicas.
+    assert!(host_b.resv_preempt(KEY_B, KEY_A, WRITE_EXCLUSIVE));
+    let v = host_a.resv_report();
+    assert_eq!(v["regctl"], 1, "the first host should be unregistered");
+    assert_eq!(
+        v["regctlext"][0]["hostid"].as_str().unwrap(),
+        HOSTID_B.replace('-', "")
+    );
+    for r in [&r0, &r1] {
+        wait_replica_resv(r, KEY_B, WRITE_EXCLUSIVE).await;
+    }
+
+    // A new replica gets the reservation replayed once rebuilt.
+    nex.add_replica(&replicas[2], false).await.unwrap();
+    nex.wait_children_online(Duration::from_secs(10))
+        .await
+        .unwrap();
+    let r2 = connect_replica(&replicas[2]);
+    wait_replica_resv(&r2, KEY_B, WRITE_EXCLUSIVE).await;
+
+    drop((host_a, host_b, r0, r1, r2));
+    nex.destroy().await.unwrap();
+}
+
+#[tokio::test]
+async fn replica_reservation_ptpl() {
+    common::composer_init();
+
+    const PTPL_DIR: &str = "/tmp/replica_ptpl";
+    common::delete_file(&[PTPL_DIR.into()]);
+
+    let test = Builder::new()
+        .name("replica_reservation")
+        .add_container_bin(
+            "ms_repl",
+            Binary::from_dbg("io-engine")
+                .with_args(vec!["-l", "1", "--ptpl-dir", &format!("/host{PTPL_DIR}")])
+                .with_bind("/tmp", "/host/tmp"),
+        )
+        .with_clean(true)
+        .build()
+        .await
+        .unwrap();
+
+    let conn = GrpcConnect::new(&test);
+    let ms_repl = conn.grpc_handle_shared("ms_repl").await.unwrap();
+
+    let mut pool = PoolBuilder::new(ms_repl.clone())
+        .with_name("pool0")
+        .with_new_uuid()
+        .with_malloc("mem0", 64);
+    pool.create().await.unwrap();
+    let mut repl = ReplicaBuilder::new(ms_repl.clone())
+        .with_pool(&pool)
+        .with_name("r0")
+        .with_new_uuid()
+        .with_size_mb(32)
+        .with_thin(false);
+    repl.create().await.unwrap();
+    repl.share().await.unwrap();
+
+    let r = connect_replica(&repl);
+    assert!(r.resv_register(KEY_R));
+    assert!(r.resv_acquire(KEY_R, WRITE_EXCLUSIVE));
+
+    // The PTPL file of the replica is kept in the format of SPDK.
+    let file = format!(
+        "{PTPL_DIR}/pool/{}/replica/{}.json",
+        pool.uuid(),
+        repl.uuid()
+    );
+    let ptpl: serde_json::Value = serde_json::from_slice(&std::fs::read(&file).unwrap()).unwrap();
+    assert_eq!(ptpl["ptpl"], true);
+    assert_eq!(ptpl["rtype"], WRITE_EXCLUSIVE);
+    assert_eq!(ptpl["crkey"], KEY_R);
+    assert_eq!(ptpl["registrants"][0]["rkey"], KEY_R);
+    assert_eq!(
+        ptpl["holder_uuid"].as_str().unwrap().replace('-', ""),
+        HOSTID_R.replace('-', "")
+    );
+
+    // It's loaded when the replica is shared again.
+    drop(r);
+    repl.unshare().await.unwrap();
+    repl.share().await.unwrap();
+    let r = connect_replica(&repl);
+    let v = r.resv_report();
+    assert_eq!(v["rtype"], WRITE_EXCLUSIVE);
+    assert_eq!(v["regctlext"][0]["rkey"], KEY_R);
+    assert_eq!(v["ptpls"], 1);
+
+    // Unlike those of a nexus, the reservations of a replica only persist as
+    // the hosts ask for.
+    assert!(r.resv_register_volatile(KEY_R));
+    assert_eq!(r.resv_report()["ptpls"], 0);
+
+    drop(r);
+    repl.destroy().await.unwrap();
+}
@@ -66,3 +66,148 @@ async fn nexus_share_test() {
         })
         .await;
 }
+
+#[tokio::test]
+async fn nexus_share_ublk_test() {
+    if !std::path::Path::new("/dev/ublk-control").exists() {
+        std::process::Command::new("modprobe")
+            .arg("ublk_drv")
+            .status()
+            .ok();
+    }
+    assert!(
+        std::path::Path::new("/dev/ublk-control").exists(),
+        "the ublk_drv kernel module is required"
+    );
+
+    let args = MayastorCliArgs {
+        reactor_mask: "0x3".into(),
+        ..Default::default()
+    };
+
+    MayastorTest::new(args)
+        .spawn(async {
+            // create a nexus and expose it via ublk
+            Reactor::block_on(async {
+                nexus_create(
+                    "nexus0",
+                    48 * 1024 * 1024,
+                    None,
+                    &[
+                        "malloc:///malloc0?size_mb=64".into(),
+                        "malloc:///malloc1?size_mb=64".into(),
+                    ],
+                )
+                .await
+                .unwrap();
+
+                let mut nexus = nexus_lookup_mut("nexus0").unwrap();
+
+                // publishing over the same protocol must be idempotent
+                let share = nexus.as_mut().share(Protocol::Ublk, None).await.unwrap();
+                let share2 = nexus.as_mut().share(Protocol::Ublk, None).await.unwrap();
+                assert_eq!(share, share2);
+                assert!(share.starts_with("file:///dev/ublkb"));
+                assert_eq!(nexus.get_share_uri(), Some(share));
+
+                // publishing over a different protocol must fail
+                assert!(nexus.as_mut().share(Protocol::Nvmf, None).await.is_err());
+            });
+
+            // concurrent shares get distinct devices
+            Reactor::block_on(async {
+                for name in ["nexus1", "nexus2"] {
+                    nexus_create(
+                        name,
+                        48 * 1024 * 1024,
+                        None,
+                        &[format!("malloc:///{name}_malloc?size_mb=64")],
+                    )
+                    .await
+                    .unwrap();
+                }
+                let (share1, share2) = futures::join!(
+                    nexus_lookup_mut("nexus1")
+                        .unwrap()
+                        .share(Protocol::Ublk, None),
+                    nexus_lookup_mut("nexus2")
+                        .unwrap()
+                        .share(Protocol::Ublk, None)
+                );
+                let (share1, share2) = (share1.unwrap(), share2.unwrap());
+                assert_ne!(share1, share2);
+                for name in ["nexus1", "nexus2"] {
+                    nexus_lookup_mut(name).unwrap().destroy().await.unwrap();
+                }
+            });
+
+            // unpublish the nexus
+            Reactor::block_on(async {
+                let nexus = nexus_lookup_mut("nexus0").unwrap();
+                nexus.unshare_nexus().await.unwrap();
+            });
+
+            Reactor::block_on(async {
+                let nexus = nexus_lookup_mut("nexus0").unwrap();
+                assert_eq!(nexus.get_share_uri(), None);
+                let bdev = UntypedBdev::lookup_by_name("nexus0").unwrap();
+                assert_eq!(bdev.shared(), Some(Protocol::Off));
+                nexus.destroy().await.unwrap();
+            });
+
+            mayastor_env_stop(0);
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn nexus_share_vhost_test() {
+    let args = MayastorCliArgs {
+        reactor_mask: "0x3".into(),
+        vhost_socket_dir: "/tmp/io-engine-vhost-test".into(),
+        ..Default::default()
+    };
+
+    MayastorTest::new(args)
+        .spawn(async {
+            // create a nexus and expose it via vhost-user-blk
+            Reactor::block_on(async {
+                nexus_create(
+                    "nexus0",
+                    48 * 1024 * 1024,
+                    None,
+                    &[
+                        "malloc:///malloc0?size_mb=64".into(),
+                        "malloc:///malloc1?size_mb=64".into(),
+                    ],
+                )
+                .await
+                .unwrap();
+
+                let mut nexus = nexus_lookup_mut("nexus0").unwrap();
+
+                // publishing over the same protocol must be idempotent
+                let share = nexus.as_mut().share(Protocol::Vhost, None).await.unwrap();
+                let share2 = nexus.as_mut().share(Protocol::Vhost, None).await.unwrap();
+                assert_eq!(share, share2);
+                assert_eq!(share, "vhost-user:///tmp/io-engine-vhost-test/nexus0");
+                assert!(std::path::Path::new("/tmp/io-engine-vhost-test/nexus0").exists());
+            });
+
+            // unpublish the nexus
+            Reactor::block_on(async {
+                let nexus = nexus_lookup_mut("nexus0").unwrap();
+                nexus.unshare_nexus().await.unwrap();
+            });
+
+            Reactor::block_on(async {
+                let nexus = nexus_lookup_mut("nexus0").unwrap();
+                assert_eq!(nexus.get_share_uri(), None);
+                assert!(!std::path::Path::new("/tmp/io-engine-vhost-test/nexus0").exists());
+                nexus.destroy().await.unwrap();
+            });
+
+            mayastor_env_stop(0);
+        })
+        .await;
+}
@@ -0,0 +1,126 @@
+use once_cell::sync::OnceCell;
+use tonic::{metadata::MetadataMap, transport::ClientTlsConfig};
+
+pub mod common;
+use common::compose::MayastorTest;
+use io_engine::{
+    core::MayastorCliArgs,
+    lvs::LvsLvol,
+    pool_backend::{FindPoolArgs, PoolFactory, ReplicaArgs},
+    subsys::registration::{
+        registration_grpc::{ApiVersion, Registration, RegistrationCommand},
+        NodeSummary,
+    },
+};
+use io_engine_tests::pool::{PoolBuilderLocal, PoolOps};
+
+static MAYASTOR: OnceCell<MayastorTest> = OnceCell::new();
+
+fn get_ms() -> &'static MayastorTest<'static> {
+    MAYASTOR.get_or_init(|| MayastorTest::new(MayastorCliArgs::default()))
+}
+
+#[tokio::test]
+async fn node_summary_collect() {
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        let summary = NodeSummary::collect().await;
+        assert!(summary.pools.is_empty());
+        assert_eq!(summary.replica_count, 0);
+        assert_eq!(summary.nexus_count, 0);
+        assert_eq!(summary.reactors.count, 1);
+        assert_eq!(summary.reactors.frozen, None);
+
+        let pool = PoolBuilderLocal::malloc("md_summary", 64).await.unwrap();
+        let uuid = "6c1f2e3d-4b5a-4e7f-8a9b-0c1d2e3f4a5b";
+        let replica = pool
+            .create_repl(uuid, 8 * 1024 * 1024, Some(uuid), true, None)
+            .await
+            .unwrap();
+
+        // The pools and replicas are cached until a change is signalled.
+        let summary = NodeSummary::collect().await;
+        assert!(summary.pools.is_empty());
+        assert_eq!(summary.replica_count, 0);
+
+        NodeSummary::invalidate();
+        let summary = NodeSummary::collect().await;
+        assert_eq!(summary.pools.len(), 1);
+        assert_eq!(summary.pools[0].name, "md_summary");
+        assert!(summary.pools[0].capacity > 0);
+        assert_eq!(summary.replica_count, 1);
+
+        // The changes made through the pool and replica backends are seen
+        // right away.
+        let pool = PoolFactory::find(FindPoolArgs::name_uuid("md_summary".into(), &None))
+            .await
+            .unwrap();
+        let uuid = "0a1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d";
+        let other = pool
+            .create_repl(ReplicaArgs {
+                name: uuid.to_string(),
+                size: 8 * 1024 * 1024,
+                uuid: uuid.to_string(),
+                thin: true,
+                entity_id: None,
+                use_extent_table: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(NodeSummary::collect().await.replica_count, 2);
+        other.destroy().await.unwrap();
+        assert_eq!(NodeSummary::collect().await.replica_count, 1);
+
+        // The summary is sent as JSON.
+        let json = serde_json::to_vec(&summary).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<NodeSummary>(&json).unwrap(),
+            summary
+        );
+
+        replica.destroy_replica().await.unwrap();
+    })
+    .await;
+}
+
+#[test]
+fn registration_commands() {
+    let mut metadata = MetadataMap::new();
+    assert!(RegistrationCommand::from_metadata(&metadata).is_empty());
+
+    metadata.insert("node-commands", "resync, unknown,drain".parse().unwrap());
+    assert_eq!(
+        RegistrationCommand::from_metadata(&metadata),
+        vec![RegistrationCommand::Resync, RegistrationCommand::Drain]
+    );
+}
+
+#[tokio::test]
+async fn registration_authentication() {
+    assert!(!RegistrationCommand::Resync.is_disruptive());
+    assert!(RegistrationCommand::Drain.is_disruptive());
+
+    let registration = |addr: &str, tls: Option<ClientTlsConfig>| {
+        Registration::new(
+            "node_summary",
+            &None,
+            "127.0.0.1:10124",
+            addr.parse().unwrap(),
+            vec![ApiVersion::V1],
+            tls,
+        )
+        .unwrap()
+    };
+
+    // Disruptive commands are only accepted from a control plane reached over
+    // https with a TLS configuration.
+    assert!(!registration("http://127.0.0.1:50051", None).is_authenticated());
+    assert!(
+        !registration("http://127.0.0.1:50051", Some(ClientTlsConfig::new())).is_authenticated()
+    );
+    assert!(!registration("https://127.0.0.1:50051", None).is_authenticated());
+    assert!(
+        registration("https://127.0.0.1:50051", Some(ClientTlsConfig::new())).is_authenticated()
+    );
+}
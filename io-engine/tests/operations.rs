@@ -0,0 +1,179 @@
+pub mod common;
+
+use common::compose::{
+    rpc::v1::{
+        json::JsonRpcRequest,
+        pool::{CreatePoolRequest, DestroyPoolRequest, ListPoolOptions},
+        GrpcConnect, RpcHandle,
+    },
+    Builder,
+};
+use io_engine::grpc::operations::is_tracked;
+use serde_json::{json, Value};
+use tonic::{Code, Request, Status};
+
+const POOL_UUID: &str = "6f0a3d46-42c9-4b4e-9a5c-8a0f4fd2b7a1";
+
+/// Tag the request with the given operation id.
+fn with_operation_id<T>(id: &str, message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    request
+        .metadata_mut()
+        .insert("operation-id", id.parse().unwrap());
+    request
+}
+
+/// Call a json-rpc method of the io-engine.
+async fn json_rpc(rpc: &mut RpcHandle, method: &str, params: Value) -> Result<Value, Status> {
+    let response = rpc
+        .json
+        .json_rpc_call(JsonRpcRequest {
+            method: method.to_string(),
+            params: params.to_string(),
+        })
+        .await?
+        .into_inner();
+    Ok(serde_json::from_str(&response.result).unwrap())
+}
+
+fn create_pool(name: &str, uuid: &str) -> CreatePoolRequest {
+    CreatePoolRequest {
+        name: name.to_string(),
+        uuid: Some(uuid.to_string()),
+        pooltype: 0,
+        disks: vec![format!("malloc:///{name}?size_mb=64")],
+        cluster_size: None,
+        md_args: None,
+    }
+}
+
+#[tokio::test]
+async fn operations() {
+    common::composer_init();
+
+    let test = Builder::new()
+        .name("operations")
+        .network("10.1.0.0/16")
+        .unwrap()
+        .add_container_dbg("ms1")
+        .with_clean(true)
+        .build()
+        .await
+        .unwrap();
+    let mut rpc = GrpcConnect::new(&test).grpc_handle("ms1").await.unwrap();
+
+    assert_eq!(
+        json_rpc(&mut rpc, "operation_list", json!({}))
+            .await
+            .unwrap(),
+        json!([])
+    );
+    let status = json_rpc(&mut rpc, "operation_get", json!({ "id": "op-1" }))
+        .await
+        .unwrap_err();
+    assert!(status.message().contains("not found"));
+    assert!(
+        json_rpc(&mut rpc, "operation_cancel", json!({ "id": "op-1" }))
+            .await
+            .is_err()
+    );
+
+    // Retrying a completed operation returns its result without running it
+    // again.
+    for _ in 0..3 {
+        let pool = rpc
+            .pool
+            .create_pool(with_operation_id("op-create", create_pool("p0", POOL_UUID)))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(pool.uuid, POOL_UUID);
+    }
+    let info = json_rpc(&mut rpc, "operation_get", json!({ "id": "op-create" }))
+        .await
+        .unwrap();
+    assert_eq!(info["state"], "succeeded");
+    assert_eq!(info["method"], "create_pool");
+    assert!(info["finished_at"].is_string());
+
+    // The id can't be reused for another request.
+    let status = rpc
+        .pool
+        .create_pool(with_operation_id(
+            "op-create",
+            create_pool("p1", "0c3b7b2e-6f8d-4f0e-a7f2-3a3f5e0b9d11"),
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::AlreadyExists);
+
+    // A failed operation is replayed as well.
+    for _ in 0..2 {
+        let status = rpc
+            .pool
+            .destroy_pool(with_operation_id(
+                "op-destroy",
+                DestroyPoolRequest {
+                    name: "nopool".into(),
+                    uuid: None,
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+    }
+    let info = json_rpc(&mut rpc, "operation_get", json!({ "id": "op-destroy" }))
+        .await
+        .unwrap();
+    assert_eq!(info["state"], "failed");
+    assert!(info["error"].is_string());
+
+    // A completed operation can no longer be cancelled.
+    let status = json_rpc(&mut rpc, "operation_cancel", json!({ "id": "op-create" }))
+        .await
+        .unwrap_err();
+    assert!(status.message().contains("can no longer be cancelled"));
+
+    // The methods which can't be tracked reject an operation id.
+    let status = rpc
+        .pool
+        .list_pools(with_operation_id(
+            "op-list",
+            ListPoolOptions {
+                name: None,
+                pooltype: None,
+                uuid: None,
+            },
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let ids = json_rpc(&mut rpc, "operation_list", json!({}))
+        .await
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|op| op["id"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["op-create", "op-destroy"]);
+
+    rpc.pool
+        .destroy_pool(DestroyPoolRequest {
+            name: "p0".into(),
+            uuid: None,
+        })
+        .await
+        .unwrap();
+}
+
+#[test]
+fn operation_tracked_methods() {
+    assert!(is_tracked("/mayastor.v1.PoolRpc/CreatePool"));
+    assert!(is_tracked("/mayastor.v1.NexusRpc/PublishNexus"));
+    assert!(is_tracked("/mayastor.v1.SnapshotRpc/DestroySnapshot"));
+    assert!(!is_tracked("/mayastor.v1.PoolRpc/ListPools"));
+    assert!(!is_tracked("/mayastor.v1.TestRpc/WipeReplica"));
+    assert!(!is_tracked("/mayastor.Mayastor/CreatePool"));
+}
@@ -0,0 +1,130 @@
+use std::pin::Pin;
+
+use chrono::Utc;
+use once_cell::sync::OnceCell;
+use uuid::Uuid;
+
+pub mod common;
+use common::compose::MayastorTest;
+use io_engine::{
+    core::{LogicalVolume, LvolSnapshotOps, MayastorCliArgs, Share, SnapshotParams},
+    lvs::{check_pool, Lvs, LvsLvol, PoolCheckError, PoolCheckOptions},
+    pool_backend::{PoolArgs, PoolBackend},
+};
+use io_engine_tests::pool::{PoolBuilderLocal, PoolOps};
+
+static MAYASTOR: OnceCell<MayastorTest> = OnceCell::new();
+
+fn get_ms() -> &'static MayastorTest<'static> {
+    MAYASTOR.get_or_init(|| {
+        MayastorTest::new(MayastorCliArgs {
+            ..Default::default()
+        })
+    })
+}
+
+const SIZE_MB: u64 = 8;
+const POOL_SZ_MB: u64 = SIZE_MB * 8;
+
+#[tokio::test]
+async fn pool_check_healthy() {
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        let pool = PoolBuilderLocal::malloc("md_check", POOL_SZ_MB)
+            .await
+            .unwrap();
+        let uuid = "3f6c1d2e-8a4b-4c5d-9e7f-0a1b2c3d4e5f";
+        let replica = pool
+            .create_repl(uuid, SIZE_MB * 1024 * 1024, Some(uuid), true, None)
+            .await
+            .unwrap();
+
+        let snapshot_params = SnapshotParams::new(
+            Some(format!("{uuid}_e1")),
+            Some(replica.uuid()),
+            Some(Uuid::new_v4().to_string()),
+            Some(format!("{uuid}_snap1")),
+            Some(Uuid::new_v4().to_string()),
+            Some(Utc::now().to_string()),
+            false,
+        );
+        let snapshot = replica.create_snapshot(snapshot_params).await.unwrap();
+
+        let report = check_pool(
+            "md_check",
+            None,
+            PoolCheckOptions {
+                repair: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert!(!report.offline);
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+        assert!(report.is_healthy());
+        assert_eq!(report.leaked_clusters, 0);
+        // the super blob, the replica and its snapshot
+        assert!(report.blobs_checked >= 3);
+
+        replica.destroy_replica().await.unwrap();
+        snapshot.destroy_snapshot().await.unwrap();
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn pool_check_not_imported() {
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        let result = check_pool("md_check_missing", None, PoolCheckOptions::default()).await;
+        assert!(matches!(
+            result,
+            Err(PoolCheckError::CheckPoolNotFound { .. })
+        ));
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn pool_check_offline() {
+    const DISK: &str = "/tmp/pool_check_offline.img";
+    common::delete_file(&[DISK.into()]);
+    common::truncate_file(DISK, POOL_SZ_MB * 1024);
+
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        let disks = vec![format!("aio://{DISK}")];
+        let pool = Lvs::create_or_import(PoolArgs {
+            name: "md_check_offline".to_string(),
+            disks: disks.clone(),
+            uuid: None,
+            cluster_size: None,
+            md_args: None,
+            backend: PoolBackend::Lvs,
+        })
+        .await
+        .unwrap();
+        let uuid = "7b2e4f60-1c3d-4a5b-8e9f-6d7c8b9a0e1f";
+        let mut replica = pool
+            .create_lvol(uuid, SIZE_MB * 1024 * 1024, Some(uuid), true, None)
+            .await
+            .unwrap();
+        Pin::new(&mut replica).share_nvmf(None).await.unwrap();
+        pool.export().await.unwrap();
+
+        let report = check_pool("md_check_offline", Some(disks), PoolCheckOptions::default())
+            .await
+            .unwrap();
+        assert!(report.offline);
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+        // the pool is exported again once checked
+        assert!(Lvs::lookup("md_check_offline").is_none());
+    })
+    .await;
+
+    common::delete_file(&[DISK.into()]);
+}
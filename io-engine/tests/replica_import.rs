@@ -0,0 +1,213 @@
+use once_cell::sync::OnceCell;
+use std::{io::Write, time::Duration};
+
+use io_engine::{core::MayastorCliArgs, rebuild::RebuildState, sleep::mayastor_sleep};
+
+pub mod common;
+use common::compose::MayastorTest;
+use io_engine::{
+    core::LogicalVolume,
+    lvs::{Lvol, LvsLvol},
+    rebuild::{ImportSource, ReplicaImportJob},
+};
+use io_engine_tests::pool::{PoolBuilderLocal, PoolLocal, PoolOps};
+
+static MAYASTOR: OnceCell<MayastorTest> = OnceCell::new();
+
+fn get_ms() -> &'static MayastorTest<'static> {
+    MAYASTOR.get_or_init(|| {
+        MayastorTest::new(MayastorCliArgs {
+            ..Default::default()
+        })
+    })
+}
+
+async fn create_replica(pool: &PoolLocal, uuid: &str, thin: bool) -> Result<Lvol, String> {
+    pool.create_repl(uuid, SIZE_MB * 1024 * 1024, Some(uuid), thin, None)
+        .await
+        .map_err(|error| error.to_string())
+}
+async fn destroy_replica(replica: Lvol) -> Result<(), String> {
+    replica
+        .destroy_replica()
+        .await
+        .map_err(|error| error.to_string())?;
+    Ok(())
+}
+
+/// Creates an image file of the given size, where only the first `data_mb`
+/// are filled with non-zero data.
+fn create_image(path: &str, size_mb: u64, data_mb: u64) {
+    let mut file = std::fs::File::create(path).unwrap();
+    file.write_all(&vec![0xa5; (data_mb * 1024 * 1024) as usize])
+        .unwrap();
+    file.set_len(size_mb * 1024 * 1024).unwrap();
+}
+
+const BLOCK_SIZE: u64 = 512;
+fn mb_to_blocks(mb: u64) -> u64 {
+    (mb * 1024 * 1024) / BLOCK_SIZE
+}
+const SIZE_MB: u64 = 32;
+const POOL_SZ_MB: u64 = SIZE_MB * 3;
+
+#[tokio::test]
+async fn file_to_thin_replica() {
+    let ms = get_ms();
+    let image = "/tmp/replica_import_file_to_thin_replica.img";
+    create_image(image, SIZE_MB, 4);
+
+    ms.spawn(async move {
+        let pool = PoolBuilderLocal::malloc("md", POOL_SZ_MB).await.unwrap();
+        let replica = create_replica(&pool, "3be1219f-682b-4672-b88b-8b9d07e8104a", true)
+            .await
+            .unwrap();
+
+        let job = ReplicaImportJob::builder()
+            .with_replica_uuid(&replica.uuid())
+            .with_source(ImportSource::parse(&format!("file://{image}")).unwrap())
+            .build()
+            .await
+            .unwrap()
+            .store()
+            .unwrap();
+
+        let chan = job.start().await.unwrap();
+        assert!(ReplicaImportJob::lookup(&replica.uuid()).is_ok());
+
+        let state = chan.await.unwrap();
+        let stats = job.stats().await;
+        job.destroy();
+
+        assert_eq!(state, RebuildState::Completed, "Import should succeed");
+        assert_eq!(stats.blocks_recovered, mb_to_blocks(SIZE_MB));
+        // zero segments are not written into the thin replica
+        assert_eq!(stats.blocks_transferred, mb_to_blocks(4));
+        assert!(replica.allocated() < SIZE_MB * 1024 * 1024);
+
+        destroy_replica(replica).await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[image.into()]);
+}
+
+#[tokio::test]
+async fn file_to_thick_replica() {
+    let ms = get_ms();
+    let image = "/tmp/replica_import_file_to_thick_replica.img";
+    create_image(image, SIZE_MB, 4);
+
+    ms.spawn(async move {
+        let pool = PoolBuilderLocal::malloc("md", POOL_SZ_MB).await.unwrap();
+        let replica = create_replica(&pool, "3be1219f-682b-4672-b88b-8b9d07e8104a", false)
+            .await
+            .unwrap();
+
+        let job = ReplicaImportJob::builder()
+            .with_replica_uuid(&replica.uuid())
+            .with_source(ImportSource::parse(&format!("file://{image}")).unwrap())
+            .build()
+            .await
+            .unwrap()
+            .store()
+            .unwrap();
+
+        let state = job.start().await.unwrap().await.unwrap();
+        let stats = job.stats().await;
+        job.destroy();
+
+        assert_eq!(state, RebuildState::Completed, "Import should succeed");
+        assert_eq!(stats.blocks_transferred, mb_to_blocks(SIZE_MB));
+
+        destroy_replica(replica).await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[image.into()]);
+}
+
+#[tokio::test]
+async fn file_too_large() {
+    let ms = get_ms();
+    let image = "/tmp/replica_import_file_too_large.img";
+    create_image(image, SIZE_MB * 2, 4);
+
+    ms.spawn(async move {
+        let pool = PoolBuilderLocal::malloc("md", POOL_SZ_MB).await.unwrap();
+        let replica = create_replica(&pool, "3be1219f-682b-4672-b88b-8b9d07e8104a", true)
+            .await
+            .unwrap();
+
+        let result = ReplicaImportJob::builder()
+            .with_replica_uuid(&replica.uuid())
+            .with_source(ImportSource::parse(&format!("file://{image}")).unwrap())
+            .build()
+            .await;
+        assert!(result.is_err(), "Image larger than the replica must fail");
+
+        destroy_replica(replica).await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[image.into()]);
+}
+
+#[tokio::test]
+async fn stream_to_thin_replica() {
+    let ms = get_ms();
+    let fifo = "/tmp/replica_import_stream_to_thin_replica.fifo";
+    std::fs::remove_file(fifo).ok();
+    assert!(std::process::Command::new("mkfifo")
+        .arg(fifo)
+        .status()
+        .unwrap()
+        .success());
+
+    // The stream can only be read sequentially, and is spooled first.
+    let writer = std::thread::spawn(move || {
+        let mut stream = std::fs::OpenOptions::new().write(true).open(fifo).unwrap();
+        stream
+            .write_all(&vec![0xa5; (4 * 1024 * 1024) as usize])
+            .unwrap();
+    });
+
+    ms.spawn(async move {
+        let pool = PoolBuilderLocal::malloc("md", POOL_SZ_MB).await.unwrap();
+        let replica = create_replica(&pool, "3be1219f-682b-4672-b88b-8b9d07e8104a", true)
+            .await
+            .unwrap();
+
+        // The import is created right away, and spools within the job.
+        let source = ImportSource::parse(&format!("stream://{fifo}")).unwrap();
+        ReplicaImportJob::create(&replica.uuid(), &replica.uuid(), source.clone())
+            .await
+            .unwrap();
+        // Creating it again must neither fail nor restart the spooling.
+        ReplicaImportJob::create(&replica.uuid(), &replica.uuid(), source)
+            .await
+            .unwrap();
+
+        let job = loop {
+            if let Ok(job) = ReplicaImportJob::lookup(&replica.uuid()) {
+                if job.state().done() {
+                    break job;
+                }
+            }
+            mayastor_sleep(Duration::from_millis(10)).await.unwrap();
+        };
+        let state = job.state();
+        let stats = job.stats().await;
+        job.destroy();
+
+        assert_eq!(state, RebuildState::Completed, "Import should succeed");
+        assert_eq!(stats.blocks_recovered, mb_to_blocks(4));
+        assert_eq!(stats.blocks_transferred, mb_to_blocks(4));
+
+        destroy_replica(replica).await.unwrap();
+    })
+    .await;
+
+    writer.join().unwrap();
+    std::fs::remove_file(fifo).unwrap();
+}
@@ -0,0 +1,155 @@
+use once_cell::sync::OnceCell;
+
+pub mod common;
+use common::compose::MayastorTest;
+use io_engine::{
+    bdev::nexus::{nexus_create, nexus_lookup_mut},
+    core::{partition::DATA_PARTITION_OFFSET, LogicalVolume, MayastorCliArgs, UntypedBdev},
+    lvs::{Lvol, LvsLvol, ReclaimError},
+};
+use io_engine_tests::pool::{PoolBuilderLocal, PoolLocal, PoolOps};
+
+static MAYASTOR: OnceCell<MayastorTest> = OnceCell::new();
+
+fn get_ms() -> &'static MayastorTest<'static> {
+    MAYASTOR.get_or_init(|| {
+        MayastorTest::new(MayastorCliArgs {
+            bs_cluster_unmap: true,
+            ..Default::default()
+        })
+    })
+}
+
+const SIZE_MB: u64 = 32;
+const POOL_SZ_MB: u64 = SIZE_MB * 3;
+
+async fn create_replica(pool: &PoolLocal, uuid: &str, thin: bool) -> Lvol {
+    pool.create_repl(uuid, SIZE_MB * 1024 * 1024, Some(uuid), thin, None)
+        .await
+        .unwrap()
+}
+
+/// Fill the cluster at the given index with the given byte.
+async fn fill_cluster(replica: &Lvol, cluster: u64, byte: u8) {
+    let cluster_size = replica.usage().cluster_size;
+    let hdl = replica.as_bdev().open(true).unwrap().into_handle().unwrap();
+    let mut buf = hdl.dma_malloc(cluster_size).unwrap();
+    buf.fill(byte);
+    hdl.write_at(cluster * cluster_size, &buf).await.unwrap();
+}
+
+#[tokio::test]
+async fn trim_zero_clusters() {
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        let pool = PoolBuilderLocal::malloc("md_trim", POOL_SZ_MB)
+            .await
+            .unwrap();
+        let replica = create_replica(&pool, "5d0f4c5e-2f1b-4b0c-9a4e-7d3a1c2b8e61", true).await;
+        let cluster_size = replica.usage().cluster_size;
+        assert_eq!(replica.reclaimed_bytes(), 0);
+
+        fill_cluster(&replica, 0, 0xa5).await;
+        fill_cluster(&replica, 1, 0xa5).await;
+        fill_cluster(&replica, 1, 0).await;
+        assert_eq!(replica.allocated(), 2 * cluster_size);
+
+        let stats = replica.trim().await.unwrap();
+        assert_eq!(stats.clusters_scanned, 2);
+        assert_eq!(stats.zero_clusters, 1);
+        assert_eq!(stats.reclaimed_bytes, cluster_size);
+        assert_eq!(stats.total_reclaimed_bytes, cluster_size);
+        assert_eq!(replica.allocated(), cluster_size);
+        assert_eq!(replica.reclaimed_bytes(), cluster_size);
+
+        replica.destroy_replica().await.unwrap();
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn trim_after_nexus_unmap() {
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        let pool = PoolBuilderLocal::malloc("md_nexus_unmap", POOL_SZ_MB)
+            .await
+            .unwrap();
+        let uuid = "c4b1e7d2-6a3f-4e85-9b0d-2f7c8a1e5d94";
+        let replica = create_replica(&pool, uuid, true).await;
+        let cluster_size = replica.usage().cluster_size;
+
+        nexus_create(
+            "nexus_unmap",
+            SIZE_MB * 1024 * 1024 / 2,
+            Some(uuid),
+            &[format!("bdev:///{uuid}?uuid={uuid}")],
+        )
+        .await
+        .unwrap();
+        let hdl = UntypedBdev::open_by_name("nexus_unmap", true)
+            .unwrap()
+            .into_handle()
+            .unwrap();
+
+        let mut buf = hdl.dma_malloc(2 * cluster_size).unwrap();
+        buf.fill(0xa5);
+        hdl.write_at(0, &buf).await.unwrap();
+        let allocated = replica.allocated();
+
+        // The nexus data starts mid-cluster on the replica, so neither unmap
+        // covers a whole cluster of the replica, which stay allocated.
+        hdl.unmap_at(0, cluster_size).await.unwrap();
+        hdl.unmap_at(cluster_size, cluster_size).await.unwrap();
+        assert_eq!(replica.allocated(), allocated);
+
+        // The nexus claims the replica, which can't be trimmed meanwhile.
+        assert!(matches!(
+            replica.trim().await,
+            Err(ReclaimError::ReplicaInUse { .. })
+        ));
+
+        drop(hdl);
+        nexus_lookup_mut("nexus_unmap")
+            .unwrap()
+            .destroy()
+            .await
+            .unwrap();
+
+        // The clusters the unmaps covered in parts are released by a trim.
+        let start = DATA_PARTITION_OFFSET;
+        let end = start + 2 * cluster_size;
+        let released = end / cluster_size - (start + cluster_size - 1) / cluster_size;
+        assert!(released > 0);
+        let stats = replica.trim().await.unwrap();
+        assert!(stats.zero_clusters >= released);
+        assert_eq!(stats.reclaimed_bytes, allocated - replica.allocated());
+        assert_eq!(replica.reclaimed_bytes(), stats.reclaimed_bytes);
+        // The trim releases its claim of the replica.
+        assert!(!replica.as_bdev().is_claimed());
+
+        replica.destroy_replica().await.unwrap();
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn trim_thick_replica() {
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        let pool = PoolBuilderLocal::malloc("md_trim_thick", POOL_SZ_MB)
+            .await
+            .unwrap();
+        let replica = create_replica(&pool, "a2c9e5b1-8d7f-4e3a-b6c0-1f9d2e4a7b53", false).await;
+
+        assert!(
+            replica.trim().await.is_err(),
+            "thick replicas can't be trimmed"
+        );
+
+        replica.destroy_replica().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,277 @@
+use chrono::{DateTime, Duration, Utc};
+use once_cell::sync::OnceCell;
+
+pub mod common;
+use common::compose::MayastorTest;
+use io_engine::{
+    bdev::nexus::{nexus_create, nexus_lookup_mut},
+    core::{LvolSnapshotOps, MayastorCliArgs, ReplicaXattrs},
+    lvs::{Lvol, LvsLvol, NexusSnapshotPolicy, SnapshotPolicy},
+};
+use io_engine_tests::pool::{PoolBuilderLocal, PoolLocal, PoolOps};
+
+static MAYASTOR: OnceCell<MayastorTest> = OnceCell::new();
+
+fn get_ms() -> &'static MayastorTest<'static> {
+    MAYASTOR.get_or_init(|| {
+        MayastorTest::new(MayastorCliArgs {
+            ..Default::default()
+        })
+    })
+}
+
+const SIZE_MB: u64 = 8;
+const POOL_SZ_MB: u64 = SIZE_MB * 16;
+
+async fn create_replica(pool: &PoolLocal, uuid: &str) -> Lvol {
+    pool.create_repl(uuid, SIZE_MB * 1024 * 1024, Some(uuid), true, None)
+        .await
+        .unwrap()
+}
+
+fn start_time() -> DateTime<Utc> {
+    "2024-01-10T12:00:00Z".parse().unwrap()
+}
+
+#[tokio::test]
+async fn snapshot_policy_persisted() {
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        let pool = PoolBuilderLocal::malloc("md_persisted", POOL_SZ_MB)
+            .await
+            .unwrap();
+        let replica = create_replica(&pool, "9c5d6a82-5e25-4bd4-9a94-1bc8bd3b4d17").await;
+        assert_eq!(SnapshotPolicy::get(&replica), None);
+
+        let policy = SnapshotPolicy {
+            interval_secs: Some(3600),
+            keep_last: Some(3),
+            keep_daily: None,
+        };
+        SnapshotPolicy::set(&replica, Some(&policy)).await.unwrap();
+        assert_eq!(SnapshotPolicy::get(&replica), Some(policy));
+
+        let invalid = SnapshotPolicy {
+            keep_last: Some(0),
+            ..Default::default()
+        };
+        assert!(SnapshotPolicy::set(&replica, Some(&invalid)).await.is_err());
+
+        // clearing the policy removes the attribute altogether
+        SnapshotPolicy::set(&replica, None).await.unwrap();
+        assert_eq!(SnapshotPolicy::get(&replica), None);
+        assert_eq!(
+            Lvol::get_blob_xattr(replica.blob_checked(), ReplicaXattrs::SnapshotPolicy.name()),
+            None
+        );
+
+        replica.destroy_replica().await.unwrap();
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn snapshot_policy_keep_last() {
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        let pool = PoolBuilderLocal::malloc("md_keep_last", POOL_SZ_MB)
+            .await
+            .unwrap();
+        let replica = create_replica(&pool, "0b4b4b8e-3a3c-4d8a-8b52-2a1e43f0c6f9").await;
+        let policy = SnapshotPolicy {
+            interval_secs: Some(60),
+            keep_last: Some(2),
+            keep_daily: None,
+        };
+
+        let now = start_time();
+        let outcome = policy.evaluate(&replica, now).await.unwrap();
+        assert!(outcome.created.is_some());
+
+        // not due yet
+        let outcome = policy
+            .evaluate(&replica, now + Duration::seconds(30))
+            .await
+            .unwrap();
+        assert_eq!(outcome.created, None);
+
+        let outcome = policy
+            .evaluate(&replica, now + Duration::seconds(60))
+            .await
+            .unwrap();
+        assert!(outcome.created.is_some());
+        assert!(outcome.destroyed.is_empty());
+
+        assert_eq!(replica.list_lvol_snapshot_by_source_uuid().len(), 2);
+
+        let outcome = policy
+            .evaluate(&replica, now + Duration::seconds(120))
+            .await
+            .unwrap();
+        assert!(outcome.created.is_some());
+        assert_eq!(outcome.destroyed.len(), 1);
+        assert_eq!(replica.list_lvol_snapshot_by_source_uuid().len(), 2);
+
+        for snapshot in replica.list_lvol_snapshot_by_source_uuid() {
+            snapshot
+                .snapshot_lvol()
+                .clone()
+                .destroy_snapshot()
+                .await
+                .unwrap();
+        }
+        replica.destroy_replica().await.unwrap();
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn snapshot_policy_keep_daily() {
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        let pool = PoolBuilderLocal::malloc("md_keep_daily", POOL_SZ_MB)
+            .await
+            .unwrap();
+        let replica = create_replica(&pool, "e1f1c3a4-6f0a-4c57-b8a8-5f3b2d6f7a10").await;
+        let policy = SnapshotPolicy {
+            interval_secs: Some(6 * 3600),
+            keep_last: Some(1),
+            keep_daily: Some(2),
+        };
+
+        // 4 snapshots per day over 3 days
+        let mut destroyed = 0;
+        for i in 0..12 {
+            let now = start_time() + Duration::hours(6 * i);
+            let outcome = policy.evaluate(&replica, now).await.unwrap();
+            assert!(outcome.created.is_some());
+            destroyed += outcome.destroyed.len();
+        }
+
+        // the latest snapshot, which is also the newest of today, and the
+        // newest snapshot of yesterday are retained
+        let snapshots = replica.list_lvol_snapshot_by_source_uuid();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(destroyed, 10);
+
+        for snapshot in snapshots {
+            snapshot
+                .snapshot_lvol()
+                .clone()
+                .destroy_snapshot()
+                .await
+                .unwrap();
+        }
+        replica.destroy_replica().await.unwrap();
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn snapshot_policy_nexus() {
+    let ms = get_ms();
+
+    ms.spawn(async move {
+        let pool = PoolBuilderLocal::malloc("md_nexus", POOL_SZ_MB)
+            .await
+            .unwrap();
+        let replica_uuid = "5b0a6d3e-2f5c-4b8e-9d3a-7c1e8f4a2b60";
+        let replica = create_replica(&pool, replica_uuid).await;
+        let nexus_uuid = "7f2c9e41-8d6b-4a35-b1e7-3c5d9a0f8e24";
+        nexus_create(
+            "md_nexus",
+            SIZE_MB * 1024 * 1024,
+            Some(nexus_uuid),
+            &[format!("bdev:///{replica_uuid}?uuid={replica_uuid}")],
+        )
+        .await
+        .unwrap();
+        assert_eq!(NexusSnapshotPolicy::get(nexus_uuid), None);
+
+        let policy = SnapshotPolicy {
+            interval_secs: Some(60),
+            keep_last: Some(1),
+            keep_daily: None,
+        };
+        NexusSnapshotPolicy::set(nexus_uuid, Some(&policy))
+            .await
+            .unwrap();
+        assert!(NexusSnapshotPolicy::set("no-such-nexus", Some(&policy))
+            .await
+            .is_err());
+
+        // the policy is kept in the xattrs of the local replica
+        let nexus_policy = NexusSnapshotPolicy::get(nexus_uuid).unwrap();
+        assert_eq!(nexus_policy.policy, policy);
+        assert_eq!(
+            NexusSnapshotPolicy::get_replica(&replica),
+            Some(nexus_policy.clone())
+        );
+        assert_eq!(SnapshotPolicy::get(&replica), None);
+
+        let now = start_time();
+        for i in 0..2 {
+            let at = now + Duration::seconds(60 * i);
+            assert_eq!(
+                nexus_policy.evaluate(nexus_uuid, at).await.unwrap(),
+                Some(at)
+            );
+        }
+        assert_eq!(
+            NexusSnapshotPolicy::last_snapshot(nexus_uuid),
+            Some(now.timestamp() + 60)
+        );
+        assert_eq!(
+            nexus_policy
+                .evaluate(nexus_uuid, now + Duration::seconds(90))
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(replica.list_lvol_snapshot_by_source_uuid().len(), 2);
+
+        // the retention rules of the nexus apply to its local replica
+        let retention = SnapshotPolicy {
+            interval_secs: None,
+            keep_last: Some(1),
+            keep_daily: None,
+        };
+        let outcome = retention
+            .evaluate(&replica, now + Duration::seconds(90))
+            .await
+            .unwrap();
+        assert_eq!(outcome.created, None);
+        assert_eq!(outcome.destroyed.len(), 1);
+
+        // clearing the policy removes it from the replica, which is then no
+        // longer pruned
+        NexusSnapshotPolicy::set(nexus_uuid, None).await.unwrap();
+        assert_eq!(NexusSnapshotPolicy::get(nexus_uuid), None);
+        assert_eq!(
+            Lvol::get_blob_xattr(
+                replica.blob_checked(),
+                ReplicaXattrs::NexusSnapshotPolicy.name()
+            ),
+            None
+        );
+
+        nexus_lookup_mut("md_nexus")
+            .unwrap()
+            .destroy()
+            .await
+            .unwrap();
+        for snapshot in replica.list_lvol_snapshot_by_source_uuid() {
+            snapshot
+                .snapshot_lvol()
+                .clone()
+                .destroy_snapshot()
+                .await
+                .unwrap();
+        }
+        replica.destroy_replica().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use io_engine::{logger, telemetry};
+use opentelemetry_proto::tonic::collector::trace::v1::{
+    trace_service_server::{TraceService, TraceServiceServer},
+    ExportTraceServiceRequest, ExportTraceServiceResponse,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{transport::Server, Request, Response, Status};
+use tracing::Instrument;
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+/// Stand-in for an OTLP collector, which forwards the name and the trace id
+/// of the spans it receives.
+struct Collector(mpsc::UnboundedSender<(String, String)>);
+
+#[tonic::async_trait]
+impl TraceService for Collector {
+    async fn export(
+        &self,
+        request: Request<ExportTraceServiceRequest>,
+    ) -> Result<Response<ExportTraceServiceResponse>, Status> {
+        request
+            .into_inner()
+            .resource_spans
+            .iter()
+            .flat_map(|r| &r.scope_spans)
+            .flat_map(|s| &s.spans)
+            .for_each(|span| {
+                self.0
+                    .send((span.name.clone(), hex::encode(&span.trace_id)))
+                    .ok();
+            });
+        Ok(Response::new(ExportTraceServiceResponse {
+            partial_success: None,
+        }))
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn otlp_export() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    tokio::spawn(
+        Server::builder()
+            .add_service(TraceServiceServer::new(Collector(sender)))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    logger::init_ex(
+        "info",
+        Default::default(),
+        None,
+        Some(endpoint.parse().unwrap()),
+    );
+
+    // A gRPC request which carries the trace context of the control-plane,
+    // and the operation it starts.
+    let request = http::Request::builder()
+        .uri("/mayastor.v1.NexusRpc/AddChildNexus")
+        .header("traceparent", format!("00-{TRACE_ID}-00f067aa0ba902b7-01"))
+        .body(())
+        .unwrap();
+    async {
+        async {}
+            .instrument(telemetry::operation_span("nexus.add_child", "nexus-1"))
+            .await;
+    }
+    .instrument(telemetry::grpc_span(&request))
+    .await;
+
+    telemetry::shutdown();
+
+    let mut spans = Vec::new();
+    while spans.len() < 2 {
+        let span = tokio::time::timeout(Duration::from_secs(10), receiver.recv())
+            .await
+            .expect("spans were not exported")
+            .unwrap();
+        spans.push(span);
+    }
+    spans.sort();
+
+    assert_eq!(
+        spans,
+        vec![
+            (
+                "/mayastor.v1.NexusRpc/AddChildNexus".to_string(),
+                TRACE_ID.to_string()
+            ),
+            ("nexus.add_child".to_string(), TRACE_ID.to_string()),
+        ]
+    );
+}